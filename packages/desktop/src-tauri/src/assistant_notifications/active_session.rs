@@ -0,0 +1,266 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+use super::preferences::WhenFocused;
+use super::SuppressionReason;
+
+/// A window that lost focus this recently still counts as watching its session, so
+/// glancing at another app doesn't produce a banner for what was just on screen.
+const BRIEF_BLUR_GRACE: Duration = Duration::from_secs(10);
+
+/// The session each window is showing, as reported by the frontend, keyed by window label.
+#[derive(Default)]
+pub struct ActiveSessions {
+    windows: Mutex<HashMap<String, WindowSession>>,
+}
+
+#[derive(Default)]
+struct WindowSession {
+    session_id: Option<String>,
+    blurred_at: Option<Instant>,
+}
+
+impl ActiveSessions {
+    pub fn set(&self, label: &str, session_id: Option<String>) {
+        if let Ok(mut windows) = self.windows.lock() {
+            windows.entry(label.to_string()).or_default().session_id =
+                session_id.filter(|id| !id.is_empty());
+        }
+    }
+
+    pub fn window_focused(&self, label: &str) {
+        if let Ok(mut windows) = self.windows.lock() {
+            if let Some(window) = windows.get_mut(label) {
+                window.blurred_at = None;
+            }
+        }
+    }
+
+    pub fn window_blurred(&self, label: &str) {
+        if let Ok(mut windows) = self.windows.lock() {
+            if let Some(window) = windows.get_mut(label) {
+                window.blurred_at = Some(Instant::now());
+            }
+        }
+    }
+
+    pub fn window_closed(&self, label: &str) {
+        if let Ok(mut windows) = self.windows.lock() {
+            windows.remove(label);
+        }
+    }
+
+    /// Labels of windows showing `session_id` with when they lost focus, or `None` if the
+    /// frontend has never reported an active session.
+    fn windows_showing(&self, session_id: &str) -> Option<Vec<(String, Option<Instant>)>> {
+        let windows = self.windows.lock().ok()?;
+        if windows.is_empty() {
+            return None;
+        }
+        Some(
+            windows
+                .iter()
+                .filter(|(_, window)| window.session_id.as_deref() == Some(session_id))
+                .map(|(label, window)| (label.clone(), window.blurred_at))
+                .collect(),
+        )
+    }
+}
+
+/// What notifications need to know about a window, read once per check.
+#[derive(Clone, Copy, Debug, Default)]
+struct WindowState {
+    visible: bool,
+    focused: bool,
+    minimized: bool,
+}
+
+impl WindowState {
+    fn of(window: &WebviewWindow) -> Self {
+        Self {
+            visible: window.is_visible().unwrap_or(false),
+            focused: window.is_focused().unwrap_or(false),
+            minimized: window.is_minimized().unwrap_or(false),
+        }
+    }
+
+    /// A window hidden to the tray, or never shown because the app started there, may
+    /// still claim focus briefly; it does not count.
+    fn on_screen(self) -> bool {
+        self.visible && !self.minimized
+    }
+}
+
+/// Whether a notification may be shown while OpenChamber is in front, following
+/// `notifications.whenFocused`, or why it stays quiet. Every notification checks focus
+/// here so the setting applies to all of them alike. `session_id` is the session the
+/// notification is about, if any.
+pub(super) fn should_notify(
+    app: &AppHandle,
+    when_focused: WhenFocused,
+    session_id: Option<&str>,
+) -> Result<(), SuppressionReason> {
+    let windows: HashMap<String, WindowState> = app
+        .webview_windows()
+        .iter()
+        .map(|(label, window)| (label.clone(), WindowState::of(window)))
+        .collect();
+    let active = app.state::<ActiveSessions>();
+    suppression(&active, &windows, when_focused, session_id).map_or(Ok(()), Err)
+}
+
+fn suppression(
+    active: &ActiveSessions,
+    windows: &HashMap<String, WindowState>,
+    when_focused: WhenFocused,
+    session_id: Option<&str>,
+) -> Option<SuppressionReason> {
+    match (when_focused, session_id) {
+        (WhenFocused::Always, _) => None,
+        // Other sessions notify whatever the focus; only the one on screen stays quiet.
+        (WhenFocused::Never | WhenFocused::UnlessSessionVisible, Some(session_id)) => {
+            session_in_view(active, windows, session_id)
+        }
+        // Server and app-wide notifications belong to no session.
+        (WhenFocused::Never, None) => {
+            window_in_front(windows).then_some(SuppressionReason::WindowFocused)
+        }
+        (WhenFocused::UnlessSessionVisible, None) => None,
+    }
+}
+
+/// Whether the user is in the app: some visible window is both focused and not
+/// minimized. Secondary windows count, so looking at settings or a detached session view
+/// keeps notifications quiet just like the main window does.
+fn window_in_front(windows: &HashMap<String, WindowState>) -> bool {
+    windows
+        .values()
+        .any(|window| window.on_screen() && window.focused)
+}
+
+/// Why a notification about `session_id` would only repeat what the user is looking at,
+/// or `None` when it should be shown. Sessions in other tabs or windows always notify.
+/// Falls back to the window-level focus check until the frontend reports active sessions.
+fn session_in_view(
+    active: &ActiveSessions,
+    windows: &HashMap<String, WindowState>,
+    session_id: &str,
+) -> Option<SuppressionReason> {
+    let Some(showing) = active.windows_showing(session_id) else {
+        return window_in_front(windows).then_some(SuppressionReason::WindowFocused);
+    };
+
+    let visible = showing.into_iter().any(|(label, blurred_at)| {
+        let Some(window) = windows.get(&label) else {
+            return false;
+        };
+        window.on_screen()
+            && (window.focused || blurred_at.is_some_and(|at| at.elapsed() < BRIEF_BLUR_GRACE))
+    });
+    visible.then_some(SuppressionReason::SessionVisible)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FOCUSED: WindowState = WindowState {
+        visible: true,
+        focused: true,
+        minimized: false,
+    };
+
+    fn main_window(state: WindowState) -> HashMap<String, WindowState> {
+        HashMap::from([("main".to_string(), state)])
+    }
+
+    fn showing(session_id: &str) -> ActiveSessions {
+        let active = ActiveSessions::default();
+        active.set("main", Some(session_id.to_string()));
+        active
+    }
+
+    #[test]
+    fn background_sessions_notify_by_default_while_focused() {
+        let active = showing("ses_a");
+        let windows = main_window(FOCUSED);
+        let when_focused = WhenFocused::default();
+
+        assert_eq!(
+            suppression(&active, &windows, when_focused, Some("ses_b")),
+            None
+        );
+        assert_eq!(
+            suppression(&active, &windows, when_focused, Some("ses_a")),
+            Some(SuppressionReason::SessionVisible)
+        );
+        assert_eq!(
+            suppression(&active, &windows, when_focused, None),
+            Some(SuppressionReason::WindowFocused)
+        );
+    }
+
+    #[test]
+    fn when_focused_cases() {
+        let active = showing("ses_a");
+        let focused = main_window(FOCUSED);
+        let minimized = main_window(WindowState {
+            minimized: true,
+            ..FOCUSED
+        });
+
+        let cases = [
+            (WhenFocused::Always, &focused, Some("ses_a"), None),
+            (WhenFocused::Always, &focused, None, None),
+            (
+                WhenFocused::UnlessSessionVisible,
+                &focused,
+                Some("ses_a"),
+                Some(SuppressionReason::SessionVisible),
+            ),
+            (
+                WhenFocused::UnlessSessionVisible,
+                &focused,
+                Some("ses_b"),
+                None,
+            ),
+            (WhenFocused::UnlessSessionVisible, &focused, None, None),
+            (WhenFocused::Never, &minimized, Some("ses_a"), None),
+            (WhenFocused::Never, &minimized, None, None),
+        ];
+        for (when_focused, windows, session_id, expected) in cases {
+            assert_eq!(
+                suppression(&active, windows, when_focused, session_id),
+                expected,
+                "{when_focused:?} {session_id:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn focus_decides_until_the_frontend_reports_sessions() {
+        let active = ActiveSessions::default();
+        assert_eq!(
+            suppression(
+                &active,
+                &main_window(FOCUSED),
+                WhenFocused::Never,
+                Some("ses_b")
+            ),
+            Some(SuppressionReason::WindowFocused)
+        );
+        let blurred = main_window(WindowState {
+            focused: false,
+            ..FOCUSED
+        });
+        assert_eq!(
+            suppression(&active, &blurred, WhenFocused::Never, Some("ses_b")),
+            None
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use serde_json::Value;
+
+/// A completion that arrived while its session was being compacted.
+pub(super) struct HeldCompletion {
+    pub(super) properties: Value,
+    pub(super) directory: Option<String>,
+}
+
+/// Sessions OpenCode is compacting. A completion that arrives meanwhile is held rather
+/// than notified: compaction usually continues the turn, and the reply that follows is
+/// the one worth notifying about.
+#[derive(Default)]
+pub struct CompactingSessions {
+    sessions: Mutex<HashMap<String, Option<HeldCompletion>>>,
+}
+
+impl CompactingSessions {
+    pub(super) fn start(&self, session_id: &str) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.entry(session_id.to_string()).or_insert(None);
+        }
+    }
+
+    /// Hold the completion if its session is compacting, replacing any held before.
+    /// Returns false when the session is not compacting and the caller should notify.
+    pub(super) fn hold(
+        &self,
+        session_id: &str,
+        properties: &Value,
+        directory: Option<&str>,
+    ) -> bool {
+        let Ok(mut sessions) = self.sessions.lock() else {
+            return false;
+        };
+        let Some(held) = sessions.get_mut(session_id) else {
+            return false;
+        };
+        *held = Some(HeldCompletion {
+            properties: properties.clone(),
+            directory: directory.map(str::to_string),
+        });
+        true
+    }
+
+    /// Compaction ended; returns the completion held meanwhile, if any.
+    pub(super) fn finish(&self, session_id: &str) -> Option<HeldCompletion> {
+        self.sessions.lock().ok()?.remove(session_id).flatten()
+    }
+}
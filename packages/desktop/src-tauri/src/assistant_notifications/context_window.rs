@@ -0,0 +1,197 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use log::debug;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use super::active_session::should_notify;
+use super::preferences::{load_notification_preferences, NotificationCategory};
+use super::session_titles::session_title;
+use super::sounds::SoundKind;
+use super::{
+    model_name, record_suppressed, show_session_notification, MutedSessions, OpenCodeApi,
+    SessionNotification, SuppressionReason,
+};
+
+const LIMIT_FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+/// Usage falling below this share of the level that notified means the session was
+/// compacted, and the next crossing notifies again.
+const COMPACTION_RESET_FRACTION: f64 = 0.5;
+
+/// Context sizes of the models in use and the sessions already warned about.
+#[derive(Default)]
+pub struct ContextWindows {
+    /// Context size per `provider/model`; `None` when OpenCode does not report one.
+    limits: Mutex<HashMap<String, Option<u64>>>,
+    /// Tokens in use when each session was last warned.
+    notified: Mutex<HashMap<String, u64>>,
+}
+
+impl ContextWindows {
+    /// Whether `used` tokens cross `threshold_percent` of `limit` for the first time since
+    /// the session was last compacted. A crossing is remembered.
+    fn crossed(&self, session_id: &str, used: u64, limit: u64, threshold_percent: u64) -> bool {
+        let Ok(mut notified) = self.notified.lock() else {
+            return false;
+        };
+        if let Some(&at) = notified.get(session_id) {
+            if (used as f64) >= at as f64 * COMPACTION_RESET_FRACTION {
+                return false;
+            }
+            notified.remove(session_id);
+        }
+        if used * 100 < limit * threshold_percent {
+            return false;
+        }
+        notified.insert(session_id.to_string(), used);
+        true
+    }
+
+    pub(super) fn remove(&self, session_id: &str) {
+        if let Ok(mut notified) = self.notified.lock() {
+            notified.remove(session_id);
+        }
+    }
+
+    async fn limit(
+        &self,
+        api: &OpenCodeApi<'_>,
+        provider: &str,
+        model: &str,
+        directory: Option<&str>,
+    ) -> Option<u64> {
+        let key = format!("{provider}/{model}");
+        if let Some(limit) = self.limits.lock().ok()?.get(&key) {
+            return *limit;
+        }
+        let providers = fetch_providers(api, directory).await?;
+        let Ok(mut limits) = self.limits.lock() else {
+            return None;
+        };
+        for provider in providers
+            .get("providers")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let Some(provider_id) = provider.get("id").and_then(Value::as_str) else {
+                continue;
+            };
+            for (model_id, model) in provider
+                .get("models")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+            {
+                let context = model
+                    .get("limit")
+                    .and_then(|limit| limit.get("context"))
+                    .and_then(Value::as_u64)
+                    .filter(|context| *context > 0);
+                limits.insert(format!("{provider_id}/{model_id}"), context);
+            }
+        }
+        // Remember models OpenCode does not list so they are not looked up every time.
+        *limits.entry(key).or_insert(None)
+    }
+}
+
+async fn fetch_providers(api: &OpenCodeApi<'_>, directory: Option<&str>) -> Option<Value> {
+    let url = format!("{}/config/providers", api.base);
+    let mut request = api.get(&url).timeout(LIMIT_FETCH_TIMEOUT);
+    if let Some(directory) = directory {
+        request = request.query(&[("directory", directory)]);
+    }
+    match request.send().await {
+        Ok(response) if response.status().is_success() => response.json::<Value>().await.ok(),
+        Ok(response) => {
+            debug!(
+                "[desktop:notify] Provider lookup returned status {}",
+                response.status()
+            );
+            None
+        }
+        Err(err) => {
+            debug!("[desktop:notify] Provider lookup failed: {err}");
+            None
+        }
+    }
+}
+
+/// Warn once per session when an assistant message shows the context window filling up.
+pub(super) async fn check_context_window(
+    app: &AppHandle,
+    api: &OpenCodeApi<'_>,
+    properties: &Value,
+    directory: Option<&str>,
+) {
+    let Some(info) = properties.get("info") else {
+        return;
+    };
+    if info.get("role").and_then(Value::as_str) != Some("assistant") {
+        return;
+    }
+    let (Some(session_id), Some(provider), Some(model)) = (
+        info.get("sessionID").and_then(Value::as_str),
+        info.get("providerID").and_then(Value::as_str),
+        info.get("modelID").and_then(Value::as_str),
+    ) else {
+        return;
+    };
+    let Some(tokens) = info.get("tokens") else {
+        return;
+    };
+    let count = |value: Option<&Value>| value.and_then(Value::as_u64).unwrap_or(0);
+    let cache = tokens.get("cache");
+    let used = count(tokens.get("input"))
+        + count(tokens.get("output"))
+        + count(tokens.get("reasoning"))
+        + count(cache.and_then(|cache| cache.get("read")))
+        + count(cache.and_then(|cache| cache.get("write")));
+    if used == 0 {
+        return;
+    }
+
+    let windows = app.state::<ContextWindows>();
+    let Some(limit) = windows.limit(api, provider, model, directory).await else {
+        return;
+    };
+    let preferences = load_notification_preferences(app, directory).await;
+    if !windows.crossed(session_id, used, limit, preferences.context_window_percent) {
+        return;
+    }
+
+    let category = NotificationCategory::ContextWindow;
+    if let Some(reason) = preferences.suppression(category) {
+        record_suppressed(app, category, session_id, reason);
+        return;
+    }
+    if let Err(reason) = should_notify(app, preferences.when_focused, Some(session_id)) {
+        record_suppressed(app, category, session_id, reason);
+        return;
+    }
+    if app.state::<MutedSessions>().is_muted(session_id) {
+        record_suppressed(app, category, session_id, SuppressionReason::Muted);
+        return;
+    }
+
+    let percent = (used * 100 / limit).min(100);
+    let name = model_name(app, api, Some(provider), model, directory).await;
+    let session = match session_title(app, api, session_id, directory).await {
+        Some(title) => format!("Session '{title}'"),
+        None => "This session".to_string(),
+    };
+    show_session_notification(
+        app,
+        SessionNotification {
+            category,
+            session_id,
+            directory,
+            title: preferences.title("Context window nearly full"),
+            body: format!("{session} is at {percent}% of {name}'s context window"),
+            sound: SoundKind::Question,
+            count: 1,
+        },
+    )
+    .await;
+}
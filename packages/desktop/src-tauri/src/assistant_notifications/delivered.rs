@@ -0,0 +1,146 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use log::debug;
+use tauri::{AppHandle, Manager};
+
+use super::NotificationCategory;
+
+/// Notifications shown this recently are left alone on focus; the user may not have read
+/// the banner yet.
+const STALE_AFTER: Duration = Duration::from_secs(5);
+/// Older entries are forgotten; the notification center keeps only a handful per app.
+const MAX_TRACKED: usize = 200;
+
+/// Platform identifiers of notifications we have shown, so completions can be withdrawn
+/// from the notification center once the user is back in the app.
+pub struct DeliveredNotifications {
+    state: Mutex<DeliveredState>,
+}
+
+struct DeliveredState {
+    next_id: i32,
+    shown: VecDeque<Delivered>,
+}
+
+struct Delivered {
+    id: i32,
+    category: NotificationCategory,
+    session_id: String,
+    shown_at: Instant,
+}
+
+impl Default for DeliveredNotifications {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(DeliveredState {
+                next_id: 1,
+                shown: VecDeque::new(),
+            }),
+        }
+    }
+}
+
+impl DeliveredNotifications {
+    /// A fresh identifier for the next notification builder.
+    pub(super) fn next_id(&self) -> i32 {
+        let Ok(mut state) = self.state.lock() else {
+            return 0;
+        };
+        let id = state.next_id;
+        state.next_id = state.next_id.checked_add(1).unwrap_or(1);
+        id
+    }
+
+    pub(super) fn record(&self, id: i32, category: NotificationCategory, session_id: &str) {
+        if let Ok(mut state) = self.state.lock() {
+            state.shown.push_back(Delivered {
+                id,
+                category,
+                session_id: session_id.to_string(),
+                shown_at: Instant::now(),
+            });
+            while state.shown.len() > MAX_TRACKED {
+                state.shown.pop_front();
+            }
+        }
+    }
+
+    /// Stop tracking and return completions shown more than `STALE_AFTER` ago. Questions
+    /// and everything else stay in the notification center.
+    fn take_stale_completions(&self) -> Vec<i32> {
+        let Ok(mut state) = self.state.lock() else {
+            return Vec::new();
+        };
+        let mut stale = Vec::new();
+        state.shown.retain(|delivered| {
+            let is_stale = delivered.category == NotificationCategory::AssistantCompleted
+                && delivered.shown_at.elapsed() >= STALE_AFTER;
+            if is_stale {
+                stale.push(delivered.id);
+            }
+            !is_stale
+        });
+        stale
+    }
+
+    /// Stop tracking and return every `category` notification shown for `session_id`.
+    fn take_session(&self, category: NotificationCategory, session_id: &str) -> Vec<i32> {
+        let Ok(mut state) = self.state.lock() else {
+            return Vec::new();
+        };
+        let mut taken = Vec::new();
+        state.shown.retain(|delivered| {
+            let matches = delivered.category == category && delivered.session_id == session_id;
+            if matches {
+                taken.push(delivered.id);
+            }
+            !matches
+        });
+        taken
+    }
+}
+
+/// Remove a session's question notifications, reminders included, once nothing in the
+/// session is waiting for an answer any more.
+pub(super) fn withdraw_answered_questions(app: &AppHandle, session_id: &str) {
+    let answered = app
+        .state::<DeliveredNotifications>()
+        .take_session(NotificationCategory::QuestionAsked, session_id);
+    if answered.is_empty() {
+        return;
+    }
+    remove_delivered(app, answered);
+}
+
+/// Remove completion notifications the user no longer needs now that the app has focus.
+pub(super) fn withdraw_stale_completions(app: &AppHandle) {
+    let stale = app
+        .state::<DeliveredNotifications>()
+        .take_stale_completions();
+    if stale.is_empty() {
+        return;
+    }
+    remove_delivered(app, stale);
+}
+
+#[cfg(mobile)]
+fn remove_delivered(app: &AppHandle, ids: Vec<i32>) {
+    use tauri_plugin_notification::NotificationExt;
+
+    if let Err(err) = app.notification().remove_active(ids) {
+        debug!("[desktop:notify] Failed to withdraw notifications: {err}");
+    }
+}
+
+/// The desktop notification backends cannot remove a delivered notification.
+#[cfg(desktop)]
+fn remove_delivered(_app: &AppHandle, ids: Vec<i32>) {
+    debug!(
+        "[desktop:notify] Withdrawing {} notification(s) is not supported on this platform",
+        ids.len()
+    );
+}
@@ -0,0 +1,168 @@
+use std::{
+    collections::BTreeMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Completion notifications that arrived while a digest window was open.
+///
+/// The first completion notifies right away and opens the window; later ones inside it
+/// are held and delivered together once the window closes.
+#[derive(Default)]
+pub struct CompletionDigest {
+    state: Mutex<DigestState>,
+}
+
+#[derive(Default)]
+struct DigestState {
+    window_until: Option<Instant>,
+    /// Bumped every time a window opens, so a flush scheduled for an older window is a no-op.
+    generation: u64,
+    held: Vec<HeldCompletion>,
+}
+
+pub(super) struct HeldCompletion {
+    pub(super) session_id: String,
+    pub(super) agent: String,
+    /// Project directory the completion came from.
+    pub(super) directory: Option<String>,
+}
+
+pub(super) enum Admission {
+    /// Show this completion now.
+    Notify,
+    /// Held for the digest. The first hold in a window asks the caller to schedule a flush.
+    Held { flush: Option<(Instant, u64)> },
+}
+
+impl CompletionDigest {
+    pub(super) fn admit(
+        &self,
+        session_id: &str,
+        agent: &str,
+        directory: Option<&str>,
+        window: Duration,
+        now: Instant,
+    ) -> Admission {
+        let Ok(mut state) = self.state.lock() else {
+            return Admission::Notify;
+        };
+
+        match state.window_until {
+            Some(until) if now < until => {
+                state.held.push(HeldCompletion {
+                    session_id: session_id.to_string(),
+                    agent: agent.to_string(),
+                    directory: directory.map(str::to_string),
+                });
+                let flush = (state.held.len() == 1).then_some((until, state.generation));
+                Admission::Held { flush }
+            }
+            _ => {
+                state.window_until = Some(now + window);
+                state.generation = state.generation.wrapping_add(1);
+                Admission::Notify
+            }
+        }
+    }
+
+    /// Take held completions. With `generation`, only if that window is still current.
+    pub(super) fn take(&self, generation: Option<u64>) -> Vec<HeldCompletion> {
+        let Ok(mut state) = self.state.lock() else {
+            return Vec::new();
+        };
+        if generation.is_some_and(|generation| generation != state.generation) {
+            return Vec::new();
+        }
+        std::mem::take(&mut state.held)
+    }
+
+    /// Drop anything held and close the window.
+    pub(super) fn reset(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.held.clear();
+            state.window_until = None;
+        }
+    }
+}
+
+/// The project every held completion came from, or `None` when they span several or
+/// any came without a directory.
+pub(super) fn shared_directory(held: &[HeldCompletion]) -> Option<&str> {
+    let (first, rest) = held.split_first()?;
+    let directory = first.directory.as_deref()?;
+    rest.iter()
+        .all(|completion| completion.directory.as_deref() == Some(directory))
+        .then_some(directory)
+}
+
+/// "3 more agents finished (Build ×2, Plan ×1)"
+pub(super) fn digest_body(held: &[HeldCompletion]) -> String {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for completion in held {
+        *counts.entry(completion.agent.as_str()).or_default() += 1;
+    }
+    let breakdown = counts
+        .iter()
+        .map(|(agent, count)| format!("{agent} ×{count}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let noun = if held.len() == 1 { "agent" } else { "agents" };
+    format!("{} more {noun} finished ({breakdown})", held.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn held(directories: &[Option<&str>]) -> Vec<HeldCompletion> {
+        directories
+            .iter()
+            .enumerate()
+            .map(|(index, directory)| HeldCompletion {
+                session_id: format!("ses_{index}"),
+                agent: "Build".to_string(),
+                directory: directory.map(str::to_string),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shared_directory_cases() {
+        let cases = [
+            (vec![], None),
+            (vec![Some("/work/acme")], Some("/work/acme")),
+            (
+                vec![Some("/work/acme"), Some("/work/acme")],
+                Some("/work/acme"),
+            ),
+            (vec![Some("/work/acme"), Some("/work/widgets")], None),
+            (vec![Some("/work/acme"), None], None),
+            (vec![None, None], None),
+        ];
+        for (directories, expected) in cases {
+            assert_eq!(
+                shared_directory(&held(&directories)),
+                expected,
+                "{directories:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn held_completions_keep_their_directory() {
+        let digest = CompletionDigest::default();
+        let now = Instant::now();
+        let window = Duration::from_secs(10);
+        assert!(matches!(
+            digest.admit("ses_1", "Build", Some("/work/acme"), window, now),
+            Admission::Notify
+        ));
+        digest.admit("ses_2", "Plan", Some("/work/acme"), window, now);
+        digest.admit("ses_3", "Build", Some("/work/acme"), window, now);
+
+        let held = digest.take(None);
+        assert_eq!(held.len(), 2);
+        assert_eq!(shared_directory(&held), Some("/work/acme"));
+    }
+}
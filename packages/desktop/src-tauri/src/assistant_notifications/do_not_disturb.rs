@@ -0,0 +1,129 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+/// How long a detected state is reused. Notifications arrive in bursts, and each lookup
+/// spawns `gsettings` or parses the Focus database.
+const CACHE_TTL: Duration = Duration::from_secs(5);
+
+static CACHED_STATE: Lazy<Mutex<Option<(Instant, DoNotDisturbState)>>> =
+    Lazy::new(|| Mutex::new(None));
+
+/// The system-wide Do Not Disturb / Focus state, as far as this platform lets us see it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DoNotDisturbState {
+    Active,
+    Inactive,
+    /// The state can't be queried here; notifications behave as if it were off.
+    Unknown,
+}
+
+impl DoNotDisturbState {
+    pub(crate) fn is_active(self) -> bool {
+        self == DoNotDisturbState::Active
+    }
+}
+
+/// Query the platform off the async runtime; the lookups read files or spawn processes.
+/// A result younger than `CACHE_TTL` is returned without asking again.
+pub async fn do_not_disturb_state() -> DoNotDisturbState {
+    let cached = CACHED_STATE.lock().ok().and_then(|cached| {
+        cached
+            .filter(|(at, _)| at.elapsed() < CACHE_TTL)
+            .map(|(_, state)| state)
+    });
+    if let Some(state) = cached {
+        return state;
+    }
+
+    let state = tauri::async_runtime::spawn_blocking(detect)
+        .await
+        .unwrap_or(DoNotDisturbState::Unknown);
+    if let Ok(mut cached) = CACHED_STATE.lock() {
+        *cached = Some((Instant::now(), state));
+    }
+    state
+}
+
+/// Focus modes record their assertions in a per-user database. Reading it can need Full
+/// Disk Access, so a failed read means unknown rather than off.
+#[cfg(target_os = "macos")]
+fn detect() -> DoNotDisturbState {
+    let Some(home) = dirs::home_dir() else {
+        return DoNotDisturbState::Unknown;
+    };
+    let path = home.join("Library/DoNotDisturb/DB/Assertions.json");
+    let Some(assertions) = std::fs::read(&path)
+        .ok()
+        .and_then(|raw| serde_json::from_slice::<serde_json::Value>(&raw).ok())
+    else {
+        return DoNotDisturbState::Unknown;
+    };
+
+    let active = assertions
+        .get("data")
+        .and_then(serde_json::Value::as_array)
+        .is_some_and(|entries| {
+            entries.iter().any(|entry| {
+                entry
+                    .get("storeAssertionRecords")
+                    .and_then(serde_json::Value::as_array)
+                    .is_some_and(|records| !records.is_empty())
+            })
+        });
+    if active {
+        DoNotDisturbState::Active
+    } else {
+        DoNotDisturbState::Inactive
+    }
+}
+
+/// Only Focus Assist's quiet hours and presentation mode count. `QUNS_BUSY` and
+/// `QUNS_RUNNING_D3D_FULL_SCREEN` just mean some window is full screen, which covers a
+/// maximized video or game the user may well want to be interrupted from.
+#[cfg(target_os = "windows")]
+fn detect() -> DoNotDisturbState {
+    // QUERY_USER_NOTIFICATION_STATE values from shellapi.h.
+    const QUNS_PRESENTATION_MODE: i32 = 4;
+    const QUNS_QUIET_TIME: i32 = 6;
+
+    #[link(name = "shell32")]
+    extern "system" {
+        fn SHQueryUserNotificationState(state: *mut i32) -> i32;
+    }
+
+    let mut state = 0;
+    // SAFETY: the call only writes one i32 through the pointer we pass.
+    let result = unsafe { SHQueryUserNotificationState(&mut state) };
+    if result != 0 {
+        return DoNotDisturbState::Unknown;
+    }
+    match state {
+        QUNS_PRESENTATION_MODE | QUNS_QUIET_TIME => DoNotDisturbState::Active,
+        _ => DoNotDisturbState::Inactive,
+    }
+}
+
+/// GNOME exposes Do Not Disturb as the inverse of `show-banners`; other desktops have no
+/// common interface.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn detect() -> DoNotDisturbState {
+    let output = std::process::Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.notifications", "show-banners"])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            match String::from_utf8_lossy(&output.stdout).trim() {
+                "false" => DoNotDisturbState::Active,
+                "true" => DoNotDisturbState::Inactive,
+                _ => DoNotDisturbState::Unknown,
+            }
+        }
+        _ => DoNotDisturbState::Unknown,
+    }
+}
@@ -0,0 +1,106 @@
+use std::{collections::VecDeque, sync::Mutex};
+
+use chrono::Utc;
+use serde::Serialize;
+
+use super::preferences::NotificationCategory;
+
+const HISTORY_CAPACITY: usize = 200;
+
+/// Why a notification was not shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum SuppressionReason {
+    WindowFocused,
+    /// The session is open in a window the user is looking at.
+    SessionVisible,
+    CategoryDisabled,
+    ProjectMuted,
+    ProjectQuestionsOnly,
+    QuietHours,
+    /// The system Do Not Disturb or Focus mode was on.
+    DoNotDisturb,
+    /// The user muted the session.
+    Muted,
+    /// Held for a completion digest, which gets its own entry when delivered.
+    Digest,
+    /// The agent mode is filtered out by `notifications.modeFilter`.
+    ModeFiltered,
+    /// Background streams were paused.
+    Paused,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case", tag = "status", content = "reason")]
+pub(crate) enum NotificationOutcome {
+    Shown,
+    ShownSilently,
+    Failed(String),
+    Suppressed(SuppressionReason),
+    /// Delivered, but the user's shell hook for it failed.
+    HookFailed(String),
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationRecord {
+    /// Milliseconds since the Unix epoch.
+    timestamp: i64,
+    category: NotificationCategory,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session_id: Option<String>,
+    /// Missing when the notification was suppressed before its text was built.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    outcome: NotificationOutcome,
+}
+
+impl NotificationRecord {
+    pub(crate) fn new(
+        category: NotificationCategory,
+        session_id: Option<&str>,
+        text: Option<(&str, &str)>,
+        outcome: NotificationOutcome,
+    ) -> Self {
+        Self {
+            timestamp: Utc::now().timestamp_millis(),
+            category,
+            session_id: session_id.filter(|id| !id.is_empty()).map(str::to_string),
+            title: text.map(|(title, _)| title.to_string()),
+            body: text.map(|(_, body)| body.to_string()),
+            outcome,
+        }
+    }
+}
+
+/// The most recent notification decisions, shown or not, newest last.
+#[derive(Default)]
+pub struct NotificationHistory {
+    entries: Mutex<VecDeque<NotificationRecord>>,
+}
+
+impl NotificationHistory {
+    pub(crate) fn record(&self, record: NotificationRecord) {
+        if let Ok(mut entries) = self.entries.lock() {
+            if entries.len() == HISTORY_CAPACITY {
+                entries.pop_front();
+            }
+            entries.push_back(record);
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<NotificationRecord> {
+        self.entries
+            .lock()
+            .map(|entries| entries.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::{process::Stdio, sync::Arc, time::Duration};
+
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use tauri::{AppHandle, Manager};
+use tokio::{process::Command, sync::Semaphore};
+
+use super::history::{NotificationHistory, NotificationOutcome, NotificationRecord};
+use super::preferences::NotificationCategory;
+use crate::desktop_settings::HookSettings;
+
+/// A hook still running after this is killed.
+const HOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Hooks allowed to run at once. Events past the cap skip their hook rather than queue
+/// behind one that hangs.
+const MAX_RUNNING_HOOKS: usize = 4;
+/// How much of a failing hook's stderr ends up in the log and history.
+const HOOK_ERROR_LENGTH: usize = 200;
+
+static RUNNING_HOOKS: Lazy<Arc<Semaphore>> =
+    Lazy::new(|| Arc::new(Semaphore::new(MAX_RUNNING_HOOKS)));
+
+/// A delivered notification, as passed to the user's hook.
+pub(super) struct HookEvent<'a> {
+    pub(super) category: NotificationCategory,
+    pub(super) session_id: &'a str,
+    pub(super) directory: Option<&'a str>,
+    pub(super) title: &'a str,
+    pub(super) body: &'a str,
+}
+
+/// The `hooks` key for a category, for the categories hooks can be set for.
+fn event_name(category: NotificationCategory) -> Option<&'static str> {
+    match category {
+        NotificationCategory::AssistantCompleted => Some("completion"),
+        NotificationCategory::QuestionAsked | NotificationCategory::PermissionRequested => {
+            Some("question")
+        }
+        NotificationCategory::SessionError => Some("error"),
+        _ => None,
+    }
+}
+
+/// Start the hook configured for the event, if any, in the background. Notifications never
+/// wait on it; failures are logged and added to the notification history.
+pub(super) fn run(app: &AppHandle, hooks: &HookSettings, event: HookEvent<'_>) {
+    if !hooks.enabled {
+        return;
+    }
+    let Some(name) = event_name(event.category) else {
+        return;
+    };
+    let Some(command) = hooks.command(name) else {
+        return;
+    };
+    let Ok(permit) = RUNNING_HOOKS.clone().try_acquire_owned() else {
+        let error = format!("{name} hook skipped, {MAX_RUNNING_HOOKS} hooks are still running");
+        record_failure(
+            app,
+            event.category,
+            event.session_id,
+            (event.title, event.body),
+            error,
+        );
+        return;
+    };
+
+    let mut cmd = shell_command(command);
+    cmd.env("OPENCHAMBER_SESSION_ID", event.session_id)
+        .env("OPENCHAMBER_EVENT", name)
+        .env("OPENCHAMBER_DIRECTORY", event.directory.unwrap_or_default())
+        .env("OPENCHAMBER_TITLE", event.title)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(directory) = event.directory.filter(|directory| !directory.is_empty()) {
+        cmd.current_dir(directory);
+    }
+
+    let app = app.clone();
+    let category = event.category;
+    let session_id = event.session_id.to_string();
+    let title = event.title.to_string();
+    let body = event.body.to_string();
+    tauri::async_runtime::spawn(async move {
+        let result = wait_for(cmd).await;
+        drop(permit);
+        match result {
+            Ok(()) => debug!("[desktop:notify] {name} hook finished"),
+            Err(error) => record_failure(
+                &app,
+                category,
+                &session_id,
+                (&title, &body),
+                format!("{name} hook {error}"),
+            ),
+        }
+    });
+}
+
+fn shell_command(command: &str) -> Command {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd.exe".to_string(), "/C")
+    } else {
+        (
+            std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string()),
+            "-c",
+        )
+    };
+    let mut cmd = Command::new(shell);
+    cmd.arg(flag).arg(command);
+    cmd
+}
+
+/// Run the hook to completion, killing it at the timeout.
+async fn wait_for(mut cmd: Command) -> Result<(), String> {
+    let child = cmd
+        .spawn()
+        .map_err(|err| format!("failed to start: {err}"))?;
+    // Dropping the output future on timeout kills the child.
+    let output = tokio::time::timeout(HOOK_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("timed out after {}s", HOOK_TIMEOUT.as_secs()))?
+        .map_err(|err| format!("failed: {err}"))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stderr: String = stderr.trim().chars().take(HOOK_ERROR_LENGTH).collect();
+    let status = match output.status.code() {
+        Some(code) => format!("exited with status {code}"),
+        None => "was terminated by a signal".to_string(),
+    };
+    if stderr.is_empty() {
+        Err(status)
+    } else {
+        Err(format!("{status}: {stderr}"))
+    }
+}
+
+fn record_failure(
+    app: &AppHandle,
+    category: NotificationCategory,
+    session_id: &str,
+    text: (&str, &str),
+    error: String,
+) {
+    warn!("[desktop:notify] {error}");
+    app.state::<NotificationHistory>()
+        .record(NotificationRecord::new(
+            category,
+            Some(session_id),
+            Some(text),
+            NotificationOutcome::HookFailed(error),
+        ));
+}
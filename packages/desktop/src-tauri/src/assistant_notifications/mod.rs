@@ -0,0 +1,1968 @@
+mod active_session;
+mod compacting_sessions;
+mod context_window;
+mod delivered;
+mod digest;
+mod do_not_disturb;
+mod history;
+mod hooks;
+mod muted_sessions;
+mod pending_questions;
+mod preferences;
+mod question_reminders;
+mod quiet_hours;
+mod rate_limits;
+mod running_tools;
+mod server_status;
+mod session_titles;
+mod sounds;
+pub(crate) mod webhook;
+
+use std::{
+    path::Path,
+    sync::{Arc, Mutex as StdMutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use futures_util::TryStreamExt;
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+use tokio::{
+    io::AsyncBufReadExt,
+    sync::{broadcast, Mutex},
+};
+use tokio_util::io::StreamReader;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::compaction::{is_compacting_status, is_compaction_summary};
+use crate::connectivity::OFFLINE_RETRY;
+use crate::desktop_settings::{HookSettings, DEFAULT_STREAM_IDLE_TIMEOUT_SECS};
+use crate::event_stream::{
+    active_project_moved, auth_rejection, connect_event_stream, is_keepalive, sleep_unless_woken,
+    stream_idle, unparsed_prefix, wait_for_reauth, WakeReceiver,
+};
+use crate::events::SessionPayload;
+use crate::model_names::ModelNames;
+use crate::opencode_instances::{follow_project_instances, ProjectInstance};
+use crate::opencode_manager::{server_moved, with_auth, OpenCodeManager};
+use crate::power_events::power_state_changed;
+use crate::project_names::ProjectNames;
+use crate::recent_keys::RecentKeys;
+use crate::repeated_log::{RepeatedLog, ThrottledLog};
+use crate::secrets::WEBHOOK_SECRET;
+use crate::session_info::SessionInfoCache;
+use crate::settings_watcher::{next_settings_change, SettingsChanged};
+use crate::simulated_events::SimulatedEvents;
+use crate::task_registry::{ChildTask, TaskHandle};
+use crate::telemetry::ReconnectReason;
+use crate::DesktopRuntime;
+use active_session::should_notify;
+use context_window::check_context_window;
+use delivered::{withdraw_answered_questions, withdraw_stale_completions};
+use digest::{digest_body, shared_directory, Admission};
+use hooks::HookEvent;
+use pending_questions::seed_pending_questions;
+use preferences::load_notification_preferences;
+use question_reminders::{request_attention, request_question_attention};
+use quiet_hours::{local_now, QuietHours, QuietHoursDecision};
+use rate_limits::handle_session_status;
+use running_tools::ToolRun;
+use session_titles::session_title;
+use webhook::{Webhook, WebhookPayload};
+
+pub use active_session::ActiveSessions;
+pub use compacting_sessions::CompactingSessions;
+pub use context_window::ContextWindows;
+pub use delivered::DeliveredNotifications;
+pub use do_not_disturb::{do_not_disturb_state, DoNotDisturbState};
+pub use history::NotificationHistory;
+pub(crate) use history::{NotificationOutcome, NotificationRecord, SuppressionReason};
+pub use muted_sessions::{mute_session, unmute_session, MutedSession, MutedSessions};
+pub use pending_questions::{
+    pending_question_details, sync_question_badge, PendingQuestionDetails, PendingQuestions,
+};
+pub(crate) use preferences::NotificationCategory;
+pub use question_reminders::{cancel_attention, QuestionReminders};
+pub use quiet_hours::QuietHoursBacklog;
+pub use rate_limits::RateLimits;
+pub use running_tools::RunningTools;
+pub use server_status::{
+    notify_port_conflict, notify_server_running, notify_server_stopped, notify_server_unreachable,
+    ServerStatusNotifier,
+};
+pub(crate) use sounds::{available_sounds, configured_sound, resolve_sound, SoundKind};
+
+pub use digest::CompletionDigest;
+
+/// Upper bound on a single wait for quiet hours to end, in case a settings edit is missed.
+const QUIET_HOURS_RECHECK: Duration = Duration::from_secs(15 * 60);
+/// Do Not Disturb changes without notice, so poll it while notifications are held.
+const DO_NOT_DISTURB_RECHECK: Duration = Duration::from_secs(60);
+const ERROR_SUMMARY_LENGTH: usize = 120;
+const PERMISSION_DETAIL_LENGTH: usize = 120;
+const QUESTION_TEXT_LENGTH: usize = 160;
+const QUESTION_OPTIONS_SHOWN: usize = 3;
+const TOOL_DETAIL_LENGTH: usize = 120;
+const MESSAGE_FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+static MARKDOWN_LINK: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").expect("valid regex"));
+static MARKDOWN_LINE_PREFIX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^\s*(?:#{1,6}\s+|>\s?|[-*+]\s+|\d+[.)]\s+)").expect("valid regex")
+});
+static MARKDOWN_INLINE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"```[^\n]*|[`*~]+").expect("valid regex"));
+static MARKDOWN_UNDERSCORE_EMPHASIS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b_{1,2}([^_\n]+?)_{1,2}\b").expect("valid regex"));
+
+#[derive(Deserialize)]
+struct EventEnvelope {
+    #[serde(rename = "type")]
+    event_type: String,
+    #[serde(default)]
+    properties: Value,
+    /// Project directory the event belongs to, when it arrived through the global stream.
+    #[serde(skip)]
+    directory: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct MultiplexedEventEnvelope {
+    #[serde(default)]
+    directory: Option<String>,
+    payload: EventEnvelope,
+}
+
+/// Where to look up message details that are not part of the event itself.
+struct OpenCodeApi<'a> {
+    client: &'a Client,
+    base: &'a str,
+    /// The server's auth token, sent with every lookup.
+    token: Option<&'a str>,
+}
+
+impl OpenCodeApi<'_> {
+    fn get(&self, url: &str) -> RequestBuilder {
+        with_auth(self.client.get(url), self.token)
+    }
+}
+
+/// How long after a notification is shown a window activation is treated as a click on it.
+const NOTIFICATION_ACTIVATION_WINDOW: Duration = Duration::from_secs(30);
+
+/// Session of the most recently shown notification.
+///
+/// The notification plugin does not report clicks on desktop, so the main window gaining
+/// focus shortly after a notification is taken as the user activating it.
+#[derive(Default)]
+pub struct NotificationActivation {
+    last_shown: StdMutex<Option<(String, Instant)>>,
+}
+
+impl NotificationActivation {
+    fn record(&self, session_id: &str) {
+        if let Ok(mut last_shown) = self.last_shown.lock() {
+            *last_shown = Some((session_id.to_string(), Instant::now()));
+        }
+    }
+
+    fn take_recent(&self) -> Option<String> {
+        let mut last_shown = self.last_shown.lock().ok()?;
+        match last_shown.take() {
+            Some((session_id, shown_at))
+                if shown_at.elapsed() <= NOTIFICATION_ACTIVATION_WINDOW =>
+            {
+                Some(session_id)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Called when the main window gains focus. If a notification was shown recently, bring the
+/// window forward and ask the UI to open the session it was about.
+pub fn handle_window_activated(app: &AppHandle) {
+    // The user is looking at the app now, so held completions are no longer news.
+    app.state::<CompletionDigest>().reset();
+    withdraw_stale_completions(app);
+
+    let Some(session_id) = app.state::<NotificationActivation>().take_recent() else {
+        return;
+    };
+
+    if let Some(window) = app.get_webview_window("main") {
+        if window.is_minimized().unwrap_or(false) {
+            let _ = window.unminimize();
+        }
+        let _ = window.set_focus();
+    }
+
+    let _ = app.emit(
+        "openchamber:navigate-session",
+        SessionPayload {
+            session_id: &session_id,
+        },
+    );
+}
+
+/// A notification about a session, ready to be shown.
+struct SessionNotification<'a> {
+    category: NotificationCategory,
+    session_id: &'a str,
+    /// Project directory the event came from, forwarded to the webhook.
+    directory: Option<&'a str>,
+    title: String,
+    body: String,
+    sound: SoundKind,
+    /// How many events this stands for; a digest covers several completions.
+    count: usize,
+}
+
+/// Returns true if the notification reached the OS.
+async fn show_session_notification(app: &AppHandle, notification: SessionNotification<'_>) -> bool {
+    let delivery = if app
+        .state::<MutedSessions>()
+        .is_muted(notification.session_id)
+    {
+        Err(SuppressionReason::Muted)
+    } else {
+        apply_delivery_rules(
+            app,
+            notification.category,
+            notification.count,
+            Some(notification.session_id),
+        )
+        .await
+    };
+    let with_sound = match delivery {
+        Ok(with_sound) => with_sound,
+        Err(reason) => {
+            app.state::<NotificationHistory>()
+                .record(NotificationRecord::new(
+                    notification.category,
+                    Some(notification.session_id),
+                    Some((&notification.title, &notification.body)),
+                    NotificationOutcome::Suppressed(reason),
+                ));
+            return false;
+        }
+    };
+    if let Some(webhook) = load_webhook(app).await {
+        let project = notification
+            .directory
+            .and_then(|directory| app.state::<ProjectNames>().display_name(directory));
+        webhook::forward(
+            app.state::<DesktopRuntime>().http().api(),
+            webhook,
+            &WebhookPayload::new(
+                notification.category,
+                notification.session_id,
+                &notification.title,
+                &notification.body,
+                notification.directory,
+                project,
+            ),
+        );
+    }
+    hooks::run(
+        app,
+        &load_hooks(app).await,
+        HookEvent {
+            category: notification.category,
+            session_id: notification.session_id,
+            directory: notification.directory,
+            title: &notification.title,
+            body: &notification.body,
+        },
+    );
+
+    let sound = if with_sound {
+        configured_sound(app, notification.sound).await
+    } else {
+        None
+    };
+    let outcome = show_notification(
+        app,
+        notification.category,
+        notification.session_id,
+        notification.title,
+        notification.body,
+        sound,
+    );
+    matches!(
+        outcome,
+        NotificationOutcome::Shown | NotificationOutcome::ShownSilently
+    )
+}
+
+fn show_notification(
+    app: &AppHandle,
+    category: NotificationCategory,
+    session_id: &str,
+    title: String,
+    body: String,
+    sound: Option<&str>,
+) -> NotificationOutcome {
+    let delivered = app.state::<DeliveredNotifications>();
+    let id = delivered.next_id();
+    let mut builder = app
+        .notification()
+        .builder()
+        .id(id)
+        .title(title.clone())
+        .body(body.clone());
+    if let Some(sound) = sound {
+        builder = builder.sound(sound);
+    }
+    let result = builder.show();
+    if result.is_ok() {
+        delivered.record(id, category, session_id);
+        if !session_id.is_empty() {
+            app.state::<NotificationActivation>().record(session_id);
+        }
+    }
+    app.state::<DesktopRuntime>()
+        .telemetry()
+        .record_notification(&result);
+
+    let outcome = match &result {
+        Ok(_) if sound.is_some() => NotificationOutcome::Shown,
+        Ok(_) => NotificationOutcome::ShownSilently,
+        Err(err) => NotificationOutcome::Failed(err.to_string()),
+    };
+    app.state::<NotificationHistory>()
+        .record(NotificationRecord::new(
+            category,
+            Some(session_id),
+            Some((&title, &body)),
+            outcome.clone(),
+        ));
+    outcome
+}
+
+/// What happened at each step of a test notification, for diagnosing reports that
+/// notifications don't arrive.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestNotificationReport {
+    /// The OS permission state: `granted`, `denied`, or `prompt`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    permission: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    permission_error: Option<String>,
+    do_not_disturb: DoNotDisturbState,
+    /// The sound the completion slot resolved to; `None` when it is set to `none`.
+    sound: Option<&'static str>,
+    /// The builder result, including the platform error when showing failed.
+    outcome: NotificationOutcome,
+}
+
+/// Show a completion notification through the same path as real ones, skipping only the
+/// quiet hours and Do Not Disturb hold so the user sees it right away.
+pub(crate) async fn show_test_notification(app: &AppHandle) -> TestNotificationReport {
+    let (permission, permission_error) = match app.notification().permission_state() {
+        Ok(state) => (Some(state.to_string()), None),
+        Err(err) => (None, Some(err.to_string())),
+    };
+    let do_not_disturb = do_not_disturb_state().await;
+    let preferences = load_notification_preferences(app, None).await;
+    let sound = configured_sound(app, SoundKind::Completion).await;
+
+    let outcome = show_notification(
+        app,
+        NotificationCategory::AssistantCompleted,
+        "",
+        preferences.title("Test notification"),
+        "Notifications from OpenChamber are working".to_string(),
+        sound,
+    );
+    if let NotificationOutcome::Failed(err) = &outcome {
+        warn!("[desktop:notify] Test notification failed: {err}");
+    }
+
+    TestNotificationReport {
+        permission,
+        permission_error,
+        do_not_disturb,
+        sound,
+        outcome,
+    }
+}
+
+fn record_suppressed(
+    app: &AppHandle,
+    category: NotificationCategory,
+    session_id: &str,
+    reason: SuppressionReason,
+) {
+    app.state::<NotificationHistory>()
+        .record(NotificationRecord::new(
+            category,
+            Some(session_id),
+            None,
+            NotificationOutcome::Suppressed(reason),
+        ));
+}
+
+/// Check quiet hours and the system Do Not Disturb state before showing a notification.
+/// Returns whether to play a sound, or why the notification was held for the summary
+/// shown once both are over.
+pub(crate) async fn apply_delivery_rules(
+    app: &AppHandle,
+    category: NotificationCategory,
+    count: usize,
+    session_id: Option<&str>,
+) -> Result<bool, SuppressionReason> {
+    // Not held for later either: the user asked for nothing to happen in the background.
+    if app.state::<DesktopRuntime>().is_paused() {
+        return Err(SuppressionReason::Paused);
+    }
+    let quiet_hours = load_quiet_hours(app).await;
+    let decision = quiet_hours
+        .as_ref()
+        .map(|quiet_hours| quiet_hours.decide(local_now()))
+        .unwrap_or(QuietHoursDecision::Deliver);
+
+    let reason = match decision {
+        QuietHoursDecision::Hold => SuppressionReason::QuietHours,
+        // The OS would swallow the banner, and some configurations still play the sound.
+        _ if do_not_disturb_state().await.is_active() => SuppressionReason::DoNotDisturb,
+        QuietHoursDecision::Deliver => return Ok(true),
+        QuietHoursDecision::DeliverSilently => return Ok(false),
+    };
+
+    if app
+        .state::<QuietHoursBacklog>()
+        .hold(category, count, session_id)
+    {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            flush_held_notifications(&app).await;
+        });
+    }
+    Err(reason)
+}
+
+async fn load_quiet_hours(app: &AppHandle) -> Option<QuietHours> {
+    let settings = app.state::<DesktopRuntime>().settings().load().await.ok()?;
+    QuietHours::from_settings(&settings)
+}
+
+async fn load_webhook(app: &AppHandle) -> Option<Webhook> {
+    let runtime = app.state::<DesktopRuntime>();
+    let settings = runtime.settings().load().await.ok()?;
+    let secret = runtime
+        .secrets()
+        .get(WEBHOOK_SECRET)
+        .await
+        .unwrap_or_else(|err| {
+            debug!("[desktop:notify] Failed to read webhook secret: {err}");
+            None
+        });
+    Webhook::from_settings(&settings, secret)
+}
+
+async fn load_hooks(app: &AppHandle) -> HookSettings {
+    app.state::<DesktopRuntime>()
+        .settings()
+        .load_typed()
+        .await
+        .map(|settings| settings.hooks)
+        .unwrap_or_default()
+}
+
+/// Wait for quiet hours and Do Not Disturb to end, re-reading settings whenever they
+/// change so edits take effect, then summarize what was held.
+async fn flush_held_notifications(app: &AppHandle) {
+    let mut settings_changes = app.state::<DesktopRuntime>().subscribe_settings_changes();
+    loop {
+        let now = local_now();
+        let remaining = match load_quiet_hours(app).await {
+            Some(quiet_hours) if quiet_hours.is_active_at(now) => (quiet_hours.next_end_after(now)
+                - now)
+                .to_std()
+                .unwrap_or_default(),
+            _ if do_not_disturb_state().await.is_active() => DO_NOT_DISTURB_RECHECK,
+            _ => break,
+        };
+        tokio::select! {
+            _ = tokio::time::sleep(remaining.clamp(Duration::from_secs(1), QUIET_HOURS_RECHECK)) => {}
+            _ = notification_settings_changed(&mut settings_changes) => {}
+        }
+    }
+
+    if let Some((summary, last_session)) = app.state::<QuietHoursBacklog>().take_summary() {
+        show_notification(
+            app,
+            NotificationCategory::Other,
+            last_session.as_deref().unwrap_or_default(),
+            "Missed notifications".to_string(),
+            summary,
+            configured_sound(app, SoundKind::Completion).await,
+        );
+    }
+}
+
+async fn notification_settings_changed(changes: &mut broadcast::Receiver<SettingsChanged>) {
+    while !next_settings_change(changes).await.notifications_changed() {}
+}
+
+pub fn spawn_assistant_notifications(
+    app: AppHandle,
+    runtime: DesktopRuntime,
+) -> tauri::async_runtime::JoinHandle<()> {
+    let tasks = runtime.tasks().clone();
+    let shutdown_rx = runtime.subscribe_shutdown();
+    tasks.supervise(
+        app.clone(),
+        "assistant-notifications",
+        shutdown_rx,
+        move |task| run_assistant_notifications(app.clone(), runtime.clone(), task),
+    )
+}
+
+async fn run_assistant_notifications(app: AppHandle, runtime: DesktopRuntime, task: TaskHandle) {
+    let mut shutdown_rx = runtime.subscribe_shutdown();
+    let seen = Arc::new(SeenEvents::default());
+    let opencode = runtime.opencode_manager();
+
+    // In multi-instance mode every project server gets a stream of its own.
+    let start_project_stream = {
+        let app = app.clone();
+        let runtime = runtime.clone();
+        let seen = seen.clone();
+        move |instance| {
+            tauri::async_runtime::spawn(run_project_stream(
+                app.clone(),
+                runtime.clone(),
+                seen.clone(),
+                instance,
+            ))
+        }
+    };
+    let projects = ChildTask::spawn(follow_project_instances(
+        runtime.opencode_instances(),
+        start_project_stream,
+    ));
+    let simulated = ChildTask::spawn(follow_simulated_events(
+        app.clone(),
+        runtime.clone(),
+        seen.clone(),
+    ));
+    // While the server is down every reconnect fails the same way.
+    let mut loop_errors = RepeatedLog::default();
+    let mut wake = runtime.subscribe_stream_wake();
+
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.recv() => {
+                info!("[desktop:notify] Shutdown received, stopping SSE listener");
+                drop(projects);
+                drop(simulated);
+                flush_completion_digest(&app, None).await;
+                break;
+            }
+            _ = async {
+                task.heartbeat();
+                match run_once(&app, &runtime, &opencode, None, &seen, &wake).await {
+                    Ok(()) => loop_errors.settle(|line| warn!("{line}")),
+                    Err(err) => loop_errors.record(
+                        format!("[desktop:notify] SSE loop error: {err:?}"),
+                        |line| warn!("{line}"),
+                    ),
+                }
+                sleep_unless_woken(&mut wake, Duration::from_secs(2)).await;
+            } => {}
+        }
+    }
+}
+
+/// Events already notified about, shared by the streams of every OpenCode instance.
+#[derive(Default)]
+struct SeenEvents {
+    messages: Mutex<RecentKeys>,
+    questions: Mutex<RecentKeys>,
+    errors: Mutex<RecentKeys>,
+    permissions: Mutex<RecentKeys>,
+}
+
+/// Listen to one project instance until it is stopped.
+async fn run_project_stream(
+    app: AppHandle,
+    runtime: DesktopRuntime,
+    seen: Arc<SeenEvents>,
+    instance: ProjectInstance,
+) {
+    let directory = instance.directory.to_string_lossy().to_string();
+    let mut loop_errors = RepeatedLog::default();
+    let mut wake = runtime.subscribe_stream_wake();
+    while !instance.manager.is_shutting_down() {
+        let result = run_once(
+            &app,
+            &runtime,
+            &instance.manager,
+            Some(&directory),
+            &seen,
+            &wake,
+        )
+        .await;
+        match result {
+            Ok(()) => loop_errors.settle(|line| warn!("{line}")),
+            Err(err) => loop_errors.record(
+                format!("[desktop:notify] SSE loop error for {directory}: {err:?}"),
+                |line| warn!("{line}"),
+            ),
+        }
+        sleep_unless_woken(&mut wake, Duration::from_secs(2)).await;
+    }
+}
+
+/// Follow one server's event stream until it ends. `directory` is the project of a
+/// dedicated instance; events without a directory of their own are attributed to it.
+async fn run_once(
+    app: &AppHandle,
+    runtime: &DesktopRuntime,
+    opencode: &OpenCodeManager,
+    directory: Option<&str>,
+    seen: &SeenEvents,
+    wake: &WakeReceiver,
+) -> Result<()> {
+    runtime.wait_until_awake().await;
+    runtime.wait_until_resumed().await;
+    let mut power = runtime.subscribe_power();
+    // Picked up per connection so proxy changes apply on the next reconnect.
+    let client = runtime.http().streaming();
+    let api_client = runtime.http().api();
+    let mut auth_token = opencode.subscribe_auth_token();
+    let mut status = opencode.subscribe_status();
+    let mut settings_changes = runtime.subscribe_settings_changes();
+    let base = status.borrow_and_update().base_url();
+    let Some(base) = base else {
+        info!("[desktop:notify] OpenCode not running; waiting for it to start");
+        runtime.wait_for_opencode_change(&mut status, wake).await;
+        return Ok(());
+    };
+    if runtime.is_offline_for(&base) && !runtime.wait_until_online(OFFLINE_RETRY).await {
+        return Ok(());
+    }
+    let connected = connect_event_stream(
+        runtime,
+        opencode,
+        &client,
+        &base,
+        directory.map(Path::new),
+        "[desktop:notify]",
+    )
+    .await;
+    let (response, scope) = match connected {
+        Ok(connected) => connected,
+        // The activity tracker tells the UI; this stream just waits alongside it.
+        Err(err) if auth_rejection(&err).is_some() => {
+            debug!("[desktop:notify] {err}; waiting for new credentials");
+            wait_for_reauth(
+                runtime,
+                &mut auth_token,
+                &mut status,
+                &mut settings_changes,
+                &base,
+            )
+            .await;
+            return Ok(());
+        }
+        Err(err) => {
+            runtime
+                .telemetry()
+                .record_reconnect(ReconnectReason::ConnectFailed);
+            return Err(err);
+        }
+    };
+    // The server may have been reconfigured while the stream was down.
+    app.state::<ModelNames>().invalidate();
+    let token = opencode.auth_token();
+    // Questions asked while the stream was down sent no event this stream will see.
+    seed_pending_questions(
+        app,
+        &OpenCodeApi {
+            client: &api_client,
+            base: &base,
+            token: token.as_deref(),
+        },
+        directory,
+    )
+    .await;
+
+    let stream = response
+        .bytes_stream()
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err));
+    let mut reader = StreamReader::new(stream);
+    let mut buf = Vec::new();
+    let mut data_lines: Vec<String> = Vec::new();
+    let mut unparsed = ThrottledLog::default();
+    let idle_timeout = runtime
+        .settings()
+        .load_typed()
+        .await
+        .map(|settings| settings.events.notify_idle_timeout())
+        .unwrap_or(Some(Duration::from_secs(DEFAULT_STREAM_IDLE_TIMEOUT_SECS)));
+
+    loop {
+        buf.clear();
+        let read = tokio::select! {
+            read = reader.read_until(b'\n', &mut buf) => read,
+            _ = runtime.wait_until_paused() => {
+                info!("[desktop:notify] Background streams paused; disconnecting SSE");
+                return Ok(());
+            }
+            _ = stream_idle(idle_timeout) => {
+                info!("[desktop:notify] SSE stream went silent; reconnecting");
+                runtime
+                    .telemetry()
+                    .record_reconnect(ReconnectReason::IdleTimeout);
+                return Ok(());
+            }
+            _ = server_moved(&mut status, &base) => {
+                info!("[desktop:notify] OpenCode server changed; reconnecting SSE");
+                runtime
+                    .telemetry()
+                    .record_reconnect(ReconnectReason::ServerChanged);
+                return Ok(());
+            }
+            _ = active_project_moved(&mut settings_changes, &scope), if directory.is_none() => {
+                info!("[desktop:notify] Active project changed; reconnecting SSE");
+                runtime
+                    .telemetry()
+                    .record_reconnect(ReconnectReason::DirectoryChanged);
+                return Ok(());
+            }
+            _ = power_state_changed(&mut power) => {
+                info!("[desktop:notify] System sleep state changed; reconnecting SSE");
+                return Ok(());
+            }
+        };
+        let bytes_read = match read {
+            Ok(n) => n,
+            Err(err) => {
+                warn!("[desktop:notify] Read error in SSE stream: {err:?}");
+                runtime
+                    .telemetry()
+                    .record_reconnect(ReconnectReason::ReadError);
+                return Err(err.into());
+            }
+        };
+        if bytes_read == 0 {
+            runtime
+                .telemetry()
+                .record_reconnect(ReconnectReason::StreamEnded);
+            break;
+        }
+
+        let line = match std::str::from_utf8(&buf) {
+            Ok(s) => s.trim_end_matches(&['\r', '\n'][..]).to_string(),
+            Err(err) => {
+                warn!("[desktop:notify] Non-UTF8 SSE chunk: {err}");
+                continue;
+            }
+        };
+
+        if line.is_empty() {
+            if data_lines.is_empty() {
+                continue;
+            }
+            let raw = data_lines.join("\n");
+            data_lines.clear();
+
+            match parse_event_envelope(&raw) {
+                // The activity tracker counts keepalives; every stream sees the same ones.
+                Ok(None) => {}
+                Ok(Some(mut event)) => {
+                    if event.directory.is_none() {
+                        event.directory = directory.map(str::to_string);
+                    }
+                    runtime.sse_events().record(
+                        "notifications",
+                        &event.event_type,
+                        event.directory.as_deref(),
+                        &event.properties,
+                    );
+                    let api = OpenCodeApi {
+                        client: &api_client,
+                        base: &base,
+                        token: token.as_deref(),
+                    };
+                    handle_event(
+                        app,
+                        &api,
+                        event,
+                        &seen.messages,
+                        &seen.questions,
+                        &seen.errors,
+                        &seen.permissions,
+                    )
+                    .await
+                }
+                Err(err) => {
+                    runtime.telemetry().record_parse_failure();
+                    unparsed.record(
+                        format!(
+                            "[desktop:notify] Failed to parse SSE data: {err}; raw={}",
+                            unparsed_prefix(&raw)
+                        ),
+                        |line| warn!("{line}"),
+                    );
+                }
+            }
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("data:") {
+            data_lines.push(rest.trim_start().to_string());
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether `raw` parses the way a streamed event must, for `simulate_desktop_event`.
+pub(crate) fn validate_event(raw: &str) -> Result<()> {
+    parse_event_envelope(raw)?
+        .map(|_| ())
+        .ok_or_else(|| anyhow!("A keepalive carries no event"))
+}
+
+/// Handle simulated events the way `run_once` handles streamed ones, sharing its record
+/// of what was already notified. Replays skip this listener unless asked to notify.
+async fn follow_simulated_events(app: AppHandle, runtime: DesktopRuntime, seen: Arc<SeenEvents>) {
+    let mut events = app.state::<SimulatedEvents>().subscribe();
+    let api_client = runtime.http().api();
+    loop {
+        let raw = match events.recv().await {
+            Ok(event) if event.notify => event.raw,
+            Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        // Checked before the event was sent.
+        let Ok(Some(event)) = parse_event_envelope(&raw) else {
+            continue;
+        };
+        debug!("[desktop:notify] Simulated {}", event.event_type);
+        // Lookups such as session titles go to the server when one is running.
+        let opencode = runtime.opencode_manager();
+        let base = opencode
+            .subscribe_status()
+            .borrow()
+            .base_url()
+            .unwrap_or_default();
+        let token = opencode.auth_token();
+        let api = OpenCodeApi {
+            client: &api_client,
+            base: &base,
+            token: token.as_deref(),
+        };
+        handle_event(
+            &app,
+            &api,
+            event,
+            &seen.messages,
+            &seen.questions,
+            &seen.errors,
+            &seen.permissions,
+        )
+        .await;
+    }
+}
+
+/// `None` for a keepalive frame, which carries no event.
+fn parse_event_envelope(raw: &str) -> Result<Option<EventEnvelope>> {
+    if is_keepalive(raw) {
+        return Ok(None);
+    }
+    if let Ok(event) = serde_json::from_str::<EventEnvelope>(raw) {
+        return Ok(Some(event));
+    }
+
+    let multiplexed = serde_json::from_str::<MultiplexedEventEnvelope>(raw)?;
+    let mut event = multiplexed.payload;
+    event.directory = multiplexed.directory;
+    Ok(Some(event))
+}
+
+async fn handle_event(
+    app: &AppHandle,
+    api: &OpenCodeApi<'_>,
+    event: EventEnvelope,
+    notified_messages: &Mutex<RecentKeys>,
+    notified_questions: &Mutex<RecentKeys>,
+    notified_errors: &Mutex<RecentKeys>,
+    notified_permissions: &Mutex<RecentKeys>,
+) {
+    let directory = event.directory.as_deref();
+    match event.event_type.as_str() {
+        // `permission.updated` is the older name for the same ask event.
+        "permission.asked" | "permission.updated" => {
+            handle_permission_asked(app, &event.properties, directory, notified_permissions).await;
+        }
+        "permission.replied" => {
+            handle_permission_replied(&event.properties, notified_permissions).await;
+        }
+        "message.updated" if is_failed_message(&event.properties) => {
+            handle_message_failed(app, &event.properties, directory, notified_errors).await;
+        }
+        "session.error" => {
+            handle_session_error(app, &event.properties, directory, notified_errors).await;
+        }
+        "message.updated" => {
+            check_context_window(app, api, &event.properties, directory).await;
+            handle_message_updated(app, api, &event.properties, directory, notified_messages).await;
+        }
+        "question.asked" => {
+            track_question_asked(app, &event.properties, directory);
+            handle_question_asked(app, api, &event.properties, directory, notified_questions).await;
+        }
+        "question.replied" | "question.answered" | "question.rejected" => {
+            track_question_resolved(app, &event.properties);
+        }
+        "message.part.updated" => {
+            handle_tool_part_updated(app, &event.properties, directory).await;
+        }
+        "session.status" => {
+            track_compaction(app, api, &event.properties, notified_messages).await;
+            handle_session_status(app, api, &event.properties, directory).await;
+        }
+        "session.idle" => {
+            if let Some(session_id) = event.properties.get("sessionID").and_then(Value::as_str) {
+                app.state::<RunningTools>().finish_session(session_id);
+                app.state::<RateLimits>().finish(session_id);
+            }
+            track_compaction(app, api, &event.properties, notified_messages).await;
+        }
+        "session.updated" => {
+            if let Some(info) = event.properties.get("info") {
+                app.state::<SessionInfoCache>().update_from_event(info);
+            }
+        }
+        "session.deleted" => {
+            let session_id = event
+                .properties
+                .get("info")
+                .and_then(|info| info.get("id"))
+                .and_then(Value::as_str);
+            if let Some(session_id) = session_id {
+                app.state::<RunningTools>().finish_session(session_id);
+                app.state::<SessionInfoCache>().remove(session_id);
+                app.state::<ContextWindows>().remove(session_id);
+                app.state::<RateLimits>().finish(session_id);
+                app.state::<CompactingSessions>().finish(session_id);
+                if app.state::<PendingQuestions>().remove_session(session_id) {
+                    sync_question_badge(app);
+                }
+                withdraw_answered_questions(app, session_id);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn track_question_asked(app: &AppHandle, properties: &Value, directory: Option<&str>) {
+    let pending = app.state::<PendingQuestions>();
+    let pruned = pending.prune_stale();
+    if pending.add(properties, directory) || pruned {
+        sync_question_badge(app);
+    }
+}
+
+/// A question was answered or rejected. Replies name the question `requestID`; some
+/// servers send `id`, and some leave out `sessionID`, in which case the session is taken
+/// from the pending entry.
+fn track_question_resolved(app: &AppHandle, properties: &Value) {
+    let Some(question_id) = ["requestID", "id"]
+        .iter()
+        .find_map(|key| properties.get(*key).and_then(Value::as_str))
+    else {
+        return;
+    };
+    app.state::<QuestionReminders>().cancel(question_id);
+    let pending = app.state::<PendingQuestions>();
+    let removed = pending.remove(question_id);
+    if removed.is_some() {
+        sync_question_badge(app);
+    }
+    let session_id = properties
+        .get("sessionID")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .or(removed);
+    if let Some(session_id) = session_id {
+        if !pending.has_session(&session_id) {
+            withdraw_answered_questions(app, &session_id);
+        }
+    }
+}
+
+/// Follow compaction from status events. When a session that was compacting goes idle,
+/// no reply followed the compaction, and the completion held meanwhile is notified; when
+/// it goes back to work, the reply that finishes the turn will notify instead.
+async fn track_compaction(
+    app: &AppHandle,
+    api: &OpenCodeApi<'_>,
+    properties: &Value,
+    notified_messages: &Mutex<RecentKeys>,
+) {
+    let Some(session_id) = properties.get("sessionID").and_then(Value::as_str) else {
+        return;
+    };
+    let compacting = app.state::<CompactingSessions>();
+    if is_compacting_status(properties) {
+        compacting.start(session_id);
+        return;
+    }
+    let Some(held) = compacting.finish(session_id) else {
+        return;
+    };
+    let busy = properties
+        .get("status")
+        .and_then(|status| status.get("type"))
+        .and_then(Value::as_str)
+        .is_some_and(|status_type| status_type != "idle");
+    if !busy {
+        handle_message_updated(
+            app,
+            api,
+            &held.properties,
+            held.directory.as_deref(),
+            notified_messages,
+        )
+        .await;
+    }
+}
+
+async fn handle_question_asked(
+    app: &AppHandle,
+    api: &OpenCodeApi<'_>,
+    properties: &Value,
+    directory: Option<&str>,
+    notified_questions: &Mutex<RecentKeys>,
+) {
+    let session_id = properties.get("sessionID").and_then(Value::as_str);
+    let question_id = properties.get("id").and_then(Value::as_str);
+
+    let (session_id, question_id) = match (session_id, question_id) {
+        (Some(s), Some(q)) => (s, q),
+        _ => return,
+    };
+
+    let key = format!("{}:{}", session_id, question_id);
+    {
+        let mut notified = notified_questions.lock().await;
+        if notified.contains(&key) {
+            return;
+        }
+        notified.insert(key);
+    }
+
+    let preferences = load_notification_preferences(app, directory).await;
+    if let Some(reason) = preferences.suppression(NotificationCategory::QuestionAsked) {
+        record_suppressed(app, NotificationCategory::QuestionAsked, session_id, reason);
+        return;
+    }
+
+    if let Err(reason) = should_notify(app, preferences.when_focused, Some(session_id)) {
+        record_suppressed(app, NotificationCategory::QuestionAsked, session_id, reason);
+        return;
+    }
+
+    let body = question_body(properties)
+        .unwrap_or_else(|| "Agent is waiting for your response".to_string());
+    let session_title = session_title(app, api, session_id, directory).await;
+    let title = match &session_title {
+        Some(session_title) => format!("Input needed: '{session_title}'"),
+        None => "Input needed".to_string(),
+    };
+    let shown = show_session_notification(
+        app,
+        SessionNotification {
+            category: NotificationCategory::QuestionAsked,
+            session_id,
+            directory,
+            title: preferences.title(title),
+            body: body.clone(),
+            sound: SoundKind::Question,
+            count: 1,
+        },
+    )
+    .await;
+    if shown && preferences.request_attention_on_question {
+        request_question_attention(app);
+    }
+
+    let delay = preferences.question_reminder_delay;
+    if !shown || delay.is_zero() || !app.state::<QuestionReminders>().schedule(question_id) {
+        return;
+    }
+    let app = app.clone();
+    let session_id = session_id.to_string();
+    let question_id = question_id.to_string();
+    let directory = directory.map(str::to_string);
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(delay).await;
+        let question = UnansweredQuestion {
+            session_id: &session_id,
+            question_id: &question_id,
+            directory: directory.as_deref(),
+            session_title: session_title.as_deref(),
+            body,
+        };
+        remind_unanswered_question(&app, question).await;
+    });
+}
+
+/// A shown question notification, kept for the reminder.
+struct UnansweredQuestion<'a> {
+    session_id: &'a str,
+    question_id: &'a str,
+    directory: Option<&'a str>,
+    session_title: Option<&'a str>,
+    body: String,
+}
+
+/// Re-notify once about a question that is still pending after the reminder delay.
+async fn remind_unanswered_question(app: &AppHandle, question: UnansweredQuestion<'_>) {
+    let UnansweredQuestion {
+        session_id,
+        question_id,
+        directory,
+        session_title,
+        body,
+    } = question;
+    if !app.state::<QuestionReminders>().take_due(question_id)
+        || !app.state::<PendingQuestions>().contains(question_id)
+    {
+        return;
+    }
+
+    let preferences = load_notification_preferences(app, directory).await;
+    if let Some(reason) = preferences.suppression(NotificationCategory::QuestionAsked) {
+        record_suppressed(app, NotificationCategory::QuestionAsked, session_id, reason);
+        return;
+    }
+
+    if let Err(reason) = should_notify(app, preferences.when_focused, Some(session_id)) {
+        record_suppressed(app, NotificationCategory::QuestionAsked, session_id, reason);
+        return;
+    }
+
+    let title = match session_title {
+        Some(session_title) => format!("Still waiting: '{session_title}'"),
+        None => "Still waiting for your response".to_string(),
+    };
+    let shown = show_session_notification(
+        app,
+        SessionNotification {
+            category: NotificationCategory::QuestionAsked,
+            session_id,
+            directory,
+            title: preferences.title(title),
+            body,
+            sound: SoundKind::Question,
+            count: 1,
+        },
+    )
+    .await;
+    if shown && preferences.question_reminder_attention {
+        request_attention(app);
+    }
+}
+
+async fn handle_message_updated(
+    app: &AppHandle,
+    api: &OpenCodeApi<'_>,
+    properties: &Value,
+    directory: Option<&str>,
+    notified_messages: &Mutex<RecentKeys>,
+) {
+    let Some(info) = properties.get("info") else {
+        return;
+    };
+
+    let role = info.get("role").and_then(Value::as_str).unwrap_or_default();
+    if role != "assistant" {
+        return;
+    }
+
+    let finish = info.get("finish").and_then(Value::as_str);
+    if finish != Some("stop") || is_compaction_summary(info) {
+        return;
+    }
+
+    let message_id = match info.get("id").and_then(Value::as_str) {
+        Some(id) => id.to_string(),
+        None => return,
+    };
+
+    if let Some(session_id) = info.get("sessionID").and_then(Value::as_str) {
+        if app
+            .state::<CompactingSessions>()
+            .hold(session_id, properties, directory)
+        {
+            return;
+        }
+    }
+
+    {
+        let mut notified = notified_messages.lock().await;
+        if notified.contains(&message_id) {
+            return;
+        }
+        notified.insert(message_id.clone());
+    }
+
+    let session_id = info
+        .get("sessionID")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+
+    let preferences = load_notification_preferences(app, directory).await;
+    if let Some(reason) = preferences.suppression(NotificationCategory::AssistantCompleted) {
+        record_suppressed(
+            app,
+            NotificationCategory::AssistantCompleted,
+            session_id,
+            reason,
+        );
+        return;
+    }
+
+    let mode = info
+        .get("mode")
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty());
+    if let Some(reason) = preferences.mode_suppression(mode) {
+        record_suppressed(
+            app,
+            NotificationCategory::AssistantCompleted,
+            session_id,
+            reason,
+        );
+        return;
+    }
+    let raw_mode = mode.unwrap_or("agent");
+    let raw_model = info
+        .get("modelID")
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty())
+        .unwrap_or("assistant");
+
+    if let Err(reason) = should_notify(app, preferences.when_focused, Some(session_id)) {
+        record_suppressed(
+            app,
+            NotificationCategory::AssistantCompleted,
+            session_id,
+            reason,
+        );
+        return;
+    }
+
+    // Checked before the digest so a muted session can't hold a digest window open.
+    if app.state::<MutedSessions>().is_muted(session_id) {
+        record_suppressed(
+            app,
+            NotificationCategory::AssistantCompleted,
+            session_id,
+            SuppressionReason::Muted,
+        );
+        return;
+    }
+
+    let agent = format_mode(raw_mode);
+    if !preferences.digest_window.is_zero() {
+        let admission = app.state::<CompletionDigest>().admit(
+            session_id,
+            &agent,
+            directory,
+            preferences.digest_window,
+            Instant::now(),
+        );
+        if let Admission::Held { flush } = admission {
+            if let Some((at, generation)) = flush {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    tokio::time::sleep_until(at.into()).await;
+                    flush_completion_digest(&app, Some(generation)).await;
+                });
+            }
+            record_suppressed(
+                app,
+                NotificationCategory::AssistantCompleted,
+                session_id,
+                SuppressionReason::Digest,
+            );
+            return;
+        }
+    }
+
+    let snippet = match preferences.reply_snippet_length {
+        Some(max_len) => {
+            let text = match text_from_parts(properties.get("parts")) {
+                Some(text) => Some(text),
+                None => fetch_message_text(api, session_id, &message_id, directory).await,
+            };
+            text.and_then(|text| reply_snippet(&text, max_len))
+        }
+        None => None,
+    };
+
+    let title = match session_title(app, api, session_id, directory).await {
+        Some(session_title) => format!("{agent} agent finished: '{session_title}'"),
+        None => format!("{agent} agent is ready"),
+    };
+    let title = preferences.title(title);
+    let body = match snippet {
+        Some(snippet) => snippet,
+        None => {
+            let provider = info.get("providerID").and_then(Value::as_str);
+            let model = model_name(app, api, provider, raw_model, directory).await;
+            format!("{model} completed the task")
+        }
+    };
+    show_session_notification(
+        app,
+        SessionNotification {
+            category: NotificationCategory::AssistantCompleted,
+            session_id,
+            directory,
+            title,
+            body,
+            sound: SoundKind::Completion,
+            count: 1,
+        },
+    )
+    .await;
+}
+
+/// Deliver held completions as one notification. `generation` limits this to the digest
+/// window the flush was scheduled for.
+async fn flush_completion_digest(app: &AppHandle, generation: Option<u64>) {
+    let held = app.state::<CompletionDigest>().take(generation);
+    let Some(last) = held.last() else {
+        return;
+    };
+    // A digest spanning several projects goes out under the global preferences, with no
+    // project name in its title.
+    let directory = shared_directory(&held);
+    let preferences = load_notification_preferences(app, directory).await;
+    if let Some(reason) = preferences.suppression(NotificationCategory::AssistantCompleted) {
+        record_suppressed(
+            app,
+            NotificationCategory::AssistantCompleted,
+            &last.session_id,
+            reason,
+        );
+        return;
+    }
+    show_session_notification(
+        app,
+        SessionNotification {
+            category: NotificationCategory::AssistantCompleted,
+            session_id: &last.session_id,
+            directory,
+            title: preferences.title("Agents finished"),
+            body: digest_body(&held),
+            sound: SoundKind::Completion,
+            count: held.len(),
+        },
+    )
+    .await;
+}
+
+/// The first question's text followed by its leading options, e.g.
+/// "Apply the migration? (Yes / No / Show diff)".
+fn question_body(properties: &Value) -> Option<String> {
+    let question = properties.get("questions")?.as_array()?.first()?;
+    let text = ["question", "header"]
+        .iter()
+        .filter_map(|key| question.get(*key).and_then(Value::as_str))
+        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .find(|text| !text.is_empty())?;
+    let text = truncate_graphemes(&text, QUESTION_TEXT_LENGTH);
+
+    let options = question
+        .get("options")
+        .and_then(Value::as_array)
+        .map(|options| {
+            options
+                .iter()
+                .filter_map(|option| option.get("label").and_then(Value::as_str))
+                .map(str::trim)
+                .filter(|label| !label.is_empty())
+                .take(QUESTION_OPTIONS_SHOWN)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    if options.is_empty() {
+        Some(text)
+    } else {
+        Some(format!("{text} ({})", options.join(" / ")))
+    }
+}
+
+/// Join the non-synthetic text parts of a message.
+fn text_from_parts(parts: Option<&Value>) -> Option<String> {
+    let text = parts?
+        .as_array()?
+        .iter()
+        .filter(|part| part.get("type").and_then(Value::as_str) == Some("text"))
+        .filter(|part| {
+            !part
+                .get("synthetic")
+                .and_then(Value::as_bool)
+                .unwrap_or(false)
+        })
+        .filter_map(|part| part.get("text").and_then(Value::as_str))
+        .collect::<Vec<_>>()
+        .join("\n");
+    (!text.trim().is_empty()).then_some(text)
+}
+
+async fn fetch_message_text(
+    api: &OpenCodeApi<'_>,
+    session_id: &str,
+    message_id: &str,
+    directory: Option<&str>,
+) -> Option<String> {
+    if session_id.is_empty() {
+        return None;
+    }
+
+    let url = format!("{}/session/{session_id}/message/{message_id}", api.base);
+    let mut request = api.get(&url).timeout(MESSAGE_FETCH_TIMEOUT);
+    if let Some(directory) = directory {
+        request = request.query(&[("directory", directory)]);
+    }
+
+    let message = match request.send().await {
+        Ok(response) if response.status().is_success() => response.json::<Value>().await.ok()?,
+        Ok(response) => {
+            debug!(
+                "[desktop:notify] Message fetch returned status {}",
+                response.status()
+            );
+            return None;
+        }
+        Err(err) => {
+            debug!("[desktop:notify] Message fetch failed: {err}");
+            return None;
+        }
+    };
+
+    text_from_parts(message.get("parts"))
+}
+
+/// Plain-text preview of a reply: markdown syntax removed, whitespace collapsed, and cut
+/// to at most `max_len` graphemes.
+fn reply_snippet(text: &str, max_len: usize) -> Option<String> {
+    let text = MARKDOWN_LINK.replace_all(text, "$1");
+    let text = MARKDOWN_LINE_PREFIX.replace_all(&text, "");
+    let text = MARKDOWN_INLINE.replace_all(&text, "");
+    let text = MARKDOWN_UNDERSCORE_EMPHASIS.replace_all(&text, "$1");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() || max_len == 0 {
+        return None;
+    }
+
+    Some(truncate_graphemes(&text, max_len))
+}
+
+/// Cut `text` to at most `max_len` graphemes, marking the cut with an ellipsis.
+pub(crate) fn truncate_graphemes(text: &str, max_len: usize) -> String {
+    let graphemes: Vec<&str> = text.graphemes(true).collect();
+    if graphemes.len() <= max_len {
+        return text.to_string();
+    }
+
+    let truncated = graphemes[..max_len.saturating_sub(1)].concat();
+    format!("{}…", truncated.trim_end())
+}
+
+async fn handle_permission_asked(
+    app: &AppHandle,
+    properties: &Value,
+    directory: Option<&str>,
+    notified_permissions: &Mutex<RecentKeys>,
+) {
+    let session_id = properties.get("sessionID").and_then(Value::as_str);
+    let permission_id = properties.get("id").and_then(Value::as_str);
+
+    let (session_id, permission_id) = match (session_id, permission_id) {
+        (Some(s), Some(p)) => (s, p),
+        _ => return,
+    };
+
+    {
+        let mut notified = notified_permissions.lock().await;
+        if notified.contains(permission_id) {
+            return;
+        }
+        notified.insert(permission_id.to_string());
+    }
+
+    let preferences = load_notification_preferences(app, directory).await;
+    if let Some(reason) = preferences.suppression(NotificationCategory::PermissionRequested) {
+        record_suppressed(
+            app,
+            NotificationCategory::PermissionRequested,
+            session_id,
+            reason,
+        );
+        return;
+    }
+
+    if let Err(reason) = should_notify(app, preferences.when_focused, Some(session_id)) {
+        record_suppressed(
+            app,
+            NotificationCategory::PermissionRequested,
+            session_id,
+            reason,
+        );
+        return;
+    }
+
+    let body = permission_body(properties);
+    show_session_notification(
+        app,
+        SessionNotification {
+            category: NotificationCategory::PermissionRequested,
+            session_id,
+            directory,
+            title: preferences.title("Permission needed"),
+            body,
+            sound: SoundKind::Question,
+            count: 1,
+        },
+    )
+    .await;
+}
+
+/// Once a permission is answered anywhere, keep its id marked so a late or replayed ask
+/// event for it does not notify.
+async fn handle_permission_replied(properties: &Value, notified_permissions: &Mutex<RecentKeys>) {
+    let permission_id = ["requestID", "permissionID"]
+        .iter()
+        .find_map(|key| properties.get(*key).and_then(Value::as_str));
+    if let Some(permission_id) = permission_id {
+        notified_permissions
+            .lock()
+            .await
+            .insert(permission_id.to_string());
+    }
+}
+
+/// "Agent wants to run: rm -rf build/" for shell commands, and the tool plus its patterns
+/// for everything else.
+fn permission_body(properties: &Value) -> String {
+    let tool = properties
+        .get("permission")
+        .or_else(|| properties.get("type"))
+        .and_then(Value::as_str)
+        .unwrap_or("a tool");
+    let command = properties
+        .get("metadata")
+        .and_then(|metadata| metadata.get("command"))
+        .and_then(Value::as_str);
+    let patterns = match properties
+        .get("patterns")
+        .or_else(|| properties.get("pattern"))
+    {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(", "),
+        Some(Value::String(pattern)) => pattern.clone(),
+        _ => String::new(),
+    };
+
+    let (action, detail) = match tool {
+        "bash" => (
+            "run".to_string(),
+            command.map(str::to_string).unwrap_or(patterns),
+        ),
+        "edit" | "write" => ("edit".to_string(), patterns),
+        _ => (format!("use {tool}"), patterns),
+    };
+    let detail = detail.split_whitespace().collect::<Vec<_>>().join(" ");
+    if detail.is_empty() {
+        return format!("Agent wants to {action}");
+    }
+    format!(
+        "Agent wants to {action}: {}",
+        truncate_graphemes(&detail, PERMISSION_DETAIL_LENGTH)
+    )
+}
+
+/// Track tool calls from part updates and schedule a one-time check for calls that might
+/// outlive the long-running threshold, such as a bash command stuck on interactive input.
+async fn handle_tool_part_updated(app: &AppHandle, properties: &Value, directory: Option<&str>) {
+    let Some(part) = properties.get("part") else {
+        return;
+    };
+    if part.get("type").and_then(Value::as_str) != Some("tool") {
+        return;
+    }
+    let Some(call_id) = part.get("callID").and_then(Value::as_str) else {
+        return;
+    };
+    let state = part.get("state");
+    let status = state
+        .and_then(|state| state.get("status"))
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if status != "running" {
+        if matches!(status, "completed" | "error") {
+            app.state::<RunningTools>().finish(call_id);
+        }
+        return;
+    }
+
+    let Some(session_id) = part.get("sessionID").and_then(Value::as_str) else {
+        return;
+    };
+    let threshold = load_notification_preferences(app, directory)
+        .await
+        .long_running_tool_threshold;
+    if threshold.is_zero() {
+        return;
+    }
+
+    let tool = part
+        .get("tool")
+        .and_then(Value::as_str)
+        .unwrap_or("tool")
+        .to_string();
+    let detail = state
+        .and_then(|state| state.get("input"))
+        .and_then(|input| input.get("command").or_else(|| input.get("description")))
+        .and_then(Value::as_str)
+        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|text| !text.is_empty())
+        .map(|text| truncate_graphemes(&text, TOOL_DETAIL_LENGTH));
+    let run = ToolRun {
+        session_id: session_id.to_string(),
+        tool,
+        detail,
+    };
+    if !app.state::<RunningTools>().start(call_id, run) {
+        return;
+    }
+
+    // Count from when the tool actually started, which matters after a reconnect.
+    let elapsed = state
+        .and_then(|state| state.get("time"))
+        .and_then(|time| time.get("start"))
+        .and_then(Value::as_i64)
+        .and_then(|start| u64::try_from(Utc::now().timestamp_millis() - start).ok())
+        .map(Duration::from_millis)
+        .unwrap_or_default();
+    let remaining = threshold.saturating_sub(elapsed);
+
+    let app = app.clone();
+    let call_id = call_id.to_string();
+    let directory = directory.map(str::to_string);
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(remaining).await;
+        notify_long_running_tool(&app, &call_id, directory.as_deref(), threshold).await;
+    });
+}
+
+async fn notify_long_running_tool(
+    app: &AppHandle,
+    call_id: &str,
+    directory: Option<&str>,
+    threshold: Duration,
+) {
+    let Some(run) = app.state::<RunningTools>().take_overdue(call_id) else {
+        return;
+    };
+    let session_id = run.session_id.as_str();
+
+    let preferences = load_notification_preferences(app, directory).await;
+    if let Some(reason) = preferences.suppression(NotificationCategory::LongRunningTool) {
+        record_suppressed(
+            app,
+            NotificationCategory::LongRunningTool,
+            session_id,
+            reason,
+        );
+        return;
+    }
+
+    if let Err(reason) = should_notify(app, preferences.when_focused, Some(session_id)) {
+        record_suppressed(
+            app,
+            NotificationCategory::LongRunningTool,
+            session_id,
+            reason,
+        );
+        return;
+    }
+
+    let minutes = (threshold.as_secs() / 60).max(1);
+    let plural = if minutes == 1 { "" } else { "s" };
+    let what = if run.tool == "bash" {
+        "A bash command".to_string()
+    } else {
+        format!("The {} tool", run.tool)
+    };
+    let mut body = format!("{what} has been running for {minutes} minute{plural}");
+    if let Some(detail) = &run.detail {
+        body.push_str(&format!(": {detail}"));
+    }
+
+    show_session_notification(
+        app,
+        SessionNotification {
+            category: NotificationCategory::LongRunningTool,
+            session_id,
+            directory,
+            title: preferences.title("Tool still running"),
+            body,
+            sound: SoundKind::Question,
+            count: 1,
+        },
+    )
+    .await;
+}
+
+/// An assistant message that ended on an error or was aborted rather than finishing.
+fn is_failed_message(properties: &Value) -> bool {
+    let Some(info) = properties.get("info") else {
+        return false;
+    };
+    if info.get("role").and_then(Value::as_str) != Some("assistant") {
+        return false;
+    }
+    let finish = info.get("finish").and_then(Value::as_str);
+    info.get("error").is_some_and(|error| !error.is_null())
+        || matches!(finish, Some("error") | Some("aborted"))
+}
+
+async fn handle_message_failed(
+    app: &AppHandle,
+    properties: &Value,
+    directory: Option<&str>,
+    notified_errors: &Mutex<RecentKeys>,
+) {
+    let Some(info) = properties.get("info") else {
+        return;
+    };
+    let Some(session_id) = info.get("sessionID").and_then(Value::as_str) else {
+        return;
+    };
+    let mode = info.get("mode").and_then(Value::as_str);
+    let error = info.get("error").filter(|error| !error.is_null());
+    let aborted = info.get("finish").and_then(Value::as_str) == Some("aborted");
+
+    let failure = SessionFailure {
+        session_id,
+        mode,
+        error,
+        aborted,
+    };
+    notify_session_failure(app, failure, directory, notified_errors).await;
+}
+
+async fn handle_session_error(
+    app: &AppHandle,
+    properties: &Value,
+    directory: Option<&str>,
+    notified_errors: &Mutex<RecentKeys>,
+) {
+    let Some(session_id) = properties.get("sessionID").and_then(Value::as_str) else {
+        return;
+    };
+    let error = properties.get("error").filter(|error| !error.is_null());
+
+    let failure = SessionFailure {
+        session_id,
+        mode: None,
+        error,
+        aborted: false,
+    };
+    notify_session_failure(app, failure, directory, notified_errors).await;
+}
+
+struct SessionFailure<'a> {
+    session_id: &'a str,
+    mode: Option<&'a str>,
+    error: Option<&'a Value>,
+    aborted: bool,
+}
+
+/// Shared by `session.error` and failed `message.updated` events. Both usually describe the
+/// same failure, so they dedup on the session plus the error summary; retries that fail the
+/// same way stay quiet.
+async fn notify_session_failure(
+    app: &AppHandle,
+    failure: SessionFailure<'_>,
+    directory: Option<&str>,
+    notified_errors: &Mutex<RecentKeys>,
+) {
+    let SessionFailure {
+        session_id,
+        mode,
+        error,
+        aborted,
+    } = failure;
+    let error_name = error
+        .and_then(|error| error.get("name"))
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let aborted = aborted || error_name == "MessageAbortedError";
+    let summary = if aborted {
+        None
+    } else {
+        error.and_then(error_summary)
+    };
+
+    let key = format!(
+        "{session_id}:{}",
+        summary
+            .as_deref()
+            .unwrap_or(if aborted { "aborted" } else { error_name })
+    );
+    {
+        let mut notified = notified_errors.lock().await;
+        if notified.contains(&key) {
+            return;
+        }
+        notified.insert(key);
+    }
+
+    let preferences = load_notification_preferences(app, directory).await;
+    if let Some(reason) = preferences.suppression(NotificationCategory::SessionError) {
+        record_suppressed(app, NotificationCategory::SessionError, session_id, reason);
+        return;
+    }
+
+    if let Err(reason) = should_notify(app, preferences.when_focused, Some(session_id)) {
+        record_suppressed(app, NotificationCategory::SessionError, session_id, reason);
+        return;
+    }
+
+    let agent = mode
+        .filter(|mode| !mode.is_empty())
+        .map(|mode| format!("{} agent", format_mode(mode)))
+        .unwrap_or_else(|| "Agent".to_string());
+    let title = preferences.title(if aborted {
+        format!("{agent} was stopped")
+    } else {
+        format!("{agent} hit an error")
+    });
+    let body = match summary {
+        Some(summary) => summary,
+        None if aborted => "The response was aborted before it finished".to_string(),
+        None => "The session stopped with an error".to_string(),
+    };
+
+    show_session_notification(
+        app,
+        SessionNotification {
+            category: NotificationCategory::SessionError,
+            session_id,
+            directory,
+            title,
+            body,
+            sound: SoundKind::Error,
+            count: 1,
+        },
+    )
+    .await;
+}
+
+/// Short, human-readable description of an opencode error object.
+fn error_summary(error: &Value) -> Option<String> {
+    let message = error
+        .get("data")
+        .and_then(|data| data.get("message"))
+        .and_then(Value::as_str)
+        .or_else(|| error.get("message").and_then(Value::as_str))
+        .map(|message| message.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|message| !message.is_empty());
+
+    let message = match message {
+        Some(message) => message,
+        None => {
+            let name = error.get("name").and_then(Value::as_str)?;
+            let name = name.strip_suffix("Error").unwrap_or(name);
+            let words = split_camel_case(name);
+            if words.is_empty() {
+                return None;
+            }
+            words.to_lowercase()
+        }
+    };
+
+    Some(truncate_graphemes(&message, ERROR_SUMMARY_LENGTH))
+}
+
+fn split_camel_case(raw: &str) -> String {
+    let mut result = String::new();
+    for (index, ch) in raw.chars().enumerate() {
+        if index > 0 && ch.is_uppercase() {
+            result.push(' ');
+        }
+        result.push(ch);
+    }
+    result.trim().to_string()
+}
+
+fn format_mode(raw: &str) -> String {
+    if raw.is_empty() {
+        return "Agent".to_string();
+    }
+    raw.split(&['-', '_', ' '][..])
+        .filter(|s| !s.is_empty())
+        .map(capitalize)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Display name for a model, from OpenCode's catalog where it lists one.
+async fn model_name(
+    app: &AppHandle,
+    api: &OpenCodeApi<'_>,
+    provider: Option<&str>,
+    model: &str,
+    directory: Option<&str>,
+) -> String {
+    let names = app.state::<ModelNames>();
+    names.load(api.client, api.base, api.token, directory).await;
+    names.model(provider, model)
+}
+
+/// Display name for a provider, from OpenCode's catalog where it lists one.
+async fn provider_name(
+    app: &AppHandle,
+    api: &OpenCodeApi<'_>,
+    provider: &str,
+    directory: Option<&str>,
+) -> String {
+    let names = app.state::<ModelNames>();
+    names.load(api.client, api.base, api.token, directory).await;
+    names.provider(provider)
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => String::new(),
+    }
+}
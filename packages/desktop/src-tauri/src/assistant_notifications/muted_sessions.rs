@@ -0,0 +1,103 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MutedSession {
+    session_id: String,
+    /// Milliseconds since the Unix epoch, or absent for a mute that lasts until undone.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<i64>,
+}
+
+/// Sessions whose notifications the user has muted, with an optional expiry.
+#[derive(Default)]
+pub struct MutedSessions {
+    by_id: Mutex<HashMap<String, Option<DateTime<Utc>>>>,
+}
+
+impl MutedSessions {
+    pub(super) fn is_muted(&self, session_id: &str) -> bool {
+        let Ok(by_id) = self.by_id.lock() else {
+            return false;
+        };
+        match by_id.get(session_id) {
+            Some(Some(until)) => *until > Utc::now(),
+            Some(None) => true,
+            None => false,
+        }
+    }
+
+    pub fn list(&self) -> Vec<MutedSession> {
+        let now = Utc::now();
+        let Ok(by_id) = self.by_id.lock() else {
+            return Vec::new();
+        };
+        let mut sessions = by_id
+            .iter()
+            .filter(|(_, until)| until.is_none_or(|until| until > now))
+            .map(|(session_id, until)| MutedSession {
+                session_id: session_id.clone(),
+                expires_at: until.map(|until| until.timestamp_millis()),
+            })
+            .collect::<Vec<_>>();
+        sessions.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+        sessions
+    }
+
+    /// Remove the mute if it still ends at `until`; a newer mute replaced it otherwise.
+    fn expire(&self, session_id: &str, until: DateTime<Utc>) -> bool {
+        let Ok(mut by_id) = self.by_id.lock() else {
+            return false;
+        };
+        if by_id.get(session_id) != Some(&Some(until)) {
+            return false;
+        }
+        by_id.remove(session_id);
+        true
+    }
+}
+
+fn emit_muted_sessions(app: &AppHandle) {
+    let sessions = app.state::<MutedSessions>().list();
+    let _ = app.emit("openchamber:muted-sessions-changed", sessions);
+}
+
+/// Mute a session, for `duration_minutes` or until unmuted. Timed mutes are lifted by a
+/// timer so the UI hears about it even if no event for the session arrives.
+pub fn mute_session(app: &AppHandle, session_id: &str, duration_minutes: Option<u64>) {
+    let until = duration_minutes
+        .and_then(|minutes| i64::try_from(minutes).ok())
+        .map(|minutes| Utc::now() + TimeDelta::minutes(minutes));
+    if let Ok(mut by_id) = app.state::<MutedSessions>().by_id.lock() {
+        by_id.insert(session_id.to_string(), until);
+    }
+    emit_muted_sessions(app);
+
+    if let Some(until) = until {
+        let app = app.clone();
+        let session_id = session_id.to_string();
+        tauri::async_runtime::spawn(async move {
+            let remaining = (until - Utc::now()).to_std().unwrap_or_default();
+            tokio::time::sleep(remaining).await;
+            if app.state::<MutedSessions>().expire(&session_id, until) {
+                emit_muted_sessions(&app);
+            }
+        });
+    }
+}
+
+pub fn unmute_session(app: &AppHandle, session_id: &str) {
+    let removed = app
+        .state::<MutedSessions>()
+        .by_id
+        .lock()
+        .map(|mut by_id| by_id.remove(session_id).is_some())
+        .unwrap_or(false);
+    if removed {
+        emit_muted_sessions(app);
+    }
+}
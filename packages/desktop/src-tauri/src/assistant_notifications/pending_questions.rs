@@ -0,0 +1,293 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use chrono::Utc;
+use log::{debug, info};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+use tokio::sync::watch;
+
+use super::question_reminders::cancel_attention;
+use super::OpenCodeApi;
+
+/// A question still pending after this long was most likely answered while no stream
+/// was connected, so it stops counting.
+const PENDING_QUESTION_MAX_AGE: Duration = Duration::from_secs(6 * 60 * 60);
+const QUESTION_FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Questions the agent has asked that have not been answered or rejected yet, keyed by
+/// question id. Tracked from the event stream regardless of notification settings, so it
+/// stays correct when questions are answered from the focused UI.
+pub struct PendingQuestions {
+    by_id: Mutex<BTreeMap<String, PendingQuestion>>,
+    next_order: AtomicU64,
+    /// How many questions are pending, for whatever mirrors the count outside the app.
+    count: watch::Sender<usize>,
+}
+
+struct PendingQuestion {
+    session_id: String,
+    /// Increases with every question added, so the oldest one has the lowest value.
+    order: u64,
+    text: Option<String>,
+    options: Vec<String>,
+    /// Milliseconds since the epoch when the desktop app first saw the question.
+    asked_at: i64,
+    directory: Option<String>,
+}
+
+/// A pending question as `get_pending_questions` reports it.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingQuestionDetails {
+    pub session_id: String,
+    pub question_id: String,
+    pub text: Option<String>,
+    pub options: Vec<String>,
+    pub asked_at: i64,
+    pub directory: Option<String>,
+}
+
+impl Default for PendingQuestions {
+    fn default() -> Self {
+        Self {
+            by_id: Mutex::default(),
+            next_order: AtomicU64::default(),
+            count: watch::channel(0).0,
+        }
+    }
+}
+
+impl PendingQuestions {
+    /// Track the question a `question.asked` event, or the server's list of pending
+    /// questions, describes. Returns true if the question was not already pending.
+    pub(super) fn add(&self, properties: &Value, directory: Option<&str>) -> bool {
+        let session_id = properties.get("sessionID").and_then(Value::as_str);
+        let question_id = properties.get("id").and_then(Value::as_str);
+        let (Some(session_id), Some(question_id)) = (session_id, question_id) else {
+            return false;
+        };
+        let question = properties
+            .get("questions")
+            .and_then(Value::as_array)
+            .and_then(|questions| questions.first());
+        self.by_id
+            .lock()
+            .map(|mut by_id| {
+                if by_id.contains_key(question_id) {
+                    return false;
+                }
+                let order = self.next_order.fetch_add(1, Ordering::Relaxed);
+                by_id.insert(
+                    question_id.to_string(),
+                    PendingQuestion {
+                        session_id: session_id.to_string(),
+                        order,
+                        text: question.and_then(question_text),
+                        options: question.map(option_labels).unwrap_or_default(),
+                        asked_at: Utc::now().timestamp_millis(),
+                        directory: directory.map(str::to_string),
+                    },
+                );
+                self.publish_count(by_id.len());
+                true
+            })
+            .unwrap_or(false)
+    }
+
+    /// Returns the question's session if it was pending.
+    pub(super) fn remove(&self, question_id: &str) -> Option<String> {
+        let mut by_id = self.by_id.lock().ok()?;
+        let removed = by_id.remove(question_id)?;
+        self.publish_count(by_id.len());
+        Some(removed.session_id)
+    }
+
+    /// Whether any question from `session_id` is still waiting.
+    pub(super) fn has_session(&self, session_id: &str) -> bool {
+        self.by_id
+            .lock()
+            .map(|by_id| {
+                by_id
+                    .values()
+                    .any(|question| question.session_id == session_id)
+            })
+            .unwrap_or(false)
+    }
+
+    /// Drop every question belonging to a deleted session. Returns true if any were removed.
+    pub(super) fn remove_session(&self, session_id: &str) -> bool {
+        self.by_id
+            .lock()
+            .map(|mut by_id| {
+                let before = by_id.len();
+                by_id.retain(|_, question| question.session_id != session_id);
+                self.publish_count(by_id.len());
+                by_id.len() != before
+            })
+            .unwrap_or(false)
+    }
+
+    pub(super) fn contains(&self, question_id: &str) -> bool {
+        self.by_id
+            .lock()
+            .map(|by_id| by_id.contains_key(question_id))
+            .unwrap_or(false)
+    }
+
+    /// Every pending question, oldest first.
+    pub fn details(&self) -> Vec<PendingQuestionDetails> {
+        let Ok(by_id) = self.by_id.lock() else {
+            return Vec::new();
+        };
+        let mut questions: Vec<_> = by_id.iter().collect();
+        questions.sort_by_key(|(_, question)| question.order);
+        questions
+            .into_iter()
+            .map(|(question_id, question)| PendingQuestionDetails {
+                session_id: question.session_id.clone(),
+                question_id: question_id.clone(),
+                text: question.text.clone(),
+                options: question.options.clone(),
+                asked_at: question.asked_at,
+                directory: question.directory.clone(),
+            })
+            .collect()
+    }
+
+    /// Drop questions pending for longer than [`PENDING_QUESTION_MAX_AGE`]. Returns true
+    /// if any were removed.
+    pub fn prune_stale(&self) -> bool {
+        let Ok(mut by_id) = self.by_id.lock() else {
+            return false;
+        };
+        let cutoff = Utc::now().timestamp_millis() - PENDING_QUESTION_MAX_AGE.as_millis() as i64;
+        let before = by_id.len();
+        by_id.retain(|_, question| question.asked_at >= cutoff);
+        let pruned = before - by_id.len();
+        if pruned == 0 {
+            return false;
+        }
+        info!(
+            "[desktop:notify] Dropped {pruned} pending question(s) older than {} hours",
+            PENDING_QUESTION_MAX_AGE.as_secs() / 3600
+        );
+        self.publish_count(by_id.len());
+        true
+    }
+
+    /// The session of the question that has been waiting longest.
+    pub fn oldest_session(&self) -> Option<String> {
+        self.by_id
+            .lock()
+            .ok()?
+            .values()
+            .min_by_key(|question| question.order)
+            .map(|question| question.session_id.clone())
+    }
+
+    fn count(&self) -> usize {
+        *self.count.borrow()
+    }
+
+    fn publish_count(&self, count: usize) {
+        self.count
+            .send_if_modified(|current| std::mem::replace(current, count) != count);
+    }
+
+    pub fn subscribe_count(&self) -> watch::Receiver<usize> {
+        self.count.subscribe()
+    }
+}
+
+/// Add the questions the server reports as pending, so ones asked while no stream was
+/// connected still count. Servers without a question list are left alone.
+pub(super) async fn seed_pending_questions(
+    app: &AppHandle,
+    api: &OpenCodeApi<'_>,
+    directory: Option<&str>,
+) {
+    let url = format!("{}/question", api.base);
+    let mut request = api.get(&url).timeout(QUESTION_FETCH_TIMEOUT);
+    if let Some(directory) = directory {
+        request = request.query(&[("directory", directory)]);
+    }
+    let questions = match request.send().await {
+        Ok(response) if response.status().is_success() => {
+            response.json::<Vec<Value>>().await.unwrap_or_default()
+        }
+        Ok(response) => {
+            debug!(
+                "[desktop:notify] Pending question list returned status {}",
+                response.status()
+            );
+            return;
+        }
+        Err(err) => {
+            debug!("[desktop:notify] Pending question list fetch failed: {err}");
+            return;
+        }
+    };
+    let pending = app.state::<PendingQuestions>();
+    let mut added = 0;
+    for question in &questions {
+        if pending.add(question, directory) {
+            added += 1;
+        }
+    }
+    if added > 0 {
+        info!("[desktop:notify] Found {added} question(s) already waiting on the server");
+        sync_question_badge(app);
+    }
+}
+
+/// Pending questions after dropping stale ones, for `get_pending_questions`.
+pub fn pending_question_details(app: &AppHandle) -> Vec<PendingQuestionDetails> {
+    let pending = app.state::<PendingQuestions>();
+    if pending.prune_stale() {
+        sync_question_badge(app);
+    }
+    pending.details()
+}
+
+/// Show the number of pending questions on the app badge, clearing it at zero. An
+/// attention request for a question ends with the last one answered.
+pub fn sync_question_badge(app: &AppHandle) {
+    let count = app.state::<PendingQuestions>().count();
+    if let Some(window) = app.get_webview_window("main") {
+        let badge = (count > 0).then_some(count as i64);
+        let _ = window.set_badge_count(badge);
+    }
+    if count == 0 {
+        cancel_attention(app);
+    }
+}
+
+/// The question's text with whitespace collapsed, falling back to its header.
+fn question_text(question: &Value) -> Option<String> {
+    ["question", "header"]
+        .iter()
+        .filter_map(|key| question.get(*key).and_then(Value::as_str))
+        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .find(|text| !text.is_empty())
+}
+
+fn option_labels(question: &Value) -> Vec<String> {
+    question
+        .get("options")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|option| option.get("label").and_then(Value::as_str))
+        .map(str::trim)
+        .filter(|label| !label.is_empty())
+        .map(str::to_string)
+        .collect()
+}
@@ -0,0 +1,265 @@
+use std::{path::Path, time::Duration};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use super::history::SuppressionReason;
+use crate::desktop_settings::{DesktopSettings, ModeFilter};
+use crate::project_names::ProjectNames;
+use crate::DesktopRuntime;
+
+const DEFAULT_REPLY_SNIPPET_LENGTH: usize = 120;
+const DEFAULT_DIGEST_WINDOW_SECS: u64 = 10;
+const DEFAULT_LONG_RUNNING_TOOL_MINUTES: u64 = 5;
+const DEFAULT_QUESTION_REMINDER_MINUTES: u64 = 5;
+const DEFAULT_CONTEXT_WINDOW_PERCENT: u64 = 85;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum NotificationCategory {
+    AssistantCompleted,
+    QuestionAsked,
+    PermissionRequested,
+    SessionError,
+    /// A single tool call has been running past the configured threshold.
+    LongRunningTool,
+    /// A session's context window is close to full.
+    ContextWindow,
+    /// A provider is rate limiting the session and OpenCode is waiting to retry.
+    RateLimited,
+    /// Notifications requested by the frontend through `desktop_notify`.
+    Other,
+}
+
+impl NotificationCategory {
+    /// Categories that need the user to respond before the agent can continue.
+    fn is_actionable(self) -> bool {
+        matches!(
+            self,
+            NotificationCategory::QuestionAsked | NotificationCategory::PermissionRequested
+        )
+    }
+
+    /// "4 completions", "1 question pending", used in quiet hours summaries.
+    pub(super) fn summary_label(self, count: usize) -> String {
+        let plural = if count == 1 { "" } else { "s" };
+        match self {
+            NotificationCategory::AssistantCompleted => format!("{count} completion{plural}"),
+            NotificationCategory::QuestionAsked => format!("{count} question{plural} pending"),
+            NotificationCategory::PermissionRequested => {
+                format!("{count} permission request{plural} pending")
+            }
+            NotificationCategory::SessionError => format!("{count} error{plural}"),
+            NotificationCategory::LongRunningTool => format!("{count} long-running tool{plural}"),
+            NotificationCategory::ContextWindow => format!("{count} context warning{plural}"),
+            NotificationCategory::RateLimited => format!("{count} rate limit{plural}"),
+            NotificationCategory::Other => format!("{count} other notification{plural}"),
+        }
+    }
+}
+
+/// Per-project notification level, stored as `notifications` on each project entry.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum ProjectNotificationLevel {
+    #[default]
+    All,
+    QuestionsOnly,
+    Muted,
+}
+
+impl ProjectNotificationLevel {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "all" => Some(Self::All),
+            "questions-only" => Some(Self::QuestionsOnly),
+            "muted" => Some(Self::Muted),
+            _ => None,
+        }
+    }
+}
+
+/// `notifications.whenFocused`: whether notifications still show while OpenChamber is
+/// the focused app.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) enum WhenFocused {
+    /// Stay quiet about the session a window in front is showing, and about notifications
+    /// that belong to no session while any window is in front.
+    #[default]
+    Never,
+    Always,
+    /// Stay quiet only about the session a window in front is showing.
+    UnlessSessionVisible,
+}
+
+impl WhenFocused {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "never" => Some(Self::Never),
+            "always" => Some(Self::Always),
+            "unless-session-visible" => Some(Self::UnlessSessionVisible),
+            _ => None,
+        }
+    }
+}
+
+/// Per-category toggles from the `notifications` settings object, combined with the
+/// overrides and level of the project an event belongs to. Every category is on unless
+/// explicitly disabled.
+pub(super) struct NotificationPreferences {
+    assistant_completed: bool,
+    question_asked: bool,
+    session_error: bool,
+    permission_requested: bool,
+    long_running_tool: bool,
+    context_window: bool,
+    rate_limited: bool,
+    /// The project's `overrides.modeFilter` if it has one, else the global filter.
+    mode_filter: Option<ModeFilter>,
+    /// Maximum snippet length in graphemes, or `None` when reply snippets are disabled.
+    pub(super) reply_snippet_length: Option<usize>,
+    /// How long after a completion notification further completions are held for a digest.
+    /// Zero disables coalescing.
+    pub(super) digest_window: Duration,
+    /// How long a single tool call may run before it notifies. Zero disables the check.
+    pub(super) long_running_tool_threshold: Duration,
+    /// Share of the context window, in percent, at which a session warns.
+    pub(super) context_window_percent: u64,
+    /// How long a shown question may go unanswered before a reminder. Zero disables it.
+    pub(super) question_reminder_delay: Duration,
+    /// Whether reminders also bounce the dock icon or flash the taskbar.
+    pub(super) question_reminder_attention: bool,
+    /// Whether a new question bounces the dock icon or flashes the taskbar while the
+    /// window is in the background.
+    pub(super) request_attention_on_question: bool,
+    pub(super) when_focused: WhenFocused,
+    project_level: ProjectNotificationLevel,
+    /// Put in front of titles while several projects are configured.
+    project_name: Option<String>,
+}
+
+impl NotificationPreferences {
+    fn from_settings(
+        settings: &DesktopSettings,
+        directory: Option<&str>,
+        project_name: Option<String>,
+    ) -> Self {
+        let effective = settings.effective_settings(
+            directory
+                .filter(|directory| !directory.is_empty())
+                .map(Path::new),
+        );
+        let notifications = &effective.notifications;
+        let reply_snippet_length = notifications.reply_snippet.then(|| {
+            notifications
+                .reply_snippet_length
+                .map(|length| length as usize)
+                .unwrap_or(DEFAULT_REPLY_SNIPPET_LENGTH)
+        });
+        let digest_window = notifications
+            .digest_window_seconds
+            .unwrap_or(DEFAULT_DIGEST_WINDOW_SECS);
+        let long_running_tool_minutes = notifications
+            .long_running_tool_minutes
+            .unwrap_or(DEFAULT_LONG_RUNNING_TOOL_MINUTES);
+        let question_reminder_minutes = notifications
+            .question_reminder_minutes
+            .unwrap_or(DEFAULT_QUESTION_REMINDER_MINUTES);
+
+        let project_level = match effective
+            .project
+            .and_then(|project| project.notifications.as_deref())
+            .and_then(ProjectNotificationLevel::parse)
+            .unwrap_or_default()
+        {
+            _ if effective.muted => ProjectNotificationLevel::Muted,
+            // An `overrides.muted` of false unmutes a project whose level is muted.
+            ProjectNotificationLevel::Muted => ProjectNotificationLevel::All,
+            level => level,
+        };
+
+        Self {
+            assistant_completed: notifications.assistant_completed,
+            question_asked: notifications.question_asked,
+            session_error: notifications.session_error,
+            permission_requested: notifications.permission_requested,
+            long_running_tool: notifications.long_running_tool,
+            context_window: notifications.context_window,
+            rate_limited: notifications.rate_limited,
+            mode_filter: notifications.mode_filter.clone(),
+            reply_snippet_length,
+            digest_window: Duration::from_secs(digest_window),
+            long_running_tool_threshold: Duration::from_secs(long_running_tool_minutes * 60),
+            question_reminder_delay: Duration::from_secs(question_reminder_minutes * 60),
+            context_window_percent: notifications
+                .context_window_percent
+                .unwrap_or(DEFAULT_CONTEXT_WINDOW_PERCENT)
+                .clamp(50, 99),
+            question_reminder_attention: notifications.question_reminder_attention,
+            request_attention_on_question: notifications.request_attention_on_question,
+            when_focused: notifications
+                .when_focused
+                .as_deref()
+                .and_then(WhenFocused::parse)
+                .unwrap_or_default(),
+            project_level,
+            project_name,
+        }
+    }
+
+    /// Why `category` should not notify under these preferences, or `None` if it may.
+    pub(super) fn suppression(&self, category: NotificationCategory) -> Option<SuppressionReason> {
+        let enabled = match category {
+            NotificationCategory::AssistantCompleted => self.assistant_completed,
+            NotificationCategory::QuestionAsked => self.question_asked,
+            NotificationCategory::PermissionRequested => self.permission_requested,
+            NotificationCategory::SessionError => self.session_error,
+            NotificationCategory::LongRunningTool => self.long_running_tool,
+            NotificationCategory::ContextWindow => self.context_window,
+            NotificationCategory::RateLimited => self.rate_limited,
+            NotificationCategory::Other => true,
+        };
+        if !enabled {
+            return Some(SuppressionReason::CategoryDisabled);
+        }
+        match self.project_level {
+            ProjectNotificationLevel::All => None,
+            ProjectNotificationLevel::QuestionsOnly if category.is_actionable() => None,
+            ProjectNotificationLevel::QuestionsOnly => {
+                Some(SuppressionReason::ProjectQuestionsOnly)
+            }
+            ProjectNotificationLevel::Muted => Some(SuppressionReason::ProjectMuted),
+        }
+    }
+
+    /// Whether completions from the agent `mode` are filtered out.
+    pub(super) fn mode_suppression(&self, mode: Option<&str>) -> Option<SuppressionReason> {
+        let filter = self.mode_filter.as_ref()?;
+        (!filter.allows(mode)).then_some(SuppressionReason::ModeFiltered)
+    }
+
+    /// Prefix the title with the project name so multi-project users can tell them apart,
+    /// as in "acme-app · Build agent is ready".
+    pub(super) fn title(&self, title: impl Into<String>) -> String {
+        let title = title.into();
+        match &self.project_name {
+            Some(name) => format!("{name} · {title}"),
+            None => title,
+        }
+    }
+}
+
+/// Read preferences fresh for every notification so settings edits apply without a restart.
+pub(super) async fn load_notification_preferences(
+    app: &AppHandle,
+    directory: Option<&str>,
+) -> NotificationPreferences {
+    let settings = app
+        .state::<DesktopRuntime>()
+        .settings()
+        .load_typed()
+        .await
+        .unwrap_or_default();
+    let project_name =
+        directory.and_then(|directory| app.state::<ProjectNames>().title_prefix(directory));
+    NotificationPreferences::from_settings(&settings, directory, project_name)
+}
@@ -0,0 +1,86 @@
+use std::{collections::HashSet, sync::Mutex};
+
+use tauri::{AppHandle, Manager, UserAttentionType};
+
+use crate::recent_keys::RecentKeys;
+
+/// Reminders scheduled for shown question notifications, keyed by question id. Lives in
+/// app state rather than the event loop so timers keep running across SSE reconnects.
+#[derive(Default)]
+pub struct QuestionReminders {
+    state: Mutex<ReminderState>,
+}
+
+#[derive(Default)]
+struct ReminderState {
+    scheduled: HashSet<String>,
+    /// Questions that were reminded about or resolved; neither gets another reminder.
+    done: RecentKeys,
+}
+
+impl QuestionReminders {
+    /// Returns true if no reminder was scheduled or sent for the question yet, when the
+    /// caller should start the timer.
+    pub(super) fn schedule(&self, question_id: &str) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+        if state.done.contains(question_id) {
+            return false;
+        }
+        state.scheduled.insert(question_id.to_string())
+    }
+
+    /// The question was answered or rejected; its timer will find nothing to do.
+    pub(super) fn cancel(&self, question_id: &str) {
+        if let Ok(mut state) = self.state.lock() {
+            state.scheduled.remove(question_id);
+            state.done.insert(question_id);
+        }
+    }
+
+    /// Returns true if the reminder is still due. Each question is reminded at most once.
+    pub(super) fn take_due(&self, question_id: &str) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+        if !state.scheduled.remove(question_id) {
+            return false;
+        }
+        state.done.insert(question_id);
+        true
+    }
+}
+
+/// Bounce the dock icon on macOS or flash the taskbar button on Windows.
+pub(super) fn request_attention(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.request_user_attention(Some(UserAttentionType::Critical));
+    }
+}
+
+/// Ask for attention on a new question while the window is in the background: the dock
+/// bounces until the app is activated on macOS, the taskbar button flashes on Windows,
+/// and the urgency hint is set on Linux.
+pub(super) fn request_question_attention(app: &AppHandle) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    if window.is_focused().unwrap_or(false) {
+        return;
+    }
+    // Critical flashes the whole window on Windows; the taskbar button is enough there.
+    let attention = if cfg!(target_os = "windows") {
+        UserAttentionType::Informational
+    } else {
+        UserAttentionType::Critical
+    };
+    let _ = window.request_user_attention(Some(attention));
+}
+
+/// Stop bouncing or flashing, once the window is focused or no question is left waiting.
+pub fn cancel_attention(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.request_user_attention(None);
+    }
+}
@@ -0,0 +1,190 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use chrono::{Datelike, Local, NaiveDateTime, NaiveTime, TimeDelta};
+use serde_json::Value;
+
+use super::preferences::NotificationCategory;
+
+/// What to do with notifications that fall inside quiet hours.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum QuietHoursMode {
+    /// Hold them and summarize once quiet hours end.
+    Suppress,
+    /// Show them without sound.
+    Silent,
+}
+
+/// The `notifications.quietHours` setting: a daily local-time interval, optionally limited
+/// to some weekdays. The interval may wrap past midnight (22:00–07:00); it then belongs to
+/// the day it starts on.
+pub(super) struct QuietHours {
+    start: NaiveTime,
+    end: NaiveTime,
+    /// Indexed by days from Monday.
+    days: [bool; 7],
+    mode: QuietHoursMode,
+}
+
+pub(super) enum QuietHoursDecision {
+    Deliver,
+    DeliverSilently,
+    Hold,
+}
+
+impl QuietHours {
+    pub(super) fn from_settings(settings: &Value) -> Option<Self> {
+        let section = settings.get("notifications")?.get("quietHours")?;
+        if !section
+            .get("enabled")
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+        {
+            return None;
+        }
+
+        let parse_time = |key: &str| {
+            section
+                .get(key)
+                .and_then(Value::as_str)
+                .and_then(|raw| NaiveTime::parse_from_str(raw, "%H:%M").ok())
+        };
+        let start = parse_time("start")?;
+        let end = parse_time("end")?;
+        if start == end {
+            return None;
+        }
+
+        let days = match section.get("days").and_then(Value::as_array) {
+            Some(entries) => {
+                let mut days = [false; 7];
+                for index in entries
+                    .iter()
+                    .filter_map(|entry| weekday_index(entry.as_str()?))
+                {
+                    days[index] = true;
+                }
+                days
+            }
+            None => [true; 7],
+        };
+
+        let mode = match section.get("mode").and_then(Value::as_str) {
+            Some("silent") => QuietHoursMode::Silent,
+            _ => QuietHoursMode::Suppress,
+        };
+
+        Some(Self {
+            start,
+            end,
+            days,
+            mode,
+        })
+    }
+
+    pub(super) fn decide(&self, now: NaiveDateTime) -> QuietHoursDecision {
+        if !self.is_active_at(now) {
+            return QuietHoursDecision::Deliver;
+        }
+        match self.mode {
+            QuietHoursMode::Suppress => QuietHoursDecision::Hold,
+            QuietHoursMode::Silent => QuietHoursDecision::DeliverSilently,
+        }
+    }
+
+    pub(super) fn is_active_at(&self, now: NaiveDateTime) -> bool {
+        let time = now.time();
+        let today = now.weekday().num_days_from_monday() as usize;
+
+        if self.start < self.end {
+            return self.days[today] && time >= self.start && time < self.end;
+        }
+
+        // Wrapping interval: the evening half belongs to today, the early-morning half to
+        // the day before.
+        if time >= self.start {
+            self.days[today]
+        } else if time < self.end {
+            self.days[(today + 6) % 7]
+        } else {
+            false
+        }
+    }
+
+    /// The next time the interval ends, strictly after `now`.
+    pub(super) fn next_end_after(&self, now: NaiveDateTime) -> NaiveDateTime {
+        let end_today = now.date().and_time(self.end);
+        if end_today > now {
+            end_today
+        } else {
+            end_today + TimeDelta::days(1)
+        }
+    }
+}
+
+fn weekday_index(raw: &str) -> Option<usize> {
+    let index = match raw.to_ascii_lowercase().as_str() {
+        "mon" => 0,
+        "tue" => 1,
+        "wed" => 2,
+        "thu" => 3,
+        "fri" => 4,
+        "sat" => 5,
+        "sun" => 6,
+        _ => return None,
+    };
+    Some(index)
+}
+
+pub(super) fn local_now() -> NaiveDateTime {
+    Local::now().naive_local()
+}
+
+/// Notifications held back during quiet hours or Do Not Disturb, summarized once both end.
+#[derive(Default)]
+pub struct QuietHoursBacklog {
+    state: Mutex<BacklogState>,
+}
+
+#[derive(Default)]
+struct BacklogState {
+    counts: BTreeMap<NotificationCategory, usize>,
+    last_session: Option<String>,
+    flush_scheduled: bool,
+}
+
+impl QuietHoursBacklog {
+    /// Record a held notification. Returns true when the caller should schedule the flush.
+    pub(super) fn hold(
+        &self,
+        category: NotificationCategory,
+        count: usize,
+        session_id: Option<&str>,
+    ) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+        *state.counts.entry(category).or_default() += count;
+        if let Some(session_id) = session_id.filter(|id| !id.is_empty()) {
+            state.last_session = Some(session_id.to_string());
+        }
+        !std::mem::replace(&mut state.flush_scheduled, true)
+    }
+
+    /// Take the summary text and the most recent session, if anything was held.
+    pub(super) fn take_summary(&self) -> Option<(String, Option<String>)> {
+        let mut state = self.state.lock().ok()?;
+        state.flush_scheduled = false;
+        let counts = std::mem::take(&mut state.counts);
+        let last_session = state.last_session.take();
+        if counts.is_empty() {
+            return None;
+        }
+
+        let parts = counts
+            .iter()
+            .map(|(category, count)| category.summary_label(*count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        Some((format!("While you were away: {parts}"), last_session))
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use super::active_session::should_notify;
+use super::preferences::{load_notification_preferences, NotificationCategory};
+use super::session_titles::session_title;
+use super::sounds::SoundKind;
+use super::{
+    provider_name, record_suppressed, show_session_notification, OpenCodeApi, SessionNotification,
+};
+use crate::recent_keys::{RecentKeys, DEFAULT_CAPACITY};
+use crate::retry_status::RetryStatus;
+
+/// A session that keeps retrying notifies at most once per this window.
+const NOTIFY_INTERVAL: Duration = Duration::from_secs(3 * 60);
+/// Retrying must have gone on this long before the session resuming is worth a note.
+const RESUMED_AFTER: Duration = Duration::from_secs(60);
+
+/// Sessions currently waiting out provider errors, so a storm of retries notifies once
+/// and its end can be announced.
+pub struct RateLimits {
+    state: Mutex<RateLimitState>,
+}
+
+struct RateLimitState {
+    storms: HashMap<String, RetryStorm>,
+    /// Sessions notified within the last `NOTIFY_INTERVAL`.
+    notified: RecentKeys,
+}
+
+struct RetryStorm {
+    started: Instant,
+    /// Whether the user was actually shown a notification about it.
+    shown: bool,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(RateLimitState {
+                storms: HashMap::new(),
+                notified: RecentKeys::new(DEFAULT_CAPACITY, NOTIFY_INTERVAL),
+            }),
+        }
+    }
+}
+
+impl RateLimits {
+    /// Record a retry. Returns true when it should notify.
+    fn retrying(&self, session_id: &str) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+        state
+            .storms
+            .entry(session_id.to_string())
+            .or_insert_with(|| RetryStorm {
+                started: Instant::now(),
+                shown: false,
+            });
+        state.notified.insert(session_id)
+    }
+
+    fn mark_shown(&self, session_id: &str) {
+        if let Ok(mut state) = self.state.lock() {
+            if let Some(storm) = state.storms.get_mut(session_id) {
+                storm.shown = true;
+            }
+        }
+    }
+
+    /// End the session's retry storm. Returns how long it lasted if the user was told
+    /// about it.
+    pub(super) fn finish(&self, session_id: &str) -> Option<Duration> {
+        let storm = self.state.lock().ok()?.storms.remove(session_id)?;
+        storm.shown.then(|| storm.started.elapsed())
+    }
+}
+
+/// Notify when a session starts retrying after a provider error, and again when it gets
+/// going after a long stretch of retries.
+pub(super) async fn handle_session_status(
+    app: &AppHandle,
+    api: &OpenCodeApi<'_>,
+    properties: &Value,
+    directory: Option<&str>,
+) {
+    let Some(session_id) = properties.get("sessionID").and_then(Value::as_str) else {
+        return;
+    };
+    let rate_limits = app.state::<RateLimits>();
+    let Some(retry) = RetryStatus::from_status_event(properties) else {
+        let busy = properties
+            .get("status")
+            .and_then(|status| status.get("type"))
+            .and_then(Value::as_str)
+            == Some("busy");
+        match rate_limits.finish(session_id) {
+            Some(lasted) if busy && lasted >= RESUMED_AFTER => {
+                notify_resumed(app, api, session_id, directory, lasted).await;
+            }
+            _ => {}
+        }
+        return;
+    };
+    if !rate_limits.retrying(session_id) {
+        return;
+    }
+
+    let category = NotificationCategory::RateLimited;
+    let preferences = load_notification_preferences(app, directory).await;
+    if let Some(reason) = preferences.suppression(category) {
+        record_suppressed(app, category, session_id, reason);
+        return;
+    }
+    if let Err(reason) = should_notify(app, preferences.when_focused, Some(session_id)) {
+        record_suppressed(app, category, session_id, reason);
+        return;
+    }
+
+    let provider = match retry.provider.as_deref() {
+        Some(provider) => provider_name(app, api, provider, directory).await,
+        None => "Provider".to_string(),
+    };
+    let mut body = match retry.remaining() {
+        Some(remaining) => format!(
+            "{provider} rate limited — retrying in {}",
+            format_wait(remaining)
+        ),
+        None => format!("{provider} rate limited — retrying soon"),
+    };
+    if let Some(attempt) = retry.attempt {
+        body.push_str(&format!(", attempt {attempt}"));
+    }
+
+    let shown = show_session_notification(
+        app,
+        SessionNotification {
+            category,
+            session_id,
+            directory,
+            title: preferences.title("Rate limited"),
+            body,
+            sound: SoundKind::Error,
+            count: 1,
+        },
+    )
+    .await;
+    if shown {
+        rate_limits.mark_shown(session_id);
+    }
+}
+
+async fn notify_resumed(
+    app: &AppHandle,
+    api: &OpenCodeApi<'_>,
+    session_id: &str,
+    directory: Option<&str>,
+    lasted: Duration,
+) {
+    let category = NotificationCategory::RateLimited;
+    let preferences = load_notification_preferences(app, directory).await;
+    if let Some(reason) = preferences.suppression(category) {
+        record_suppressed(app, category, session_id, reason);
+        return;
+    }
+    if let Err(reason) = should_notify(app, preferences.when_focused, Some(session_id)) {
+        record_suppressed(app, category, session_id, reason);
+        return;
+    }
+
+    let session = match session_title(app, api, session_id, directory).await {
+        Some(title) => format!("Session '{title}'"),
+        None => "The session".to_string(),
+    };
+    show_session_notification(
+        app,
+        SessionNotification {
+            category,
+            session_id,
+            directory,
+            title: preferences.title("Rate limit cleared"),
+            body: format!(
+                "{session} resumed after retrying for {}",
+                format_wait(lasted)
+            ),
+            sound: SoundKind::Completion,
+            count: 1,
+        },
+    )
+    .await;
+}
+
+/// "45s", "2m 5s".
+fn format_wait(duration: Duration) -> String {
+    let seconds = duration.as_secs().max(1);
+    match (seconds / 60, seconds % 60) {
+        (0, seconds) => format!("{seconds}s"),
+        (minutes, 0) => format!("{minutes}m"),
+        (minutes, seconds) => format!("{minutes}m {seconds}s"),
+    }
+}
@@ -0,0 +1,63 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use crate::recent_keys::RecentKeys;
+
+/// A tool invocation that has started but not yet completed.
+#[derive(Clone)]
+pub(super) struct ToolRun {
+    pub(super) session_id: String,
+    pub(super) tool: String,
+    /// Shown in the notification body, e.g. the bash command line.
+    pub(super) detail: Option<String>,
+}
+
+/// Tool calls currently running, keyed by call id, so a delayed check can tell whether a
+/// call is still going when its threshold passes.
+#[derive(Default)]
+pub struct RunningTools {
+    state: Mutex<RunningState>,
+}
+
+#[derive(Default)]
+struct RunningState {
+    runs: HashMap<String, ToolRun>,
+    notified: RecentKeys,
+}
+
+impl RunningTools {
+    /// Track a running call. Returns true the first time a call is seen, when the caller
+    /// should schedule the overdue check.
+    pub(super) fn start(&self, call_id: &str, run: ToolRun) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+        if state.runs.contains_key(call_id) || state.notified.contains(call_id) {
+            return false;
+        }
+        state.runs.insert(call_id.to_string(), run);
+        true
+    }
+
+    pub(super) fn finish(&self, call_id: &str) {
+        if let Ok(mut state) = self.state.lock() {
+            state.runs.remove(call_id);
+        }
+    }
+
+    /// Forget every call of a session that went idle or was deleted; whatever is left
+    /// running there was interrupted.
+    pub(super) fn finish_session(&self, session_id: &str) {
+        if let Ok(mut state) = self.state.lock() {
+            state.runs.retain(|_, run| run.session_id != session_id);
+        }
+    }
+
+    /// The call, if it is still running and has not been reported yet. Each call is
+    /// reported at most once.
+    pub(super) fn take_overdue(&self, call_id: &str) -> Option<ToolRun> {
+        let mut state = self.state.lock().ok()?;
+        let run = state.runs.remove(call_id)?;
+        state.notified.insert(call_id);
+        Some(run)
+    }
+}
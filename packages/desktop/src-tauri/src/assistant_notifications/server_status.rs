@@ -0,0 +1,186 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::events::ServerStatusPayload;
+use crate::opencode_manager::{OpenCodeStatus, PortConflict};
+
+use super::active_session::should_notify;
+use super::preferences::load_notification_preferences;
+use super::{
+    apply_delivery_rules, configured_sound, record_suppressed, show_notification,
+    NotificationCategory, SoundKind,
+};
+
+/// A crash-looping server notifies at most once per interval.
+const CRASH_NOTIFICATION_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Tracks crashes of the embedded OpenCode server so the user hears about a crash and the
+/// recovery that follows, without a notification per restart attempt.
+#[derive(Default)]
+pub struct ServerStatusNotifier {
+    state: Mutex<ServerStatusState>,
+}
+
+#[derive(Default)]
+struct ServerStatusState {
+    last_crash_notification: Option<Instant>,
+    /// A crash was reported (in-app or by notification) and recovery has not been yet.
+    recovering: bool,
+    /// The crash got an OS notification, so the recovery gets one too.
+    notified_crash: bool,
+}
+
+fn emit_server_status(app: &AppHandle, status: &str, exit_code: Option<i32>) {
+    let _ = app.emit(
+        "openchamber:server-status",
+        ServerStatusPayload { status, exit_code },
+    );
+}
+
+/// The server process exited on its own and the watchdog is about to restart it.
+pub async fn notify_server_stopped(app: &AppHandle, exit_code: Option<i32>) {
+    emit_server_status(app, "restarting", exit_code);
+
+    let first_in_interval = {
+        let notifier = app.state::<ServerStatusNotifier>();
+        let Ok(mut state) = notifier.state.lock() else {
+            return;
+        };
+        state.recovering = true;
+        let recent = state
+            .last_crash_notification
+            .is_some_and(|at| at.elapsed() < CRASH_NOTIFICATION_INTERVAL);
+        if !recent {
+            state.last_crash_notification = Some(Instant::now());
+        }
+        !recent
+    };
+    if !first_in_interval {
+        return;
+    }
+
+    let preferences = load_notification_preferences(app, None).await;
+    if let Err(reason) = should_notify(app, preferences.when_focused, None) {
+        record_suppressed(app, NotificationCategory::Other, "", reason);
+        return;
+    }
+    let Ok(with_sound) = apply_delivery_rules(app, NotificationCategory::Other, 1, None).await
+    else {
+        return;
+    };
+
+    let body = match exit_code {
+        Some(code) => {
+            format!("OpenCode server stopped unexpectedly (exit code {code}) — restarting")
+        }
+        None => "OpenCode server stopped unexpectedly — restarting".to_string(),
+    };
+    let sound = if with_sound {
+        configured_sound(app, SoundKind::Error).await
+    } else {
+        None
+    };
+    show_notification(
+        app,
+        NotificationCategory::Other,
+        "",
+        "OpenCode server stopped".to_string(),
+        body,
+        sound,
+    );
+    if let Ok(mut state) = app.state::<ServerStatusNotifier>().state.lock() {
+        state.notified_crash = true;
+    }
+}
+
+/// The server is healthy again. Only reported after a crash.
+pub fn notify_server_running(app: &AppHandle) {
+    let notified_crash = {
+        let notifier = app.state::<ServerStatusNotifier>();
+        let Ok(mut state) = notifier.state.lock() else {
+            return;
+        };
+        if !std::mem::take(&mut state.recovering) {
+            return;
+        }
+        std::mem::take(&mut state.notified_crash)
+    };
+    emit_server_status(app, "running", None);
+
+    if notified_crash {
+        show_notification(
+            app,
+            NotificationCategory::Other,
+            "",
+            "OpenCode server restarted".to_string(),
+            "The server is running again".to_string(),
+            None,
+        );
+    }
+}
+
+/// OpenCode could not start because its port is taken. Reported once per conflict; the
+/// watchdog keeps retrying quietly and the server comes up once the port is free.
+pub async fn notify_port_conflict(app: &AppHandle, conflict: &PortConflict) {
+    let Ok(with_sound) = apply_delivery_rules(app, NotificationCategory::Other, 1, None).await
+    else {
+        return;
+    };
+
+    let body = if conflict.pinned {
+        format!(
+            "Port {} is in use by another process. Free it or change the OpenCode port in settings.",
+            conflict.port
+        )
+    } else {
+        format!(
+            "No free port in the configured range starting at {}.",
+            conflict.port
+        )
+    };
+    let sound = if with_sound {
+        configured_sound(app, SoundKind::Error).await
+    } else {
+        None
+    };
+    show_notification(
+        app,
+        NotificationCategory::Other,
+        "",
+        "OpenCode could not start".to_string(),
+        body,
+        sound,
+    );
+}
+
+/// The external OpenCode server stopped answering. Reported once until it is reached
+/// again; nothing is respawned.
+pub async fn notify_server_unreachable(app: &AppHandle, status: &OpenCodeStatus) {
+    emit_server_status(app, "unreachable", None);
+
+    let Ok(with_sound) = apply_delivery_rules(app, NotificationCategory::Other, 1, None).await
+    else {
+        return;
+    };
+    let address = match status.port {
+        Some(port) => format!("{}:{port}", status.host),
+        None => status.host.clone(),
+    };
+    let sound = if with_sound {
+        configured_sound(app, SoundKind::Error).await
+    } else {
+        None
+    };
+    show_notification(
+        app,
+        NotificationCategory::Other,
+        "",
+        "OpenCode server unreachable".to_string(),
+        format!("The OpenCode server at {address} is not responding"),
+        sound,
+    );
+}
@@ -0,0 +1,17 @@
+use tauri::AppHandle;
+
+use super::OpenCodeApi;
+use crate::session_info::session_info;
+
+/// The session's title from the shared cache, or fetched with a short timeout. `None` on
+/// any failure so callers fall back to their usual text.
+pub(super) async fn session_title(
+    app: &AppHandle,
+    api: &OpenCodeApi<'_>,
+    session_id: &str,
+    directory: Option<&str>,
+) -> Option<String> {
+    session_info(app, api.client, api.base, api.token, session_id, directory)
+        .await?
+        .title
+}
@@ -0,0 +1,119 @@
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::DesktopRuntime;
+
+/// Setting value that turns a sound off.
+const NO_SOUND: &str = "none";
+
+/// Which sound slot a notification uses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SoundKind {
+    Completion,
+    Question,
+    Error,
+}
+
+impl SoundKind {
+    fn setting_key(self) -> Option<&'static str> {
+        match self {
+            SoundKind::Completion => Some("completionSound"),
+            SoundKind::Question => Some("questionSound"),
+            SoundKind::Error => None,
+        }
+    }
+}
+
+/// macOS system sounds from /System/Library/Sounds.
+#[cfg(target_os = "macos")]
+const PLATFORM_SOUNDS: &[&str] = &[
+    "Basso",
+    "Blow",
+    "Bottle",
+    "Frog",
+    "Funk",
+    "Glass",
+    "Hero",
+    "Morse",
+    "Ping",
+    "Pop",
+    "Purr",
+    "Sosumi",
+    "Submarine",
+    "Tink",
+];
+
+/// Completion, question, and error defaults.
+#[cfg(target_os = "macos")]
+const PLATFORM_DEFAULTS: (&str, &str, &str) = ("Glass", "Glass", "Basso");
+
+/// Toast sounds understood by the Windows notification backend.
+#[cfg(target_os = "windows")]
+const PLATFORM_SOUNDS: &[&str] = &["Default", "IM", "Mail", "Reminder", "SMS"];
+
+#[cfg(target_os = "windows")]
+const PLATFORM_DEFAULTS: (&str, &str, &str) = ("Default", "IM", "Reminder");
+
+/// Names from the freedesktop sound theme.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const PLATFORM_SOUNDS: &[&str] = &[
+    "message-new-instant",
+    "complete",
+    "bell",
+    "dialog-information",
+    "dialog-warning",
+];
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const PLATFORM_DEFAULTS: (&str, &str, &str) = ("complete", "message-new-instant", "dialog-warning");
+
+fn platform_default(kind: SoundKind) -> &'static str {
+    let (completion, question, error) = PLATFORM_DEFAULTS;
+    match kind {
+        SoundKind::Completion => completion,
+        SoundKind::Question => question,
+        SoundKind::Error => error,
+    }
+}
+
+/// Sounds that can be chosen on this platform, not counting `none`.
+pub(crate) fn available_sounds() -> &'static [&'static str] {
+    PLATFORM_SOUNDS
+}
+
+/// Map a configured value onto a sound this platform can play. `None` means silent;
+/// unknown names fall back to the platform default.
+pub(crate) fn resolve_sound(configured: Option<&str>, kind: SoundKind) -> Option<&'static str> {
+    match configured.map(str::trim) {
+        Some(NO_SOUND) => None,
+        Some(name) => Some(
+            PLATFORM_SOUNDS
+                .iter()
+                .copied()
+                .find(|sound| sound.eq_ignore_ascii_case(name))
+                .unwrap_or_else(|| platform_default(kind)),
+        ),
+        None => Some(platform_default(kind)),
+    }
+}
+
+fn sound_from_settings(settings: &Value, kind: SoundKind) -> Option<&'static str> {
+    let configured = kind.setting_key().and_then(|key| {
+        settings
+            .get("notifications")
+            .and_then(|section| section.get(key))
+            .and_then(Value::as_str)
+    });
+    resolve_sound(configured, kind)
+}
+
+/// Read the configured sound fresh, for notifications sent outside a preferences lookup.
+pub(crate) async fn configured_sound(app: &AppHandle, kind: SoundKind) -> Option<&'static str> {
+    let settings = app
+        .state::<DesktopRuntime>()
+        .settings()
+        .load()
+        .await
+        .unwrap_or(Value::Null);
+    sound_from_settings(&settings, kind)
+}
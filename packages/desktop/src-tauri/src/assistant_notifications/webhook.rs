@@ -0,0 +1,172 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::Sha256;
+
+use super::NotificationCategory;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+const WEBHOOK_ATTEMPTS: u32 = 2;
+const WEBHOOK_RETRY_DELAY: Duration = Duration::from_secs(2);
+/// Failures are logged as warnings at most this often; the rest go to debug.
+const FAILURE_LOG_INTERVAL: Duration = Duration::from_secs(10 * 60);
+pub(crate) const SIGNATURE_HEADER: &str = "X-OpenChamber-Signature";
+
+static LAST_FAILURE_LOG: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+/// `notifications.webhookUrl` and the optional signing secret, which lives in the secret
+/// store once migrated out of `notifications.webhookSecret`.
+pub(super) struct Webhook {
+    url: String,
+    secret: Option<String>,
+}
+
+impl Webhook {
+    pub(super) fn from_settings(settings: &Value, stored_secret: Option<String>) -> Option<Self> {
+        let section = settings.get("notifications")?;
+        let url = section
+            .get("webhookUrl")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|url| !url.is_empty())?;
+        // A secret still in settings has not been migrated yet.
+        let secret = stored_secret
+            .filter(|secret| !secret.is_empty())
+            .or_else(|| {
+                section
+                    .get("webhookSecret")
+                    .and_then(Value::as_str)
+                    .filter(|secret| !secret.is_empty())
+                    .map(str::to_string)
+            });
+        Some(Self {
+            url: url.to_string(),
+            secret,
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct WebhookPayload<'a> {
+    category: NotificationCategory,
+    session_id: &'a str,
+    title: &'a str,
+    body: &'a str,
+    directory: Option<&'a str>,
+    /// The project's label, or its folder name.
+    project: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    timestamp: i64,
+}
+
+impl<'a> WebhookPayload<'a> {
+    pub(super) fn new(
+        category: NotificationCategory,
+        session_id: &'a str,
+        title: &'a str,
+        body: &'a str,
+        directory: Option<&'a str>,
+        project: Option<String>,
+    ) -> Self {
+        Self {
+            category,
+            session_id,
+            title,
+            body,
+            directory,
+            project,
+            timestamp: Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+/// Hex HMAC-SHA256 of the request body, sent as `sha256=<hex>`. Telemetry uploads are
+/// signed the same way.
+pub(crate) fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    let digest = mac.finalize().into_bytes();
+    let hex = digest
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    format!("sha256={hex}")
+}
+
+/// POST the payload in the background. Local notifications never wait on this.
+pub(super) fn forward(client: Client, webhook: Webhook, payload: &WebhookPayload<'_>) {
+    let body = match serde_json::to_vec(payload) {
+        Ok(body) => body,
+        Err(err) => {
+            debug!("[desktop:notify] Failed to encode webhook payload: {err}");
+            return;
+        }
+    };
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = post_with_retry(&client, &webhook, body).await {
+            log_failure(&err);
+        }
+    });
+}
+
+async fn post_with_retry(client: &Client, webhook: &Webhook, body: Vec<u8>) -> Result<(), String> {
+    let signature = webhook
+        .secret
+        .as_deref()
+        .map(|secret| signature(secret, &body));
+
+    let mut last_error = String::new();
+    for attempt in 1..=WEBHOOK_ATTEMPTS {
+        let mut request = client
+            .post(&webhook.url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.clone());
+        if let Some(signature) = &signature {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            // Client errors won't change on retry.
+            Ok(response) if response.status().is_client_error() => {
+                return Err(format!("webhook returned {}", response.status()));
+            }
+            Ok(response) => last_error = format!("webhook returned {}", response.status()),
+            Err(err) => last_error = err.to_string(),
+        }
+        if attempt < WEBHOOK_ATTEMPTS {
+            tokio::time::sleep(WEBHOOK_RETRY_DELAY).await;
+        }
+    }
+    Err(last_error)
+}
+
+fn log_failure(err: &str) {
+    let due = LAST_FAILURE_LOG
+        .lock()
+        .map(|mut last| {
+            let due = last.is_none_or(|at| at.elapsed() >= FAILURE_LOG_INTERVAL);
+            if due {
+                *last = Some(Instant::now());
+            }
+            due
+        })
+        .unwrap_or(true);
+    if due {
+        warn!("[desktop:notify] Webhook delivery failed: {err}");
+    } else {
+        debug!("[desktop:notify] Webhook delivery failed: {err}");
+    }
+}
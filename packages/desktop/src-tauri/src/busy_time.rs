@@ -0,0 +1,425 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+
+const BUSY_TIME_FILE: &str = "busy-time.json";
+const DAILY_SUMMARY_EVENT: &str = "openchamber:busy-time-daily";
+const SAVE_DEBOUNCE: Duration = Duration::from_secs(2);
+/// Days older than this are dropped from the store.
+const RETENTION_DAYS: u64 = 90;
+/// Days covered by a report that does not name a start.
+const DEFAULT_REPORT_DAYS: u64 = 7;
+/// Upper bound on a single wait for midnight, so a clock change or a sleep is noticed.
+const MIDNIGHT_RECHECK: Duration = Duration::from_secs(15 * 60);
+const DAY_FORMAT: &str = "%Y-%m-%d";
+
+/// Busy time of one session on one day.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionDay {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    directory: Option<String>,
+    #[serde(default)]
+    busy_ms: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct BusyTimeFile {
+    /// Per local date (`YYYY-MM-DD`), per session.
+    #[serde(default)]
+    days: BTreeMap<String, BTreeMap<String, SessionDay>>,
+}
+
+/// A busy stretch that has not ended yet.
+struct OpenSpan {
+    directory: Option<String>,
+    started: DateTime<Local>,
+}
+
+#[derive(Default)]
+struct Ledger {
+    days: BTreeMap<String, BTreeMap<String, SessionDay>>,
+    open: HashMap<String, OpenSpan>,
+}
+
+impl Ledger {
+    fn close(&mut self, session_id: &str, end: DateTime<Local>) -> bool {
+        let Some(span) = self.open.remove(session_id) else {
+            return false;
+        };
+        for (day, busy_ms) in split_by_day(span.started, end) {
+            let entry = self
+                .days
+                .entry(day)
+                .or_default()
+                .entry(session_id.to_string())
+                .or_default();
+            entry.busy_ms += busy_ms;
+            if span.directory.is_some() {
+                entry.directory = span.directory.clone();
+            }
+        }
+        true
+    }
+
+    fn prune(&mut self) {
+        let cutoff = Local::now()
+            .date_naive()
+            .checked_sub_days(chrono::Days::new(RETENTION_DAYS))
+            .map(|date| date.format(DAY_FORMAT).to_string())
+            .unwrap_or_default();
+        self.days = self.days.split_off(&cutoff);
+    }
+}
+
+/// Milliseconds between `start` and `end` per local date, split at midnight.
+fn split_by_day(mut start: DateTime<Local>, end: DateTime<Local>) -> Vec<(String, u64)> {
+    let mut days = Vec::new();
+    while start < end {
+        let next_midnight = start
+            .date_naive()
+            .succ_opt()
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+            .unwrap_or(end);
+        let until = end.min(next_midnight);
+        if until <= start {
+            break;
+        }
+        let busy_ms = (until - start).num_milliseconds().max(0) as u64;
+        days.push((start.format(DAY_FORMAT).to_string(), busy_ms));
+        start = until;
+    }
+    days
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DayBusyTime {
+    pub date: String,
+    pub busy_ms: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectBusyTime {
+    /// Absent for sessions whose project was never known.
+    pub directory: Option<String>,
+    pub busy_ms: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionBusyTime {
+    pub session_id: String,
+    pub directory: Option<String>,
+    pub busy_ms: u64,
+    /// Still working; `busy_ms` includes the stretch so far.
+    pub busy: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeReport {
+    pub from: String,
+    pub to: String,
+    pub total_ms: u64,
+    /// Every day in the range, oldest first.
+    pub days: Vec<DayBusyTime>,
+    /// Most time first.
+    pub projects: Vec<ProjectBusyTime>,
+    /// Most time first.
+    pub sessions: Vec<SessionBusyTime>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DailySummary {
+    date: String,
+    total_ms: u64,
+    sessions: usize,
+    projects: usize,
+}
+
+/// Wall-clock time sessions spent working, per session and per day, taken from the
+/// activity tracker's phase transitions.
+#[derive(Clone, Default)]
+pub struct BusyTime {
+    ledger: Arc<Mutex<Ledger>>,
+    save_generation: Arc<AtomicU64>,
+}
+
+impl BusyTime {
+    /// Start from the stored totals, if there are any.
+    pub fn load() -> Self {
+        let days = busy_time_file_path()
+            .and_then(|path| Ok(std::fs::read(path)?))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<BusyTimeFile>(&bytes).ok())
+            .map(|file| file.days)
+            .unwrap_or_default();
+        let mut ledger = Ledger {
+            days,
+            ..Ledger::default()
+        };
+        ledger.prune();
+        Self {
+            ledger: Arc::new(Mutex::new(ledger)),
+            ..Self::default()
+        }
+    }
+
+    /// Follow a session's phase: a busy stretch starts when it gets busy and is counted
+    /// once it stops.
+    pub fn set_busy(&self, session_id: &str, directory: Option<&str>, busy: bool) {
+        let Ok(mut ledger) = self.ledger.lock() else {
+            return;
+        };
+        if busy {
+            ledger
+                .open
+                .entry(session_id.to_string())
+                .or_insert_with(|| OpenSpan {
+                    directory: directory.map(str::to_string),
+                    started: Local::now(),
+                });
+            return;
+        }
+        let closed = ledger.close(session_id, Local::now());
+        drop(ledger);
+        if closed {
+            self.schedule_save();
+        }
+    }
+
+    /// The machine is going to sleep: count busy stretches up to now, so the time asleep
+    /// is not.
+    pub fn suspend(&self) {
+        let Ok(mut ledger) = self.ledger.lock() else {
+            return;
+        };
+        let now = Local::now();
+        let open: Vec<String> = ledger.open.keys().cloned().collect();
+        for session_id in &open {
+            ledger.close(session_id, now);
+        }
+        drop(ledger);
+        if !open.is_empty() {
+            self.schedule_save();
+        }
+    }
+
+    /// A session was reset after a wake. A stretch still open here started before a sleep
+    /// that was not reported, so there is no telling how much of it was spent working.
+    pub fn discard(&self, session_id: &str) {
+        if let Ok(mut ledger) = self.ledger.lock() {
+            ledger.open.remove(session_id);
+        }
+    }
+
+    /// Totals from `from` to `to`, inclusive local dates as `YYYY-MM-DD`. Defaults to the
+    /// last 7 days. Sessions still busy count their stretch so far.
+    pub fn report(&self, from: Option<&str>, to: Option<&str>) -> Result<TimeReport, String> {
+        let today = Local::now().date_naive();
+        let to = match to {
+            Some(to) => parse_day(to)?,
+            None => today,
+        };
+        let from = match from {
+            Some(from) => parse_day(from)?,
+            None => to
+                .checked_sub_days(chrono::Days::new(DEFAULT_REPORT_DAYS - 1))
+                .unwrap_or(to),
+        };
+        if from > to {
+            return Err(format!("Report starts ({from}) after it ends ({to})"));
+        }
+        let (first_day, last_day) = (from, to);
+        let from = from.format(DAY_FORMAT).to_string();
+        let to = to.format(DAY_FORMAT).to_string();
+
+        let ledger = self
+            .ledger
+            .lock()
+            .map_err(|_| "Busy time ledger unavailable".to_string())?;
+        let mut days: BTreeMap<String, u64> = BTreeMap::new();
+        let mut sessions: HashMap<String, SessionBusyTime> = HashMap::new();
+        let mut add = |day: &str, id: &str, directory: Option<&String>, busy_ms: u64| {
+            *days.entry(day.to_string()).or_default() += busy_ms;
+            let session = sessions
+                .entry(id.to_string())
+                .or_insert_with(|| SessionBusyTime {
+                    session_id: id.to_string(),
+                    directory: None,
+                    busy_ms: 0,
+                    busy: false,
+                });
+            session.busy_ms += busy_ms;
+            if directory.is_some() {
+                session.directory = directory.cloned();
+            }
+        };
+        for (day, day_sessions) in ledger.days.range(from.clone()..=to.clone()) {
+            for (session_id, session) in day_sessions {
+                add(day, session_id, session.directory.as_ref(), session.busy_ms);
+            }
+        }
+        let now = Local::now();
+        for (session_id, span) in &ledger.open {
+            for (day, busy_ms) in split_by_day(span.started, now) {
+                if day >= from && day <= to {
+                    add(&day, session_id, span.directory.as_ref(), busy_ms);
+                }
+            }
+        }
+        for (session_id, session) in &mut sessions {
+            session.busy = ledger.open.contains_key(session_id);
+        }
+        drop(ledger);
+
+        // List every day in the range, idle ones included, so charts need no gap filling.
+        let mut day_totals = Vec::new();
+        let mut date = first_day;
+        while date <= last_day {
+            let key = date.format(DAY_FORMAT).to_string();
+            day_totals.push(DayBusyTime {
+                busy_ms: days.get(&key).copied().unwrap_or(0),
+                date: key,
+            });
+            let Some(next) = date.succ_opt() else {
+                break;
+            };
+            date = next;
+        }
+
+        let mut projects: HashMap<Option<String>, u64> = HashMap::new();
+        for session in sessions.values() {
+            *projects.entry(session.directory.clone()).or_default() += session.busy_ms;
+        }
+        let mut projects: Vec<ProjectBusyTime> = projects
+            .into_iter()
+            .map(|(directory, busy_ms)| ProjectBusyTime { directory, busy_ms })
+            .collect();
+        projects.sort_by(|a, b| b.busy_ms.cmp(&a.busy_ms));
+        let mut sessions: Vec<SessionBusyTime> = sessions.into_values().collect();
+        sessions.sort_by(|a, b| b.busy_ms.cmp(&a.busy_ms));
+
+        Ok(TimeReport {
+            from,
+            to,
+            total_ms: day_totals.iter().map(|day| day.busy_ms).sum(),
+            days: day_totals,
+            projects,
+            sessions,
+        })
+    }
+
+    fn schedule_save(&self) {
+        let generation = self.save_generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let tracker = self.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(SAVE_DEBOUNCE).await;
+            if tracker.save_generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            if let Err(err) = tracker.save().await {
+                warn!("[desktop] Failed to save busy time: {err}");
+            }
+        });
+    }
+
+    async fn save(&self) -> Result<()> {
+        let data = {
+            let mut ledger = self
+                .ledger
+                .lock()
+                .map_err(|_| anyhow!("Busy time ledger poisoned"))?;
+            ledger.prune();
+            serde_json::to_vec(&BusyTimeFile {
+                days: ledger.days.clone(),
+            })?
+        };
+        let path = busy_time_file_path()?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let temp_path = path.with_extension("json.tmp");
+        tokio::fs::write(&temp_path, data).await?;
+        tokio::fs::rename(&temp_path, &path).await?;
+        Ok(())
+    }
+}
+
+/// Wait for the local date to move past `current`, then advance it and return the day
+/// that ended as `YYYY-MM-DD`.
+pub(crate) async fn day_ended(current: &mut NaiveDate) -> String {
+    loop {
+        tokio::time::sleep(until_midnight().min(MIDNIGHT_RECHECK)).await;
+        let today = Local::now().date_naive();
+        if today != *current {
+            let ended = current.format(DAY_FORMAT).to_string();
+            *current = today;
+            return ended;
+        }
+    }
+}
+
+/// At every local midnight, emit a summary of the day that just ended.
+pub async fn announce_daily_summaries(app: AppHandle) {
+    let mut current = Local::now().date_naive();
+    loop {
+        let ended = day_ended(&mut current).await;
+        let report = match app.state::<BusyTime>().report(Some(&ended), Some(&ended)) {
+            Ok(report) => report,
+            Err(err) => {
+                warn!("[desktop] Failed to summarize busy time for {ended}: {err}");
+                continue;
+            }
+        };
+        let _ = app.emit(
+            DAILY_SUMMARY_EVENT,
+            DailySummary {
+                date: ended,
+                total_ms: report.total_ms,
+                sessions: report.sessions.len(),
+                projects: report.projects.len(),
+            },
+        );
+    }
+}
+
+fn until_midnight() -> Duration {
+    let now = Local::now();
+    now.date_naive()
+        .succ_opt()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+        .and_then(|midnight| (midnight - now).to_std().ok())
+        .unwrap_or(MIDNIGHT_RECHECK)
+}
+
+fn parse_day(raw: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(raw.trim(), DAY_FORMAT)
+        .map_err(|_| format!("Invalid date \"{raw}\"; expected YYYY-MM-DD"))
+}
+
+fn busy_time_file_path() -> Result<PathBuf> {
+    let mut path = dirs::home_dir().ok_or_else(|| anyhow!("No home directory"))?;
+    path.push(".config");
+    path.push("openchamber");
+    path.push(BUSY_TIME_FILE);
+    Ok(path)
+}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::session_abort::{self, AbortResult};
+use crate::session_info::{session_info, SessionInfo, SessionInfoCache};
+use crate::DesktopRuntime;
+
+/// User actions that should bring event streams back immediately.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UserIntentKind {
+    OpenSession,
+    SendMessage,
+    SwitchProject,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserIntentResult {
+    /// True when no stream was parked or backing off, so nothing had to be resumed.
+    already_live: bool,
+    /// True when the OpenCode server was down and this signal started waking it.
+    waking_server: bool,
+}
+
+/// Resume parked SSE streams right away after a user action, skipping any pending
+/// reconnect delay. Returns once streams are connecting, not necessarily connected.
+#[tauri::command]
+pub async fn signal_user_intent(
+    kind: UserIntentKind,
+    state: State<'_, DesktopRuntime>,
+) -> Result<UserIntentResult, String> {
+    let already_live = state.streams_live();
+    let waking_server = state.wake_streams();
+    log::debug!(
+        "[desktop:activity] User intent {kind:?} (already_live={already_live}, waking_server={waking_server})"
+    );
+
+    Ok(UserIntentResult {
+        already_live,
+        waking_server,
+    })
+}
+
+/// A session's title, parent and project, looked up on the server when not cached yet.
+/// `None` when the server does not know the session or is not running.
+#[tauri::command]
+pub async fn get_session_info(
+    app: AppHandle,
+    session_id: String,
+) -> Result<Option<SessionInfo>, String> {
+    let runtime = app.state::<DesktopRuntime>();
+    let opencode = runtime.opencode_manager();
+    let base = opencode.subscribe_status().borrow().base_url();
+    let Some(base) = base else {
+        return Ok(app.state::<SessionInfoCache>().get(&session_id));
+    };
+    let client = runtime.http().api();
+    let token = opencode.auth_token();
+    Ok(session_info(&app, &client, &base, token.as_deref(), &session_id, None).await)
+}
+
+/// Stop one session, wherever it runs.
+#[tauri::command]
+pub async fn abort_session(app: AppHandle, session_id: String) -> Result<AbortResult, String> {
+    Ok(session_abort::abort_session(&app, &session_id).await)
+}
+
+/// Stop every session the activity tracker sees running, reporting each one.
+#[tauri::command]
+pub async fn abort_all_busy_sessions(app: AppHandle) -> Result<Vec<AbortResult>, String> {
+    Ok(session_abort::abort_all_busy_sessions(&app).await)
+}
+
+/// Disconnect both event streams and stop notifying until resumed, also across restarts.
+#[tauri::command]
+pub async fn pause_background_streams(state: State<'_, DesktopRuntime>) -> Result<(), String> {
+    state
+        .set_background_paused(true)
+        .await
+        .map_err(|e| format!("Failed to save settings: {e}"))
+}
+
+/// Reconnect right away after `pause_background_streams`.
+#[tauri::command]
+pub async fn resume_background_streams(state: State<'_, DesktopRuntime>) -> Result<(), String> {
+    state
+        .set_background_paused(false)
+        .await
+        .map_err(|e| format!("Failed to save settings: {e}"))
+}
@@ -0,0 +1,14 @@
+use tauri::State;
+
+use crate::busy_time::{BusyTime, TimeReport};
+
+/// Time sessions spent working between two local dates (`YYYY-MM-DD`, inclusive), by day,
+/// project and session. Without dates it covers the last 7 days.
+#[tauri::command]
+pub fn get_time_report(
+    busy_time: State<'_, BusyTime>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<TimeReport, String> {
+    busy_time.report(from.as_deref(), to.as_deref())
+}
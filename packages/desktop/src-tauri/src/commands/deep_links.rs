@@ -0,0 +1,19 @@
+use tauri::{AppHandle, State};
+
+use crate::crash_reports::CrashReports;
+use crate::deep_links::{open_deep_link, DeepLinks};
+
+/// Called by the frontend once it listens for navigation events. Follows the links that
+/// arrived before, such as the one the app was launched with, and reports a crash from
+/// the previous run.
+#[tauri::command]
+pub fn deep_links_ready(
+    app: AppHandle,
+    links: State<'_, DeepLinks>,
+    crash_reports: State<'_, CrashReports>,
+) {
+    crash_reports.frontend_ready(&app);
+    for url in links.frontend_ready() {
+        tauri::async_runtime::spawn(open_deep_link(app.clone(), url));
+    }
+}
@@ -0,0 +1,40 @@
+use chrono::Local;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::diagnostics::export_bundle;
+use crate::path_utils::expand_path;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsExport {
+    pub path: String,
+    pub size: u64,
+}
+
+/// Write a diagnostics bundle for bug reports. `path` is the archive to create, or a
+/// folder to create a timestamped one in.
+#[tauri::command]
+pub async fn export_diagnostics(app: AppHandle, path: String) -> Result<DiagnosticsExport, String> {
+    let mut path = expand_path(path.trim());
+    if path.as_os_str().is_empty() {
+        return Err("No destination given for the diagnostics bundle".to_string());
+    }
+    if tokio::fs::metadata(&path)
+        .await
+        .is_ok_and(|metadata| metadata.is_dir())
+    {
+        path.push(format!(
+            "openchamber-diagnostics-{}.zip",
+            Local::now().format("%Y%m%d-%H%M%S")
+        ));
+    }
+
+    let size = export_bundle(&app, path.clone())
+        .await
+        .map_err(|err| format!("Failed to export diagnostics: {err}"))?;
+    Ok(DiagnosticsExport {
+        path: path.to_string_lossy().to_string(),
+        size,
+    })
+}
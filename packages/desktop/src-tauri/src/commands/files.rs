@@ -1,4 +1,4 @@
-use crate::path_utils::expand_tilde_path;
+use crate::path_utils::expand_path;
 use crate::{DesktopRuntime, SettingsStore};
 use serde::{Deserialize, Serialize};
 use std::{
@@ -432,7 +432,7 @@ async fn resolve_sandboxed_path(
         .unwrap_or_else(default_home_directory);
 
     let candidate_path = match candidate_input {
-        Some(value) => expand_tilde_path(value),
+        Some(value) => expand_path(value),
         None => fallback_root.clone(),
     };
 
@@ -462,7 +462,7 @@ async fn resolve_creatable_path(
     workspace_roots: &[PathBuf],
     default_root: Option<&PathBuf>,
 ) -> Result<PathBuf, FsCommandError> {
-    let candidate = expand_tilde_path(path);
+    let candidate = expand_path(path);
     if candidate.as_os_str().is_empty() {
         return Err(FsCommandError::Other("Path is required".to_string()));
     }
@@ -511,9 +511,7 @@ async fn resolve_workspace_roots(settings: &SettingsStore) -> (Vec<PathBuf>, Opt
                     }
                     entry.get("path").and_then(|v| v.as_str())
                 }) {
-                    if let Ok(canonicalized) =
-                        fs::canonicalize(expand_tilde_path(active_path)).await
-                    {
+                    if let Ok(canonicalized) = fs::canonicalize(expand_path(active_path)).await {
                         default_root = Some(canonicalized.clone());
                         roots.push(canonicalized);
                     }
@@ -524,7 +522,7 @@ async fn resolve_workspace_roots(settings: &SettingsStore) -> (Vec<PathBuf>, Opt
         if let Some(projects) = value.get("projects").and_then(|v| v.as_array()) {
             for entry in projects {
                 if let Some(path) = entry.get("path").and_then(|v| v.as_str()) {
-                    if let Ok(canonicalized) = fs::canonicalize(expand_tilde_path(path)).await {
+                    if let Ok(canonicalized) = fs::canonicalize(expand_path(path)).await {
                         roots.push(canonicalized);
                     }
                 }
@@ -532,7 +530,7 @@ async fn resolve_workspace_roots(settings: &SettingsStore) -> (Vec<PathBuf>, Opt
         }
 
         if let Some(last_dir) = value.get("lastDirectory").and_then(|v| v.as_str()) {
-            if let Ok(canonicalized) = fs::canonicalize(expand_tilde_path(last_dir)).await {
+            if let Ok(canonicalized) = fs::canonicalize(expand_path(last_dir)).await {
                 if default_root.is_none() {
                     default_root = Some(canonicalized.clone());
                 }
@@ -1,4 +1,4 @@
-use crate::path_utils::expand_tilde_path;
+use crate::path_utils::expand_path;
 use crate::{DesktopRuntime, SettingsStore};
 use anyhow::{anyhow, Context, Result};
 use log::{error, info, warn};
@@ -554,7 +554,7 @@ fn append_git_option_map(args: &mut Vec<String>, map: &serde_json::Map<String, V
 // Removed unused resolve_workspace_root function
 
 async fn validate_git_path(path: &str, _settings: &SettingsStore) -> Result<PathBuf> {
-    let path_buf = expand_tilde_path(path);
+    let path_buf = expand_path(path);
     if !path_buf.exists() {
         return Err(anyhow!("Directory does not exist: {}", path));
     }
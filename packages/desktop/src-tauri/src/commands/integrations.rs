@@ -0,0 +1,32 @@
+use serde::Serialize;
+use tauri::State;
+
+use crate::status_file::status_file_path;
+use crate::DesktopRuntime;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusFileInfo {
+    /// `integrations.statusFile`.
+    enabled: bool,
+    /// Where the file is written, for the settings page to show.
+    path: Option<String>,
+}
+
+/// The status file's setting and location.
+#[tauri::command]
+pub async fn get_status_file_info(
+    state: State<'_, DesktopRuntime>,
+) -> Result<StatusFileInfo, String> {
+    let settings = state
+        .settings()
+        .load_typed()
+        .await
+        .map_err(|err| err.to_string())?;
+    Ok(StatusFileInfo {
+        enabled: settings.integrations.status_file,
+        path: status_file_path()
+            .ok()
+            .map(|path| path.to_string_lossy().to_string()),
+    })
+}
@@ -1,5 +1,10 @@
+use crate::log_levels::{self, ModuleLogLevel};
 use crate::logging::log_file_path;
+use crate::opencode_log;
+use crate::DesktopRuntime;
+use log::info;
 use serde::Serialize;
+use tauri::State;
 use tokio::fs;
 
 #[derive(Serialize)]
@@ -23,3 +28,39 @@ pub async fn fetch_desktop_logs() -> Result<DesktopLogFile, String> {
 
     Ok(DesktopLogFile { file_name, content })
 }
+
+/// Longest tail a single request may ask for.
+const MAX_OPENCODE_LOG_LINES: usize = 5_000;
+
+/// The most recent lines the OpenCode server printed, oldest first.
+#[tauri::command]
+pub async fn get_opencode_logs(lines: usize) -> Result<Vec<String>, String> {
+    Ok(opencode_log::tail(lines.min(MAX_OPENCODE_LOG_LINES)).await)
+}
+
+/// The level of each module whose logging can be adjusted at runtime.
+#[tauri::command]
+pub fn get_log_levels() -> Vec<ModuleLogLevel> {
+    log_levels::levels()
+}
+
+/// Change a module's log level now and remember it across restarts.
+#[tauri::command]
+pub async fn set_log_level(
+    state: State<'_, DesktopRuntime>,
+    module: String,
+    level: String,
+) -> Result<Vec<ModuleLogLevel>, String> {
+    let (module, level) = log_levels::set(&module, &level)?;
+    info!("[desktop] Log level for {module} set to {level}");
+    state
+        .settings()
+        .update_typed(|settings| {
+            settings
+                .log_levels
+                .insert(module.to_string(), level.as_str().to_ascii_lowercase());
+        })
+        .await
+        .map_err(|err| format!("Failed to save log level: {err}"))?;
+    Ok(log_levels::levels())
+}
@@ -1,7 +1,19 @@
+pub mod activity;
+pub mod busy_time;
+pub mod deep_links;
+pub mod diagnostics;
 pub mod files;
 pub mod git;
+pub mod integrations;
 pub mod logs;
 pub mod notifications;
 pub mod permissions;
+pub mod power;
+pub mod secrets;
 pub mod settings;
+pub mod shortcut;
+pub mod sse_events;
+pub mod tasks;
 pub mod terminal;
+pub mod usage;
+pub mod window;
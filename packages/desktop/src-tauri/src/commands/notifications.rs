@@ -1,7 +1,21 @@
-use serde::Deserialize;
-use tauri::{AppHandle, Runtime};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Manager, State};
 use tauri_plugin_notification::NotificationExt;
 
+use crate::assistant_notifications::{
+    apply_delivery_rules, available_sounds, configured_sound, do_not_disturb_state, mute_session,
+    pending_question_details, resolve_sound, show_test_notification, unmute_session,
+    ActiveSessions, DoNotDisturbState, MutedSession, MutedSessions, NotificationCategory,
+    NotificationHistory, NotificationOutcome, NotificationRecord, PendingQuestionDetails,
+    SoundKind, TestNotificationReport,
+};
+use crate::DesktopRuntime;
+
+const PERMISSION_REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NotificationPayload {
@@ -10,8 +24,8 @@ pub struct NotificationPayload {
 }
 
 #[tauri::command]
-pub async fn desktop_notify<R: Runtime>(
-    app: AppHandle<R>,
+pub async fn desktop_notify(
+    app: AppHandle,
     payload: Option<NotificationPayload>,
 ) -> Result<bool, String> {
     let title = payload
@@ -23,15 +37,254 @@ pub async fn desktop_notify<R: Runtime>(
         .and_then(|p| p.body.as_deref())
         .unwrap_or("Task completed");
 
-    match app
-        .notification()
+    let history = app.state::<NotificationHistory>();
+    let record = |outcome| {
+        NotificationRecord::new(
+            NotificationCategory::Other,
+            None,
+            Some((title, body)),
+            outcome,
+        )
+    };
+
+    // Held during quiet hours or Do Not Disturb; it shows up in the summary once they end.
+    let with_sound = match apply_delivery_rules(&app, NotificationCategory::Other, 1, None).await {
+        Ok(with_sound) => with_sound,
+        Err(reason) => {
+            history.record(record(NotificationOutcome::Suppressed(reason)));
+            return Ok(false);
+        }
+    };
+
+    let sound = if with_sound {
+        configured_sound(&app, SoundKind::Completion).await
+    } else {
+        None
+    };
+    let mut builder = app.notification().builder().title(title).body(body);
+    if let Some(sound) = sound {
+        builder = builder.sound(sound);
+    }
+    match builder.show() {
+        Ok(_) => {
+            history.record(record(if sound.is_some() {
+                NotificationOutcome::Shown
+            } else {
+                NotificationOutcome::ShownSilently
+            }));
+            Ok(true)
+        }
+        Err(e) => {
+            history.record(record(NotificationOutcome::Failed(e.to_string())));
+            Err(e.to_string())
+        }
+    }
+}
+
+/// Record which session `window` is showing, so notifications about it are held back while
+/// the user is looking at it. `None` when no session is open.
+#[tauri::command]
+pub async fn set_active_session(
+    window: tauri::Window,
+    active: State<'_, ActiveSessions>,
+    session_id: Option<String>,
+) -> Result<(), String> {
+    active.set(window.label(), session_id);
+    Ok(())
+}
+
+/// Mute notifications for a session, for `duration_minutes` or until unmuted.
+#[tauri::command]
+pub async fn mute_session_notifications(
+    app: AppHandle,
+    session_id: String,
+    duration_minutes: Option<u64>,
+) -> Result<(), String> {
+    let session_id = session_id.trim();
+    if session_id.is_empty() {
+        return Err("Session id is required".to_string());
+    }
+    mute_session(
+        &app,
+        session_id,
+        duration_minutes.filter(|minutes| *minutes > 0),
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unmute_session_notifications(
+    app: AppHandle,
+    session_id: String,
+) -> Result<(), String> {
+    unmute_session(&app, session_id.trim());
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_muted_sessions(
+    muted: State<'_, MutedSessions>,
+) -> Result<Vec<MutedSession>, String> {
+    Ok(muted.list())
+}
+
+/// Show a notification through the regular pipeline and report each step, so the settings
+/// screen can tell OS permission problems apart from event handling ones.
+#[tauri::command]
+pub async fn send_test_notification(app: AppHandle) -> Result<TestNotificationReport, String> {
+    Ok(show_test_notification(&app).await)
+}
+
+/// Whether the system Do Not Disturb / Focus mode is on, for display in settings.
+#[tauri::command]
+pub async fn get_do_not_disturb_state() -> Result<DoNotDisturbState, String> {
+    Ok(do_not_disturb_state().await)
+}
+
+/// Questions the agent is still waiting on, oldest first, as counted by the badge, the
+/// taskbar and the tray.
+#[tauri::command]
+pub async fn get_pending_questions(app: AppHandle) -> Result<Vec<PendingQuestionDetails>, String> {
+    Ok(pending_question_details(&app))
+}
+
+/// Recent notification decisions, including suppressed ones and why, oldest first.
+#[tauri::command]
+pub async fn get_notification_history(
+    history: State<'_, NotificationHistory>,
+) -> Result<Vec<NotificationRecord>, String> {
+    Ok(history.snapshot())
+}
+
+#[tauri::command]
+pub async fn clear_notification_history(
+    history: State<'_, NotificationHistory>,
+) -> Result<(), String> {
+    history.clear();
+    Ok(())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationSounds {
+    /// Sound names this platform can play; `none` is always accepted as well.
+    sounds: Vec<&'static str>,
+    completion_default: Option<&'static str>,
+    question_default: Option<&'static str>,
+}
+
+/// List the notification sounds the settings UI can offer on this platform.
+#[tauri::command]
+pub async fn list_notification_sounds() -> Result<NotificationSounds, String> {
+    Ok(NotificationSounds {
+        sounds: available_sounds().to_vec(),
+        completion_default: resolve_sound(None, SoundKind::Completion),
+        question_default: resolve_sound(None, SoundKind::Question),
+    })
+}
+
+/// Play a notification sound so it can be auditioned from settings. Unknown names play the
+/// platform default, matching what a real notification would do.
+#[tauri::command]
+pub async fn play_notification_sound_preview(
+    app: AppHandle,
+    sound: String,
+) -> Result<bool, String> {
+    let Some(sound) = resolve_sound(Some(&sound), SoundKind::Completion) else {
+        return Ok(false);
+    };
+
+    if play_sound_directly(sound).await {
+        return Ok(true);
+    }
+
+    // No standalone player available; let the notification backend play it.
+    app.notification()
         .builder()
-        .title(title)
-        .body(body)
-        .sound("Glass")
+        .title("OpenChamber")
+        .body("Notification sound preview")
+        .sound(sound)
         .show()
-    {
-        Ok(_) => Ok(true),
-        Err(e) => Err(e.to_string()),
+        .map(|_| true)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(target_os = "macos")]
+async fn play_sound_directly(sound: &str) -> bool {
+    tokio::process::Command::new("afplay")
+        .arg(format!("/System/Library/Sounds/{sound}.aiff"))
+        .status()
+        .await
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+#[cfg(target_os = "linux")]
+async fn play_sound_directly(sound: &str) -> bool {
+    tokio::process::Command::new("canberra-gtk-play")
+        .args(["-i", sound])
+        .status()
+        .await
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+async fn play_sound_directly(_sound: &str) -> bool {
+    false
+}
+
+/// How to answer a pending tool permission request.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionReply {
+    Once,
+    Always,
+    Reject,
+}
+
+impl PermissionReply {
+    fn as_str(self) -> &'static str {
+        match self {
+            PermissionReply::Once => "once",
+            PermissionReply::Always => "always",
+            PermissionReply::Reject => "reject",
+        }
+    }
+}
+
+/// Approve or deny an OpenCode permission request by id, without going through the UI's
+/// session state. `directory` scopes the request to a project when it is known.
+#[tauri::command]
+pub async fn reply_to_permission(
+    permission_id: String,
+    reply: PermissionReply,
+    directory: Option<String>,
+    state: State<'_, DesktopRuntime>,
+) -> Result<bool, String> {
+    let opencode = state.opencode_manager();
+    let port = opencode
+        .current_port()
+        .ok_or_else(|| "OpenCode is not running".to_string())?;
+    let url = format!(
+        "http://{}:{port}{}/permission/{}/reply",
+        opencode.host(),
+        opencode.api_prefix(),
+        urlencoding::encode(&permission_id)
+    );
+
+    let mut request = opencode
+        .authorize(state.http().api().post(&url))
+        .timeout(PERMISSION_REPLY_TIMEOUT)
+        .json(&json!({ "reply": reply.as_str() }));
+    if let Some(directory) = directory.filter(|value| !value.trim().is_empty()) {
+        request = request.query(&[("directory", directory)]);
+    }
+
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("Permission reply failed: {}", response.status()));
     }
+
+    Ok(true)
 }
@@ -6,7 +6,7 @@ use tauri::AppHandle;
 use tauri::State;
 use uuid::Uuid;
 
-use crate::path_utils::expand_tilde_path;
+use crate::path_utils::expand_path;
 use crate::DesktopRuntime;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -40,7 +40,7 @@ pub async fn process_directory_selection(
     state: State<'_, DesktopRuntime>,
 ) -> Result<DirectoryPermissionResult, String> {
     // Validate directory exists
-    let mut path_buf = expand_tilde_path(&path);
+    let mut path_buf = expand_path(&path);
     if let Ok(canonicalized) = std::fs::canonicalize(&path_buf) {
         path_buf = canonicalized;
     }
@@ -175,7 +175,7 @@ pub async fn request_directory_access(
 ) -> Result<DirectoryPermissionResult, String> {
     let path = request.path;
 
-    let mut path_buf = expand_tilde_path(&path);
+    let mut path_buf = expand_path(&path);
     if let Ok(canonicalized) = std::fs::canonicalize(&path_buf) {
         path_buf = canonicalized;
     }
@@ -0,0 +1,9 @@
+use tauri::State;
+
+use crate::sleep_inhibitor::{SleepInhibition, SleepInhibitionState};
+
+/// Whether sleep prevention is on and whether it is blocking system sleep right now.
+#[tauri::command]
+pub fn get_power_state(inhibition: State<'_, SleepInhibition>) -> SleepInhibitionState {
+    inhibition.snapshot()
+}
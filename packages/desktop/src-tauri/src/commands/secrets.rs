@@ -0,0 +1,66 @@
+use tauri::State;
+
+use crate::secrets::OPENCODE_AUTH_TOKEN;
+use crate::DesktopRuntime;
+
+const MAX_SECRET_NAME_LENGTH: usize = 128;
+
+/// Names are dotted identifiers such as `notifications.webhookSecret`.
+fn validate_secret_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_SECRET_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid secret name: {name}"))
+    }
+}
+
+/// Store a secret in the OS keychain (or the encrypted fallback file).
+#[tauri::command]
+pub async fn set_secret(
+    name: String,
+    value: String,
+    state: State<'_, DesktopRuntime>,
+) -> Result<(), String> {
+    validate_secret_name(&name)?;
+    state
+        .secrets()
+        .set(&name, &value)
+        .await
+        .map_err(|e| format!("Failed to store secret: {}", e))?;
+    if name == OPENCODE_AUTH_TOKEN {
+        state.reload_opencode_auth_token().await;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_secret(
+    name: String,
+    state: State<'_, DesktopRuntime>,
+) -> Result<Option<String>, String> {
+    validate_secret_name(&name)?;
+    state
+        .secrets()
+        .get(&name)
+        .await
+        .map_err(|e| format!("Failed to read secret: {}", e))
+}
+
+#[tauri::command]
+pub async fn delete_secret(name: String, state: State<'_, DesktopRuntime>) -> Result<(), String> {
+    validate_secret_name(&name)?;
+    state
+        .secrets()
+        .delete(&name)
+        .await
+        .map_err(|e| format!("Failed to delete secret: {}", e))?;
+    if name == OPENCODE_AUTH_TOKEN {
+        state.reload_opencode_auth_token().await;
+    }
+    Ok(())
+}
@@ -1,11 +1,17 @@
 use serde::{Deserialize, Serialize};
-use chrono::Utc;
+use log::warn;
 use serde_json::{json, Value};
 use std::collections::HashSet;
+use std::path::Path;
 use tauri::State;
-use uuid::Uuid;
 
-use crate::path_utils::expand_tilde_path;
+use crate::desktop_settings::{
+    migrate as migrate_settings, DesktopSettings, SETTINGS_SCHEMA_VERSION,
+};
+use crate::log_levels;
+use crate::path_utils::expand_path;
+use crate::secrets::{migrate_settings_secrets, strip_secrets};
+use crate::sse_event_log;
 use crate::DesktopRuntime;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -21,13 +27,51 @@ pub struct RestartResult {
     restarted: bool,
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsImportResult {
+    settings: Value,
+    /// Imported project paths that do not exist on this machine. They are kept so the
+    /// user can fix them up rather than re-add them.
+    missing_projects: Vec<String>,
+}
+
+/// Why `update_setting` refused a change. Nothing is written when this is returned.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsUpdateError {
+    code: SettingsUpdateErrorCode,
+    path: String,
+    message: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum SettingsUpdateErrorCode {
+    /// The path is empty, malformed, or runs through a value that is not a container.
+    InvalidPath,
+    /// The value has the wrong type, is not an allowed variant, or names an unknown setting.
+    InvalidValue,
+    /// Reading or writing the settings file failed.
+    Storage,
+}
+
+impl SettingsUpdateError {
+    fn new(code: SettingsUpdateErrorCode, path: &str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            path: path.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
 /// Load settings from disk.
 #[tauri::command]
 pub async fn load_settings(state: State<'_, DesktopRuntime>) -> Result<SettingsLoadResult, String> {
     let (settings, _) = state
         .settings()
         .update_with(|mut settings| {
-            migrate_legacy_project_settings(&mut settings);
             normalize_project_selection(&mut settings);
             (settings, ())
         })
@@ -58,9 +102,355 @@ pub async fn save_settings(
         .await
         .map_err(|e| format!("Failed to save settings: {}", e))?;
 
+    let merged = apply_saved_settings(&state, merged).await;
     Ok(format_settings_response(&merged))
 }
 
+/// Change a single setting at a JSON pointer path such as `/notifications/questionAsked`,
+/// without rewriting the rest of the file. A `null` value removes the key. The change is
+/// validated before anything is written, and the full settings are returned so the
+/// frontend can reconcile its copy.
+#[tauri::command]
+pub async fn update_setting(
+    path: String,
+    value: Value,
+    state: State<'_, DesktopRuntime>,
+) -> Result<Value, SettingsUpdateError> {
+    let segments = parse_setting_path(&path)?;
+
+    let (merged, outcome) = state
+        .settings()
+        .update_with(|current| {
+            let applied = apply_setting(&current, &path, &segments, value);
+            match applied {
+                Ok(mut merged) => {
+                    normalize_project_selection(&mut merged);
+                    (merged, Ok(()))
+                }
+                Err(err) => (current, Err(err)),
+            }
+        })
+        .await
+        .map_err(|e| {
+            SettingsUpdateError::new(
+                SettingsUpdateErrorCode::Storage,
+                &path,
+                format!("Failed to update settings: {e}"),
+            )
+        })?;
+    outcome?;
+
+    let merged = apply_saved_settings(&state, merged).await;
+    Ok(format_settings_response(&merged))
+}
+
+/// Write the settings and project list to `path` for another machine. Secrets and
+/// security-scoped bookmarks, which only mean something here, are left out.
+#[tauri::command]
+pub async fn export_settings(path: String, state: State<'_, DesktopRuntime>) -> Result<(), String> {
+    let settings = state
+        .settings()
+        .load_typed()
+        .await
+        .map_err(|e| format!("Failed to load settings: {}", e))?;
+
+    let mut exported = settings.into_value();
+    strip_secrets(&mut exported);
+    if let Some(obj) = exported.as_object_mut() {
+        obj.remove("securityScopedBookmarks");
+    }
+
+    let bytes = serde_json::to_vec_pretty(&exported)
+        .map_err(|e| format!("Failed to encode settings: {}", e))?;
+    tokio::fs::write(expand_path(&path), bytes)
+        .await
+        .map_err(|e| format!("Failed to write settings export: {}", e))
+}
+
+/// Read settings exported by `export_settings`. With `merge`, incoming projects are added
+/// to the existing ones and incoming preferences win; otherwise the file replaces the
+/// current settings. Files from a newer build are refused.
+#[tauri::command]
+pub async fn import_settings(
+    path: String,
+    merge: bool,
+    state: State<'_, DesktopRuntime>,
+) -> Result<SettingsImportResult, String> {
+    let bytes = tokio::fs::read(expand_path(&path))
+        .await
+        .map_err(|e| format!("Failed to read settings file: {}", e))?;
+    let mut incoming: Value = serde_json::from_slice(&bytes)
+        .map_err(|e| format!("Settings file is not valid JSON: {}", e))?;
+    if !incoming.is_object() {
+        return Err("Settings file does not contain a settings object".to_string());
+    }
+
+    let version = incoming
+        .get("schemaVersion")
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    if version > SETTINGS_SCHEMA_VERSION {
+        return Err(format!(
+            "Settings file uses schema version {version}, but this version of OpenChamber only understands up to {SETTINGS_SCHEMA_VERSION}. Update the app and try again."
+        ));
+    }
+    migrate_settings(&mut incoming);
+    strip_secrets(&mut incoming);
+
+    // Only keys the app knows, in the shapes it accepts, get in.
+    let sanitized = sanitize_settings_update(&incoming);
+    let missing_projects = sanitized
+        .get("projects")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|project| project.get("path").and_then(Value::as_str))
+        .filter(|path| !Path::new(path).is_dir())
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    for path in &missing_projects {
+        warn!("[desktop] Imported project does not exist on this machine: {path}");
+    }
+
+    let (imported, _) = state
+        .settings()
+        .update_with(|current| {
+            let mut imported = if merge {
+                merge_imported_settings(&current, &sanitized)
+            } else {
+                let mut replaced = merge_persisted_settings(&json!({}), &sanitized);
+                // Bookmarks grant access on this machine only and never come from a file.
+                if let (Some(obj), Some(bookmarks)) = (
+                    replaced.as_object_mut(),
+                    current.get("securityScopedBookmarks"),
+                ) {
+                    obj.insert("securityScopedBookmarks".to_string(), bookmarks.clone());
+                }
+                replaced
+            };
+            imported["schemaVersion"] = json!(SETTINGS_SCHEMA_VERSION);
+            normalize_project_selection(&mut imported);
+            (imported, ())
+        })
+        .await
+        .map_err(|e| format!("Failed to save imported settings: {}", e))?;
+
+    let imported = apply_saved_settings(&state, imported).await;
+    Ok(SettingsImportResult {
+        settings: format_settings_response(&imported),
+        missing_projects,
+    })
+}
+
+/// Incoming preferences over the current ones, with the union of both project lists.
+/// A project already present at the same path keeps its id so the active project and
+/// anything else referring to it stay valid.
+fn merge_imported_settings(current: &Value, incoming: &Value) -> Value {
+    let mut changes = incoming.clone();
+    let incoming_projects = changes
+        .as_object_mut()
+        .and_then(|obj| obj.remove("projects"))
+        .and_then(|projects| projects.as_array().cloned())
+        .unwrap_or_default();
+    // The active project only makes sense against this machine's list.
+    if let Some(obj) = changes.as_object_mut() {
+        obj.remove("activeProjectId");
+    }
+
+    let mut projects = current
+        .get("projects")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    for project in incoming_projects {
+        let path = project.get("path").and_then(Value::as_str);
+        let existing = projects
+            .iter_mut()
+            .find(|existing| existing.get("path").and_then(Value::as_str) == path);
+        match existing {
+            Some(existing) => {
+                let id = existing.get("id").cloned();
+                if let (Some(existing), Some(project)) =
+                    (existing.as_object_mut(), project.as_object())
+                {
+                    for (key, value) in project {
+                        existing.insert(key.clone(), value.clone());
+                    }
+                    if let Some(id) = id {
+                        existing.insert("id".to_string(), id);
+                    }
+                }
+            }
+            None => projects.push(project),
+        }
+    }
+    changes["projects"] = sanitize_projects(&Value::Array(projects)).unwrap_or(json!([]));
+
+    merge_persisted_settings(current, &changes)
+}
+
+/// Bring running services in line with settings that were just written, and move any
+/// credentials they contain into the secret store. Returns the settings as they ended up
+/// on disk.
+async fn apply_saved_settings(state: &DesktopRuntime, settings: Value) -> Value {
+    let settings = migrate_settings_secrets(state.settings(), state.secrets())
+        .await
+        .unwrap_or(settings);
+    state.reload_opencode_auth_token().await;
+
+    if !DesktopSettings::from_value(settings.clone()).multi_instance_opencode {
+        state.opencode_instances().stop_projects().await;
+    }
+    let opencode = state.opencode_manager();
+    if opencode.apply_settings(&settings) {
+        // Switch between the external server and a spawned one in the background.
+        tauri::async_runtime::spawn(async move {
+            if let Err(err) = opencode.restart().await {
+                warn!("[desktop] Failed to switch OpenCode server: {err}");
+            }
+        });
+    }
+    settings
+}
+
+/// Split a JSON pointer into unescaped segments. The leading `/` is optional.
+fn parse_setting_path(path: &str) -> Result<Vec<String>, SettingsUpdateError> {
+    let trimmed = path.strip_prefix('/').unwrap_or(path);
+    let segments: Vec<String> = trimmed
+        .split('/')
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    if segments.iter().any(String::is_empty) {
+        return Err(SettingsUpdateError::new(
+            SettingsUpdateErrorCode::InvalidPath,
+            path,
+            "Setting paths must name at least one key and contain no empty segments",
+        ));
+    }
+    Ok(segments)
+}
+
+/// Apply one change to `current` and validate the affected top-level setting, first with
+/// the same sanitizer `save_settings` uses and then against the typed model.
+fn apply_setting(
+    current: &Value,
+    path: &str,
+    segments: &[String],
+    value: Value,
+) -> Result<Value, SettingsUpdateError> {
+    let top_key = &segments[0];
+    let removing = value.is_null();
+
+    let mut top_value = current.get(top_key).cloned().unwrap_or(Value::Null);
+    if segments.len() == 1 {
+        top_value = value;
+    } else {
+        set_at_path(&mut top_value, &segments[1..], value).map_err(|message| {
+            SettingsUpdateError::new(SettingsUpdateErrorCode::InvalidPath, path, message)
+        })?;
+    }
+
+    let pointer = segments.iter().fold(String::new(), |mut pointer, segment| {
+        pointer.push('/');
+        pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+        pointer
+    });
+
+    let mut changes = json!({});
+    if !top_value.is_null() {
+        let sanitized = sanitize_settings_update(&json!({ top_key.as_str(): top_value }));
+        match sanitized.get(top_key) {
+            Some(sanitized_top) if removing || sanitized.pointer(&pointer).is_some() => {
+                changes[top_key.as_str()] = sanitized_top.clone();
+            }
+            None if removing => {}
+            _ => {
+                return Err(SettingsUpdateError::new(
+                    SettingsUpdateErrorCode::InvalidValue,
+                    path,
+                    format!("`{path}` is not a known setting or does not accept this value"),
+                ))
+            }
+        }
+    }
+
+    let mut merged = merge_persisted_settings(current, &changes);
+    if removing {
+        // Merging keeps keys the change left out, so drop the removed one explicitly.
+        let _ = set_at_path(&mut merged, segments, Value::Null);
+    }
+
+    // Typed fields fall back to their defaults when a value has the wrong shape, so a
+    // value that does not survive a round trip through the model is rejected.
+    let typed = DesktopSettings::from_value(merged.clone()).into_value();
+    if !removing && typed.pointer(&pointer) != merged.pointer(&pointer) {
+        return Err(SettingsUpdateError::new(
+            SettingsUpdateErrorCode::InvalidValue,
+            path,
+            format!("`{path}` has the wrong type for this setting"),
+        ));
+    }
+
+    Ok(merged)
+}
+
+/// Set (or, for `null`, remove) the value at `segments` below `target`, creating
+/// intermediate objects as needed. Array elements are addressed by index, and `-`
+/// appends.
+fn set_at_path(target: &mut Value, segments: &[String], value: Value) -> Result<(), String> {
+    let (last, parents) = segments
+        .split_last()
+        .ok_or_else(|| "Setting path is empty".to_string())?;
+
+    let mut node = target;
+    for segment in parents {
+        if node.is_null() {
+            *node = json!({});
+        }
+        node = match node {
+            Value::Object(map) => map.entry(segment.clone()).or_insert(Value::Null),
+            Value::Array(items) => segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get_mut(index))
+                .ok_or_else(|| format!("No array element at `{segment}`"))?,
+            _ => return Err(format!("`{segment}` is not inside an object")),
+        };
+    }
+
+    if node.is_null() {
+        *node = json!({});
+    }
+    match node {
+        Value::Object(map) => {
+            if value.is_null() {
+                map.remove(last);
+            } else {
+                map.insert(last.clone(), value);
+            }
+        }
+        Value::Array(items) if last == "-" => {
+            if !value.is_null() {
+                items.push(value);
+            }
+        }
+        Value::Array(items) => {
+            let index = last
+                .parse::<usize>()
+                .ok()
+                .filter(|index| *index < items.len())
+                .ok_or_else(|| format!("No array element at `{last}`"))?;
+            if value.is_null() {
+                items.remove(index);
+            } else {
+                items[index] = value;
+            }
+        }
+        _ => return Err(format!("`{last}` is not inside an object")),
+    }
+    Ok(())
+}
+
 /// Restart the backend process (config reload).
 #[tauri::command]
 pub async fn restart_opencode(state: State<'_, DesktopRuntime>) -> Result<RestartResult, String> {
@@ -94,7 +484,7 @@ fn sanitize_projects(value: &Value) -> Option<Value> {
             continue;
         }
 
-        let expanded = expand_tilde_path(raw_path).to_string_lossy().to_string();
+        let expanded = expand_path(raw_path).to_string_lossy().to_string();
         let normalized = if expanded == "/" {
             expanded
         } else {
@@ -135,6 +525,12 @@ fn sanitize_projects(value: &Value) -> Option<Value> {
             }
         }
 
+        if let Some(Value::String(level)) = obj.get("notifications") {
+            if matches!(level.as_str(), "all" | "questions-only" | "muted") {
+                project.insert("notifications".to_string(), json!(level));
+            }
+        }
+
         // Preserve worktreeDefaults
         if let Some(Value::Object(wt)) = obj.get("worktreeDefaults") {
             let mut defaults = serde_json::Map::new();
@@ -156,6 +552,10 @@ fn sanitize_projects(value: &Value) -> Option<Value> {
             }
         }
 
+        if let Some(overrides) = obj.get("overrides").and_then(sanitize_project_overrides) {
+            project.insert("overrides".to_string(), overrides);
+        }
+
         result.push(Value::Object(project));
     }
 
@@ -170,6 +570,45 @@ fn sanitize_projects(value: &Value) -> Option<Value> {
     }
 }
 
+/// Per-project `overrides`: a muted flag, category toggles, a cooldown, and a mode filter.
+fn sanitize_project_overrides(input: &Value) -> Option<Value> {
+    let obj = input.as_object()?;
+    let mut overrides = serde_json::Map::new();
+
+    if let Some(Value::Bool(muted)) = obj.get("muted") {
+        overrides.insert("muted".to_string(), json!(muted));
+    }
+    if let Some(Value::Object(categories)) = obj.get("notifications") {
+        let mut sanitized = serde_json::Map::new();
+        for key in [
+            "assistantCompleted",
+            "questionAsked",
+            "sessionError",
+            "permissionRequested",
+            "longRunningTool",
+            "contextWindow",
+            "rateLimited",
+        ] {
+            if let Some(Value::Bool(enabled)) = categories.get(key) {
+                sanitized.insert(key.to_string(), json!(enabled));
+            }
+        }
+        if !sanitized.is_empty() {
+            overrides.insert("notifications".to_string(), Value::Object(sanitized));
+        }
+    }
+    if let Some(seconds) = obj.get("cooldownSeconds").and_then(Value::as_u64) {
+        if seconds <= 600 {
+            overrides.insert("cooldownSeconds".to_string(), json!(seconds));
+        }
+    }
+    if let Some(filter) = obj.get("modeFilter").and_then(sanitize_mode_filter) {
+        overrides.insert("modeFilter".to_string(), filter);
+    }
+
+    (!overrides.is_empty()).then_some(Value::Object(overrides))
+}
+
 /// Sanitize settings update payload (port of Express sanitizeSettingsUpdate)
 fn sanitize_settings_update(payload: &Value) -> Value {
     let mut result = json!({});
@@ -200,13 +639,13 @@ fn sanitize_settings_update(payload: &Value) -> Value {
         }
         if let Some(Value::String(s)) = obj.get("lastDirectory") {
             if !s.is_empty() {
-                let expanded = expand_tilde_path(s).to_string_lossy().to_string();
+                let expanded = expand_path(s).to_string_lossy().to_string();
                 result_obj.insert("lastDirectory".to_string(), json!(expanded));
             }
         }
         if let Some(Value::String(s)) = obj.get("homeDirectory") {
             if !s.is_empty() {
-                let expanded = expand_tilde_path(s).to_string_lossy().to_string();
+                let expanded = expand_path(s).to_string_lossy().to_string();
                 result_obj.insert("homeDirectory".to_string(), json!(expanded));
             }
         }
@@ -290,6 +729,25 @@ fn sanitize_settings_update(payload: &Value) -> Value {
         if let Some(Value::Bool(b)) = obj.get("autoCreateWorktree") {
             result_obj.insert("autoCreateWorktree".to_string(), json!(b));
         }
+        if let Some(Value::Bool(b)) = obj.get("multiInstanceOpencode") {
+            result_obj.insert("multiInstanceOpencode".to_string(), json!(b));
+        }
+        if let Some(Value::Bool(b)) = obj.get("forwardFileChanges") {
+            result_obj.insert("forwardFileChanges".to_string(), json!(b));
+        }
+        if let Some(Value::Bool(b)) = obj.get("persistUsage") {
+            result_obj.insert("persistUsage".to_string(), json!(b));
+        }
+        if let Some(Value::Bool(b)) = obj.get("simulateEvents") {
+            result_obj.insert("simulateEvents".to_string(), json!(b));
+        }
+        if let Some(Value::Bool(b)) = obj.get("showTrayIcon") {
+            result_obj.insert("showTrayIcon".to_string(), json!(b));
+        }
+        if let Some(Value::String(s)) = obj.get("globalShortcut") {
+            // Empty turns the shortcut off; combinations are checked when registered.
+            result_obj.insert("globalShortcut".to_string(), json!(s.trim()));
+        }
 
         // Number fields
         if let Some(Value::Number(n)) = obj.get("autoDeleteAfterDays") {
@@ -305,6 +763,47 @@ fn sanitize_settings_update(payload: &Value) -> Value {
                 result_obj.insert("autoDeleteAfterDays".to_string(), json!(clamped));
             }
         }
+        if let Some(Value::Number(n)) = obj.get("activityErrorDecaySeconds") {
+            let parsed = n
+                .as_u64()
+                .or_else(|| n.as_f64().map(|value| value.round().max(0.0) as u64));
+            if let Some(value) = parsed {
+                let clamped = value.max(1).min(3600);
+                result_obj.insert("activityErrorDecaySeconds".to_string(), json!(clamped));
+            }
+        }
+        if let Some(Value::Number(n)) = obj.get("sseEventBufferSize") {
+            let parsed = n
+                .as_u64()
+                .or_else(|| n.as_f64().map(|value| value.round().max(0.0) as u64));
+            if let Some(value) = parsed {
+                let clamped = value.min(sse_event_log::MAX_CAPACITY as u64);
+                result_obj.insert("sseEventBufferSize".to_string(), json!(clamped));
+            }
+        }
+        // An empty object stops using an external server
+        if let Some(Value::Object(opencode)) = obj.get("opencode") {
+            let mut sanitized = serde_json::Map::new();
+            if let Some(external) = opencode.get("external").and_then(sanitize_external_server) {
+                sanitized.insert("external".to_string(), external);
+            }
+            result_obj.insert("opencode".to_string(), Value::Object(sanitized));
+        }
+
+        // 0 unpins the OpenCode port
+        if let Some(port) = obj.get("opencodePort").and_then(sanitize_port) {
+            result_obj.insert("opencodePort".to_string(), json!(port));
+        }
+        if let Some(Value::Object(range)) = obj.get("opencodePortRange") {
+            let start = range.get("start").and_then(sanitize_port);
+            let end = range.get("end").and_then(sanitize_port);
+            if let (Some(start), Some(end)) = (start, end) {
+                result_obj.insert(
+                    "opencodePortRange".to_string(),
+                    json!({ "start": start.min(end), "end": start.max(end) }),
+                );
+            }
+        }
 
         // Array fields
         if let Some(arr) = obj.get("approvedDirectories") {
@@ -330,6 +829,132 @@ fn sanitize_settings_update(payload: &Value) -> Value {
             }
         }
 
+        // Telemetry (opt-in, disabled unless explicitly enabled)
+        if let Some(Value::Object(telemetry)) = obj.get("telemetry") {
+            let mut sanitized = serde_json::Map::new();
+            if let Some(Value::Bool(b)) = telemetry.get("enabled") {
+                sanitized.insert("enabled".to_string(), json!(b));
+            }
+            if let Some(Value::String(s)) = telemetry.get("endpoint") {
+                let trimmed = s.trim();
+                if trimmed.starts_with("https://") || trimmed.starts_with("http://") {
+                    sanitized.insert("endpoint".to_string(), json!(trimmed));
+                }
+            }
+            // Moved into the secret store on save, like `notifications.webhookSecret`.
+            if let Some(Value::String(secret)) = telemetry.get("secret") {
+                if secret.len() <= 256 {
+                    sanitized.insert("secret".to_string(), json!(secret));
+                }
+            }
+            if !sanitized.is_empty() {
+                result_obj.insert("telemetry".to_string(), Value::Object(sanitized));
+            }
+        }
+
+        if let Some(Value::Object(http)) = obj.get("http") {
+            let mut sanitized = serde_json::Map::new();
+            if let Some(Value::String(s)) = http.get("proxy") {
+                let trimmed = s.trim();
+                // An empty string clears the proxy.
+                if trimmed.is_empty() || trimmed.contains("://") {
+                    sanitized.insert("proxy".to_string(), json!(trimmed));
+                }
+            }
+            if let Some(Value::String(s)) = http.get("noProxy") {
+                sanitized.insert("noProxy".to_string(), json!(s.trim()));
+            }
+            if !sanitized.is_empty() {
+                result_obj.insert("http".to_string(), Value::Object(sanitized));
+            }
+        }
+
+        if let Some(Value::Object(window)) = obj.get("window") {
+            let mut sanitized = serde_json::Map::new();
+            for key in ["startHidden", "closeToTray", "statusInTitle"] {
+                if let Some(Value::Bool(b)) = window.get(key) {
+                    sanitized.insert(key.to_string(), json!(b));
+                }
+            }
+            if !sanitized.is_empty() {
+                result_obj.insert("window".to_string(), Value::Object(sanitized));
+            }
+        }
+
+        if let Some(Value::Object(power)) = obj.get("power") {
+            if let Some(Value::Bool(b)) = power.get("preventSleepWhileBusy") {
+                result_obj.insert("power".to_string(), json!({ "preventSleepWhileBusy": b }));
+            }
+        }
+
+        if let Some(Value::Object(integrations)) = obj.get("integrations") {
+            if let Some(Value::Bool(b)) = integrations.get("statusFile") {
+                result_obj.insert("integrations".to_string(), json!({ "statusFile": b }));
+            }
+        }
+
+        if let Some(Value::Object(events)) = obj.get("events") {
+            let mut sanitized = serde_json::Map::new();
+            if let Some(Value::Array(types)) = events.get("forwardTypes") {
+                let mut seen = HashSet::new();
+                let types: Vec<&str> = types
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(str::trim)
+                    .filter(|event_type| !event_type.is_empty() && event_type.len() <= 128)
+                    .filter(|event_type| seen.insert(*event_type))
+                    .take(256)
+                    .collect();
+                sanitized.insert("forwardTypes".to_string(), json!(types));
+            }
+            // 0 turns the timeout off; anything else is kept to at most an hour.
+            for key in ["notifyIdleTimeoutSeconds", "activityIdleTimeoutSeconds"] {
+                if let Some(seconds) = events.get(key).and_then(Value::as_u64) {
+                    sanitized.insert(key.to_string(), json!(seconds.min(3600)));
+                }
+            }
+            if let Some(Value::Bool(paused)) = events.get("paused") {
+                sanitized.insert("paused".to_string(), json!(paused));
+            }
+            if !sanitized.is_empty() {
+                result_obj.insert("events".to_string(), Value::Object(sanitized));
+            }
+        }
+
+        if let Some(Value::Object(hooks)) = obj.get("hooks") {
+            let mut sanitized = serde_json::Map::new();
+            if let Some(Value::Bool(b)) = hooks.get("enabled") {
+                sanitized.insert("enabled".to_string(), json!(b));
+            }
+            // An empty string clears the hook.
+            for key in ["completion", "question", "error"] {
+                if let Some(Value::String(s)) = hooks.get(key) {
+                    sanitized.insert(key.to_string(), json!(s.trim()));
+                }
+            }
+            if !sanitized.is_empty() {
+                result_obj.insert("hooks".to_string(), Value::Object(sanitized));
+            }
+        }
+
+        if let Some(Value::Object(levels)) = obj.get("logLevels") {
+            let sanitized: serde_json::Map<String, Value> = levels
+                .iter()
+                .filter(|(module, _)| log_levels::is_module(module))
+                .filter_map(|(module, level)| {
+                    let level = log_levels::parse_level(level.as_str()?)?;
+                    Some((module.clone(), json!(level.as_str().to_ascii_lowercase())))
+                })
+                .collect();
+            if !sanitized.is_empty() {
+                result_obj.insert("logLevels".to_string(), Value::Object(sanitized));
+            }
+        }
+
+        if let Some(notifications) = obj.get("notifications").and_then(sanitize_notifications) {
+            result_obj.insert("notifications".to_string(), notifications);
+        }
+
         // Skill catalogs (array of objects)
         if let Some(Value::Array(arr)) = obj.get("skillCatalogs") {
             let mut seen: HashSet<String> = HashSet::new();
@@ -394,92 +1019,6 @@ fn sanitize_settings_update(payload: &Value) -> Value {
     result
 }
 
-fn migrate_legacy_project_settings(settings: &mut Value) {
-    if !settings.is_object() {
-        *settings = json!({});
-    }
-
-    let now = Utc::now().timestamp_millis();
-    let obj = settings.as_object_mut().unwrap();
-
-    let has_projects = obj
-        .get("projects")
-        .and_then(|value| value.as_array())
-        .map(|arr| !arr.is_empty())
-        .unwrap_or(false);
-
-    if has_projects {
-        return;
-    }
-
-    let last_directory = obj
-        .get("lastDirectory")
-        .and_then(|value| value.as_str())
-        .map(str::trim)
-        .filter(|value| !value.is_empty())
-        .map(expand_tilde_path);
-
-    let Some(mut last_directory) = last_directory else {
-        return;
-    };
-
-    if let Ok(canonicalized) = std::fs::canonicalize(&last_directory) {
-        last_directory = canonicalized;
-    }
-
-    let Ok(stats) = std::fs::metadata(&last_directory) else {
-        return;
-    };
-    if !stats.is_dir() {
-        return;
-    }
-
-    let normalized_path = last_directory.to_string_lossy().to_string();
-    if normalized_path.trim().is_empty() {
-        return;
-    }
-
-    let project_id = Uuid::new_v4().to_string();
-    let active_project_id = project_id.clone();
-    let project_path = normalized_path.clone();
-
-    let projects_value = obj.entry("projects").or_insert_with(|| json!([]));
-    *projects_value = json!([
-        {
-            "id": project_id,
-            "path": project_path,
-            "addedAt": now,
-            "lastOpenedAt": now
-        }
-    ]);
-
-    obj.insert("activeProjectId".to_string(), json!(active_project_id));
-
-    // Ensure approvedDirectories includes the migrated project root.
-    let approved_value = obj
-        .entry("approvedDirectories")
-        .or_insert_with(|| json!([]));
-    if !approved_value.is_array() {
-        *approved_value = json!([]);
-    }
-
-    if let Some(array) = approved_value.as_array_mut() {
-        array.push(json!(normalized_path.clone()));
-        array.retain(|entry| entry.as_str().is_some_and(|value| !value.trim().is_empty()));
-        let mut seen = HashSet::new();
-        array.retain(|entry| {
-            let Some(value) = entry.as_str() else {
-                return false;
-            };
-            if seen.contains(value) {
-                return false;
-            }
-            seen.insert(value.to_string());
-            true
-        });
-    }
-}
-
 fn normalize_project_selection(settings: &mut Value) {
     let Some(obj) = settings.as_object_mut() else {
         return;
@@ -608,6 +1147,34 @@ fn merge_persisted_settings(current: &Value, changes: &Value) -> Value {
             }
             result_obj.insert("typographySizes".to_string(), json!(merged_typo));
         }
+
+        // Merge nested objects so partial updates keep sibling keys
+        for key in [
+            "telemetry",
+            "http",
+            "window",
+            "hooks",
+            "power",
+            "integrations",
+            "events",
+            "notifications",
+            "logLevels",
+        ] {
+            if !changes_obj.contains_key(key) {
+                continue;
+            }
+            let mut merged = current
+                .get(key)
+                .and_then(|v| v.as_object())
+                .cloned()
+                .unwrap_or_default();
+            if let Some(changes) = changes_obj.get(key).and_then(|v| v.as_object()) {
+                for (nested_key, value) in changes {
+                    merged.insert(nested_key.clone(), value.clone());
+                }
+            }
+            result_obj.insert(key.to_string(), Value::Object(merged));
+        }
     }
 
     result
@@ -678,6 +1245,222 @@ fn normalize_string_array(input: &Value) -> Value {
     }
 }
 
+/// Sanitize the notification preferences object, keeping only known keys
+fn sanitize_notifications(input: &Value) -> Option<Value> {
+    let obj = input.as_object()?;
+    let mut result = serde_json::Map::new();
+
+    for key in &[
+        "assistantCompleted",
+        "questionAsked",
+        "sessionError",
+        "permissionRequested",
+        "replySnippet",
+        "longRunningTool",
+        "contextWindow",
+        "rateLimited",
+        "questionReminderAttention",
+        "requestAttentionOnQuestion",
+    ] {
+        if let Some(Value::Bool(b)) = obj.get(*key) {
+            result.insert(key.to_string(), json!(b));
+        }
+    }
+
+    if let Some(Value::String(url)) = obj.get("webhookUrl") {
+        let url = url.trim();
+        let valid = url.is_empty()
+            || (url.len() <= 2048
+                && url::Url::parse(url)
+                    .is_ok_and(|parsed| matches!(parsed.scheme(), "http" | "https")));
+        if valid {
+            result.insert("webhookUrl".to_string(), json!(url));
+        }
+    }
+    if let Some(Value::String(secret)) = obj.get("webhookSecret") {
+        if secret.len() <= 256 {
+            result.insert("webhookSecret".to_string(), json!(secret));
+        }
+    }
+
+    for key in &["completionSound", "questionSound"] {
+        if let Some(Value::String(sound)) = obj.get(*key) {
+            let sound = sound.trim();
+            if !sound.is_empty() && sound.len() <= 64 {
+                result.insert(key.to_string(), json!(sound));
+            }
+        }
+    }
+
+    if let Some(quiet_hours) = obj.get("quietHours").and_then(sanitize_quiet_hours) {
+        result.insert("quietHours".to_string(), quiet_hours);
+    }
+
+    if let Some(Value::Number(n)) = obj.get("digestWindowSeconds") {
+        let parsed = n
+            .as_u64()
+            .or_else(|| n.as_f64().map(|value| value.round().max(0.0) as u64));
+        if let Some(value) = parsed {
+            result.insert("digestWindowSeconds".to_string(), json!(value.min(300)));
+        }
+    }
+
+    if let Some(Value::Number(n)) = obj.get("replySnippetLength") {
+        let parsed = n
+            .as_u64()
+            .or_else(|| n.as_f64().map(|value| value.round().max(0.0) as u64));
+        if let Some(value) = parsed {
+            let clamped = value.max(20).min(500);
+            result.insert("replySnippetLength".to_string(), json!(clamped));
+        }
+    }
+
+    if let Some(Value::Number(n)) = obj.get("longRunningToolMinutes") {
+        let parsed = n
+            .as_u64()
+            .or_else(|| n.as_f64().map(|value| value.round().max(0.0) as u64));
+        if let Some(value) = parsed {
+            result.insert("longRunningToolMinutes".to_string(), json!(value.min(240)));
+        }
+    }
+
+    if let Some(Value::Number(n)) = obj.get("questionReminderMinutes") {
+        let parsed = n
+            .as_u64()
+            .or_else(|| n.as_f64().map(|value| value.round().max(0.0) as u64));
+        if let Some(value) = parsed {
+            result.insert("questionReminderMinutes".to_string(), json!(value.min(240)));
+        }
+    }
+
+    if let Some(Value::Number(n)) = obj.get("contextWindowPercent") {
+        let parsed = n
+            .as_u64()
+            .or_else(|| n.as_f64().map(|value| value.round().max(0.0) as u64));
+        if let Some(value) = parsed {
+            let clamped = value.max(50).min(99);
+            result.insert("contextWindowPercent".to_string(), json!(clamped));
+        }
+    }
+
+    if let Some(filter) = obj.get("modeFilter").and_then(sanitize_mode_filter) {
+        result.insert("modeFilter".to_string(), filter);
+    }
+
+    if let Some(Value::String(when)) = obj.get("whenFocused") {
+        if matches!(when.as_str(), "never" | "always" | "unless-session-visible") {
+            result.insert("whenFocused".to_string(), json!(when));
+        }
+    }
+
+    if result.is_empty() {
+        None
+    } else {
+        Some(Value::Object(result))
+    }
+}
+
+/// `modeFilter`: an `allow` or `deny` list of agent modes, deduplicated ignoring case.
+fn sanitize_mode_filter(input: &Value) -> Option<Value> {
+    let obj = input.as_object()?;
+    let mut result = serde_json::Map::new();
+
+    if let Some(Value::String(kind)) = obj.get("kind") {
+        if matches!(kind.as_str(), "allow" | "deny") {
+            result.insert("kind".to_string(), json!(kind));
+        }
+    }
+    if let Some(Value::Array(modes)) = obj.get("modes") {
+        let mut seen = HashSet::new();
+        let modes: Vec<&str> = modes
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::trim)
+            .filter(|mode| !mode.is_empty() && mode.len() <= 64)
+            .filter(|mode| seen.insert(mode.to_lowercase()))
+            .take(32)
+            .collect();
+        result.insert("modes".to_string(), json!(modes));
+    }
+    if let Some(Value::Bool(notify)) = obj.get("notifyUnknown") {
+        result.insert("notifyUnknown".to_string(), json!(notify));
+    }
+
+    (!result.is_empty()).then_some(Value::Object(result))
+}
+
+fn sanitize_external_server(input: &Value) -> Option<Value> {
+    let obj = input.as_object()?;
+    let port = obj
+        .get("port")
+        .and_then(sanitize_port)
+        .filter(|port| *port > 0)?;
+    let mut result = serde_json::Map::new();
+    result.insert("port".to_string(), json!(port));
+    if let Some(Value::String(host)) = obj.get("host") {
+        let trimmed = host.trim();
+        if !trimmed.is_empty() && !trimmed.contains(|c: char| c == '/' || c.is_whitespace()) {
+            result.insert("host".to_string(), json!(trimmed));
+        }
+    }
+    if let Some(Value::String(prefix)) = obj.get("apiPrefix") {
+        result.insert("apiPrefix".to_string(), json!(prefix.trim()));
+    }
+    // Moved into the secret store on save, like `notifications.webhookSecret`. Long
+    // enough for a JWT.
+    if let Some(Value::String(token)) = obj.get("authToken") {
+        let trimmed = token.trim();
+        if !trimmed.is_empty() && trimmed.len() <= 4096 {
+            result.insert("authToken".to_string(), json!(trimmed));
+        }
+    }
+    Some(Value::Object(result))
+}
+
+fn sanitize_port(value: &Value) -> Option<u16> {
+    value
+        .as_u64()
+        .or_else(|| value.as_f64().map(|port| port.round().max(0.0) as u64))
+        .and_then(|port| u16::try_from(port).ok())
+}
+
+fn sanitize_quiet_hours(input: &Value) -> Option<Value> {
+    let obj = input.as_object()?;
+    let mut result = serde_json::Map::new();
+
+    if let Some(Value::Bool(b)) = obj.get("enabled") {
+        result.insert("enabled".to_string(), json!(b));
+    }
+    for key in &["start", "end"] {
+        if let Some(Value::String(s)) = obj.get(*key) {
+            if chrono::NaiveTime::parse_from_str(s.trim(), "%H:%M").is_ok() {
+                result.insert(key.to_string(), json!(s.trim()));
+            }
+        }
+    }
+    if let Some(Value::Array(days)) = obj.get("days") {
+        let valid = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+        let days: Vec<String> = days
+            .iter()
+            .filter_map(Value::as_str)
+            .map(|day| day.trim().to_ascii_lowercase())
+            .filter(|day| valid.contains(&day.as_str()))
+            .collect();
+        result.insert("days".to_string(), json!(days));
+    }
+    if let Some(Value::String(mode)) = obj.get("mode") {
+        if matches!(mode.as_str(), "suppress" | "silent") {
+            result.insert("mode".to_string(), json!(mode));
+        }
+    }
+
+    if result.is_empty() {
+        None
+    } else {
+        Some(Value::Object(result))
+    }
+}
+
 /// Sanitize typography sizes partial helper
 fn sanitize_typography_sizes_partial(input: &Value) -> Option<Value> {
     if let Some(obj) = input.as_object() {
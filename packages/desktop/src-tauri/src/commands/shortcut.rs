@@ -0,0 +1,9 @@
+use tauri::State;
+
+use crate::global_shortcut::{GlobalShortcutState, GlobalShortcutStatus};
+
+/// The registered global shortcut, or why the configured one could not be registered.
+#[tauri::command]
+pub fn get_global_shortcut_status(state: State<'_, GlobalShortcutState>) -> GlobalShortcutStatus {
+    state.status()
+}
@@ -0,0 +1,122 @@
+use std::path::PathBuf;
+
+use tauri::State;
+
+use crate::session_activity::{EventStreamHealth, StreamHealth};
+use crate::simulated_events::SimulatedEvents;
+use crate::sse_capture::{replay, CaptureStatus, SseCapture};
+use crate::sse_event_log::{EventTypeCount, SseEventRecord};
+use crate::DesktopRuntime;
+
+/// How fast a capture replays unless told otherwise.
+const DEFAULT_REPLAY_SPEED: f64 = 1.0;
+
+/// Shown when a simulated event does not parse.
+const EXAMPLE_EVENTS: &str = r#"Expected an OpenCode event, for example:
+{"type":"session.status","properties":{"sessionID":"ses_demo","status":{"type":"busy"}}}
+{"type":"message.updated","properties":{"info":{"id":"msg_demo","sessionID":"ses_demo","role":"assistant","finish":"stop","mode":"build","modelID":"claude-sonnet-4-5"}}}
+{"type":"question.asked","properties":{"id":"que_demo","sessionID":"ses_demo","questions":[{"question":"Apply the migration?","options":[{"label":"Yes"},{"label":"No"}]}]}}
+{"type":"session.error","properties":{"sessionID":"ses_demo","error":{"name":"APIError","data":{"message":"Overloaded"}}}}
+{"type":"session.idle","properties":{"sessionID":"ses_demo"}}
+Wrap one as {"directory":"/path/to/project","payload":{...}} to attribute it to a project."#;
+
+/// The most recent SSE envelopes the listeners parsed, oldest first. `type_filter` matches
+/// an event type or a dotted prefix such as `session`.
+#[tauri::command]
+pub fn get_recent_sse_events(
+    state: State<'_, DesktopRuntime>,
+    limit: Option<usize>,
+    type_filter: Option<String>,
+) -> Vec<SseEventRecord> {
+    state.sse_events().recent(limit, type_filter.as_deref())
+}
+
+#[tauri::command]
+pub fn clear_recent_sse_events(state: State<'_, DesktopRuntime>) {
+    state.sse_events().clear();
+}
+
+/// Event types received since launch, for choosing `events.forwardTypes` from real
+/// traffic rather than a fixed list.
+#[tauri::command]
+pub fn get_known_event_types(state: State<'_, DesktopRuntime>) -> Vec<EventTypeCount> {
+    state.sse_events().known_types()
+}
+
+/// Each event stream's last reported state, including `auth-failed` for streams parked
+/// after the server rejected our credentials.
+#[tauri::command]
+pub fn get_event_stream_status(health: State<'_, EventStreamHealth>) -> Vec<StreamHealth> {
+    health.snapshot()
+}
+
+/// Reconnect streams parked on a 401 or 403, after the user has fixed the credentials.
+#[tauri::command]
+pub fn retry_sse_connections(state: State<'_, DesktopRuntime>) {
+    state.retry_rejected_streams();
+}
+
+/// Feed one event through the activity tracker and the notification listener as if a
+/// stream had delivered it, so the UI can be worked on without a server generating
+/// traffic. Refused in release builds unless `simulateEvents` is set.
+#[tauri::command]
+pub async fn simulate_desktop_event(
+    state: State<'_, DesktopRuntime>,
+    simulated: State<'_, SimulatedEvents>,
+    raw_json: String,
+) -> Result<(), String> {
+    ensure_simulation_allowed(&state).await?;
+    simulated
+        .send(&raw_json, true)
+        .map_err(|err| format!("{err}\n\n{EXAMPLE_EVENTS}"))
+}
+
+/// Start writing every SSE frame the activity streams receive to a new file in the log
+/// directory, with message text cut short, for attaching to a bug report.
+#[tauri::command]
+pub fn start_sse_capture(capture: State<'_, SseCapture>) -> Result<CaptureStatus, String> {
+    capture.start().map_err(|err| err.to_string())
+}
+
+/// Stop capturing. `None` if no capture was running.
+#[tauri::command]
+pub fn stop_sse_capture(capture: State<'_, SseCapture>) -> Option<CaptureStatus> {
+    capture.stop()
+}
+
+/// Replay a capture through the activity tracker with its timing divided by `speed`,
+/// and through the notification listener too when `notify` is set. Returns the number
+/// of frames replayed once done. Refused in release builds unless `simulateEvents` is set.
+#[tauri::command]
+pub async fn replay_sse_capture(
+    state: State<'_, DesktopRuntime>,
+    simulated: State<'_, SimulatedEvents>,
+    path: String,
+    speed: Option<f64>,
+    notify: Option<bool>,
+) -> Result<u64, String> {
+    ensure_simulation_allowed(&state).await?;
+    replay(
+        &simulated,
+        &PathBuf::from(path),
+        speed.unwrap_or(DEFAULT_REPLAY_SPEED),
+        notify.unwrap_or(false),
+    )
+    .await
+    .map_err(|err| err.to_string())
+}
+
+async fn ensure_simulation_allowed(state: &DesktopRuntime) -> Result<(), String> {
+    if cfg!(debug_assertions) {
+        return Ok(());
+    }
+    let settings = state
+        .settings()
+        .load_typed()
+        .await
+        .map_err(|err| err.to_string())?;
+    if !settings.simulate_events {
+        return Err("Simulated events are off; set simulateEvents to enable them".to_string());
+    }
+    Ok(())
+}
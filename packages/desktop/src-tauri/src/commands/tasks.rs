@@ -0,0 +1,10 @@
+use tauri::State;
+
+use crate::task_registry::TaskStatus;
+use crate::DesktopRuntime;
+
+/// Health of the desktop runtime's background tasks, for diagnostics.
+#[tauri::command]
+pub fn get_background_tasks(state: State<'_, DesktopRuntime>) -> Vec<TaskStatus> {
+    state.tasks().snapshot()
+}
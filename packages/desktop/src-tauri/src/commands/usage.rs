@@ -0,0 +1,29 @@
+use tauri::State;
+
+use crate::usage::{ModelLatency, Usage, UsageSummary};
+use crate::DesktopRuntime;
+
+/// Tokens and cost of one session's assistant messages so far.
+#[tauri::command]
+pub fn get_session_usage(state: State<'_, DesktopRuntime>, session_id: String) -> Usage {
+    state.usage().session_usage(&session_id)
+}
+
+/// Totals for `today`, `week`, `month` or `all`, with a breakdown per day.
+#[tauri::command]
+pub fn get_usage_summary(
+    state: State<'_, DesktopRuntime>,
+    period: String,
+) -> Result<UsageSummary, String> {
+    state.usage().summary(&period)
+}
+
+/// Median and 95th percentile first-token latency and generation duration per model,
+/// for one session or across all of them.
+#[tauri::command]
+pub fn get_latency_stats(
+    state: State<'_, DesktopRuntime>,
+    session_id: Option<String>,
+) -> Vec<ModelLatency> {
+    state.usage().latency_stats(session_id.as_deref())
+}
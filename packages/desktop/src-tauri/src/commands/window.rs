@@ -0,0 +1,39 @@
+use tauri::{AppHandle, Manager, State};
+
+use crate::session_activity::WindowProjects;
+use crate::window_state::{reset_window, save_window_states, WindowStateManager};
+
+/// Forget the saved geometry of the window labelled `label`, or of every window, and
+/// move the affected open windows back to their default size at the center of their
+/// display.
+#[tauri::command]
+pub async fn reset_window_geometry(
+    app: AppHandle,
+    manager: State<'_, WindowStateManager>,
+    label: Option<String>,
+) -> Result<(), String> {
+    manager.forget(label.as_deref());
+    for (window_label, window) in app.windows() {
+        if label.as_deref().is_some_and(|label| label != window_label) {
+            continue;
+        }
+        reset_window(&window).map_err(|err| err.to_string())?;
+    }
+    save_window_states(&manager.snapshot())
+        .await
+        .map_err(|err| err.to_string())
+}
+
+/// Record which project directory a window shows, so session activity for that project
+/// is sent only to it. `label` defaults to the calling window; `None` for `directory`
+/// means the window shows no project and receives activity only for unregistered ones.
+#[tauri::command]
+pub fn register_window_project(
+    window: tauri::Window,
+    projects: State<'_, WindowProjects>,
+    label: Option<String>,
+    directory: Option<String>,
+) {
+    let label = label.unwrap_or_else(|| window.label().to_string());
+    projects.register(&label, directory.as_deref());
+}
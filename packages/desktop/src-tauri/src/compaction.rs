@@ -0,0 +1,21 @@
+use serde_json::Value;
+
+/// Whether a `session.status` says OpenCode is compacting the session's context. Servers
+/// have called the status `compacting` and `summarizing`.
+pub(crate) fn is_compacting_status(properties: &Value) -> bool {
+    matches!(
+        properties
+            .get("status")
+            .and_then(|status| status.get("type"))
+            .and_then(Value::as_str),
+        Some("compacting" | "summarizing")
+    )
+}
+
+/// Whether an assistant message is the summary written by compaction rather than a reply.
+pub(crate) fn is_compaction_summary(info: &Value) -> bool {
+    info.get("summary").and_then(Value::as_bool) == Some(true)
+        || ["mode", "agent"]
+            .iter()
+            .any(|key| info.get(*key).and_then(Value::as_str) == Some("compaction"))
+}
@@ -0,0 +1,169 @@
+use std::{
+    net::{IpAddr, UdpSocket},
+    time::Duration,
+};
+
+use log::info;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::mpsc;
+
+use crate::task_registry::ChildTask;
+use crate::DesktopRuntime;
+
+const CONNECTIVITY_EVENT: &str = "openchamber:connectivity";
+/// How often the network is probed where the OS does not report changes.
+const PROBE_INTERVAL: Duration = Duration::from_secs(10);
+/// How long SSE streams to a remote server wait between checks while offline. They
+/// reconnect as soon as the network returns either way.
+pub const OFFLINE_RETRY: Duration = Duration::from_secs(60);
+/// Public resolvers used only to ask the routing table for a way out. Nothing is sent.
+const PROBE_TARGETS: [&str; 2] = ["1.1.1.1:53", "[2606:4700:4700::1111]:53"];
+
+/// Whether the machine has a route to anything beyond itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Connectivity {
+    Online,
+    Offline,
+}
+
+#[derive(Clone, Serialize)]
+struct ConnectivityChanged {
+    state: Connectivity,
+}
+
+/// Whether `base` points at this machine, where the network being down changes nothing.
+pub fn is_loopback_url(base: &str) -> bool {
+    let Ok(url) = url::Url::parse(base) else {
+        return false;
+    };
+    match url.host() {
+        Some(url::Host::Domain(domain)) => domain.eq_ignore_ascii_case("localhost"),
+        Some(url::Host::Ipv4(ip)) => IpAddr::V4(ip).is_loopback(),
+        Some(url::Host::Ipv6(ip)) => IpAddr::V6(ip).is_loopback(),
+        None => false,
+    }
+}
+
+/// Follow network changes, from the OS where it reports them and by probing otherwise,
+/// and publish them on the runtime and to the webview.
+pub fn spawn_connectivity_monitor(
+    app: AppHandle,
+    runtime: DesktopRuntime,
+) -> tauri::async_runtime::JoinHandle<()> {
+    tauri::async_runtime::spawn(async move {
+        let mut shutdown_rx = runtime.subscribe_shutdown();
+        let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+        let _source = ChildTask::spawn(platform::follow(events_tx));
+
+        loop {
+            let state = tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                state = events_rx.recv() => match state {
+                    Some(state) => state,
+                    None => break,
+                },
+            };
+            if !runtime.set_connectivity(state) {
+                continue;
+            }
+            match state {
+                Connectivity::Online => info!("[desktop:network] Network is back"),
+                Connectivity::Offline => info!("[desktop:network] Network is offline"),
+            }
+            let _ = app.emit(CONNECTIVITY_EVENT, ConnectivityChanged { state });
+        }
+    })
+}
+
+/// Connecting a UDP socket sends nothing; it only fails when no route leads out.
+fn probe() -> Connectivity {
+    let routed = PROBE_TARGETS.iter().any(|target| {
+        let local = if target.starts_with('[') {
+            "[::]:0"
+        } else {
+            "0.0.0.0:0"
+        };
+        UdpSocket::bind(local)
+            .and_then(|socket| socket.connect(target))
+            .is_ok()
+    });
+    if routed {
+        Connectivity::Online
+    } else {
+        Connectivity::Offline
+    }
+}
+
+async fn probe_periodically(events: &mpsc::UnboundedSender<Connectivity>) {
+    loop {
+        if events.send(probe()).is_err() {
+            return;
+        }
+        tokio::time::sleep(PROBE_INTERVAL).await;
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use anyhow::Result;
+    use futures_util::StreamExt;
+    use log::debug;
+    use tokio::sync::mpsc;
+
+    use super::{probe, probe_periodically, Connectivity};
+
+    /// `NM_STATE_CONNECTED_LOCAL` and above: some network is up, if not the internet.
+    const NM_STATE_CONNECTED_LOCAL: u32 = 50;
+    const NM_STATE_UNKNOWN: u32 = 0;
+
+    pub(super) async fn follow(events: mpsc::UnboundedSender<Connectivity>) {
+        if let Err(err) = follow_network_manager(&events).await {
+            debug!("[desktop:network] NetworkManager unavailable ({err}); probing instead");
+        }
+        probe_periodically(&events).await;
+    }
+
+    fn from_state(state: u32) -> Connectivity {
+        match state {
+            NM_STATE_UNKNOWN => probe(),
+            state if state >= NM_STATE_CONNECTED_LOCAL => Connectivity::Online,
+            _ => Connectivity::Offline,
+        }
+    }
+
+    async fn follow_network_manager(events: &mpsc::UnboundedSender<Connectivity>) -> Result<()> {
+        let connection = zbus::Connection::system().await?;
+        let manager = zbus::Proxy::new(
+            &connection,
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager",
+            "org.freedesktop.NetworkManager",
+        )
+        .await?;
+        let mut signals = manager.receive_signal("StateChanged").await?;
+        let state: u32 = manager.get_property("State").await?;
+        if events.send(from_state(state)).is_err() {
+            return Ok(());
+        }
+        while let Some(message) = signals.next().await {
+            let state: u32 = message.body().deserialize()?;
+            if events.send(from_state(state)).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use tokio::sync::mpsc;
+
+    use super::{probe_periodically, Connectivity};
+
+    pub(super) async fn follow(events: mpsc::UnboundedSender<Connectivity>) {
+        probe_periodically(&events).await;
+    }
+}
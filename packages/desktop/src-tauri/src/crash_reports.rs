@@ -0,0 +1,184 @@
+use std::{
+    backtrace::Backtrace,
+    fmt::Write as _,
+    future::Future,
+    panic::PanicHookInfo,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use chrono::{Local, Utc};
+use log::{error, info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+/// Matches the bundle identifier, so reports sit in Tauri's app data directory.
+const APP_IDENTIFIER: &str = "ai.opencode.openchamber";
+const REPORTS_DIR: &str = "crash-reports";
+/// Name of the newest report already announced to the frontend.
+const SEEN_MARKER: &str = ".last-seen";
+/// Reports kept; older ones are deleted as new ones are written.
+const MAX_REPORTS: usize = 10;
+
+tokio::task_local! {
+    /// Name of the supervised background task being polled, for the report.
+    static TASK_NAME: String;
+}
+
+/// Run `future` as the background task `name`, so a panic inside it is reported with
+/// the task's name.
+pub async fn in_task<F: Future>(name: String, future: F) -> F::Output {
+    TASK_NAME.scope(name, future).await
+}
+
+/// Write a crash report for every panic, then hand over to the previous hook so the
+/// panic still reaches stderr. Installed before anything else starts.
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        match write_report(info) {
+            Some(path) => error!("[desktop] Panic recorded in {}", path.display()),
+            None => error!("[desktop] Panic could not be recorded: {info}"),
+        }
+        previous(info);
+    }));
+}
+
+pub fn reports_directory() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join(APP_IDENTIFIER).join(REPORTS_DIR))
+}
+
+fn write_report(info: &PanicHookInfo) -> Option<PathBuf> {
+    let dir = reports_directory()?;
+    std::fs::create_dir_all(&dir).ok()?;
+
+    let message = if let Some(message) = info.payload().downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = info.payload().downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "(non-string panic payload)".to_string()
+    };
+    let thread = std::thread::current();
+    let task = TASK_NAME.try_with(|name| name.clone()).ok();
+
+    let mut report = String::new();
+    let _ = writeln!(report, "OpenChamber crash report");
+    let _ = writeln!(report, "Time: {}", Utc::now().to_rfc3339());
+    let _ = writeln!(report, "Version: {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(
+        report,
+        "Platform: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let _ = writeln!(report, "Thread: {}", thread.name().unwrap_or("unnamed"));
+    if let Some(task) = &task {
+        let _ = writeln!(report, "Task: {task}");
+    }
+    if let Some(location) = info.location() {
+        let _ = writeln!(report, "Location: {location}");
+    }
+    let _ = writeln!(report, "Message: {message}");
+    let _ = writeln!(report, "\nBacktrace:\n{}", Backtrace::force_capture());
+
+    let path = dir.join(format!(
+        "crash-{}.txt",
+        Local::now().format("%Y%m%d-%H%M%S%.3f")
+    ));
+    std::fs::write(&path, report).ok()?;
+    prune(&dir);
+    Some(path)
+}
+
+/// Report files, oldest first; their names sort by time.
+fn report_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("crash-") && name.ends_with(".txt"))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn prune(dir: &Path) {
+    let files = report_files(dir);
+    let excess = files.len().saturating_sub(MAX_REPORTS);
+    for path in &files[..excess] {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashDetected {
+    /// The newest report.
+    pub path: String,
+    /// Reports written since the frontend was last told about one.
+    pub new_reports: usize,
+}
+
+/// Reports written since the last launch, held until the frontend listens.
+#[derive(Default)]
+pub struct CrashReports {
+    pending: Mutex<Option<CrashDetected>>,
+}
+
+impl CrashReports {
+    /// Look for reports newer than the last one announced. Run once at startup.
+    pub fn check_for_new_reports(&self) {
+        let Some(dir) = reports_directory() else {
+            return;
+        };
+        let files = report_files(&dir);
+        let Some(newest) = files.last() else {
+            return;
+        };
+        let seen = std::fs::read_to_string(dir.join(SEEN_MARKER)).unwrap_or_default();
+        let seen = seen.trim();
+        let new_reports = files
+            .iter()
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name > seen)
+            })
+            .count();
+        if new_reports == 0 {
+            return;
+        }
+
+        info!("[desktop] Found {new_reports} new crash report(s)");
+        if let Some(name) = newest.file_name() {
+            if let Err(err) = std::fs::write(dir.join(SEEN_MARKER), name.as_encoded_bytes()) {
+                warn!("[desktop] Failed to mark crash reports as seen: {err}");
+            }
+        }
+        if let Ok(mut pending) = self.pending.lock() {
+            *pending = Some(CrashDetected {
+                path: newest.to_string_lossy().to_string(),
+                new_reports,
+            });
+        }
+    }
+
+    /// Emit `openchamber:crash-detected` for reports found at startup, once the frontend
+    /// is listening.
+    pub fn frontend_ready(&self, app: &AppHandle) {
+        let pending = self
+            .pending
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.take());
+        if let Some(detected) = pending {
+            let _ = app.emit("openchamber:crash-detected", detected);
+        }
+    }
+}
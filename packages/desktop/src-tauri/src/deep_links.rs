@@ -0,0 +1,254 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use log::{info, warn};
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+use crate::events::{SessionPayload, ToastPayload};
+use crate::path_utils::expand_path;
+use crate::tray::show_main_window;
+use crate::{activate_project, DesktopRuntime};
+
+const SCHEME: &str = "openchamber";
+const SESSION_LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What an `openchamber://` link asks for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum DeepLink {
+    /// `openchamber://session/<id>?directory=<path>`
+    Session {
+        session_id: String,
+        directory: Option<String>,
+    },
+    /// `openchamber://project?directory=<path>`
+    Project { directory: String },
+}
+
+impl DeepLink {
+    /// The reason a link cannot be followed, phrased for the user, on failure.
+    fn parse(url: &Url) -> Result<Self, String> {
+        if url.scheme() != SCHEME {
+            return Err(format!("Not an OpenChamber link: {url}"));
+        }
+        let directory = url
+            .query_pairs()
+            .find(|(key, _)| key == "directory")
+            .map(|(_, value)| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+        let segments: Vec<String> = url
+            .path_segments()
+            .into_iter()
+            .flatten()
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| {
+                urlencoding::decode(segment)
+                    .map(|decoded| decoded.into_owned())
+                    .unwrap_or_else(|_| segment.to_string())
+            })
+            .collect();
+
+        match (url.host_str(), segments.as_slice()) {
+            (Some("session"), [session_id]) if is_session_id(session_id) => Ok(Self::Session {
+                session_id: session_id.clone(),
+                directory,
+            }),
+            (Some("session"), [session_id]) => Err(format!("\"{session_id}\" is not a session id")),
+            (Some("session"), _) => Err("The link does not name a session".to_string()),
+            (Some("project"), []) => directory
+                .map(|directory| Self::Project { directory })
+                .ok_or_else(|| "The link does not name a project directory".to_string()),
+            _ => Err(format!("Unsupported OpenChamber link: {url}")),
+        }
+    }
+
+    fn directory(&self) -> Option<&str> {
+        match self {
+            Self::Session { directory, .. } => directory.as_deref(),
+            Self::Project { directory } => Some(directory),
+        }
+    }
+}
+
+/// OpenCode session ids look like `ses_` followed by letters and digits.
+fn is_session_id(value: &str) -> bool {
+    value
+        .strip_prefix("ses_")
+        .is_some_and(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Links that arrived before the frontend was listening, for example the one that
+/// launched the app.
+#[derive(Default)]
+pub struct DeepLinks {
+    queue: Mutex<DeepLinkQueue>,
+}
+
+#[derive(Default)]
+struct DeepLinkQueue {
+    frontend_ready: bool,
+    pending: Vec<Url>,
+}
+
+impl DeepLinks {
+    /// Hold `url` until the frontend is ready. Returns it back when it can be followed now.
+    fn accept(&self, url: Url) -> Option<Url> {
+        let mut queue = self.queue.lock().ok()?;
+        if queue.frontend_ready {
+            return Some(url);
+        }
+        queue.pending.push(url);
+        None
+    }
+
+    /// Mark the frontend ready and hand out everything held until now.
+    pub fn frontend_ready(&self) -> Vec<Url> {
+        self.queue
+            .lock()
+            .map(|mut queue| {
+                queue.frontend_ready = true;
+                std::mem::take(&mut queue.pending)
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Listen for `openchamber://` links, including the one the app was launched with.
+pub fn register_deep_links(app: &AppHandle) {
+    let deep_link = app.deep_link();
+    // Installed bundles register the scheme themselves; development builds and AppImages
+    // on Linux and Windows have to do it at runtime.
+    #[cfg(any(target_os = "linux", all(debug_assertions, target_os = "windows")))]
+    if let Err(err) = deep_link.register_all() {
+        warn!("[desktop] Failed to register the {SCHEME}:// scheme: {err}");
+    }
+
+    let handle = app.clone();
+    deep_link.on_open_url(move |event| handle_deep_links(&handle, event.urls()));
+
+    match deep_link.get_current() {
+        Ok(Some(urls)) => handle_deep_links(app, urls),
+        Ok(None) => {}
+        Err(err) => warn!("[desktop] Failed to read the launch link: {err}"),
+    }
+}
+
+/// The link that opens `directory` as the active project.
+pub fn project_link(directory: &Path) -> Option<Url> {
+    Url::parse_with_params(
+        &format!("{SCHEME}://project"),
+        &[("directory", directory.to_string_lossy())],
+    )
+    .ok()
+}
+
+pub fn handle_deep_links(app: &AppHandle, urls: Vec<Url>) {
+    for url in urls {
+        if let Some(url) = app.state::<DeepLinks>().accept(url) {
+            tauri::async_runtime::spawn(open_deep_link(app.clone(), url));
+        }
+    }
+}
+
+/// Follow one link: switch project when it names a directory, then open the session.
+/// Anything that goes wrong still brings the app up, with a toast saying why.
+pub async fn open_deep_link(app: AppHandle, url: Url) {
+    info!("[desktop] Opening link {url}");
+    show_main_window(&app);
+
+    let link = match DeepLink::parse(&url) {
+        Ok(link) => link,
+        Err(message) => return show_error(&app, &message),
+    };
+    let runtime = app.state::<DesktopRuntime>().inner().clone();
+
+    let mut directory = None;
+    if let Some(requested) = link.directory() {
+        match switch_project(&runtime, requested).await {
+            Ok(path) => directory = Some(path),
+            Err(message) => return show_error(&app, &message),
+        }
+    }
+
+    if let DeepLink::Session { session_id, .. } = &link {
+        if session_exists(&runtime, session_id, directory.as_deref()).await == Some(false) {
+            return show_error(&app, &format!("Session {session_id} was not found"));
+        }
+        let _ = app.emit(
+            "openchamber:navigate-session",
+            SessionPayload { session_id },
+        );
+    }
+}
+
+async fn switch_project(runtime: &DesktopRuntime, requested: &str) -> Result<PathBuf, String> {
+    let mut path = expand_path(requested);
+    if !path.is_absolute() {
+        path = dirs::home_dir().unwrap_or_default().join(path);
+    }
+    match tokio::fs::metadata(&path).await {
+        Ok(metadata) if metadata.is_dir() => {}
+        Ok(_) => {
+            return Err(format!(
+                "{} is not a folder; only folders can be opened as projects",
+                path.display()
+            ))
+        }
+        Err(_) => return Err(format!("{} does not exist", path.display())),
+    }
+    let path = tokio::fs::canonicalize(&path).await.unwrap_or(path);
+
+    activate_project(runtime.settings(), &runtime.opencode_instances(), &path)
+        .await
+        .map_err(|err| format!("Could not open project {}: {err}", path.display()))?;
+    if let Err(err) = runtime
+        .opencode_manager()
+        .set_working_directory(path.clone())
+        .await
+    {
+        warn!("[desktop] Failed to set the OpenCode working directory: {err}");
+    }
+    Ok(path)
+}
+
+/// Whether the server that owns `directory` knows the session. `None` when it cannot be
+/// asked, in which case the frontend gets to try.
+async fn session_exists(
+    runtime: &DesktopRuntime,
+    session_id: &str,
+    directory: Option<&Path>,
+) -> Option<bool> {
+    let manager = runtime
+        .opencode_instances()
+        .manager_for_directory(directory);
+    let base = manager.status().base_url()?;
+    let url = format!("{base}/session/{}", urlencoding::encode(session_id));
+    let mut request = manager
+        .authorize(runtime.http().api().get(&url))
+        .timeout(SESSION_LOOKUP_TIMEOUT);
+    if let Some(directory) = directory {
+        request = request.query(&[("directory", directory.to_string_lossy())]);
+    }
+    let response = request.send().await.ok()?;
+    if response.status().is_success() {
+        Some(true)
+    } else if response.status() == reqwest::StatusCode::NOT_FOUND {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+fn show_error(app: &AppHandle, message: &str) {
+    warn!("[desktop] Cannot open link: {message}");
+    let _ = app.emit(
+        "openchamber:toast",
+        ToastPayload {
+            kind: "error",
+            message,
+        },
+    );
+}
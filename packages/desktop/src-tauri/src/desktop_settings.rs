@@ -0,0 +1,1041 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::Utc;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::path_utils::{comparable_path, expand_path, try_expand_path};
+
+/// Schema written by this build. Files from older builds are upgraded by `migrate` when
+/// they are read.
+pub(crate) const SETTINGS_SCHEMA_VERSION: u64 = 1;
+/// How long an event stream may stay silent before it is reconnected. OpenCode sends a
+/// heartbeat well within this; a proxy that holds a dead connection open does not.
+pub(crate) const DEFAULT_STREAM_IDLE_TIMEOUT_SECS: u64 = 90;
+
+/// The parts of `settings.json` the desktop runtime reads. Everything else, most of it
+/// the frontend's, is kept in `extra` so writing the struct back loses nothing.
+///
+/// Every field tolerates a value of the wrong shape by falling back to its default, so a
+/// single bad key never hides the rest of the file.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DesktopSettings {
+    #[serde(default, deserialize_with = "lenient")]
+    pub schema_version: u64,
+    #[serde(default, deserialize_with = "lenient_list")]
+    pub projects: Vec<ProjectEntry>,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub active_project_id: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub last_directory: Option<String>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub notifications: NotificationSettings,
+    /// How long the activity tracker shows a session error before clearing it.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub activity_error_decay_seconds: Option<u64>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub telemetry: TelemetrySettings,
+    #[serde(default, deserialize_with = "lenient")]
+    pub http: HttpSettings,
+    #[serde(default, deserialize_with = "lenient")]
+    pub window: WindowSettings,
+    #[serde(default, deserialize_with = "lenient")]
+    pub hooks: HookSettings,
+    #[serde(default, deserialize_with = "lenient")]
+    pub power: PowerSettings,
+    #[serde(default, deserialize_with = "lenient")]
+    pub integrations: IntegrationSettings,
+    #[serde(default, deserialize_with = "lenient")]
+    pub events: EventSettings,
+    /// Run a server per recently opened project. Off by default since every instance is a
+    /// separate process.
+    #[serde(default, deserialize_with = "lenient")]
+    pub multi_instance_opencode: bool,
+    /// Tell the webview about every file the agent edits. Off by default since large
+    /// refactors produce thousands of events.
+    #[serde(default, deserialize_with = "lenient")]
+    pub forward_file_changes: bool,
+    /// Keep token usage and cost on disk so daily totals survive restarts.
+    #[serde(default = "enabled", deserialize_with = "lenient_enabled")]
+    pub persist_usage: bool,
+    /// Accept `simulate_desktop_event` in release builds, where it is otherwise refused.
+    #[serde(default, deserialize_with = "lenient")]
+    pub simulate_events: bool,
+    /// The tray icon listing busy sessions. On unless set to false.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub show_tray_icon: Option<bool>,
+    /// The system-wide shortcut that shows or hides the main window. Unset means
+    /// `global_shortcut::DEFAULT_SHORTCUT`; an empty string turns it off.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub global_shortcut: Option<String>,
+    /// How many recent SSE envelopes are kept for diagnostics. Unset means
+    /// `sse_event_log::DEFAULT_CAPACITY`; 0 turns recording off.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub sse_event_buffer_size: Option<u64>,
+    /// Log level per `log_levels` module, for example `"desktop:activity": "debug"`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub log_levels: BTreeMap<String, String>,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub commit_message_model: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// An entry of `projects`.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProjectEntry {
+    #[serde(default, deserialize_with = "lenient")]
+    pub id: String,
+    #[serde(default, deserialize_with = "lenient")]
+    pub path: String,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub label: Option<String>,
+    /// Notification level: `all`, `questions-only`, or `muted`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub notifications: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub added_at: Option<i64>,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub last_opened_at: Option<i64>,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub overrides: Option<ProjectOverrides>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A project's `overrides`. Anything left unset inherits the global setting; see
+/// `DesktopSettings::effective_settings_for_directory` for how they combine.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProjectOverrides {
+    /// Silence every notification from the project, or unmute one whose level is `muted`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub muted: Option<bool>,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub notifications: Option<CategoryOverrides>,
+    /// How long a session that just finished shows as cooling down.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cooldown_seconds: Option<u64>,
+    /// Replaces the global `notifications.modeFilter` for the project.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mode_filter: Option<ModeFilter>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Notification categories switched on or off for a single project.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CategoryOverrides {
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub assistant_completed: Option<bool>,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub question_asked: Option<bool>,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub session_error: Option<bool>,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub permission_requested: Option<bool>,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub long_running_tool: Option<bool>,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub context_window: Option<bool>,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rate_limited: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The `notifications` object. Categories are on unless explicitly disabled; quiet hours,
+/// sounds, and the webhook are parsed by their own modules and stay in `extra`.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct NotificationSettings {
+    #[serde(default = "enabled", deserialize_with = "lenient_enabled")]
+    pub assistant_completed: bool,
+    #[serde(default = "enabled", deserialize_with = "lenient_enabled")]
+    pub question_asked: bool,
+    #[serde(default = "enabled", deserialize_with = "lenient_enabled")]
+    pub session_error: bool,
+    #[serde(default = "enabled", deserialize_with = "lenient_enabled")]
+    pub permission_requested: bool,
+    #[serde(default = "enabled", deserialize_with = "lenient_enabled")]
+    pub long_running_tool: bool,
+    #[serde(default = "enabled", deserialize_with = "lenient_enabled")]
+    pub context_window: bool,
+    #[serde(default = "enabled", deserialize_with = "lenient_enabled")]
+    pub rate_limited: bool,
+    #[serde(default = "enabled", deserialize_with = "lenient_enabled")]
+    pub reply_snippet: bool,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reply_snippet_length: Option<u64>,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub digest_window_seconds: Option<u64>,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub long_running_tool_minutes: Option<u64>,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub question_reminder_minutes: Option<u64>,
+    /// Percent of the context window at which a session warns. Unset means 85.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub context_window_percent: Option<u64>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub question_reminder_attention: bool,
+    #[serde(default, deserialize_with = "lenient")]
+    pub request_attention_on_question: bool,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mode_filter: Option<ModeFilter>,
+    /// `never`, `always` or `unless-session-visible`. Unset means `never`.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub when_focused: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            assistant_completed: true,
+            question_asked: true,
+            session_error: true,
+            permission_requested: true,
+            long_running_tool: true,
+            context_window: true,
+            rate_limited: true,
+            reply_snippet: true,
+            reply_snippet_length: None,
+            digest_window_seconds: None,
+            long_running_tool_minutes: None,
+            question_reminder_minutes: None,
+            context_window_percent: None,
+            question_reminder_attention: false,
+            request_attention_on_question: false,
+            mode_filter: None,
+            when_focused: None,
+            extra: Map::new(),
+        }
+    }
+}
+
+/// `notifications.modeFilter`: which agent modes send completion notifications.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ModeFilter {
+    /// `allow` notifies only for the listed modes; `deny`, the default, for all but them.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub kind: Option<String>,
+    #[serde(default, deserialize_with = "lenient_list")]
+    pub modes: Vec<String>,
+    /// Whether a completion that does not say which mode produced it notifies.
+    #[serde(default = "enabled", deserialize_with = "lenient_enabled")]
+    pub notify_unknown: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for ModeFilter {
+    fn default() -> Self {
+        Self {
+            kind: None,
+            modes: Vec::new(),
+            notify_unknown: true,
+            extra: Map::new(),
+        }
+    }
+}
+
+impl ModeFilter {
+    /// Whether a completion from `mode` may notify. Mode ids are compared ignoring case,
+    /// since OpenCode does not keep theirs consistent.
+    pub fn allows(&self, mode: Option<&str>) -> bool {
+        let Some(mode) = mode.map(str::trim).filter(|mode| !mode.is_empty()) else {
+            return self.notify_unknown;
+        };
+        let mode = mode.to_lowercase();
+        let listed = self
+            .modes
+            .iter()
+            .any(|listed| listed.trim().to_lowercase() == mode);
+        match self.kind.as_deref() {
+            Some("allow") => listed,
+            _ => !listed,
+        }
+    }
+}
+
+/// The opt-in `telemetry` object.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TelemetrySettings {
+    #[serde(default, deserialize_with = "lenient")]
+    pub enabled: bool,
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub endpoint: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The `http` object: how the backend reaches the network.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HttpSettings {
+    /// Proxy for outgoing requests, e.g. `http://proxy.corp:8080`. Falls back to
+    /// `HTTPS_PROXY` and friends when unset.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub proxy: Option<String>,
+    /// Comma-separated hosts that bypass the proxy, as in `NO_PROXY`. Loopback addresses
+    /// always do.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub no_proxy: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The `window` object: how the main window behaves around the tray icon.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WindowSettings {
+    /// Launch with only the tray icon; the window opens from the tray or the shortcut.
+    #[serde(default, deserialize_with = "lenient")]
+    pub start_hidden: bool,
+    /// Closing the main window hides it; quitting is left to the tray menu.
+    #[serde(default, deserialize_with = "lenient")]
+    pub close_to_tray: bool,
+    /// Show what the agents are doing in the main window's title. On unless set to false.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub status_in_title: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl WindowSettings {
+    pub(crate) fn status_in_title(&self) -> bool {
+        self.status_in_title.unwrap_or(true)
+    }
+}
+
+/// The `power` object.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PowerSettings {
+    /// Keep the system awake, though not the display, while any session is working.
+    #[serde(default, deserialize_with = "lenient")]
+    pub prevent_sleep_while_busy: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The `integrations` object: ways for other programs to follow the app.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct IntegrationSettings {
+    /// Keep `status.json` in the config directory current for status widgets and scripts.
+    #[serde(default, deserialize_with = "lenient")]
+    pub status_file: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The `events` object: which OpenCode events are forwarded to the webview, and whether
+/// they are streamed at all.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct EventSettings {
+    /// Exact types such as `session.updated`, dotted prefixes such as `file.*`, or `*`.
+    /// Unset forwards everything. Events left out are still handled by the backend.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub forward_types: Option<Vec<String>>,
+    /// Seconds the notification stream may stay silent before it is reconnected. Unset
+    /// means `DEFAULT_STREAM_IDLE_TIMEOUT_SECS`; 0 waits forever.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub notify_idle_timeout_seconds: Option<u64>,
+    /// The same for the activity streams.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub activity_idle_timeout_seconds: Option<u64>,
+    /// Both event streams stay disconnected and nothing notifies, until resumed.
+    #[serde(default, deserialize_with = "lenient")]
+    pub paused: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl EventSettings {
+    pub(crate) fn notify_idle_timeout(&self) -> Option<Duration> {
+        idle_timeout(self.notify_idle_timeout_seconds)
+    }
+
+    pub(crate) fn activity_idle_timeout(&self) -> Option<Duration> {
+        idle_timeout(self.activity_idle_timeout_seconds)
+    }
+
+    /// Whether events of `event_type` may be forwarded to the webview.
+    pub(crate) fn forwards(&self, event_type: &str) -> bool {
+        let Some(patterns) = &self.forward_types else {
+            return true;
+        };
+        patterns.iter().any(|pattern| {
+            if pattern == "*" {
+                return true;
+            }
+            match pattern.strip_suffix(".*") {
+                Some(prefix) => event_type
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('.')),
+                None => pattern == event_type,
+            }
+        })
+    }
+}
+
+/// The `hooks` object: shell commands run when a notification is delivered. Off unless
+/// `enabled` is set.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HookSettings {
+    #[serde(default, deserialize_with = "lenient")]
+    pub enabled: bool,
+    /// Run when an assistant finishes replying.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub completion: Option<String>,
+    /// Run when a session asks a question or requests a permission.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub question: Option<String>,
+    /// Run when a session stops with an error.
+    #[serde(
+        default,
+        deserialize_with = "lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub error: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl HookSettings {
+    /// The command line for `event` (`completion`, `question` or `error`), if one is set.
+    pub fn command(&self, event: &str) -> Option<&str> {
+        let command = match event {
+            "completion" => self.completion.as_deref(),
+            "question" => self.question.as_deref(),
+            "error" => self.error.as_deref(),
+            _ => None,
+        };
+        command.map(str::trim).filter(|command| !command.is_empty())
+    }
+}
+
+/// The settings that apply to one project: the global values with the project's
+/// overrides laid over them.
+#[derive(Clone, Debug)]
+pub(crate) struct EffectiveSettings<'a> {
+    /// The project the directory belongs to, if any.
+    pub project: Option<&'a ProjectEntry>,
+    pub notifications: NotificationSettings,
+    pub muted: bool,
+    /// `None` keeps the activity tracker's default cooldown.
+    pub cooldown_seconds: Option<u64>,
+}
+
+impl DesktopSettings {
+    /// Read settings of any shape; anything other than an object reads as the defaults.
+    pub(crate) fn from_value(value: Value) -> Self {
+        serde_json::from_value(value).unwrap_or_default()
+    }
+
+    pub(crate) fn into_value(self) -> Value {
+        serde_json::to_value(self).unwrap_or_else(|_| json!({}))
+    }
+
+    pub(crate) fn tray_icon_enabled(&self) -> bool {
+        self.show_tray_icon.unwrap_or(true)
+    }
+
+    /// `window.startHidden`, honoured only while the tray icon is there to bring the
+    /// window back.
+    pub(crate) fn start_hidden(&self) -> bool {
+        self.window.start_hidden && self.tray_icon_enabled()
+    }
+
+    /// `window.closeToTray`, under the same condition as `start_hidden`.
+    pub(crate) fn close_to_tray(&self) -> bool {
+        self.window.close_to_tray && self.tray_icon_enabled()
+    }
+
+    pub(crate) fn active_project(&self) -> Option<&ProjectEntry> {
+        let active_id = self.active_project_id.as_deref()?;
+        self.projects.iter().find(|project| project.id == active_id)
+    }
+
+    /// The active project's directory, falling back to `lastDirectory` from before
+    /// projects existed.
+    pub(crate) fn project_directory(&self) -> Option<PathBuf> {
+        if let Some(project) = self.active_project() {
+            return try_expand_path(&project.path);
+        }
+        self.last_directory()
+    }
+
+    /// The project containing `directory`. The most specific project wins when project
+    /// paths are nested.
+    pub(crate) fn project_for_directory(&self, directory: &Path) -> Option<&ProjectEntry> {
+        let directory = comparable_path(&expand_path(&directory.to_string_lossy()));
+        self.projects
+            .iter()
+            .filter(|project| !project.path.is_empty())
+            .filter_map(|project| {
+                let path = comparable_path(&try_expand_path(&project.path)?);
+                directory
+                    .starts_with(&path)
+                    .then(|| (path.components().count(), project))
+            })
+            .max_by_key(|(depth, _)| *depth)
+            .map(|(_, project)| project)
+    }
+
+    /// Resolve the settings for an event from `directory`, or from the active project
+    /// when the event did not say where it came from.
+    pub(crate) fn effective_settings(&self, directory: Option<&Path>) -> EffectiveSettings<'_> {
+        match directory {
+            Some(directory) => self.effective_settings_for_directory(directory),
+            None => self.effective_settings_for_project(self.active_project()),
+        }
+    }
+
+    /// Resolve the settings for `directory`. A directory outside every project gets the
+    /// global settings unchanged.
+    ///
+    /// Precedence, highest first:
+    /// 1. The project's `overrides.muted`, when set, decides whether the project is muted.
+    /// 2. Otherwise the project's `notifications` level of `muted` mutes it.
+    /// 3. A category set in `overrides.notifications` replaces the global toggle for that
+    ///    category; unset categories keep the global `notifications` value.
+    /// 4. `overrides.cooldownSeconds` replaces the default cooldown.
+    /// 5. `overrides.modeFilter` replaces the global `notifications.modeFilter` whole; the
+    ///    two lists are not merged.
+    ///
+    /// Muting wins over every category, so a muted project stays silent even when one of
+    /// its categories is switched on.
+    pub(crate) fn effective_settings_for_directory(
+        &self,
+        directory: &Path,
+    ) -> EffectiveSettings<'_> {
+        self.effective_settings_for_project(self.project_for_directory(directory))
+    }
+
+    fn effective_settings_for_project<'a>(
+        &'a self,
+        project: Option<&'a ProjectEntry>,
+    ) -> EffectiveSettings<'a> {
+        let mut notifications = self.notifications.clone();
+        let overrides = project.and_then(|project| project.overrides.as_ref());
+
+        let level_muted =
+            project.and_then(|project| project.notifications.as_deref()) == Some("muted");
+        let muted = overrides
+            .and_then(|overrides| overrides.muted)
+            .unwrap_or(level_muted);
+
+        if let Some(categories) = overrides.and_then(|overrides| overrides.notifications.as_ref()) {
+            let apply = |global: &mut bool, value: Option<bool>| {
+                if let Some(value) = value {
+                    *global = value;
+                }
+            };
+            apply(
+                &mut notifications.assistant_completed,
+                categories.assistant_completed,
+            );
+            apply(&mut notifications.question_asked, categories.question_asked);
+            apply(&mut notifications.session_error, categories.session_error);
+            apply(
+                &mut notifications.permission_requested,
+                categories.permission_requested,
+            );
+            apply(
+                &mut notifications.long_running_tool,
+                categories.long_running_tool,
+            );
+            apply(&mut notifications.context_window, categories.context_window);
+            apply(&mut notifications.rate_limited, categories.rate_limited);
+        }
+        if let Some(filter) = overrides.and_then(|overrides| overrides.mode_filter.as_ref()) {
+            notifications.mode_filter = Some(filter.clone());
+        }
+
+        EffectiveSettings {
+            project,
+            notifications,
+            muted,
+            cooldown_seconds: overrides.and_then(|overrides| overrides.cooldown_seconds),
+        }
+    }
+
+    pub(crate) fn last_directory(&self) -> Option<PathBuf> {
+        self.last_directory
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .and_then(try_expand_path)
+    }
+}
+
+/// Upgrade settings written by older builds to `SETTINGS_SCHEMA_VERSION`, in place.
+pub(crate) fn migrate(settings: &mut Value) {
+    if !settings.is_object() {
+        *settings = json!({});
+    }
+    let version = settings
+        .get("schemaVersion")
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    if version >= SETTINGS_SCHEMA_VERSION {
+        return;
+    }
+
+    if version < 1 {
+        migrate_last_directory_to_project(settings);
+    }
+
+    settings["schemaVersion"] = json!(SETTINGS_SCHEMA_VERSION);
+}
+
+/// Version 1: settings from before projects only remember `lastDirectory`; turn it into
+/// the first project.
+fn migrate_last_directory_to_project(settings: &mut Value) {
+    let now = Utc::now().timestamp_millis();
+    let Some(obj) = settings.as_object_mut() else {
+        return;
+    };
+
+    let has_projects = obj
+        .get("projects")
+        .and_then(|value| value.as_array())
+        .map(|arr| !arr.is_empty())
+        .unwrap_or(false);
+
+    if has_projects {
+        return;
+    }
+
+    let last_directory = obj
+        .get("lastDirectory")
+        .and_then(|value| value.as_str())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .and_then(try_expand_path);
+
+    let Some(mut last_directory) = last_directory else {
+        return;
+    };
+
+    if let Ok(canonicalized) = std::fs::canonicalize(&last_directory) {
+        last_directory = canonicalized;
+    }
+
+    let Ok(stats) = std::fs::metadata(&last_directory) else {
+        return;
+    };
+    if !stats.is_dir() {
+        return;
+    }
+
+    let normalized_path = last_directory.to_string_lossy().to_string();
+    if normalized_path.trim().is_empty() {
+        return;
+    }
+
+    let project_id = Uuid::new_v4().to_string();
+    obj.insert(
+        "projects".to_string(),
+        json!([
+            {
+                "id": project_id,
+                "path": normalized_path,
+                "addedAt": now,
+                "lastOpenedAt": now
+            }
+        ]),
+    );
+    obj.insert("activeProjectId".to_string(), json!(project_id));
+
+    // Ensure approvedDirectories includes the migrated project root.
+    let approved_value = obj
+        .entry("approvedDirectories")
+        .or_insert_with(|| json!([]));
+    if !approved_value.is_array() {
+        *approved_value = json!([]);
+    }
+
+    if let Some(array) = approved_value.as_array_mut() {
+        array.push(json!(normalized_path));
+        let mut seen = HashSet::new();
+        array.retain(|entry| {
+            let Some(value) = entry.as_str().filter(|value| !value.trim().is_empty()) else {
+                return false;
+            };
+            seen.insert(value.to_string())
+        });
+    }
+}
+
+fn enabled() -> bool {
+    true
+}
+
+fn idle_timeout(seconds: Option<u64>) -> Option<Duration> {
+    match seconds.unwrap_or(DEFAULT_STREAM_IDLE_TIMEOUT_SECS) {
+        0 => None,
+        seconds => Some(Duration::from_secs(seconds)),
+    }
+}
+
+fn lenient<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    let value = Value::deserialize(deserializer)?;
+    Ok(serde_json::from_value(value).unwrap_or_default())
+}
+
+fn lenient_enabled<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Value::deserialize(deserializer)?.as_bool().unwrap_or(true))
+}
+
+/// Keeps the entries that parse when others in the list do not.
+fn lenient_list<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let Value::Array(entries) = Value::deserialize(deserializer)? else {
+        return Ok(Vec::new());
+    };
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| serde_json::from_value(entry).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Paths that do not exist, so they compare lexically on every machine.
+    const WORK: &str = "/openchamber-test/work";
+    const HOBBY: &str = "/openchamber-test/hobby";
+
+    fn settings(value: Value) -> DesktopSettings {
+        DesktopSettings::from_value(value)
+    }
+
+    fn effective(settings: &DesktopSettings, directory: &str) -> (bool, bool, bool) {
+        let effective = settings.effective_settings_for_directory(Path::new(directory));
+        (
+            effective.muted,
+            effective.notifications.assistant_completed,
+            effective.notifications.question_asked,
+        )
+    }
+
+    #[test]
+    fn defaults_apply_when_nothing_is_set() {
+        let settings = settings(json!({ "projects": [{ "id": "work", "path": WORK }] }));
+        let resolved = settings.effective_settings_for_directory(Path::new(WORK));
+
+        assert_eq!(
+            resolved.project.map(|project| project.id.as_str()),
+            Some("work")
+        );
+        assert!(!resolved.muted);
+        assert!(resolved.notifications.assistant_completed);
+        assert_eq!(resolved.cooldown_seconds, None);
+        assert_eq!(resolved.notifications.mode_filter, None);
+    }
+
+    #[test]
+    fn project_overrides_beat_global_settings_which_beat_defaults() {
+        let settings = settings(json!({
+            "notifications": { "assistantCompleted": false },
+            "projects": [
+                {
+                    "id": "work",
+                    "path": WORK,
+                    "overrides": {
+                        "notifications": { "assistantCompleted": true },
+                        "cooldownSeconds": 30,
+                    },
+                },
+                { "id": "hobby", "path": HOBBY },
+            ],
+        }));
+
+        // (muted, assistantCompleted, questionAsked): the override, then the file, then the
+        // default.
+        assert_eq!(effective(&settings, WORK), (false, true, true));
+        assert_eq!(effective(&settings, HOBBY), (false, false, true));
+        assert_eq!(effective(&settings, "/elsewhere"), (false, false, true));
+
+        let work = settings.effective_settings_for_directory(Path::new(WORK));
+        assert_eq!(work.cooldown_seconds, Some(30));
+        let hobby = settings.effective_settings_for_directory(Path::new(HOBBY));
+        assert_eq!(hobby.cooldown_seconds, None);
+    }
+
+    #[test]
+    fn muted_flag_beats_the_level_and_every_category() {
+        let settings = settings(json!({
+            "projects": [
+                {
+                    "id": "work",
+                    "path": WORK,
+                    "notifications": "muted",
+                    "overrides": { "muted": false },
+                },
+                {
+                    "id": "hobby",
+                    "path": HOBBY,
+                    "notifications": "all",
+                    "overrides": {
+                        "muted": true,
+                        "notifications": { "questionAsked": true },
+                    },
+                },
+            ],
+        }));
+
+        assert_eq!(effective(&settings, WORK), (false, true, true));
+        assert_eq!(effective(&settings, HOBBY), (true, true, true));
+    }
+
+    #[test]
+    fn muted_level_applies_without_an_override() {
+        let settings = settings(json!({
+            "projects": [{ "id": "work", "path": WORK, "notifications": "muted" }],
+        }));
+
+        assert!(effective(&settings, WORK).0);
+    }
+
+    #[test]
+    fn project_mode_filter_replaces_the_global_one() {
+        let settings = settings(json!({
+            "notifications": { "modeFilter": { "kind": "deny", "modes": ["plan"] } },
+            "projects": [{
+                "id": "work",
+                "path": WORK,
+                "overrides": { "modeFilter": { "kind": "allow", "modes": ["build"] } },
+            }],
+        }));
+
+        let filter = |directory: &str| {
+            settings
+                .effective_settings_for_directory(Path::new(directory))
+                .notifications
+                .mode_filter
+                .map(|filter| (filter.kind, filter.modes))
+        };
+        assert_eq!(
+            filter(WORK),
+            Some((Some("allow".to_string()), vec!["build".to_string()]))
+        );
+        assert_eq!(
+            filter(HOBBY),
+            Some((Some("deny".to_string()), vec!["plan".to_string()]))
+        );
+    }
+
+    #[test]
+    fn most_specific_project_wins_and_active_project_covers_unknown_directories() {
+        let nested = format!("{WORK}/client");
+        let settings = settings(json!({
+            "activeProjectId": "client",
+            "projects": [
+                { "id": "work", "path": WORK, "overrides": { "cooldownSeconds": 5 } },
+                { "id": "client", "path": nested, "overrides": { "cooldownSeconds": 60 } },
+            ],
+        }));
+
+        let cooldown = |directory: Option<&str>| {
+            settings
+                .effective_settings(directory.map(Path::new))
+                .cooldown_seconds
+        };
+        assert_eq!(cooldown(Some(&format!("{nested}/src/"))), Some(60));
+        assert_eq!(cooldown(Some(&format!("{WORK}/docs"))), Some(5));
+        assert_eq!(cooldown(Some("/elsewhere")), None);
+        assert_eq!(cooldown(None), Some(60));
+    }
+}
@@ -0,0 +1,178 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager, Url};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::logging::log_file_path;
+use crate::opencode_log;
+use crate::opencode_manager::cli_version;
+use crate::session_activity::{BusySessions, EventStreamHealth};
+use crate::DesktopRuntime;
+
+/// Lines kept from the end of each log.
+const LOG_TAIL_LINES: usize = 2_000;
+pub(crate) const REDACTED: &str = "[redacted]";
+/// Values under a key containing one of these, ignoring case, are never written out.
+const SECRET_KEY_MARKERS: &[&str] = &[
+    "secret",
+    "token",
+    "password",
+    "apikey",
+    "api_key",
+    "authorization",
+    "credential",
+    "webhook",
+];
+
+/// One file of the bundle.
+struct BundleFile {
+    name: &'static str,
+    content: String,
+}
+
+/// Gather the logs, settings and runtime state a bug report needs and write them to a zip
+/// archive at `path`. Secrets are masked and the home directory reads `~` throughout.
+/// Returns the size of the archive in bytes.
+pub async fn export_bundle(app: &AppHandle, path: PathBuf) -> Result<u64> {
+    let files = collect(app).await;
+    tauri::async_runtime::spawn_blocking(move || {
+        let written = write_archive(&path, &files);
+        if written.is_err() {
+            let _ = std::fs::remove_file(&path);
+        }
+        written
+    })
+    .await
+    .context("Diagnostics export task failed")?
+}
+
+async fn collect(app: &AppHandle) -> Vec<BundleFile> {
+    let runtime = app.state::<DesktopRuntime>().inner().clone();
+    let home = dirs::home_dir().map(|home| home.to_string_lossy().to_string());
+    let mask_home = |text: String| match home.as_deref() {
+        Some(home) if !home.is_empty() => text.replace(home, "~"),
+        _ => text,
+    };
+
+    let desktop_log = match log_file_path() {
+        Some(path) => tokio::fs::read(&path)
+            .await
+            .map(|bytes| last_lines(&String::from_utf8_lossy(&bytes), LOG_TAIL_LINES))
+            .unwrap_or_else(|err| format!("Failed to read {}: {err}\n", path.display())),
+        None => "Log location unavailable\n".to_string(),
+    };
+    let mut opencode_log = opencode_log::tail(LOG_TAIL_LINES).await.join("\n");
+    opencode_log.push('\n');
+
+    let settings = match runtime.settings().load().await {
+        Ok(mut settings) => {
+            redact_settings(&mut settings);
+            settings
+        }
+        Err(err) => json!({ "error": format!("Failed to load settings: {err}") }),
+    };
+
+    let event_streams = json!({
+        "streams": app.state::<EventStreamHealth>().snapshot(),
+        "counters": runtime.telemetry().snapshot(),
+    });
+    let sse_events = json!({
+        "capturedAt": Utc::now().to_rfc3339(),
+        "events": runtime.sse_events().recent(None, None),
+    });
+    let activity = json!({
+        "capturedAt": Utc::now().to_rfc3339(),
+        "sessions": app.state::<BusySessions>().snapshot(),
+    });
+    let environment = json!({
+        "capturedAt": Utc::now().to_rfc3339(),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "appVersion": app.package_info().version.to_string(),
+        "tauriVersion": tauri::VERSION,
+        "opencodeVersion": cli_version().await,
+        "opencode": runtime.opencode_manager().status(),
+        "backgroundTasks": runtime.tasks().snapshot(),
+    });
+
+    [
+        ("desktop.log", desktop_log),
+        ("opencode.log", opencode_log),
+        ("settings.json", pretty(&settings)),
+        ("event-streams.json", pretty(&event_streams)),
+        ("sse-events.json", pretty(&sse_events)),
+        ("activity.json", pretty(&activity)),
+        ("environment.json", pretty(&environment)),
+    ]
+    .into_iter()
+    .map(|(name, content)| BundleFile {
+        name,
+        content: mask_home(content),
+    })
+    .collect()
+}
+
+fn write_archive(path: &Path, files: &[BundleFile]) -> Result<u64> {
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut archive = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for file in files {
+        archive.start_file(file.name, options)?;
+        archive.write_all(file.content.as_bytes())?;
+    }
+    let file = archive.finish()?;
+    Ok(file.metadata()?.len())
+}
+
+fn last_lines(content: &str, lines: usize) -> String {
+    let mut tail: Vec<&str> = content.lines().rev().take(lines).collect();
+    tail.reverse();
+    let mut tail = tail.join("\n");
+    tail.push('\n');
+    tail
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+/// Whether values stored under `key` should be masked. OpenCode's `tokens` is a usage
+/// count, not a credential.
+pub(crate) fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key != "tokens" && SECRET_KEY_MARKERS.iter().any(|marker| key.contains(marker))
+}
+
+/// Mask secret-looking settings and strip credentials embedded in URLs.
+fn redact_settings(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if !value.is_null() && is_secret_key(key) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_settings(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_settings),
+        Value::String(text) => {
+            if let Ok(mut url) = Url::parse(text) {
+                if !url.username().is_empty() || url.password().is_some() {
+                    let _ = url.set_username("");
+                    let _ = url.set_password(None);
+                    *text = url.to_string();
+                }
+            }
+        }
+        _ => {}
+    }
+}
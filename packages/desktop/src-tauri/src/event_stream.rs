@@ -0,0 +1,736 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use log::{debug, info, trace};
+use reqwest::Client;
+use serde_json::Value;
+use tokio::sync::{broadcast, watch};
+
+use crate::http::HttpClients;
+use crate::opencode_manager::{
+    auth_token_changed, server_moved, with_auth, OpenCodeManager, OpenCodeStatus,
+};
+use crate::path_utils::{normalize_directory, paths_equivalent};
+use crate::settings_watcher::{next_settings_change, SettingsChanged};
+use crate::DesktopRuntime;
+
+const NEGOTIATE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Data some reverse proxies and older OpenCode builds send to keep an idle stream open.
+const KEEPALIVE_PAYLOADS: &[&str] = &["", "ping", "\"ping\"", "ok", "\"ok\"", "{}"];
+/// How much of a frame that did not parse is logged.
+const UNPARSED_PREFIX_CHARS: usize = 80;
+
+/// Which event stream a server offers, decided once per process start and shared by
+/// every SSE listener so reconnects go straight to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventEndpoint {
+    /// `/global/event`, multiplexing every project with its directory.
+    Global,
+    /// `/event` on servers that predate the global stream.
+    Legacy,
+    /// `/event?directory=…` on servers that only stream a single project.
+    Directory,
+}
+
+/// The server refused the stream with 401 or 403. Retrying with the same credentials
+/// cannot succeed, so listeners park until `wait_for_reauth` lets them go.
+#[derive(Clone, Debug)]
+pub struct AuthRejected {
+    pub url: String,
+    pub status: u16,
+}
+
+impl fmt::Display for AuthRejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SSE connect rejected with status {}", self.status)
+    }
+}
+
+impl std::error::Error for AuthRejected {}
+
+/// The rejection behind a failed connect, if the server refused our credentials.
+pub fn auth_rejection(err: &anyhow::Error) -> Option<&AuthRejected> {
+    err.downcast_ref::<AuthRejected>()
+}
+
+/// Resolves once `timeout` passes, or never without one. Raced against every read, so
+/// a stream that goes silent is dropped and reconnected.
+pub async fn stream_idle(timeout: Option<Duration>) {
+    match timeout {
+        Some(timeout) => tokio::time::sleep(timeout).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// How long a listener parks waiting for a server that is not running before it looks
+/// again on its own.
+const SERVER_IDLE_RECHECK: Duration = Duration::from_secs(60);
+
+/// Cuts stream loops' reconnect delays and server-idle parking short. Counts the loops
+/// waiting in either, so a user intent while every stream is live costs nothing.
+#[derive(Clone)]
+pub struct StreamWake {
+    generation: Arc<watch::Sender<u64>>,
+    waiting: Arc<AtomicUsize>,
+    /// An intent wake no loop has picked up yet; later intents leave it be.
+    pending: Arc<AtomicBool>,
+}
+
+impl Default for StreamWake {
+    fn default() -> Self {
+        Self {
+            generation: Arc::new(watch::channel(0).0),
+            waiting: Arc::new(AtomicUsize::new(0)),
+            pending: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl StreamWake {
+    /// For a stream loop to hold for its lifetime and pass to `sleep_unless_woken`.
+    pub fn subscribe(&self) -> WakeReceiver {
+        WakeReceiver {
+            generation: self.generation.subscribe(),
+            waiting: self.waiting.clone(),
+            pending: self.pending.clone(),
+        }
+    }
+
+    /// End the pending reconnect delay of every stream loop, or its next one if it is not
+    /// sleeping right now.
+    pub fn wake_all(&self) {
+        self.generation
+            .send_modify(|generation| *generation = generation.wrapping_add(1));
+    }
+
+    /// Whether any stream loop is parked or backing off right now.
+    pub fn any_waiting(&self) -> bool {
+        self.waiting.load(Ordering::SeqCst) > 0
+    }
+
+    /// Wake the loops that are parked or backing off. Returns false without waking anything
+    /// when none is, or when an earlier call's wake has not been picked up yet.
+    pub fn wake_waiting(&self) -> bool {
+        if !self.any_waiting() || self.pending.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        self.wake_all();
+        true
+    }
+}
+
+/// One stream loop's end of a `StreamWake`.
+pub struct WakeReceiver {
+    generation: watch::Receiver<u64>,
+    waiting: Arc<AtomicUsize>,
+    pending: Arc<AtomicBool>,
+}
+
+impl WakeReceiver {
+    /// Counts the loop as waiting until dropped.
+    fn waiting(&self) -> Waiting {
+        self.waiting.fetch_add(1, Ordering::SeqCst);
+        Waiting(self.waiting.clone())
+    }
+}
+
+struct Waiting(Arc<AtomicUsize>);
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Wait out a reconnect delay, cut short when streams are woken (a user intent, wake from
+/// sleep, the network coming back). Each stream loop keeps one `wake` receiver for its
+/// lifetime, so a wake that arrives while the loop is connecting or reading rather than
+/// sleeping still ends its next delay.
+pub async fn sleep_unless_woken(wake: &mut WakeReceiver, delay: Duration) {
+    if !wake.generation.has_changed().unwrap_or(false) {
+        let _waiting = wake.waiting();
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => return,
+            Ok(()) = wake.generation.changed() => {}
+        }
+    }
+    wake.generation.borrow_and_update();
+    wake.pending.store(false, Ordering::SeqCst);
+}
+
+/// Resolves at the next wake `wake` has not seen, leaving it unseen for the loop's
+/// following `sleep_unless_woken`.
+pub async fn woken(wake: &WakeReceiver) {
+    let mut peek = wake.generation.clone();
+    let _waiting = wake.waiting();
+    if peek.changed().await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Park a listener whose server is not running until `status` changes or it is woken,
+/// looking again after a while regardless.
+pub async fn wait_for_change<T>(status: &mut watch::Receiver<T>, wake: &WakeReceiver) {
+    tokio::select! {
+        _ = status.changed() => {}
+        _ = tokio::time::sleep(SERVER_IDLE_RECHECK) => {}
+        _ = woken(wake) => {}
+    }
+}
+
+/// Whether a data frame is a keepalive rather than an event.
+pub fn is_keepalive(raw: &str) -> bool {
+    let raw = raw.trim();
+    KEEPALIVE_PAYLOADS.contains(&raw)
+        || serde_json::from_str::<Value>(raw)
+            .is_ok_and(|value| value.as_object().is_some_and(|object| object.is_empty()))
+}
+
+/// The start of a frame that did not parse, for the log. Frames can carry whole
+/// messages, so the rest is left out.
+pub fn unparsed_prefix(raw: &str) -> String {
+    let mut chars = raw.chars();
+    let mut prefix: String = chars.by_ref().take(UNPARSED_PREFIX_CHARS).collect();
+    if chars.next().is_some() {
+        prefix.push('…');
+    }
+    prefix
+}
+
+/// What a connected stream covers.
+#[derive(Clone, Debug)]
+pub enum SseScope {
+    Global,
+    Directory(PathBuf),
+}
+
+/// Connect to the server's event stream, using the endpoint negotiated for the current
+/// process. Falls back through every endpoint shape when the negotiated one fails, and
+/// remembers whichever worked. `directory` pins the directory-scoped stream to a project;
+/// otherwise the active project from settings is used.
+pub async fn connect_event_stream(
+    runtime: &DesktopRuntime,
+    opencode: &OpenCodeManager,
+    client: &Client,
+    base: &str,
+    directory: Option<&Path>,
+    log_prefix: &str,
+) -> Result<(reqwest::Response, SseScope)> {
+    let token = opencode.auth_token();
+    let token = token.as_deref();
+    let negotiated = match opencode.event_endpoint() {
+        Some(endpoint) => endpoint,
+        None => {
+            let endpoint = negotiate_endpoint(client, base, token).await;
+            opencode.set_event_endpoint(endpoint);
+            endpoint
+        }
+    };
+
+    match connect_endpoint(
+        runtime, client, base, token, directory, negotiated, log_prefix,
+    )
+    .await
+    {
+        Ok(connected) => return Ok(connected),
+        // Every endpoint sits behind the same auth; trying the others only adds noise.
+        Err(err) if auth_rejection(&err).is_some() => return Err(err),
+        Err(err) => {
+            debug!("{log_prefix} Negotiated SSE endpoint {negotiated:?} failed ({err:?}); falling back");
+        }
+    }
+
+    let mut last_error = None;
+    for endpoint in [
+        EventEndpoint::Global,
+        EventEndpoint::Legacy,
+        EventEndpoint::Directory,
+    ] {
+        if endpoint == negotiated {
+            continue;
+        }
+        match connect_endpoint(
+            runtime, client, base, token, directory, endpoint, log_prefix,
+        )
+        .await
+        {
+            Ok(connected) => {
+                opencode.set_event_endpoint(endpoint);
+                return Ok(connected);
+            }
+            Err(err) if auth_rejection(&err).is_some() => return Err(err),
+            Err(err) => {
+                debug!("{log_prefix} SSE endpoint {endpoint:?} unavailable ({err:?})");
+                last_error = Some(err);
+            }
+        }
+    }
+    Err(last_error.unwrap_or_else(|| anyhow::anyhow!("No SSE endpoint available")))
+}
+
+/// Servers that report their version through `/global/health` also serve the global
+/// event stream; older ones get the legacy stream without probing it first.
+async fn negotiate_endpoint(client: &Client, base: &str, token: Option<&str>) -> EventEndpoint {
+    let request = client
+        .get(format!("{base}/global/health"))
+        .timeout(NEGOTIATE_TIMEOUT);
+    let response = with_auth(request, token).send().await;
+    match response {
+        Ok(response) if response.status().is_success() => {
+            let version = response
+                .json::<Value>()
+                .await
+                .ok()
+                .and_then(|health| health.get("version")?.as_str().map(str::to_string));
+            info!(
+                "[desktop:opencode] Server {} supports the global event stream",
+                version.as_deref().unwrap_or("(unknown version)")
+            );
+            EventEndpoint::Global
+        }
+        _ => {
+            info!("[desktop:opencode] Server predates the global event stream; using /event");
+            EventEndpoint::Legacy
+        }
+    }
+}
+
+async fn connect_endpoint(
+    runtime: &DesktopRuntime,
+    client: &Client,
+    base: &str,
+    token: Option<&str>,
+    directory: Option<&Path>,
+    endpoint: EventEndpoint,
+    log_prefix: &str,
+) -> Result<(reqwest::Response, SseScope)> {
+    let (url, scope) = match endpoint {
+        EventEndpoint::Global => (format!("{base}/global/event"), SseScope::Global),
+        EventEndpoint::Legacy => (format!("{base}/event"), SseScope::Global),
+        EventEndpoint::Directory => {
+            let working_dir = match directory {
+                Some(directory) => directory.to_path_buf(),
+                None => resolve_project_directory_from_settings(runtime)
+                    .await
+                    .ok_or_else(|| anyhow::anyhow!("No project directory available for SSE"))?,
+            };
+            // `~/work/acme/` and `/home/me/work/acme` are one project to us but two
+            // to the server, which would stream nothing for the former.
+            let working_dir = normalize_directory(&working_dir);
+            let mut parsed = reqwest::Url::parse(&format!("{base}/event"))?;
+            parsed
+                .query_pairs_mut()
+                .append_pair("directory", &working_dir.to_string_lossy());
+            (parsed.to_string(), SseScope::Directory(working_dir))
+        }
+    };
+
+    let response = try_connect_sse(runtime.http(), client, &url, token, log_prefix).await?;
+    debug!("{log_prefix} Using SSE endpoint: {url}");
+    Ok((response, scope))
+}
+
+/// Resolves once the active project moves away from the directory a stream was scoped
+/// to, so the stream can follow it. Never resolves for global streams.
+pub async fn active_project_moved(
+    changes: &mut broadcast::Receiver<SettingsChanged>,
+    scope: &SseScope,
+) {
+    let SseScope::Directory(connected_dir) = scope else {
+        return std::future::pending().await;
+    };
+    loop {
+        let change = next_settings_change(changes).await;
+        if !change.project_directory_changed() {
+            continue;
+        }
+        match change.current.project_directory() {
+            Some(current_dir) if !paths_equivalent(&current_dir, connected_dir) => return,
+            _ => {}
+        }
+    }
+}
+
+async fn try_connect_sse(
+    http: &HttpClients,
+    client: &Client,
+    url: &str,
+    token: Option<&str>,
+    log_prefix: &str,
+) -> Result<reqwest::Response> {
+    debug!("{log_prefix} Connecting SSE: {url}");
+
+    let request = client
+        .get(url)
+        .header("accept", "text/event-stream")
+        .header("accept-encoding", "identity");
+    let response = with_auth(request, token)
+        .send()
+        .await
+        .map_err(|err| anyhow::anyhow!(http.describe_error(url, &err)))?;
+
+    debug!("{log_prefix} SSE response status={}", response.status());
+    trace!(
+        "{log_prefix} SSE response status={} headers={:?}",
+        response.status(),
+        response.headers()
+    );
+
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        return Err(AuthRejected {
+            url: url.to_string(),
+            status: status.as_u16(),
+        }
+        .into());
+    }
+    if !status.is_success() {
+        anyhow::bail!("SSE connect failed with status {}", response.status());
+    }
+
+    Ok(response)
+}
+
+/// Park a stream the server refused until the user asks to retry (`retry_sse_connections`),
+/// the server connection settings or stored auth token change, or the server itself moves.
+/// `auth_token` is subscribed before connecting, so a token saved meanwhile is not missed.
+pub async fn wait_for_reauth(
+    runtime: &DesktopRuntime,
+    auth_token: &mut watch::Receiver<Option<String>>,
+    status: &mut watch::Receiver<OpenCodeStatus>,
+    settings_changes: &mut broadcast::Receiver<SettingsChanged>,
+    base: &str,
+) {
+    let settings_changed = async {
+        loop {
+            if next_settings_change(settings_changes)
+                .await
+                .opencode_changed()
+            {
+                return;
+            }
+        }
+    };
+    tokio::select! {
+        _ = runtime.wait_for_auth_retry() => {}
+        _ = settings_changed => {}
+        _ = auth_token_changed(auth_token) => {}
+        _ = server_moved(status, base) => {}
+    }
+}
+
+pub async fn resolve_project_directory_from_settings(runtime: &DesktopRuntime) -> Option<PathBuf> {
+    runtime
+        .settings()
+        .load_typed()
+        .await
+        .ok()?
+        .project_directory()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use axum::{
+        body::Body,
+        http::{header, HeaderMap, StatusCode},
+        response::IntoResponse,
+        routing::get,
+        Router,
+    };
+    use futures_util::{stream, StreamExt, TryStreamExt};
+    use tokio::{io::AsyncBufReadExt, sync::mpsc, time::Instant};
+    use tokio_util::io::StreamReader;
+
+    use super::*;
+
+    /// Parked-to-connecting latency the fast-resume path has to stay under.
+    const RESUME_BUDGET: Duration = Duration::from_millis(200);
+    const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+    #[tokio::test(start_paused = true)]
+    async fn wake_during_the_delay_resumes_within_budget() {
+        let wake = StreamWake::default();
+        let mut rx = wake.subscribe();
+        let sleeper = tokio::spawn(async move {
+            let started = Instant::now();
+            sleep_unless_woken(&mut rx, RECONNECT_DELAY).await;
+            started.elapsed()
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        wake.wake_all();
+
+        let slept = sleeper.await.expect("sleeper finishes");
+        assert!(slept < RESUME_BUDGET, "resumed after {slept:?}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn wake_while_connecting_ends_the_next_delay() {
+        let wake = StreamWake::default();
+        let mut rx = wake.subscribe();
+        // The loop was busy connecting when the intent arrived.
+        wake.wake_all();
+
+        let started = Instant::now();
+        sleep_unless_woken(&mut rx, RECONNECT_DELAY).await;
+        assert!(started.elapsed() < RESUME_BUDGET);
+
+        // Consumed: the delay after that runs in full.
+        let started = Instant::now();
+        sleep_unless_woken(&mut rx, RECONNECT_DELAY).await;
+        assert!(started.elapsed() >= RECONNECT_DELAY);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn repeated_wakes_end_one_delay() {
+        let wake = StreamWake::default();
+        let mut rx = wake.subscribe();
+        wake.wake_all();
+        wake.wake_all();
+        wake.wake_all();
+
+        sleep_unless_woken(&mut rx, RECONNECT_DELAY).await;
+        let started = Instant::now();
+        sleep_unless_woken(&mut rx, RECONNECT_DELAY).await;
+        assert!(started.elapsed() >= RECONNECT_DELAY);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn woken_leaves_the_wake_for_the_reconnect_delay() {
+        let wake = StreamWake::default();
+        let mut rx = wake.subscribe();
+        tokio::join!(woken(&rx), async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            wake.wake_all();
+        });
+
+        let started = Instant::now();
+        sleep_unless_woken(&mut rx, RECONNECT_DELAY).await;
+        assert!(started.elapsed() < RESUME_BUDGET);
+    }
+
+    /// A listener whose server is idle: parked until woken, then through the reconnect
+    /// delay to its next connect attempt, reported on `connecting`.
+    async fn idle_server_listener(
+        mut rx: WakeReceiver,
+        connecting: mpsc::UnboundedSender<Instant>,
+    ) {
+        let (_status_tx, mut status) = watch::channel(());
+        loop {
+            wait_for_change(&mut status, &rx).await;
+            sleep_unless_woken(&mut rx, RECONNECT_DELAY).await;
+            if connecting.send(Instant::now()).is_err() {
+                return;
+            }
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn intent_unparks_an_idle_server_listener_within_budget() {
+        let wake = StreamWake::default();
+        let (tx, mut connecting) = mpsc::unbounded_channel();
+        tokio::spawn(idle_server_listener(wake.subscribe(), tx));
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        assert!(wake.any_waiting());
+        assert!(connecting.try_recv().is_err());
+
+        let signalled = Instant::now();
+        assert!(wake.wake_waiting());
+        let reached = connecting.recv().await.expect("listener connects");
+        let latency = reached - signalled;
+        assert!(latency < RESUME_BUDGET, "connecting after {latency:?}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn repeated_intents_start_one_wake() {
+        let wake = StreamWake::default();
+        let mut generation = wake.generation.subscribe();
+        let (tx, mut connecting) = mpsc::unbounded_channel();
+        tokio::spawn(idle_server_listener(wake.subscribe(), tx));
+        tokio::time::sleep(Duration::from_secs(30)).await;
+
+        assert!(wake.wake_waiting());
+        assert!(!wake.wake_waiting());
+        assert!(!wake.wake_waiting());
+        assert_eq!(*generation.borrow_and_update(), 1);
+
+        connecting.recv().await.expect("listener connects");
+        // One wake, one attempt: the listener is parked again rather than reconnecting.
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert!(connecting.try_recv().is_err());
+        assert!(wake.any_waiting());
+
+        // Picked up, so the next intent wakes it again.
+        assert!(wake.wake_waiting());
+        assert_eq!(*generation.borrow_and_update(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn intent_while_live_does_nothing() {
+        let wake = StreamWake::default();
+        let _live = wake.subscribe();
+
+        assert!(!wake.any_waiting());
+        assert!(!wake.wake_waiting());
+        assert_eq!(*wake.generation.borrow(), 0);
+    }
+
+    /// A server whose event stream wants `Bearer secret`, counting connect attempts.
+    async fn auth_server() -> (String, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let router = Router::new().route(
+            "/global/event",
+            get(move |headers: HeaderMap| async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let authorized = headers
+                    .get(header::AUTHORIZATION)
+                    .is_some_and(|value| value == "Bearer secret");
+                if !authorized {
+                    return StatusCode::UNAUTHORIZED.into_response();
+                }
+                (
+                    [(header::CONTENT_TYPE, "text/event-stream")],
+                    "data: {}\n\n",
+                )
+                    .into_response()
+            }),
+        );
+        (serve(router).await, requests)
+    }
+
+    /// A server whose event stream sends one keepalive and then nothing, without closing.
+    async fn silent_server() -> (String, Arc<AtomicUsize>) {
+        let connects = Arc::new(AtomicUsize::new(0));
+        let counter = connects.clone();
+        let router = Router::new().route(
+            "/global/event",
+            get(move || async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let frames = stream::once(async { Ok::<_, Infallible>("data: {}\n\n") })
+                    .chain(stream::pending());
+                (
+                    [(header::CONTENT_TYPE, "text/event-stream")],
+                    Body::from_stream(frames),
+                )
+            }),
+        );
+        (serve(router).await, connects)
+    }
+
+    async fn serve(router: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock server");
+        let base = format!("http://{}", listener.local_addr().expect("local addr"));
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        base
+    }
+
+    #[tokio::test]
+    async fn rejected_stream_reconnects_once_the_token_is_saved() {
+        let (base, requests) = auth_server().await;
+        let http = HttpClients::new().expect("http clients");
+        let client = http.streaming();
+        let url = format!("{base}/global/event");
+        let opencode = Arc::new(OpenCodeManager::new_with_directory(None));
+        // Subscribed before connecting, as the stream loops do.
+        let mut auth_token = opencode.subscribe_auth_token();
+
+        let token = auth_token.borrow().clone();
+        let err = try_connect_sse(&http, &client, &url, token.as_deref(), "[test]")
+            .await
+            .expect_err("rejected without a token");
+        assert_eq!(
+            auth_rejection(&err).map(|rejected| rejected.status),
+            Some(401)
+        );
+
+        let saver = {
+            let opencode = opencode.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                opencode.set_auth_token(Some(" secret ".to_string()));
+            })
+        };
+        tokio::time::timeout(Duration::from_secs(5), auth_token_changed(&mut auth_token))
+            .await
+            .expect("saving a token ends the wait");
+        saver.await.expect("token saved");
+
+        let token = auth_token.borrow_and_update().clone();
+        let response = try_connect_sse(&http, &client, &url, token.as_deref(), "[test]")
+            .await
+            .expect("accepted with the token");
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn saving_the_same_token_does_not_wake_parked_streams() {
+        let opencode = OpenCodeManager::new_with_directory(None);
+        opencode.set_auth_token(Some("secret".to_string()));
+        let mut auth_token = opencode.subscribe_auth_token();
+
+        opencode.set_auth_token(Some("secret ".to_string()));
+        let woke = tokio::time::timeout(
+            Duration::from_millis(50),
+            auth_token_changed(&mut auth_token),
+        )
+        .await;
+        assert!(woke.is_err(), "an unchanged token woke the stream");
+    }
+
+    #[tokio::test]
+    async fn silent_stream_is_dropped_and_reconnected() {
+        let (base, connects) = silent_server().await;
+        let http = HttpClients::new().expect("http clients");
+        let client = http.streaming();
+        let url = format!("{base}/global/event");
+        let idle_timeout = Some(Duration::from_millis(200));
+
+        let follow = async {
+            for attempt in 1..=2 {
+                let response = try_connect_sse(&http, &client, &url, None, "[test]")
+                    .await
+                    .expect("connected");
+                assert_eq!(connects.load(Ordering::SeqCst), attempt);
+                let stream = response.bytes_stream().map_err(std::io::Error::other);
+                let mut reader = StreamReader::new(stream);
+                let mut lines = 0;
+                // As the stream loops read: every read races the idle timeout.
+                loop {
+                    let mut buf = Vec::new();
+                    tokio::select! {
+                        read = reader.read_until(b'\n', &mut buf) => {
+                            let read = read.expect("read");
+                            assert!(read > 0, "the server closed the stream");
+                            lines += 1;
+                        }
+                        _ = stream_idle(idle_timeout) => break,
+                    }
+                }
+                // The keepalive's data line and the blank line ending it.
+                assert_eq!(lines, 2);
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), follow)
+            .await
+            .expect("the silent stream timed out instead of hanging");
+    }
+}
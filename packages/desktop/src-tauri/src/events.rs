@@ -0,0 +1,354 @@
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::session_activity::StreamState;
+
+/// `openchamber:session-activity`: a session changed phase. The optional groups only
+/// appear for the phases they describe.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionActivityPayload {
+    pub session_id: String,
+    pub phase: &'static str,
+    /// Null for a session seen for the first time.
+    pub previous_phase: Option<&'static str>,
+    pub reason: &'static str,
+    #[serde(flatten)]
+    pub input: Option<PendingInputFields>,
+    #[serde(flatten)]
+    pub error: Option<ErrorFields>,
+    #[serde(flatten)]
+    pub retry: Option<RetryFields>,
+    /// The session's project, when a stream reported it or the session was looked up.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub directory: Option<String>,
+    /// Known once the session has been looked up; absent for its first payloads.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The parent of a subagent's session, once looked up.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+}
+
+/// The prompt a `waiting-for-input` session is waiting on.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingInputFields {
+    /// `question` or `permission`.
+    pub input_kind: &'static str,
+    pub input_id: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorFields {
+    pub error_type: String,
+    pub error_summary: String,
+}
+
+/// Sent whole for a `retry` phase, with null for whatever the server left out.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryFields {
+    pub retry_attempt: Option<u64>,
+    pub retry_provider: Option<String>,
+    pub retry_message: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    pub retry_at: Option<i64>,
+}
+
+/// One payload as is, several as an array in arrival order.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum Batched<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> From<Vec<T>> for Batched<T> {
+    fn from(mut payloads: Vec<T>) -> Self {
+        if payloads.len() == 1 {
+            Self::One(payloads.remove(0))
+        } else {
+            Self::Many(payloads)
+        }
+    }
+}
+
+/// `openchamber:event-stream-status`: whether the activity stream for a server is
+/// connected, and why not when it isn't, so the UI can explain a stream that keeps
+/// failing (a bad proxy, for instance).
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SseStatusPayload<'a> {
+    pub connected: bool,
+    /// `offline` while the network is down (see `openchamber:connectivity`), and
+    /// `auth-failed` while parked on a rejected connect.
+    pub state: StreamState,
+    /// Project of a dedicated instance; absent for the main server.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub directory: Option<&'a Path>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// `openchamber:auth-required`: sent once per rejected connect so the UI can ask for new
+/// credentials.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthRequiredPayload<'a> {
+    pub endpoint: &'a str,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub directory: Option<&'a Path>,
+}
+
+/// `openchamber:navigate-session`, `openchamber:mark-session-read` and
+/// `openchamber:session-aborted`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionPayload<'a> {
+    pub session_id: &'a str,
+}
+
+/// `openchamber:server-status`: the OpenCode server stopped, recovered or crashed.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerStatusPayload<'a> {
+    pub status: &'a str,
+    pub exit_code: Option<i32>,
+}
+
+/// `openchamber:toast`: a message the UI shows briefly.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToastPayload<'a> {
+    /// How the UI styles it, e.g. `error`.
+    pub kind: &'static str,
+    pub message: &'a str,
+}
+
+/// `openchamber:settings-error`: a setting could not be applied.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsErrorPayload<'a> {
+    /// The setting's key in `settings.json`.
+    pub key: &'static str,
+    pub message: &'a str,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, to_value};
+
+    use super::*;
+
+    fn activity(phase: &'static str) -> SessionActivityPayload {
+        SessionActivityPayload {
+            session_id: "ses_1".to_string(),
+            phase,
+            previous_phase: Some("busy"),
+            reason: "status-event",
+            input: None,
+            error: None,
+            retry: None,
+            directory: None,
+            title: None,
+            parent_id: None,
+        }
+    }
+
+    #[test]
+    fn session_activity_leaves_out_what_the_phase_does_not_use() {
+        let payload = SessionActivityPayload {
+            previous_phase: None,
+            ..activity("idle")
+        };
+        assert_eq!(
+            to_value(payload).unwrap(),
+            json!({
+                "sessionId": "ses_1",
+                "phase": "idle",
+                "previousPhase": null,
+                "reason": "status-event",
+            })
+        );
+    }
+
+    #[test]
+    fn session_activity_flattens_pending_input() {
+        let payload = SessionActivityPayload {
+            input: Some(PendingInputFields {
+                input_kind: "question",
+                input_id: "que_1".to_string(),
+            }),
+            directory: Some("/work/acme".to_string()),
+            title: Some("Fix the build".to_string()),
+            parent_id: Some("ses_0".to_string()),
+            ..activity("waiting-for-input")
+        };
+        assert_eq!(
+            to_value(payload).unwrap(),
+            json!({
+                "sessionId": "ses_1",
+                "phase": "waiting-for-input",
+                "previousPhase": "busy",
+                "reason": "status-event",
+                "inputKind": "question",
+                "inputId": "que_1",
+                "directory": "/work/acme",
+                "title": "Fix the build",
+                "parentId": "ses_0",
+            })
+        );
+    }
+
+    #[test]
+    fn session_activity_flattens_the_error() {
+        let payload = SessionActivityPayload {
+            error: Some(ErrorFields {
+                error_type: "ProviderAuthError".to_string(),
+                error_summary: "Invalid API key".to_string(),
+            }),
+            ..activity("error")
+        };
+        assert_eq!(
+            to_value(payload).unwrap(),
+            json!({
+                "sessionId": "ses_1",
+                "phase": "error",
+                "previousPhase": "busy",
+                "reason": "status-event",
+                "errorType": "ProviderAuthError",
+                "errorSummary": "Invalid API key",
+            })
+        );
+    }
+
+    #[test]
+    fn session_activity_sends_retry_fields_whole() {
+        let payload = SessionActivityPayload {
+            retry: Some(RetryFields {
+                retry_attempt: Some(2),
+                retry_provider: None,
+                retry_message: Some("Overloaded".to_string()),
+                retry_at: None,
+            }),
+            ..activity("retry")
+        };
+        assert_eq!(
+            to_value(payload).unwrap(),
+            json!({
+                "sessionId": "ses_1",
+                "phase": "retry",
+                "previousPhase": "busy",
+                "reason": "status-event",
+                "retryAttempt": 2,
+                "retryProvider": null,
+                "retryMessage": "Overloaded",
+                "retryAt": null,
+            })
+        );
+    }
+
+    #[test]
+    fn batches_of_one_are_sent_unwrapped() {
+        let one = Batched::from(vec![SessionPayload {
+            session_id: "ses_1",
+        }]);
+        assert_eq!(to_value(one).unwrap(), json!({ "sessionId": "ses_1" }));
+
+        let many = Batched::from(vec![
+            SessionPayload {
+                session_id: "ses_1",
+            },
+            SessionPayload {
+                session_id: "ses_2",
+            },
+        ]);
+        assert_eq!(
+            to_value(many).unwrap(),
+            json!([{ "sessionId": "ses_1" }, { "sessionId": "ses_2" }])
+        );
+    }
+
+    #[test]
+    fn sse_status_payload() {
+        let payload = SseStatusPayload {
+            connected: false,
+            state: StreamState::AuthFailed,
+            directory: Some(Path::new("/work/acme")),
+            error: Some("SSE connect rejected with status 401".to_string()),
+        };
+        assert_eq!(
+            to_value(payload).unwrap(),
+            json!({
+                "connected": false,
+                "state": "auth-failed",
+                "directory": "/work/acme",
+                "error": "SSE connect rejected with status 401",
+            })
+        );
+
+        let payload = SseStatusPayload {
+            connected: true,
+            state: StreamState::Connected,
+            directory: None,
+            error: None,
+        };
+        assert_eq!(
+            to_value(payload).unwrap(),
+            json!({ "connected": true, "state": "connected" })
+        );
+    }
+
+    #[test]
+    fn auth_required_payload() {
+        let payload = AuthRequiredPayload {
+            endpoint: "http://127.0.0.1:4096/global/event",
+            status: 401,
+            directory: None,
+        };
+        assert_eq!(
+            to_value(payload).unwrap(),
+            json!({ "endpoint": "http://127.0.0.1:4096/global/event", "status": 401 })
+        );
+    }
+
+    #[test]
+    fn server_status_payload() {
+        let payload = ServerStatusPayload {
+            status: "crashed",
+            exit_code: None,
+        };
+        assert_eq!(
+            to_value(payload).unwrap(),
+            json!({ "status": "crashed", "exitCode": null })
+        );
+    }
+
+    #[test]
+    fn toast_payload() {
+        let payload = ToastPayload {
+            kind: "error",
+            message: "OpenCode stopped",
+        };
+        assert_eq!(
+            to_value(payload).unwrap(),
+            json!({ "kind": "error", "message": "OpenCode stopped" })
+        );
+    }
+
+    #[test]
+    fn settings_error_payload() {
+        let payload = SettingsErrorPayload {
+            key: "http.proxy",
+            message: "Not a valid URL",
+        };
+        assert_eq!(
+            to_value(payload).unwrap(),
+            json!({ "key": "http.proxy", "message": "Not a valid URL" })
+        );
+    }
+}
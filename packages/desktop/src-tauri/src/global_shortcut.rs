@@ -0,0 +1,172 @@
+use std::str::FromStr;
+
+use log::{info, warn};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::assistant_notifications::PendingQuestions;
+use crate::events::{SessionPayload, SettingsErrorPayload};
+use crate::settings_watcher::next_settings_change;
+use crate::DesktopRuntime;
+
+/// Used while `globalShortcut` is unset. Cmd+Shift+Space on macOS, Ctrl+Shift+Space
+/// elsewhere.
+pub const DEFAULT_SHORTCUT: &str = "CommandOrControl+Shift+Space";
+
+/// What the settings screen shows next to the shortcut field.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobalShortcutStatus {
+    /// The combination currently registered, if any.
+    pub shortcut: Option<String>,
+    /// Why the configured combination is not registered.
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct GlobalShortcutState {
+    status: Mutex<GlobalShortcutStatus>,
+}
+
+impl GlobalShortcutState {
+    pub fn status(&self) -> GlobalShortcutStatus {
+        self.status.lock().clone()
+    }
+
+    fn set(&self, status: GlobalShortcutStatus) {
+        *self.status.lock() = status;
+    }
+}
+
+/// Keep the system-wide shortcut registered as `globalShortcut` says, re-registering
+/// whenever the setting changes.
+pub fn spawn_global_shortcut(
+    app: AppHandle,
+    runtime: DesktopRuntime,
+) -> tauri::async_runtime::JoinHandle<()> {
+    tauri::async_runtime::spawn(async move {
+        let mut shutdown_rx = runtime.subscribe_shutdown();
+        let mut settings_changes = runtime.subscribe_settings_changes();
+        let mut configured = runtime
+            .settings()
+            .load_typed()
+            .await
+            .map(|settings| settings.global_shortcut)
+            .unwrap_or_default();
+        let mut registered = register(&app, None, configured.as_deref());
+
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                change = next_settings_change(&mut settings_changes) => {
+                    if change.current.global_shortcut != configured {
+                        configured = change.current.global_shortcut.clone();
+                        registered = register(&app, registered, configured.as_deref());
+                    }
+                }
+            }
+        }
+
+        if let Some(shortcut) = registered {
+            let _ = app.global_shortcut().unregister(shortcut);
+        }
+    })
+}
+
+/// Replace `previous` with the configured combination. Returns what is registered now.
+fn register(
+    app: &AppHandle,
+    previous: Option<Shortcut>,
+    configured: Option<&str>,
+) -> Option<Shortcut> {
+    let shortcuts = app.global_shortcut();
+    if let Some(previous) = previous {
+        if let Err(err) = shortcuts.unregister(previous) {
+            warn!("[desktop] Failed to unregister global shortcut: {err}");
+        }
+    }
+
+    let state = app.state::<GlobalShortcutState>();
+    let combo = match configured.map(str::trim) {
+        None => DEFAULT_SHORTCUT,
+        Some("") => {
+            info!("[desktop] Global shortcut turned off");
+            state.set(GlobalShortcutStatus::default());
+            return None;
+        }
+        Some(combo) => combo,
+    };
+
+    let registered = Shortcut::from_str(combo)
+        .map_err(|err| format!("\"{combo}\" is not a valid shortcut: {err}"))
+        .and_then(|shortcut| {
+            shortcuts
+                .on_shortcut(shortcut, |app, _shortcut, event| {
+                    if event.state() == ShortcutState::Pressed {
+                        toggle_main_window(app);
+                    }
+                })
+                .map(|()| shortcut)
+                .map_err(|err| {
+                    format!(
+                        "Could not register {combo}; another application may be using it ({err})"
+                    )
+                })
+        });
+
+    match registered {
+        Ok(shortcut) => {
+            info!("[desktop] Registered global shortcut {combo}");
+            state.set(GlobalShortcutStatus {
+                shortcut: Some(combo.to_string()),
+                error: None,
+            });
+            Some(shortcut)
+        }
+        Err(message) => {
+            warn!("[desktop] {message}");
+            state.set(GlobalShortcutStatus {
+                shortcut: None,
+                error: Some(message.clone()),
+            });
+            let _ = app.emit(
+                "openchamber:settings-error",
+                SettingsErrorPayload {
+                    key: "globalShortcut",
+                    message: &message,
+                },
+            );
+            None
+        }
+    }
+}
+
+/// Hide the window when the user is looking at it, otherwise bring it up. Bringing it up
+/// with a question waiting opens the session that has been waiting longest.
+fn toggle_main_window(app: &AppHandle) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let minimized = window.is_minimized().unwrap_or(false);
+    if window.is_visible().unwrap_or(false) && !minimized && window.is_focused().unwrap_or(false) {
+        let _ = window.hide();
+        return;
+    }
+
+    if minimized {
+        let _ = window.unminimize();
+    }
+    let _ = window.show();
+    let _ = window.set_focus();
+
+    if let Some(session_id) = app.state::<PendingQuestions>().oldest_session() {
+        let _ = app.emit(
+            "openchamber:navigate-session",
+            SessionPayload {
+                session_id: &session_id,
+            },
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use std::{net::IpAddr, time::Duration};
+
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use parking_lot::RwLock;
+use reqwest::{Client, ClientBuilder, NoProxy, Proxy};
+
+use crate::desktop_settings::HttpSettings;
+
+/// Event streams have no total timeout, since a healthy one stays open for days. Each
+/// listener gives up on a silent stream with its own idle timeout instead.
+const STREAMING_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Default for ordinary requests; callers with tighter needs set their own per request.
+const API_TIMEOUT: Duration = Duration::from_secs(30);
+const TCP_KEEPALIVE: Duration = Duration::from_secs(30);
+
+/// Hosts that never go through a proxy: OpenCode listens on loopback.
+const LOOPBACK_HOSTS: [&str; 3] = ["localhost", "127.0.0.1", "::1"];
+/// Checked in order; the first one set wins. Lowercase forms are what curl reads.
+const PROXY_ENV_VARS: [&str; 6] = [
+    "HTTPS_PROXY",
+    "https_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+];
+const NO_PROXY_ENV_VARS: [&str; 2] = ["NO_PROXY", "no_proxy"];
+
+/// HTTP clients shared by the desktop backend, so every feature reuses the same
+/// connection pools. Anything that applies to all outgoing requests belongs in
+/// `build_clients`.
+pub struct HttpClients {
+    current: RwLock<Clients>,
+}
+
+struct Clients {
+    streaming: Client,
+    api: Client,
+    proxy: ProxyConfig,
+}
+
+impl HttpClients {
+    /// Clients using the proxy from the environment, until settings are applied.
+    pub fn new() -> Result<Self> {
+        let proxy = ProxyConfig::resolve(&HttpSettings::default());
+        Ok(Self {
+            current: RwLock::new(build_clients(proxy)?),
+        })
+    }
+
+    /// Rebuild the clients when the proxy configuration changed. A proxy that cannot be
+    /// used keeps the previous clients.
+    pub fn configure(&self, settings: &HttpSettings) {
+        let proxy = ProxyConfig::resolve(settings);
+        if self.current.read().proxy == proxy {
+            return;
+        }
+        match build_clients(proxy) {
+            Ok(clients) => {
+                match &clients.proxy.url {
+                    Some(url) => info!(
+                        "[desktop] Using proxy {} for outgoing requests",
+                        redact(url)
+                    ),
+                    None => info!("[desktop] Not using a proxy for outgoing requests"),
+                }
+                *self.current.write() = clients;
+            }
+            Err(err) => warn!("[desktop] Ignoring proxy configuration: {err}"),
+        }
+    }
+
+    /// For server-sent event streams that stay open for hours.
+    pub fn streaming(&self) -> Client {
+        self.current.read().streaming.clone()
+    }
+
+    /// For request/response calls: OpenCode API lookups, webhooks, uploads.
+    pub fn api(&self) -> Client {
+        self.current.read().api.clone()
+    }
+
+    /// A message for a failed request that says when the proxy is the likely culprit,
+    /// instead of reqwest's generic connection error.
+    pub fn describe_error(&self, url: &str, err: &reqwest::Error) -> String {
+        let current = self.current.read();
+        let proxied = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .is_some_and(|host| !current.proxy.bypasses(&host));
+        match &current.proxy.url {
+            Some(proxy) if proxied && (err.is_connect() || err.is_timeout()) => format!(
+                "Could not connect to {url} through proxy {}; check http.proxy or HTTPS_PROXY ({err})",
+                redact(proxy)
+            ),
+            _ => err.to_string(),
+        }
+    }
+}
+
+fn build_clients(proxy: ProxyConfig) -> Result<Clients> {
+    Ok(Clients {
+        streaming: builder(&proxy)?
+            .connect_timeout(STREAMING_CONNECT_TIMEOUT)
+            .build()?,
+        api: builder(&proxy)?.timeout(API_TIMEOUT).build()?,
+        proxy,
+    })
+}
+
+fn builder(proxy: &ProxyConfig) -> Result<ClientBuilder> {
+    // Proxies are configured explicitly, so reqwest's own environment lookup is off.
+    let mut builder = Client::builder()
+        .user_agent(concat!("OpenChamber-Desktop/", env!("CARGO_PKG_VERSION")))
+        .tcp_keepalive(Some(TCP_KEEPALIVE))
+        .no_proxy();
+    if let Some(url) = &proxy.url {
+        let proxy = Proxy::all(url)
+            .map_err(|err| anyhow!("invalid proxy URL {}: {err}", redact(url)))?
+            .no_proxy(NoProxy::from_string(&proxy.no_proxy.join(",")));
+        builder = builder.proxy(proxy);
+    }
+    Ok(builder)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct ProxyConfig {
+    url: Option<String>,
+    /// Always starts with the loopback hosts.
+    no_proxy: Vec<String>,
+}
+
+impl ProxyConfig {
+    /// Settings take precedence over the environment, field by field.
+    fn resolve(settings: &HttpSettings) -> Self {
+        let url = non_empty(settings.proxy.as_deref())
+            .map(str::to_string)
+            .or_else(|| env_value(&PROXY_ENV_VARS));
+        let listed = non_empty(settings.no_proxy.as_deref())
+            .map(str::to_string)
+            .or_else(|| env_value(&NO_PROXY_ENV_VARS))
+            .unwrap_or_default();
+
+        let mut no_proxy: Vec<String> =
+            LOOPBACK_HOSTS.iter().map(|host| host.to_string()).collect();
+        for entry in listed
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            if !no_proxy.iter().any(|existing| existing == entry) {
+                no_proxy.push(entry.to_string());
+            }
+        }
+        Self { url, no_proxy }
+    }
+
+    /// Whether requests to `host` skip the proxy: `*`, an exact match, or a domain entry
+    /// covering the host (`example.com` and `.example.com` both cover `api.example.com`).
+    fn bypasses(&self, host: &str) -> bool {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback()) {
+            return true;
+        }
+        self.no_proxy.iter().any(|entry| {
+            let domain = entry.trim_start_matches('.');
+            entry == "*"
+                || host.eq_ignore_ascii_case(domain)
+                || host
+                    .to_ascii_lowercase()
+                    .ends_with(&format!(".{}", domain.to_ascii_lowercase()))
+        })
+    }
+}
+
+fn env_value(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .find_map(|name| {
+            std::env::var(name)
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+        })
+        .map(|value| value.trim().to_string())
+}
+
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|value| !value.is_empty())
+}
+
+/// Hide credentials embedded in a proxy URL before it is logged or shown.
+fn redact(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(mut parsed) if parsed.password().is_some() || !parsed.username().is_empty() => {
+            let _ = parsed.set_username("");
+            let _ = parsed.set_password(None);
+            parsed.to_string()
+        }
+        _ => url.to_string(),
+    }
+}
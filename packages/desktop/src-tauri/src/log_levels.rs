@@ -0,0 +1,142 @@
+use std::{
+    collections::BTreeMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use log::{LevelFilter, Metadata};
+use serde::Serialize;
+
+/// Level for everything outside the modules below, dependencies included.
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+/// A group of log targets whose level can be changed while the app runs. Targets are
+/// module paths below this crate.
+struct LogModule {
+    name: &'static str,
+    targets: &'static [&'static str],
+}
+
+const MODULES: &[LogModule] = &[
+    LogModule {
+        name: "desktop:activity",
+        targets: &["session_activity", "commands::activity"],
+    },
+    LogModule {
+        name: "desktop:notify",
+        targets: &["assistant_notifications", "commands::notifications"],
+    },
+    LogModule {
+        name: "desktop:sse",
+        targets: &["event_stream", "sse_event_log"],
+    },
+    LogModule {
+        name: "opencode-manager",
+        targets: &["opencode_manager", "opencode_instances", "opencode_log"],
+    },
+    LogModule {
+        name: "settings",
+        targets: &["settings_watcher", "desktop_settings", "commands::settings"],
+    },
+];
+
+static LEVELS: [AtomicUsize; MODULES.len()] =
+    [const { AtomicUsize::new(DEFAULT_LEVEL as usize) }; MODULES.len()];
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleLogLevel {
+    pub module: &'static str,
+    pub level: String,
+}
+
+/// The log filter: records from a listed module follow its level, the rest `DEFAULT_LEVEL`.
+pub fn enabled(metadata: &Metadata) -> bool {
+    let level = module_index(metadata.target())
+        .map(|index| level_at(index))
+        .unwrap_or(DEFAULT_LEVEL);
+    metadata.level() <= level
+}
+
+/// Apply the levels stored in settings. Modules left out go back to the default.
+pub fn apply(levels: &BTreeMap<String, String>) {
+    for (index, module) in MODULES.iter().enumerate() {
+        let level = levels
+            .get(module.name)
+            .and_then(|level| parse_level(level))
+            .unwrap_or(DEFAULT_LEVEL);
+        LEVELS[index].store(level as usize, Ordering::Relaxed);
+    }
+    update_max_level();
+}
+
+/// Set one module's level, returning its canonical name. The reason is phrased for the
+/// user when the module or level is unknown.
+pub fn set(module: &str, level: &str) -> Result<(&'static str, LevelFilter), String> {
+    let index = MODULES
+        .iter()
+        .position(|candidate| candidate.name == module.trim())
+        .ok_or_else(|| {
+            let known: Vec<&str> = MODULES.iter().map(|module| module.name).collect();
+            format!(
+                "Unknown log module \"{module}\"; expected one of {}",
+                known.join(", ")
+            )
+        })?;
+    let level = parse_level(level).ok_or_else(|| {
+        format!("Unknown log level \"{level}\"; expected off, error, warn, info, debug or trace")
+    })?;
+    LEVELS[index].store(level as usize, Ordering::Relaxed);
+    update_max_level();
+    Ok((MODULES[index].name, level))
+}
+
+pub fn levels() -> Vec<ModuleLogLevel> {
+    MODULES
+        .iter()
+        .enumerate()
+        .map(|(index, module)| ModuleLogLevel {
+            module: module.name,
+            level: level_at(index).as_str().to_ascii_lowercase(),
+        })
+        .collect()
+}
+
+pub fn is_module(name: &str) -> bool {
+    MODULES.iter().any(|module| module.name == name)
+}
+
+pub fn parse_level(level: &str) -> Option<LevelFilter> {
+    level.trim().parse().ok()
+}
+
+/// Let `log` skip records no module wants before they reach the filter.
+pub fn update_max_level() {
+    let max = (0..MODULES.len())
+        .map(level_at)
+        .fold(DEFAULT_LEVEL, LevelFilter::max);
+    log::set_max_level(max);
+}
+
+fn level_at(index: usize) -> LevelFilter {
+    match LEVELS[index].load(Ordering::Relaxed) {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Error,
+        2 => LevelFilter::Warn,
+        3 => LevelFilter::Info,
+        4 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+fn module_index(target: &str) -> Option<usize> {
+    let (crate_name, path) = target.split_once("::")?;
+    if crate_name != env!("CARGO_CRATE_NAME") {
+        return None;
+    }
+    MODULES.iter().position(|module| {
+        module.targets.iter().any(|prefix| {
+            path.strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+        })
+    })
+}
@@ -18,3 +18,20 @@ pub fn log_file_path() -> Option<PathBuf> {
     dir.push("openchamber.log");
     Some(dir)
 }
+
+/// How many OpenCode output files are kept, the live one included.
+pub const OPENCODE_LOG_FILES: usize = 3;
+
+/// OpenCode server output logs, newest first: `opencode.log`, then its rotations
+/// `opencode.log.1` and `opencode.log.2`.
+pub fn opencode_log_paths() -> Option<Vec<PathBuf>> {
+    let dir = log_directory()?;
+    Some(
+        (0..OPENCODE_LOG_FILES)
+            .map(|index| match index {
+                0 => dir.join("opencode.log"),
+                _ => dir.join(format!("opencode.log.{index}")),
+            })
+            .collect(),
+    )
+}
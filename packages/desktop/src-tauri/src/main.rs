@@ -1,25 +1,73 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod assistant_notifications;
+mod busy_time;
 mod commands;
+mod compaction;
+mod connectivity;
+mod crash_reports;
+mod deep_links;
+mod desktop_settings;
+mod diagnostics;
+mod event_stream;
+mod events;
+mod global_shortcut;
+mod http;
+mod log_levels;
 mod logging;
+mod model_names;
 mod opencode_auth;
 mod opencode_config;
+mod opencode_instances;
+mod opencode_log;
 mod opencode_manager;
 mod path_utils;
+mod power_events;
+mod project_names;
+mod recent_keys;
+mod repeated_log;
+mod retry_status;
+mod secrets;
+mod session_abort;
 mod session_activity;
+mod session_info;
+mod session_lifecycle;
+mod settings_watcher;
+mod simulated_events;
+mod single_instance;
 mod skills_catalog;
+mod sleep_inhibitor;
+mod sse_capture;
+mod sse_event_log;
+mod status_file;
+mod task_registry;
+#[cfg(target_os = "windows")]
+mod taskbar;
+mod telemetry;
+mod tray;
+mod usage;
 mod window_state;
+mod window_title;
 
 use std::{
     collections::HashMap,
-    path::PathBuf,
-    sync::Arc,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, Result};
-use assistant_notifications::spawn_assistant_notifications;
+use assistant_notifications::{
+    cancel_attention, handle_window_activated, notify_port_conflict, notify_server_running,
+    notify_server_stopped, notify_server_unreachable, spawn_assistant_notifications,
+    sync_question_badge, ActiveSessions, CompactingSessions, CompletionDigest, ContextWindows,
+    DeliveredNotifications, MutedSessions, NotificationActivation, NotificationHistory,
+    PendingQuestions, QuestionReminders, QuietHoursBacklog, RateLimits, RunningTools,
+    ServerStatusNotifier,
+};
 use axum::{
     body::{to_bytes, Body},
     extract::{Request, State},
@@ -28,6 +76,7 @@ use axum::{
     routing::{any, get, post},
     Json, Router,
 };
+use busy_time::BusyTime;
 use commands::files::{create_directory, exec_commands, list_directory, read_file, read_file_binary, search_files, write_file};
 use commands::git::{
     add_git_worktree, check_is_git_repository, checkout_branch, create_branch, create_git_commit, rename_branch,
@@ -38,27 +87,79 @@ use commands::git::{
     git_fetch, git_pull, git_push, is_linked_worktree, list_git_worktrees, remove_git_worktree,
     revert_git_file, set_git_identity, update_git_identity,
 };
-use commands::logs::fetch_desktop_logs;
+use commands::logs::{fetch_desktop_logs, get_log_levels, get_opencode_logs, set_log_level};
 
-use commands::notifications::desktop_notify;
+use commands::activity::{
+    abort_all_busy_sessions, abort_session, get_session_info, pause_background_streams,
+    resume_background_streams, signal_user_intent,
+};
+use commands::busy_time::get_time_report;
+use commands::deep_links::deep_links_ready;
+use commands::diagnostics::export_diagnostics;
+use commands::integrations::get_status_file_info;
+use commands::notifications::{
+    clear_notification_history, desktop_notify, get_do_not_disturb_state, get_muted_sessions,
+    get_notification_history, get_pending_questions, list_notification_sounds,
+    mute_session_notifications, play_notification_sound_preview, reply_to_permission,
+    send_test_notification, set_active_session, unmute_session_notifications,
+};
 use commands::permissions::{
     pick_directory, process_directory_selection, request_directory_access,
     restore_bookmarks_on_startup, start_accessing_directory, stop_accessing_directory,
 };
-use commands::settings::{load_settings, restart_opencode, save_settings};
+use commands::power::get_power_state;
+use commands::secrets::{delete_secret, get_secret, set_secret};
+use commands::settings::{
+    export_settings, import_settings, load_settings, restart_opencode, save_settings,
+    update_setting,
+};
+use commands::shortcut::get_global_shortcut_status;
+use commands::sse_events::{
+    clear_recent_sse_events, get_event_stream_status, get_known_event_types, get_recent_sse_events,
+    replay_sse_capture, retry_sse_connections, simulate_desktop_event, start_sse_capture,
+    stop_sse_capture,
+};
+use commands::tasks::get_background_tasks;
 use commands::terminal::{
     close_terminal, create_terminal_session, force_kill_terminal, resize_terminal,
     restart_terminal_session, send_terminal_input, TerminalState,
 };
+use commands::usage::{get_latency_stats, get_session_usage, get_usage_summary};
+use commands::window::{register_window_project, reset_window_geometry};
+use connectivity::{is_loopback_url, spawn_connectivity_monitor, Connectivity};
+use crash_reports::CrashReports;
+use deep_links::{handle_deep_links, project_link, register_deep_links, DeepLinks};
+use desktop_settings::{migrate as migrate_settings, DesktopSettings, ProjectEntry};
+use event_stream::{wait_for_change, StreamWake, WakeReceiver};
 use futures_util::StreamExt as FuturesStreamExt;
+use global_shortcut::{spawn_global_shortcut, GlobalShortcutState};
+use http::HttpClients;
 use log::{error, info, warn};
-use opencode_manager::OpenCodeManager;
-use path_utils::expand_tilde_path;
+use model_names::ModelNames;
+use opencode_instances::OpenCodeInstances;
+use opencode_manager::{OpenCodeManager, OpenCodeState, OpenCodeStatus};
+use path_utils::{expand_path, try_expand_path};
 use portpicker::pick_unused_port;
+use power_events::{spawn_power_monitor, PowerState};
+use project_names::{spawn_project_names, ProjectNames};
 use reqwest::{header, Body as ReqwestBody, Client};
+use secrets::{migrate_settings_secrets, SecretStore, OPENCODE_AUTH_TOKEN};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use session_activity::spawn_session_activity_tracker;
+use session_activity::{
+    spawn_session_activity_tracker, BusySessions, EventStreamHealth, WindowProjects,
+};
+use session_info::SessionInfoCache;
+use session_lifecycle::SessionLifecycleEvents;
+use settings_watcher::{spawn_settings_watcher, SettingsChanged};
+use simulated_events::SimulatedEvents;
+use single_instance::{handle_launch_arguments, handle_second_instance, single_instance_enforced};
+use sleep_inhibitor::{spawn_sleep_inhibitor, SleepInhibition};
+use sse_capture::SseCapture;
+use sse_event_log::SseEventLog;
+use status_file::spawn_status_file;
+use task_registry::TaskRegistry;
+use telemetry::{spawn_telemetry_reports, TelemetryCounters};
 #[cfg(feature = "devtools")]
 use tauri::WebviewWindow;
 use tauri::{Emitter, Manager};
@@ -70,13 +171,13 @@ use tauri_plugin_shell::init as shell_plugin;
 use tokio::{
     fs,
     net::TcpListener,
-    sync::{broadcast, Mutex},
+    sync::{broadcast, watch, Mutex, Notify},
 };
 use tower_http::cors::CorsLayer;
-use window_state::{load_window_state, persist_window_state, WindowStateManager};
-
-#[cfg(target_os = "macos")]
-use std::sync::atomic::{AtomicBool, Ordering};
+use tray::spawn_session_tray;
+use usage::UsageTracker;
+use window_state::{load_window_states, persist_window_state, WindowStateManager};
+use window_title::spawn_window_title;
 
 #[cfg(target_os = "macos")]
 use window_vibrancy::{apply_vibrancy, NSVisualEffectMaterial};
@@ -146,28 +247,53 @@ const GITHUB_BUG_REPORT_URL: &str =
 const GITHUB_FEATURE_REQUEST_URL: &str =
     "https://github.com/btriapitsyn/openchamber/issues/new?template=feature_request.yml";
 const DISCORD_INVITE_URL: &str = "https://discord.gg/ZYRSdnwwKA";
+/// How long shutdown waits for background listeners before aborting them.
+const LISTENER_STOP_TIMEOUT: Duration = Duration::from_secs(2);
 
 #[derive(Clone)]
 pub(crate) struct DesktopRuntime {
     server_port: u16,
     shutdown_tx: broadcast::Sender<()>,
+    settings_changes_tx: broadcast::Sender<SettingsChanged>,
     opencode: Arc<OpenCodeManager>,
+    instances: Arc<OpenCodeInstances>,
     settings: Arc<SettingsStore>,
+    secrets: Arc<SecretStore>,
+    telemetry: Arc<TelemetryCounters>,
+    sse_events: Arc<SseEventLog>,
+    usage: UsageTracker,
+    http: Arc<HttpClients>,
+    tasks: Arc<TaskRegistry>,
+    /// Cuts reconnect delays short; see `event_stream::sleep_unless_woken`.
+    stream_wake: StreamWake,
+    /// Releases streams parked after the server rejected our credentials.
+    auth_retry: Arc<Notify>,
+    power: Arc<watch::Sender<PowerState>>,
+    connectivity: Arc<watch::Sender<Connectivity>>,
+    /// `events.paused`: the user asked for no background streaming.
+    paused: Arc<watch::Sender<bool>>,
+    server_wake_in_flight: Arc<AtomicBool>,
+    /// Background tasks that follow the shutdown broadcast; awaited before OpenCode stops.
+    listeners: Arc<parking_lot::Mutex<Vec<tauri::async_runtime::JoinHandle<()>>>>,
 }
 
 impl DesktopRuntime {
     fn initialize_sync() -> Result<Self> {
         let settings = Arc::new(SettingsStore::new()?);
+        let secrets = Arc::new(SecretStore::new()?);
         let opencode = Arc::new(OpenCodeManager::new_with_directory(None));
+        let instances = Arc::new(OpenCodeInstances::new(opencode.clone()));
 
-        let client = Client::builder().build()?;
+        let http = Arc::new(HttpClients::new()?);
 
         let (shutdown_tx, shutdown_rx) = broadcast::channel(2);
+        let (settings_changes_tx, _) = broadcast::channel(16);
         let server_port =
             pick_unused_port().ok_or_else(|| anyhow!("No free port available"))? as u16;
         let server_state = ServerState {
-            client,
+            client: http.streaming(),
             opencode: opencode.clone(),
+            instances: instances.clone(),
             settings: settings.clone(),
             server_port,
             directory_change_lock: Arc::new(Mutex::new(())),
@@ -179,12 +305,38 @@ impl DesktopRuntime {
         Ok(Self {
             server_port,
             shutdown_tx,
+            settings_changes_tx,
             opencode,
+            instances,
             settings,
+            secrets,
+            telemetry: Arc::new(TelemetryCounters::default()),
+            sse_events: Arc::new(SseEventLog::default()),
+            usage: UsageTracker::load(),
+            http,
+            tasks: Arc::new(TaskRegistry::default()),
+            stream_wake: StreamWake::default(),
+            auth_retry: Arc::new(Notify::new()),
+            power: Arc::new(watch::channel(PowerState::Awake).0),
+            connectivity: Arc::new(watch::channel(Connectivity::Online).0),
+            paused: Arc::new(watch::channel(false).0),
+            server_wake_in_flight: Arc::new(AtomicBool::new(false)),
+            listeners: Arc::new(parking_lot::Mutex::new(Vec::new())),
         })
     }
 
     async fn start_opencode(&self) {
+        if let Ok(settings) = self.settings.load_typed().await {
+            self.http.configure(&settings.http);
+            self.sse_events.set_capacity(settings.sse_event_buffer_size);
+            log_levels::apply(&settings.log_levels);
+            self.usage.set_persist(settings.persist_usage);
+            self.set_paused(settings.events.paused);
+        }
+        if let Ok(settings) = self.settings.load().await {
+            self.opencode.apply_settings(&settings);
+        }
+        self.reload_opencode_auth_token().await;
         if self.opencode.is_cli_available() {
             if let Err(e) = self.opencode.ensure_running().await {
                 warn!("[desktop] Failed to start OpenCode: {}", e);
@@ -194,8 +346,28 @@ impl DesktopRuntime {
         }
     }
 
+    fn track_listener(&self, task: tauri::async_runtime::JoinHandle<()>) {
+        self.listeners.lock().push(task);
+    }
+
     async fn shutdown(&self) {
         let _ = self.shutdown_tx.send(());
+
+        // Let the SSE listeners wind down before the servers they follow go away.
+        let mut listeners = std::mem::take(&mut *self.listeners.lock());
+        let stopped = tokio::time::timeout(
+            LISTENER_STOP_TIMEOUT,
+            futures_util::future::join_all(listeners.iter_mut()),
+        )
+        .await;
+        if stopped.is_err() {
+            warn!("[desktop] Background listeners did not stop in time; aborting them");
+            for listener in &listeners {
+                listener.abort();
+            }
+        }
+
+        self.instances.stop_projects().await;
         let _ = self.opencode.shutdown().await;
     }
 
@@ -203,19 +375,255 @@ impl DesktopRuntime {
         self.settings.as_ref()
     }
 
+    pub(crate) fn secrets(&self) -> &SecretStore {
+        self.secrets.as_ref()
+    }
+
+    /// Hand the external server's auth token from the secret store to the OpenCode
+    /// manager. Called at startup and whenever the stored token may have changed.
+    pub(crate) async fn reload_opencode_auth_token(&self) {
+        match self.secrets.get(OPENCODE_AUTH_TOKEN).await {
+            Ok(token) => self.opencode.set_auth_token(token),
+            Err(err) => warn!("[desktop] Failed to read the OpenCode auth token: {err}"),
+        }
+    }
+
     pub(crate) fn subscribe_shutdown(&self) -> broadcast::Receiver<()> {
         self.shutdown_tx.subscribe()
     }
 
+    /// Changes to the settings file, as published by the settings watcher.
+    pub(crate) fn subscribe_settings_changes(&self) -> broadcast::Receiver<SettingsChanged> {
+        self.settings_changes_tx.subscribe()
+    }
+
+    fn publish_settings_change(&self, change: SettingsChanged) {
+        if change.http_changed() {
+            self.http.configure(&change.current.http);
+        }
+        self.sse_events
+            .set_capacity(change.current.sse_event_buffer_size);
+        if change.previous.log_levels != change.current.log_levels {
+            log_levels::apply(&change.current.log_levels);
+        }
+        self.usage.set_persist(change.current.persist_usage);
+        self.set_paused(change.current.events.paused);
+        let _ = self.settings_changes_tx.send(change);
+    }
+
     pub(crate) fn opencode_manager(&self) -> Arc<OpenCodeManager> {
         self.opencode.clone()
     }
+
+    pub(crate) fn opencode_instances(&self) -> Arc<OpenCodeInstances> {
+        self.instances.clone()
+    }
+
+    pub(crate) fn telemetry(&self) -> Arc<TelemetryCounters> {
+        self.telemetry.clone()
+    }
+
+    /// Recently received SSE envelopes, for diagnosing activity that looks wrong.
+    pub(crate) fn sse_events(&self) -> &SseEventLog {
+        self.sse_events.as_ref()
+    }
+
+    /// Token usage and cost per session and day.
+    pub(crate) fn usage(&self) -> &UsageTracker {
+        &self.usage
+    }
+
+    pub(crate) fn http(&self) -> &HttpClients {
+        self.http.as_ref()
+    }
+
+    /// Long-lived background tasks, for spawning them and reporting on their health.
+    pub(crate) fn tasks(&self) -> &Arc<TaskRegistry> {
+        &self.tasks
+    }
+
+    /// For a stream loop to hold for its lifetime and pass to `sleep_unless_woken`.
+    pub(crate) fn subscribe_stream_wake(&self) -> WakeReceiver {
+        self.stream_wake.subscribe()
+    }
+
+    /// End the pending reconnect delay of every stream loop, or its next one if it is not
+    /// sleeping right now.
+    fn wake_stream_loops(&self) {
+        self.stream_wake.wake_all();
+    }
+
+    /// Sleep and wake notifications from the operating system.
+    pub(crate) fn subscribe_power(&self) -> watch::Receiver<PowerState> {
+        self.power.subscribe()
+    }
+
+    fn set_power_state(&self, state: PowerState) {
+        self.power.send_replace(state);
+        if state == PowerState::Awake {
+            self.wake_stream_loops();
+        }
+    }
+
+    /// Hold off reconnecting while the machine is suspended, so retries are not spent
+    /// against a network that is not there.
+    pub(crate) async fn wait_until_awake(&self) {
+        let mut power = self.power.subscribe();
+        let _ = power.wait_for(|state| *state == PowerState::Awake).await;
+    }
+
+    /// Returns true when the state changed. Coming back online cuts reconnect delays short.
+    fn set_connectivity(&self, state: Connectivity) -> bool {
+        let changed = self.connectivity.send_if_modified(|current| {
+            let changed = *current != state;
+            *current = state;
+            changed
+        });
+        if changed && state == Connectivity::Online {
+            self.wake_stream_loops();
+        }
+        changed
+    }
+
+    /// Whether SSE streams to `base` should hold off because the network is down. Servers
+    /// on this machine are reachable regardless.
+    pub(crate) fn is_offline_for(&self, base: &str) -> bool {
+        *self.connectivity.borrow() == Connectivity::Offline && !is_loopback_url(base)
+    }
+
+    /// Wait up to `timeout` for the network to come back, returning true once it has.
+    pub(crate) async fn wait_until_online(&self, timeout: Duration) -> bool {
+        let mut connectivity = self.connectivity.subscribe();
+        tokio::time::timeout(
+            timeout,
+            connectivity.wait_for(|state| *state == Connectivity::Online),
+        )
+        .await
+        .is_ok()
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    pub(crate) fn subscribe_paused(&self) -> watch::Receiver<bool> {
+        self.paused.subscribe()
+    }
+
+    /// Returns true when the state changed. Resuming cuts reconnect delays short.
+    pub(crate) fn set_paused(&self, paused: bool) -> bool {
+        let changed = self.paused.send_if_modified(|current| {
+            let changed = *current != paused;
+            *current = paused;
+            changed
+        });
+        if changed {
+            info!(
+                "[desktop] Background streams {}",
+                if paused { "paused" } else { "resumed" }
+            );
+            if !paused {
+                self.wake_stream_loops();
+            }
+        }
+        changed
+    }
+
+    /// Pause or resume background streams right away and remember the choice in
+    /// `events.paused`, so it also holds after a restart.
+    pub(crate) async fn set_background_paused(&self, paused: bool) -> Result<()> {
+        self.set_paused(paused);
+        self.settings
+            .update(|mut settings| {
+                if let Some(root) = settings.as_object_mut() {
+                    let events = root
+                        .entry("events")
+                        .or_insert_with(|| Value::Object(Default::default()));
+                    if !events.is_object() {
+                        *events = Value::Object(Default::default());
+                    }
+                    events["paused"] = Value::Bool(paused);
+                }
+                settings
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Hold off connecting while the user has paused background streams.
+    pub(crate) async fn wait_until_resumed(&self) {
+        let mut paused = self.paused.subscribe();
+        let _ = paused.wait_for(|paused| !*paused).await;
+    }
+
+    /// Resolves once the user pauses background streams, for a stream to disconnect.
+    pub(crate) async fn wait_until_paused(&self) {
+        let mut paused = self.paused.subscribe();
+        let _ = paused.wait_for(|paused| *paused).await;
+    }
+
+    /// Resolves when the user asks streams the server rejected to try again.
+    pub(crate) async fn wait_for_auth_retry(&self) {
+        self.auth_retry.notified().await;
+    }
+
+    /// Send streams parked on a 401 or 403 back to connecting.
+    pub(crate) fn retry_rejected_streams(&self) {
+        self.auth_retry.notify_waiters();
+    }
+
+    /// Park an SSE listener until the OpenCode status changes or a user intent wakes it.
+    /// The wake is left for the loop's next `sleep_unless_woken` to see, so reconnecting
+    /// starts right away.
+    pub(crate) async fn wait_for_opencode_change(
+        &self,
+        status: &mut watch::Receiver<OpenCodeStatus>,
+        wake: &WakeReceiver,
+    ) {
+        wait_for_change(status, wake).await;
+    }
+
+    /// Whether every SSE stream is connecting or connected, none parked or backing off.
+    pub(crate) fn streams_live(&self) -> bool {
+        !self.stream_wake.any_waiting()
+    }
+
+    /// Cut short the reconnect delays and server-idle parking of waiting SSE streams so
+    /// they start connecting now; nothing when none is waiting or an earlier wake is still
+    /// on its way. Returns true when a server wake was started by this call.
+    pub(crate) fn wake_streams(&self) -> bool {
+        self.stream_wake.wake_waiting();
+
+        if self.opencode.is_ready() || !self.opencode.is_cli_available() {
+            return false;
+        }
+
+        // Single-flight: only one wake attempt at a time, however many intents arrive.
+        if self
+            .server_wake_in_flight
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return false;
+        }
+
+        let runtime = self.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(err) = runtime.opencode.ensure_running().await {
+                warn!("[desktop] Failed to wake OpenCode on user intent: {err}");
+            }
+            runtime.server_wake_in_flight.store(false, Ordering::SeqCst);
+            runtime.wake_stream_loops();
+        });
+        true
+    }
 }
 
 #[derive(Clone)]
 struct ServerState {
     client: Client,
     opencode: Arc<OpenCodeManager>,
+    instances: Arc<OpenCodeInstances>,
     settings: Arc<SettingsStore>,
     server_port: u16,
     directory_change_lock: Arc<Mutex<()>>,
@@ -269,6 +677,7 @@ struct ServerInfoPayload {
     api_prefix: String,
     cli_available: bool,
     has_last_directory: bool,
+    telemetry_enabled: bool,
 }
 
 #[tauri::command]
@@ -288,9 +697,17 @@ async fn desktop_server_info(
         api_prefix: state.opencode.api_prefix(),
         cli_available: state.opencode.is_cli_available(),
         has_last_directory,
+        telemetry_enabled: telemetry::is_enabled(&state).await,
     })
 }
 
+#[tauri::command]
+async fn get_opencode_status(
+    state: tauri::State<'_, DesktopRuntime>,
+) -> Result<OpenCodeStatus, String> {
+    Ok(state.opencode.status())
+}
+
 #[tauri::command]
 async fn desktop_restart_opencode(state: tauri::State<'_, DesktopRuntime>) -> Result<(), String> {
     state
@@ -624,8 +1041,12 @@ fn build_macos_menu<R: tauri::Runtime>(
 }
 
 fn main() {
+    crash_reports::install_panic_hook();
+
     let mut log_builder = tauri_plugin_log::Builder::default()
-        .level(log::LevelFilter::Info)
+        // Everything reaches the filter, which applies the levels chosen per module.
+        .level(log::LevelFilter::Trace)
+        .filter(log_levels::enabled)
         .clear_targets()
         .target(Target::new(TargetKind::Stdout))
         .target(Target::new(TargetKind::Webview));
@@ -637,12 +1058,24 @@ fn main() {
         }));
     }
 
-    let app = tauri::Builder::default()
+    let mut builder = tauri::Builder::default();
+    // Registered first, so a second launch exits before anything else starts.
+    if single_instance_enforced() {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
+            handle_second_instance(app, argv, cwd);
+        }));
+    } else {
+        info!("[desktop] Single-instance check disabled by environment");
+    }
+
+    let app = builder
         .plugin(shell_plugin())
         .plugin(dialog_plugin())
         .plugin(fs_plugin())
         .plugin(notification_plugin())
         .plugin(tauri_plugin_updater::Builder::new().build())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_process::init())
         .plugin(log_builder.build())
         .menu(|app| {
@@ -656,13 +1089,55 @@ fn main() {
             }
         })
         .setup(|app| {
+            // The log plugin opened every level for the filter; narrow it until the
+            // stored levels are applied.
+            log_levels::update_max_level();
+
             #[cfg(target_os = "macos")]
             prevent_app_nap();
 
             app.manage(TerminalState::new());
+            app.manage(NotificationActivation::default());
+            app.manage(CompletionDigest::default());
+            app.manage(QuietHoursBacklog::default());
+            app.manage(NotificationHistory::default());
+            app.manage(PendingQuestions::default());
+            app.manage(RunningTools::default());
+            app.manage(CompactingSessions::default());
+            app.manage(ContextWindows::default());
+            app.manage(ModelNames::default());
+            app.manage(SimulatedEvents::default());
+            app.manage(SseCapture::default());
+            app.manage(WindowProjects::default());
+            app.manage(RateLimits::default());
+            app.manage(ServerStatusNotifier::default());
+            app.manage(ActiveSessions::default());
+            app.manage(SessionInfoCache::default());
+            app.manage(MutedSessions::default());
+            app.manage(DeliveredNotifications::default());
+            app.manage(QuestionReminders::default());
+            app.manage(BusySessions::default());
+            app.manage(BusyTime::load());
+            app.manage(SleepInhibition::default());
+            app.manage(EventStreamHealth::default());
+            app.manage(SessionLifecycleEvents::default());
+            app.manage(GlobalShortcutState::default());
+            app.manage(DeepLinks::default());
+            app.manage(ProjectNames::default());
+            let crash_reports = CrashReports::default();
+            crash_reports.check_for_new_reports();
+            app.manage(crash_reports);
 
-            let stored_state = tauri::async_runtime::block_on(load_window_state()).unwrap_or(None);
-            let manager = WindowStateManager::new(stored_state.clone().unwrap_or_default());
+            let runtime = DesktopRuntime::initialize_sync()?;
+            app.manage(runtime.clone());
+
+            let start_hidden = tauri::async_runtime::block_on(runtime.settings().load_typed())
+                .map(|settings| settings.start_hidden())
+                .unwrap_or(false);
+
+            let stored_states =
+                tauri::async_runtime::block_on(load_window_states()).unwrap_or_default();
+            let manager = WindowStateManager::new(stored_states);
             app.manage(manager.clone());
 
             if let Some(window) = app.get_webview_window("main") {
@@ -695,20 +1170,54 @@ fn main() {
                     }
                 }
 
-                if let Some(saved) = &stored_state {
-                    let _ = window_state::apply_window_state(&window, saved);
-                }
+                manager.restore(&window.as_ref().window());
 
-                let _ = window.show();
-                let _ = window.set_focus();
+                // Started in the tray, the window stays hidden until the tray menu or the
+                // global shortcut asks for it; the background tasks run either way.
+                if start_hidden {
+                    info!("[desktop] Starting hidden in the tray");
+                } else {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
             }
 
-            let runtime = DesktopRuntime::initialize_sync()?;
-            app.manage(runtime.clone());
+            // Forward server status changes so the webview follows restarts onto new ports
+            {
+                let app_handle = app.app_handle().clone();
+                let mut status = runtime.opencode_manager().subscribe_status();
+                tauri::async_runtime::spawn(async move {
+                    let mut reported_conflict = None;
+                    let mut was_unreachable = false;
+                    while status.changed().await.is_ok() {
+                        let snapshot = status.borrow_and_update().clone();
+                        let _ = app_handle.emit("openchamber:opencode-status", &snapshot);
+
+                        let unreachable = snapshot.state == OpenCodeState::Unreachable;
+                        if unreachable && !was_unreachable {
+                            notify_server_unreachable(&app_handle, &snapshot).await;
+                        }
+                        was_unreachable = unreachable;
+
+                        // Starting elsewhere is only worth the status event; failing to start
+                        // at all gets a notification.
+                        let conflict = snapshot
+                            .port_conflict
+                            .filter(|conflict| conflict.fallback.is_none());
+                        if conflict != reported_conflict {
+                            if let Some(conflict) = &conflict {
+                                notify_port_conflict(&app_handle, conflict).await;
+                            }
+                            reported_conflict = conflict;
+                        }
+                    }
+                });
+            }
 
             let app_handle = app.app_handle().clone();
             let runtime_clone = runtime.clone();
             tauri::async_runtime::spawn(async move {
+                migrate_settings_secrets(runtime_clone.settings(), runtime_clone.secrets()).await;
                 runtime_clone.start_opencode().await;
 
                 if let Err(e) =
@@ -740,6 +1249,11 @@ fn main() {
                             }
                             Ok(false) => {
                                 let _ = app_handle.emit("server.instance.disposed", ());
+                                if let Some(exit_code) =
+                                    runtime.opencode_manager().take_unexpected_exit()
+                                {
+                                    notify_server_stopped(&app_handle, exit_code).await;
+                                }
                                 if runtime.opencode_manager().is_cli_available() {
                                     if let Err(err) =
                                         runtime.opencode_manager().ensure_running().await
@@ -749,6 +1263,7 @@ fn main() {
                                         );
                                     } else {
                                         backoff_ms = 1000;
+                                        notify_server_running(&app_handle);
                                     }
                                 }
                             }
@@ -763,6 +1278,9 @@ fn main() {
                 });
             }
 
+            // Respawn OpenCode when it stops answering health checks
+            runtime.opencode_manager().spawn_health_monitor();
+
             // Health and wake monitor: emit health and port updates to webview
             {
                 let app_handle = app.app_handle().clone();
@@ -819,18 +1337,88 @@ fn main() {
                 });
             }
 
-            spawn_assistant_notifications(app.app_handle().clone(), runtime.clone());
-            spawn_session_activity_tracker(app.app_handle().clone(), runtime.clone());
+            runtime.track_listener(spawn_settings_watcher(runtime.clone()));
+            runtime.track_listener(spawn_power_monitor(runtime.clone()));
+            runtime.track_listener(spawn_connectivity_monitor(
+                app.app_handle().clone(),
+                runtime.clone(),
+            ));
+            runtime.track_listener(spawn_assistant_notifications(
+                app.app_handle().clone(),
+                runtime.clone(),
+            ));
+            runtime.track_listener(spawn_session_activity_tracker(
+                app.app_handle().clone(),
+                runtime.clone(),
+            ));
+            runtime.track_listener(spawn_session_tray(
+                app.app_handle().clone(),
+                runtime.clone(),
+            ));
+            runtime.track_listener(spawn_sleep_inhibitor(
+                app.app_handle().clone(),
+                runtime.clone(),
+            ));
+            runtime.track_listener(spawn_status_file(app.app_handle().clone(), runtime.clone()));
+            runtime.track_listener(spawn_window_title(
+                app.app_handle().clone(),
+                runtime.clone(),
+            ));
+            runtime.track_listener(spawn_project_names(
+                app.app_handle().clone(),
+                runtime.clone(),
+            ));
+            runtime.track_listener(spawn_telemetry_reports(runtime.clone()));
+            runtime.track_listener(spawn_global_shortcut(
+                app.app_handle().clone(),
+                runtime.clone(),
+            ));
+            #[cfg(target_os = "windows")]
+            runtime.track_listener(taskbar::spawn_taskbar_status(
+                app.app_handle().clone(),
+                runtime.clone(),
+            ));
+
+            register_deep_links(app.app_handle());
+            handle_launch_arguments(app.app_handle());
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             desktop_server_info,
+            get_opencode_status,
             desktop_restart_opencode,
             #[cfg(feature = "devtools")]
             desktop_open_devtools,
             load_settings,
             save_settings,
+            update_setting,
+            export_settings,
+            import_settings,
+            set_secret,
+            get_secret,
+            delete_secret,
+            get_background_tasks,
+            get_global_shortcut_status,
+            deep_links_ready,
+            reset_window_geometry,
+            register_window_project,
+            export_diagnostics,
+            get_recent_sse_events,
+            clear_recent_sse_events,
+            get_known_event_types,
+            get_event_stream_status,
+            retry_sse_connections,
+            simulate_desktop_event,
+            start_sse_capture,
+            stop_sse_capture,
+            replay_sse_capture,
+            get_session_usage,
+            get_usage_summary,
+            get_latency_stats,
+            get_time_report,
+            get_power_state,
+            get_status_file_info,
             restart_opencode,
             list_directory,
             search_files,
@@ -885,7 +1473,28 @@ fn main() {
             restart_terminal_session,
             force_kill_terminal,
             fetch_desktop_logs,
+            get_opencode_logs,
+            get_log_levels,
+            set_log_level,
             desktop_notify,
+            reply_to_permission,
+            list_notification_sounds,
+            play_notification_sound_preview,
+            get_notification_history,
+            clear_notification_history,
+            get_pending_questions,
+            get_do_not_disturb_state,
+            send_test_notification,
+            set_active_session,
+            mute_session_notifications,
+            unmute_session_notifications,
+            get_muted_sessions,
+            signal_user_intent,
+            get_session_info,
+            abort_session,
+            abort_all_busy_sessions,
+            pause_background_streams,
+            resume_background_streams,
         ])
         .on_menu_event(|app, event| {
             #[cfg(target_os = "macos")]
@@ -1011,32 +1620,48 @@ fn main() {
                 }
             }
         })
+        .on_page_load(|webview, _payload| {
+            // Windows other than main are created by the frontend; put each back where it
+            // was when its page first loads.
+            webview
+                .state::<WindowStateManager>()
+                .restore(&webview.window());
+        })
         .on_window_event(|window, event| {
             let window_state_manager = window.state::<WindowStateManager>().inner().clone();
 
             match event {
                 tauri::WindowEvent::Focused(true) => {
-                    // Clear dock badge and underlying badge state when the window gains focus
-                    let _ = window.set_badge_count(None);
+                    window
+                        .state::<ActiveSessions>()
+                        .window_focused(window.label());
+                    // The dock badge only counts pending questions, which focusing doesn't
+                    // answer; clear the frontend's unread badge state and re-sync the count.
+                    sync_question_badge(window.app_handle());
+                    cancel_attention(window.app_handle());
                     let _ = window
                         .app_handle()
                         .emit("openchamber:clear-badge-sessions", ());
+                    handle_window_activated(window.app_handle());
                 }
-                tauri::WindowEvent::Moved(position) => {
-                    let is_maximized = window.is_maximized().unwrap_or(false);
-                    window_state_manager.update_position(
-                        position.x as f64,
-                        position.y as f64,
-                        is_maximized,
-                    );
+                tauri::WindowEvent::Focused(false) => {
+                    window
+                        .state::<ActiveSessions>()
+                        .window_blurred(window.label());
                 }
-                tauri::WindowEvent::Resized(size) => {
-                    let is_maximized = window.is_maximized().unwrap_or(false);
-                    window_state_manager.update_size(
-                        size.width as f64,
-                        size.height as f64,
-                        is_maximized,
-                    );
+                tauri::WindowEvent::Destroyed => {
+                    window
+                        .state::<ActiveSessions>()
+                        .window_closed(window.label());
+                    window
+                        .state::<WindowProjects>()
+                        .window_closed(window.label());
+                }
+                tauri::WindowEvent::Moved(_) => {
+                    window_state_manager.track(window);
+                }
+                tauri::WindowEvent::Resized(_) => {
+                    window_state_manager.track(window);
                     #[cfg(target_os = "macos")]
                     if NEEDS_TRAFFIC_LIGHT_FIX.load(Ordering::SeqCst) {
                         if let Some(webview) = window.app_handle().get_webview_window("main") {
@@ -1044,18 +1669,26 @@ fn main() {
                         }
                     }
                 }
+                // Dropping a folder onto the window opens it as the project.
+                tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) => {
+                    let links = paths.first().and_then(|path| project_link(path));
+                    handle_deep_links(window.app_handle(), links.into_iter().collect());
+                }
                 tauri::WindowEvent::CloseRequested { api, .. } => {
                     api.prevent_close();
                     let runtime = window.state::<DesktopRuntime>().inner().clone();
                     let window_handle = window.clone();
-                    let manager_clone = window_state_manager.clone();
                     tauri::async_runtime::spawn(async move {
-                        if let Err(err) = persist_window_state(&window_handle, &manager_clone).await
-                        {
-                            warn!("Failed to persist window state: {}", err);
+                        let close_to_tray = runtime
+                            .settings()
+                            .load_typed()
+                            .await
+                            .is_ok_and(|settings| settings.close_to_tray());
+                        if close_to_tray && window_handle.label() == "main" {
+                            let _ = window_handle.hide();
+                            return;
                         }
-                        runtime.shutdown().await;
-                        let _ = window_handle.app_handle().exit(0);
+                        quit_gracefully(window_handle.app_handle().clone()).await;
                     });
                 }
                 _ => {}
@@ -1064,7 +1697,45 @@ fn main() {
         .build(tauri::generate_context!())
         .expect("failed to build Tauri application");
 
-    app.run(|_app_handle, _event| {});
+    app.run(|_app_handle, _event| {
+        #[cfg(target_os = "macos")]
+        match _event {
+            // Clicking the Dock icon brings back a window hidden to the tray.
+            tauri::RunEvent::Reopen {
+                has_visible_windows: false,
+                ..
+            } => {
+                if let Some(window) = _app_handle.get_webview_window("main") {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
+            }
+            // A folder dropped onto the Dock icon, or opened with the app from Finder.
+            tauri::RunEvent::Opened { urls } => {
+                let links = urls
+                    .iter()
+                    .filter_map(|url| url.to_file_path().ok())
+                    .filter_map(|path| project_link(&path))
+                    .take(1)
+                    .collect();
+                handle_deep_links(_app_handle, links);
+            }
+            _ => {}
+        }
+    });
+}
+
+/// Save the window state and stop OpenCode and the background tasks before exiting.
+/// Every way of quitting other than the OS killing the app should end here.
+pub(crate) async fn quit_gracefully(app: tauri::AppHandle) {
+    let runtime = app.state::<DesktopRuntime>().inner().clone();
+    let windows: Vec<tauri::Window> = app.windows().into_values().collect();
+    let manager = app.state::<WindowStateManager>().inner().clone();
+    if let Err(err) = persist_window_state(&windows, &manager).await {
+        warn!("Failed to persist window state: {}", err);
+    }
+    runtime.shutdown().await;
+    app.exit(0);
 }
 
 fn spawn_http_server(port: u16, state: ServerState, shutdown_rx: broadcast::Receiver<()>) {
@@ -1250,7 +1921,8 @@ fn extract_directory_from_request(req: &Request) -> Option<String> {
 }
 
 async fn resolve_directory_candidate(candidate: &str) -> Result<PathBuf, Response> {
-    let mut resolved = expand_tilde_path(candidate);
+    let mut resolved = try_expand_path(candidate)
+        .ok_or_else(|| config_error_response(StatusCode::BAD_REQUEST, "Directory not found"))?;
     if !resolved.is_absolute() {
         let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
         resolved = home.join(resolved);
@@ -1276,43 +1948,18 @@ async fn resolve_directory_candidate(candidate: &str) -> Result<PathBuf, Respons
 async fn resolve_project_directory_from_settings(
     settings: &SettingsStore,
 ) -> Result<Option<PathBuf>, Response> {
-    let raw = settings.load().await.map_err(|_| {
+    let settings = settings.load_typed().await.map_err(|_| {
         config_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to load settings")
     })?;
 
-    let active_id = raw
-        .get("activeProjectId")
-        .and_then(|value| value.as_str())
-        .unwrap_or("")
-        .trim()
-        .to_string();
-
-    let projects = raw.get("projects").and_then(|value| value.as_array());
-    if let Some(projects) = projects {
-        if let Some(entry) = projects.iter().find(|entry| {
-            entry
-                .get("id")
-                .and_then(|value| value.as_str())
-                .map(|value| value.trim() == active_id)
-                .unwrap_or(false)
-        }) {
-            if let Some(path) = entry.get("path").and_then(|value| value.as_str()) {
-                return resolve_directory_candidate(path).await.map(Some);
-            }
-        }
-
-        if let Some(entry) = projects.first() {
-            if let Some(path) = entry.get("path").and_then(|value| value.as_str()) {
-                return resolve_directory_candidate(path).await.map(Some);
-            }
-        }
+    let project = settings
+        .active_project()
+        .or_else(|| settings.projects.first());
+    if let Some(project) = project {
+        return resolve_directory_candidate(&project.path).await.map(Some);
     }
 
-    let legacy = raw
-        .get("lastDirectory")
-        .and_then(|value| value.as_str())
-        .unwrap_or("")
-        .trim();
+    let legacy = settings.last_directory.as_deref().unwrap_or("").trim();
     if !legacy.is_empty() {
         return resolve_directory_candidate(legacy).await.map(Some);
     }
@@ -2300,7 +2947,7 @@ async fn change_directory_handler(
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    let mut resolved_path = expand_tilde_path(requested_path);
+    let mut resolved_path = expand_path(requested_path);
     if !resolved_path.is_absolute() {
         let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
         resolved_path = home.join(resolved_path);
@@ -2330,67 +2977,65 @@ async fn change_directory_handler(
         resolved_path = canonicalized;
     }
 
-    let path_value = resolved_path.to_string_lossy().to_string();
-
-    state
-        .settings
-        .update(|mut settings| {
-            if !settings.is_object() {
-                settings = Value::Object(Default::default());
-            }
-
-            let mut projects = settings
-                .get("projects")
-                .and_then(|value| value.as_array())
-                .cloned()
-                .unwrap_or_default();
-
-            let existing_index = projects.iter().position(|entry| {
-                entry
-                    .get("path")
-                    .and_then(|value| value.as_str())
-                    .map(|value| value == path_value)
-                    .unwrap_or(false)
-            });
-
-            let active_project_id = if let Some(index) = existing_index {
-                projects[index]
-                    .get("id")
-                    .and_then(|value| value.as_str())
-                    .unwrap_or("")
-                    .to_string()
-            } else {
-                let id = uuid::Uuid::new_v4().to_string();
-                let project = serde_json::json!({
-                    "id": id,
-                    "path": path_value,
-                    "addedAt": chrono::Utc::now().timestamp_millis(),
-                    "lastOpenedAt": chrono::Utc::now().timestamp_millis(),
-                });
-                projects.push(project);
-                id
-            };
-
-            let map = settings.as_object_mut().unwrap();
-            map.insert("projects".to_string(), Value::Array(projects));
-            map.insert(
-                "activeProjectId".to_string(),
-                Value::String(active_project_id.clone()),
-            );
-            map.insert("lastDirectory".to_string(), Value::String(path_value.clone()));
-
-            settings
-        })
+    activate_project(&state.settings, &state.instances, &resolved_path)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(Json(DirectoryChangeResponse {
         success: true,
         restarted: false,
-        path: path_value,
+        path: resolved_path.to_string_lossy().to_string(),
     }))
 }
 
+/// Make `directory` the active project, adding it to `projects` when it is new, and start
+/// its server when every project gets its own.
+pub(crate) async fn activate_project(
+    settings: &SettingsStore,
+    instances: &Arc<OpenCodeInstances>,
+    directory: &Path,
+) -> Result<()> {
+    let path_value = directory.to_string_lossy().to_string();
+
+    let (settings, _) = settings
+        .update_typed(|settings| {
+            let existing = settings
+                .projects
+                .iter()
+                .find(|project| project.path == path_value);
+            let active_project_id = match existing {
+                Some(project) => project.id.clone(),
+                None => {
+                    let now = chrono::Utc::now().timestamp_millis();
+                    let id = uuid::Uuid::new_v4().to_string();
+                    settings.projects.push(ProjectEntry {
+                        id: id.clone(),
+                        path: path_value.clone(),
+                        added_at: Some(now),
+                        last_opened_at: Some(now),
+                        ..Default::default()
+                    });
+                    id
+                }
+            };
+            settings.active_project_id = Some(active_project_id);
+            settings.last_directory = Some(path_value.clone());
+        })
+        .await?;
+
+    let multi_instance = settings.multi_instance_opencode;
+    if multi_instance {
+        let instances = instances.clone();
+        let directory = directory.to_path_buf();
+        tauri::async_runtime::spawn(async move {
+            if let Err(err) = instances.start_project(directory).await {
+                warn!("[desktop:opencode] Failed to start project instance: {err}");
+            }
+        });
+    }
+    Ok(())
+}
+
 async fn proxy_to_opencode(
     State(state): State<ServerState>,
     req: Request,
@@ -2414,14 +3059,21 @@ async fn proxy_to_opencode(
         return handle_config_routes(state, &origin_path, method, req).await;
     }
 
-    let port = state.opencode.current_port().ok_or_else(|| {
+    let query = req.uri().query();
+    let directory = query.and_then(|q| {
+        url::form_urlencoded::parse(q.as_bytes())
+            .find(|(key, _)| key == "directory")
+            .map(|(_, value)| expand_path(&value))
+    });
+    let opencode = state.instances.manager_for_directory(directory.as_deref());
+    let host = opencode.host();
+    let port = opencode.current_port().ok_or_else(|| {
         error!("[desktop:http] PROXY FAILED: OpenCode not running (no port)");
         StatusCode::SERVICE_UNAVAILABLE
     })?;
 
-    let query = req.uri().query();
     let rewritten_path = state.opencode.rewrite_path(&origin_path);
-    let mut target = format!("http://127.0.0.1:{port}{rewritten_path}");
+    let mut target = format!("http://{host}:{port}{rewritten_path}");
     if let Some(q) = query {
         target.push('?');
         target.push_str(q);
@@ -2432,7 +3084,11 @@ async fn proxy_to_opencode(
     let mut builder = state.client.request(method, &target);
 
     let mut headers = parts.headers;
-    headers.insert(header::HOST, format!("127.0.0.1:{port}").parse().unwrap());
+    let host_header = header::HeaderValue::from_str(&format!("{host}:{port}")).map_err(|_| {
+        error!("[desktop:http] PROXY FAILED: invalid OpenCode host {host:?}");
+        StatusCode::BAD_GATEWAY
+    })?;
+    headers.insert(header::HOST, host_header);
     if headers
         .get(header::ACCEPT)
         .and_then(|v| v.to_str().ok())
@@ -2441,6 +3097,14 @@ async fn proxy_to_opencode(
     {
         headers.insert(header::CONNECTION, "keep-alive".parse().unwrap());
     }
+    if let Some(token) = opencode.auth_token() {
+        let authorization =
+            header::HeaderValue::from_str(&format!("Bearer {token}")).map_err(|_| {
+                error!("[desktop:http] PROXY FAILED: OpenCode auth token is not a valid header");
+                StatusCode::BAD_GATEWAY
+            })?;
+        headers.insert(header::AUTHORIZATION, authorization);
+    }
 
     for (key, value) in headers.iter() {
         if key == &header::CONTENT_LENGTH {
@@ -2485,6 +3149,8 @@ async fn proxy_to_opencode(
 pub(crate) struct SettingsStore {
     path: PathBuf,
     guard: Arc<Mutex<()>>,
+    /// Signalled after every write so the settings watcher does not wait for its next poll.
+    written: Notify,
 }
 
 impl SettingsStore {
@@ -2499,22 +3165,37 @@ impl SettingsStore {
         Ok(Self {
             path: dir,
             guard: Arc::new(Mutex::new(())),
+            written: Notify::new(),
         })
     }
 
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) async fn wait_for_write(&self) {
+        self.written.notified().await;
+    }
+
+    /// Settings as stored, upgraded to the current schema. The upgrade is written back by
+    /// the next update.
     pub(crate) async fn load(&self) -> Result<Value> {
         let _lock = self.guard.lock().await;
-        match fs::read(&self.path).await {
+        let mut value = match fs::read(&self.path).await {
             Ok(bytes) => {
-                let value =
-                    serde_json::from_slice(&bytes).unwrap_or(Value::Object(Default::default()));
-                Ok(value)
+                serde_json::from_slice(&bytes).unwrap_or(Value::Object(Default::default()))
             }
             Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
-                Ok(Value::Object(Default::default()))
+                Value::Object(Default::default())
             }
-            Err(err) => Err(err.into()),
-        }
+            Err(err) => return Err(err.into()),
+        };
+        migrate_settings(&mut value);
+        Ok(value)
+    }
+
+    pub(crate) async fn load_typed(&self) -> Result<DesktopSettings> {
+        Ok(DesktopSettings::from_value(self.load().await?))
     }
 
 
@@ -2524,7 +3205,7 @@ impl SettingsStore {
     {
         let _lock = self.guard.lock().await;
 
-        let current = match fs::read(&self.path).await {
+        let mut current = match fs::read(&self.path).await {
             Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or(Value::Object(Default::default())),
             Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
                 Value::Object(Default::default())
@@ -2532,7 +3213,9 @@ impl SettingsStore {
             Err(err) => return Err(err.into()),
         };
 
+        // Compare against the file as read so a migration alone is persisted too.
         let current_snapshot = current.clone();
+        migrate_settings(&mut current);
         let (next, result) = f(current);
 
         if next != current_snapshot {
@@ -2540,7 +3223,12 @@ impl SettingsStore {
                 fs::create_dir_all(parent).await.ok();
             }
             let bytes = serde_json::to_vec_pretty(&next)?;
-            fs::write(&self.path, bytes).await?;
+            // Write a sibling file and rename it over the settings so a crash mid-write
+            // never leaves a truncated file behind.
+            let temp_path = self.path.with_extension("json.tmp");
+            fs::write(&temp_path, bytes).await?;
+            fs::rename(&temp_path, &self.path).await?;
+            self.written.notify_one();
         }
 
         Ok((next, result))
@@ -2554,14 +3242,22 @@ impl SettingsStore {
         Ok(next)
     }
 
+    /// Update through the typed model. Keys it does not model are written back unchanged.
+    pub(crate) async fn update_typed<R, F>(&self, f: F) -> Result<(DesktopSettings, R)>
+    where
+        F: FnOnce(&mut DesktopSettings) -> R,
+    {
+        let (next, result) = self
+            .update_with(|current| {
+                let mut settings = DesktopSettings::from_value(current);
+                let result = f(&mut settings);
+                (settings.into_value(), result)
+            })
+            .await?;
+        Ok((DesktopSettings::from_value(next), result))
+    }
+
     pub(crate) async fn last_directory(&self) -> Result<Option<PathBuf>> {
-        let settings = self.load().await?;
-        let candidate = settings
-            .get("lastDirectory")
-            .and_then(|value| value.as_str())
-            .map(str::trim)
-            .filter(|value| !value.is_empty())
-            .map(expand_tilde_path);
-        Ok(candidate)
+        Ok(self.load_typed().await?.last_directory())
     }
 }
@@ -0,0 +1,352 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use log::debug;
+use reqwest::Client;
+use serde_json::Value;
+
+use crate::opencode_manager::with_auth;
+
+const CATALOG_FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Words the fallback formatter writes the way their vendors do.
+const KNOWN_WORDS: &[(&str, &str)] = &[
+    ("gpt", "GPT"),
+    ("glm", "GLM"),
+    ("deepseek", "DeepSeek"),
+    ("openai", "OpenAI"),
+    ("xai", "xAI"),
+    ("openrouter", "OpenRouter"),
+    ("github", "GitHub"),
+    ("opencode", "OpenCode"),
+];
+
+/// Display names from OpenCode's provider catalog, fetched once per event stream
+/// connection. Ids missing from the catalog are formatted by `format_model_id`.
+#[derive(Default)]
+pub struct ModelNames {
+    catalog: Mutex<Option<Catalog>>,
+}
+
+#[derive(Default)]
+struct Catalog {
+    /// Keyed by `provider/model`.
+    models: HashMap<String, String>,
+    /// Keyed by the bare model id, for callers that do not know the provider.
+    bare_models: HashMap<String, String>,
+    providers: HashMap<String, String>,
+}
+
+impl ModelNames {
+    /// Forget the catalog so the next lookup fetches it again, as after a reconnect,
+    /// when the server may have been reconfigured.
+    pub fn invalidate(&self) {
+        if let Ok(mut catalog) = self.catalog.lock() {
+            *catalog = None;
+        }
+    }
+
+    /// Fetch the catalog unless it is already loaded. A failed fetch leaves an empty
+    /// catalog, so the fallback formatter is used until the next connection.
+    pub async fn load(
+        &self,
+        client: &Client,
+        base: &str,
+        token: Option<&str>,
+        directory: Option<&str>,
+    ) {
+        if self.catalog.lock().is_ok_and(|catalog| catalog.is_some()) {
+            return;
+        }
+        let catalog = fetch_catalog(client, base, token, directory)
+            .await
+            .map(|providers| Catalog::from_providers(&providers))
+            .unwrap_or_default();
+        if let Ok(mut current) = self.catalog.lock() {
+            current.get_or_insert(catalog);
+        }
+    }
+
+    /// The display name for `model`, from the catalog when it lists the model.
+    pub fn model(&self, provider: Option<&str>, model: &str) -> String {
+        let listed = self.catalog.lock().ok().and_then(|catalog| {
+            let catalog = catalog.as_ref()?;
+            provider
+                .and_then(|provider| catalog.models.get(&format!("{provider}/{model}")))
+                .or_else(|| catalog.bare_models.get(model))
+                .cloned()
+        });
+        listed.unwrap_or_else(|| format_model_id(model))
+    }
+
+    /// The display name for `provider`, from the catalog when it lists the provider.
+    pub fn provider(&self, provider: &str) -> String {
+        let listed = self.catalog.lock().ok().and_then(|catalog| {
+            catalog
+                .as_ref()
+                .and_then(|catalog| catalog.providers.get(provider).cloned())
+        });
+        listed.unwrap_or_else(|| format_model_id(provider))
+    }
+}
+
+impl Catalog {
+    fn from_providers(response: &Value) -> Self {
+        let mut catalog = Self::default();
+        for provider in response
+            .get("providers")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let Some(provider_id) = provider.get("id").and_then(Value::as_str) else {
+                continue;
+            };
+            if let Some(name) = display_name(provider) {
+                catalog.providers.insert(provider_id.to_string(), name);
+            }
+            for (model_id, model) in provider
+                .get("models")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+            {
+                let Some(name) = display_name(model) else {
+                    continue;
+                };
+                catalog
+                    .bare_models
+                    .entry(model_id.clone())
+                    .or_insert_with(|| name.clone());
+                catalog
+                    .models
+                    .insert(format!("{provider_id}/{model_id}"), name);
+            }
+        }
+        catalog
+    }
+}
+
+fn display_name(entry: &Value) -> Option<String> {
+    entry
+        .get("name")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+}
+
+async fn fetch_catalog(
+    client: &Client,
+    base: &str,
+    token: Option<&str>,
+    directory: Option<&str>,
+) -> Option<Value> {
+    let url = format!("{base}/config/providers");
+    let mut request = with_auth(client.get(&url), token).timeout(CATALOG_FETCH_TIMEOUT);
+    if let Some(directory) = directory {
+        request = request.query(&[("directory", directory)]);
+    }
+    match request.send().await {
+        Ok(response) if response.status().is_success() => response.json::<Value>().await.ok(),
+        Ok(response) => {
+            debug!(
+                "[desktop:models] Provider catalog returned status {}",
+                response.status()
+            );
+            None
+        }
+        Err(err) => {
+            debug!("[desktop:models] Provider catalog fetch failed: {err}");
+            None
+        }
+    }
+}
+
+/// Make a readable name out of a model id OpenCode has no display name for, e.g.
+/// `claude-sonnet-4-5-20250929` as "Claude Sonnet 4.5" and `gpt-4o-mini` as "GPT-4o Mini".
+pub fn format_model_id(raw: &str) -> String {
+    // Routers prefix the upstream vendor, as in `anthropic/claude-sonnet-4`.
+    let raw = raw.rsplit('/').next().unwrap_or(raw).trim();
+    if raw.is_empty() {
+        return "Assistant".to_string();
+    }
+
+    let mut tokens: Vec<&str> = raw
+        .split(['-', '_', ' ', ':'])
+        .filter(|token| !token.is_empty())
+        .collect();
+    strip_date_suffix(&mut tokens);
+
+    let mut words: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        // Version fragments split on dashes: `4-5` is 4.5, `3-5-sonnet` is 3.5 Sonnet.
+        if is_version_fragment(tokens[i]) {
+            let mut version = tokens[i].to_string();
+            while i + 1 < tokens.len() && is_version_fragment(tokens[i + 1]) {
+                version.push('.');
+                version.push_str(tokens[i + 1]);
+                i += 1;
+            }
+            words.push(version);
+        } else {
+            words.push(format_word(tokens[i]));
+        }
+        i += 1;
+    }
+
+    let mut name = String::new();
+    for (index, word) in words.iter().enumerate() {
+        if index > 0 {
+            // GPT versions are written attached: GPT-4o, GPT-4.1.
+            let attached =
+                words[index - 1] == "GPT" && word.starts_with(|c: char| c.is_ascii_digit());
+            name.push(if attached { '-' } else { ' ' });
+        }
+        name.push_str(word);
+    }
+    name
+}
+
+/// Drop release dates: `20250929`, `2024-08-06`, and the `0613` of older GPT ids.
+fn strip_date_suffix(tokens: &mut Vec<&str>) {
+    let digits =
+        |token: &str, len: usize| token.len() == len && token.chars().all(|c| c.is_ascii_digit());
+    let len = tokens.len();
+    if len > 3
+        && digits(tokens[len - 3], 4)
+        && digits(tokens[len - 2], 2)
+        && digits(tokens[len - 1], 2)
+    {
+        tokens.truncate(len - 3);
+    } else if len > 1 && (digits(tokens[len - 1], 8) || digits(tokens[len - 1], 4)) {
+        tokens.truncate(len - 1);
+    }
+}
+
+/// A bare number short enough to be part of a version, like the `4` and `5` of `4-5`.
+fn is_version_fragment(token: &str) -> bool {
+    token.len() <= 2 && token.chars().all(|c| c.is_ascii_digit())
+}
+
+fn format_word(token: &str) -> String {
+    let lower = token.to_ascii_lowercase();
+    if let Some((_, word)) = KNOWN_WORDS.iter().find(|(id, _)| *id == lower) {
+        return word.to_string();
+    }
+    // OpenAI's reasoning series stays lowercase: o1, o3, o4.
+    if lower.len() > 1 && lower.starts_with('o') && lower[1..].chars().all(|c| c.is_ascii_digit()) {
+        return lower;
+    }
+    // Parameter counts and expert counts: 70b is 70B, 8x7b is 8x7B.
+    if lower.ends_with('b')
+        && lower[..lower.len() - 1]
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == 'x' || c == '.')
+        && lower.starts_with(|c: char| c.is_ascii_digit())
+    {
+        return format!("{}B", &lower[..lower.len() - 1]);
+    }
+    let mut chars = token.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn formats_popular_model_ids() {
+        let cases = [
+            ("claude-sonnet-4-5-20250929", "Claude Sonnet 4.5"),
+            ("claude-sonnet-4-5", "Claude Sonnet 4.5"),
+            ("claude-opus-4-1", "Claude Opus 4.1"),
+            ("claude-haiku-4-5", "Claude Haiku 4.5"),
+            ("claude-3-5-sonnet-20241022", "Claude 3.5 Sonnet"),
+            ("gpt-4o", "GPT-4o"),
+            ("gpt-4o-mini", "GPT-4o Mini"),
+            ("gpt-4o-2024-08-06", "GPT-4o"),
+            ("gpt-4.1-mini", "GPT-4.1 Mini"),
+            ("gpt-4-0613", "GPT-4"),
+            ("gpt-5-codex", "GPT-5 Codex"),
+            ("o1", "o1"),
+            ("o3-mini", "o3 Mini"),
+            ("gemini-2.5-pro", "Gemini 2.5 Pro"),
+            ("gemini-1.5-flash-8b", "Gemini 1.5 Flash 8B"),
+            ("deepseek-r1", "DeepSeek R1"),
+            ("glm-4.6", "GLM 4.6"),
+            ("grok-code-fast-1", "Grok Code Fast 1"),
+            ("llama-3.3-70b-instruct", "Llama 3.3 70B Instruct"),
+            ("mixtral-8x7b", "Mixtral 8x7B"),
+            ("qwen3-coder", "Qwen3 Coder"),
+            ("kimi-k2", "Kimi K2"),
+            ("mistral-large-2411", "Mistral Large"),
+            ("anthropic/claude-sonnet-4", "Claude Sonnet 4"),
+            ("openrouter/openai/gpt-4o", "GPT-4o"),
+            ("", "Assistant"),
+            ("  ", "Assistant"),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(format_model_id(raw), expected, "format_model_id({raw:?})");
+        }
+    }
+
+    #[test]
+    fn catalog_names_win_over_the_fallback() {
+        let names = ModelNames::default();
+        *names.catalog.lock().unwrap() = Some(Catalog::from_providers(&json!({
+            "providers": [
+                {
+                    "id": "anthropic",
+                    "name": "Anthropic",
+                    "models": {
+                        "claude-sonnet-4-5-20250929": { "name": "Claude Sonnet 4.5" },
+                        "claude-opus-4-1": { "name": "  " },
+                    },
+                },
+                {
+                    "id": "openrouter",
+                    "models": { "claude-sonnet-4-5-20250929": { "name": "Sonnet 4.5 (OpenRouter)" } },
+                },
+            ],
+        })));
+
+        let cases = [
+            (
+                Some("anthropic"),
+                "claude-sonnet-4-5-20250929",
+                "Claude Sonnet 4.5",
+            ),
+            (
+                Some("openrouter"),
+                "claude-sonnet-4-5-20250929",
+                "Sonnet 4.5 (OpenRouter)",
+            ),
+            // Without a provider the first provider listing the id names it.
+            (None, "claude-sonnet-4-5-20250929", "Claude Sonnet 4.5"),
+            // Blank catalog names and unknown ids fall back to the formatter.
+            (Some("anthropic"), "claude-opus-4-1", "Claude Opus 4.1"),
+            (Some("openai"), "gpt-4o-mini", "GPT-4o Mini"),
+        ];
+        for (provider, model, expected) in cases {
+            assert_eq!(
+                names.model(provider, model),
+                expected,
+                "{provider:?}/{model}"
+            );
+        }
+        assert_eq!(names.provider("anthropic"), "Anthropic");
+        assert_eq!(names.provider("openrouter"), "OpenRouter");
+
+        names.invalidate();
+        assert_eq!(
+            names.model(Some("openrouter"), "claude-sonnet-4-5-20250929"),
+            "Claude Sonnet 4.5"
+        );
+    }
+}
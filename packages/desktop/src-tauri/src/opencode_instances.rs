@@ -0,0 +1,188 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Result;
+use log::{info, warn};
+use parking_lot::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tokio::sync::watch;
+
+use crate::opencode_manager::OpenCodeManager;
+
+/// Project instances kept alive at once; starting another stops the least recently used.
+const MAX_PROJECT_INSTANCES: usize = 3;
+
+/// An OpenCode server dedicated to one project directory.
+#[derive(Clone)]
+pub struct ProjectInstance {
+    pub directory: PathBuf,
+    pub manager: Arc<OpenCodeManager>,
+}
+
+/// The primary OpenCode server plus, in multi-instance mode, one server per recently
+/// opened project. Switching projects then leaves sessions in the previous one running.
+/// Requests for directories without their own instance go to the primary.
+pub struct OpenCodeInstances {
+    primary: Arc<OpenCodeManager>,
+    /// Most recently used first.
+    projects: Mutex<Vec<ProjectInstance>>,
+    /// Bumped whenever a project instance starts or stops.
+    changes: watch::Sender<u64>,
+    start_lock: tokio::sync::Mutex<()>,
+}
+
+impl OpenCodeInstances {
+    pub fn new(primary: Arc<OpenCodeManager>) -> Self {
+        Self {
+            primary,
+            projects: Mutex::new(Vec::new()),
+            changes: watch::Sender::new(0),
+            start_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn projects(&self) -> Vec<ProjectInstance> {
+        self.projects.lock().clone()
+    }
+
+    /// Follow project instances starting and stopping.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
+
+    /// The server responsible for `directory`: the instance of the most specific project
+    /// containing it, or the primary.
+    pub fn manager_for_directory(&self, directory: Option<&Path>) -> Arc<OpenCodeManager> {
+        let Some(directory) = directory else {
+            return self.primary.clone();
+        };
+        let mut projects = self.projects.lock();
+        let found = projects
+            .iter()
+            .enumerate()
+            .filter(|(_, instance)| directory.starts_with(&instance.directory))
+            .max_by_key(|(_, instance)| instance.directory.components().count())
+            .map(|(index, _)| index);
+        match found {
+            Some(index) => {
+                let instance = projects.remove(index);
+                let manager = instance.manager.clone();
+                projects.insert(0, instance);
+                manager
+            }
+            None => self.primary.clone(),
+        }
+    }
+
+    /// Make sure `directory` has a running instance of its own, stopping the least
+    /// recently used one when over the limit.
+    pub async fn start_project(&self, directory: PathBuf) -> Result<()> {
+        // An external server is shared by every project.
+        if !self.primary.is_cli_available() || self.primary.is_external() {
+            return Ok(());
+        }
+        let _guard = self.start_lock.lock().await;
+
+        let existing = self
+            .projects
+            .lock()
+            .iter()
+            .find(|instance| instance.directory == directory)
+            .map(|instance| instance.manager.clone());
+        if let Some(manager) = existing {
+            // Mark it as the most recently used.
+            self.manager_for_directory(Some(&directory));
+            // Cheap when it is up; respawns an instance whose process died.
+            return manager.ensure_running().await;
+        }
+
+        info!("[desktop:opencode] starting project instance for {directory:?}");
+        let manager = Arc::new(self.primary.for_project(directory.clone()));
+        if let Err(err) = manager.ensure_running().await {
+            let _ = manager.shutdown().await;
+            return Err(err);
+        }
+        manager.spawn_health_monitor();
+
+        let evicted = {
+            let mut projects = self.projects.lock();
+            projects.insert(0, ProjectInstance { directory, manager });
+            projects.split_off(projects.len().min(MAX_PROJECT_INSTANCES))
+        };
+        self.changes.send_modify(|generation| *generation += 1);
+        for instance in evicted {
+            info!(
+                "[desktop:opencode] stopping least recently used instance for {:?}",
+                instance.directory
+            );
+            if let Err(err) = instance.manager.shutdown().await {
+                warn!("[desktop:opencode] failed to stop project instance: {err}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Stop every project instance, leaving the primary running.
+    pub async fn stop_projects(&self) {
+        let stopped = std::mem::take(&mut *self.projects.lock());
+        if stopped.is_empty() {
+            return;
+        }
+        self.changes.send_modify(|generation| *generation += 1);
+        for instance in stopped {
+            if let Err(err) = instance.manager.shutdown().await {
+                warn!("[desktop:opencode] failed to stop project instance: {err}");
+            }
+        }
+    }
+}
+
+/// Keep one task per live project instance, for SSE listeners that need a stream to each
+/// server. `start` runs for every instance that appears; its task is aborted once the
+/// instance stops.
+pub async fn follow_project_instances<F>(instances: Arc<OpenCodeInstances>, mut start: F)
+where
+    F: FnMut(ProjectInstance) -> JoinHandle<()>,
+{
+    let mut changes = instances.subscribe();
+    let mut running = RunningStreams::default();
+    loop {
+        let live = instances.projects();
+        running.0.retain(|directory, (manager, task)| {
+            let alive = live.iter().any(|instance| {
+                instance.directory == *directory && Arc::ptr_eq(&instance.manager, manager)
+            });
+            if !alive {
+                task.abort();
+            }
+            alive
+        });
+        for instance in live {
+            if !running.0.contains_key(&instance.directory) {
+                let task = start(instance.clone());
+                running
+                    .0
+                    .insert(instance.directory, (instance.manager, task));
+            }
+        }
+
+        if changes.changed().await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Tasks started by `follow_project_instances`, aborted together when the follower is.
+#[derive(Default)]
+struct RunningStreams(HashMap<PathBuf, (Arc<OpenCodeManager>, JoinHandle<()>)>);
+
+impl Drop for RunningStreams {
+    fn drop(&mut self) {
+        for (_, task) in self.0.values() {
+            task.abort();
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::path::PathBuf;
+
+use chrono::Local;
+use log::warn;
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::AsyncWriteExt,
+    sync::mpsc,
+};
+
+use crate::logging::{opencode_log_paths, OPENCODE_LOG_FILES};
+
+/// Rotate once the live file passes this size, keeping about 5 MB across all files.
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024 / OPENCODE_LOG_FILES as u64;
+/// Lines waiting to be written. When the writer falls behind further lines are dropped
+/// rather than slowing down the readers draining the child's pipes.
+const QUEUE_CAPACITY: usize = 2_048;
+
+/// Appends OpenCode server output to a size-rotated log file. Writes happen on a
+/// background task; `write` never waits.
+#[derive(Clone)]
+pub struct OpenCodeLog {
+    tx: mpsc::Sender<String>,
+}
+
+impl OpenCodeLog {
+    /// Start the writer, or `None` when there is no log directory.
+    pub fn spawn() -> Option<Self> {
+        let paths = opencode_log_paths()?;
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        tauri::async_runtime::spawn(run_writer(paths, rx));
+        Some(Self { tx })
+    }
+
+    pub fn write(&self, stream: &str, line: &str) {
+        let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
+        let _ = self.tx.try_send(format!("{timestamp} [{stream}] {line}\n"));
+    }
+}
+
+async fn run_writer(paths: Vec<PathBuf>, mut rx: mpsc::Receiver<String>) {
+    if let Some(dir) = paths[0].parent() {
+        let _ = fs::create_dir_all(dir).await;
+    }
+
+    let mut file: Option<File> = None;
+    let mut size = fs::metadata(&paths[0])
+        .await
+        .map(|meta| meta.len())
+        .unwrap_or(0);
+
+    while let Some(entry) = rx.recv().await {
+        if size >= MAX_FILE_BYTES {
+            file = None;
+            rotate(&paths).await;
+            size = 0;
+        }
+        if file.is_none() {
+            match OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&paths[0])
+                .await
+            {
+                Ok(opened) => file = Some(opened),
+                Err(err) => {
+                    warn!("[desktop:opencode] Failed to open output log: {err}");
+                    continue;
+                }
+            }
+        }
+        let Some(out) = file.as_mut() else {
+            continue;
+        };
+        if let Err(err) = out.write_all(entry.as_bytes()).await {
+            warn!("[desktop:opencode] Failed to write output log: {err}");
+            file = None;
+            continue;
+        }
+        size += entry.len() as u64;
+    }
+}
+
+/// Shift every file one slot older, dropping the oldest.
+async fn rotate(paths: &[PathBuf]) {
+    for index in (1..paths.len()).rev() {
+        let _ = fs::rename(&paths[index - 1], &paths[index]).await;
+    }
+}
+
+/// The last `lines` lines of OpenCode output across the rotated files, oldest first.
+pub async fn tail(lines: usize) -> Vec<String> {
+    let Some(paths) = opencode_log_paths() else {
+        return Vec::new();
+    };
+
+    let mut tail: Vec<String> = Vec::new();
+    for path in &paths {
+        if tail.len() >= lines {
+            break;
+        }
+        let Ok(bytes) = fs::read(path).await else {
+            continue;
+        };
+        let content = String::from_utf8_lossy(&bytes);
+        let needed = lines - tail.len();
+        let mut older: Vec<String> = content
+            .lines()
+            .rev()
+            .take(needed)
+            .map(str::to_string)
+            .collect();
+        older.reverse();
+        older.append(&mut tail);
+        tail = older;
+    }
+    tail
+}
@@ -3,9 +3,12 @@ use log::{debug, info, warn};
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 use regex::Regex;
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
+use serde::Serialize;
+use serde_json::Value;
 use std::{
     collections::HashMap,
+    net::IpAddr,
     path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -16,17 +19,245 @@ use std::{
 use tokio::{
     io::{AsyncBufReadExt, BufReader},
     process::{Child, Command},
-    sync::Mutex,
+    sync::{watch, Mutex},
     time::timeout,
 };
 
+use crate::event_stream::EventEndpoint;
+use crate::opencode_log::OpenCodeLog;
+use crate::repeated_log::RepeatedLog;
+
 static URL_REGEX: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r#"https?://[^:\s]+:(?P<port>\d+)(?P<path>/[^\s"']*)?"#).expect("valid regex")
 });
 
+/// Bind failures as reported by Bun and Node.
+static PORT_IN_USE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)EADDRINUSE|address already in use|port \d+ in use").expect("valid regex")
+});
+
+/// Where spawned servers listen.
+const LOCAL_HOST: &str = "127.0.0.1";
 const FIRST_SIGNAL_TIMEOUT_MS: u64 = 750;
 const READY_CHECK_TIMEOUT_MS: u64 = 20000;
 const READY_CHECK_INTERVAL_MS: u64 = 400;
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+/// Consecutive failed health checks before the process is considered hung and respawned.
+const HEALTH_CHECK_FAILURE_LIMIT: u32 = 3;
+const RESPAWN_BACKOFF_INITIAL: Duration = Duration::from_secs(1);
+const RESPAWN_BACKOFF_MAX: Duration = Duration::from_secs(60);
+/// A respawn this long after the previous one starts over at the initial backoff.
+const RESPAWN_BACKOFF_RESET: Duration = Duration::from_secs(5 * 60);
+/// Ports tried from a configured range before giving up.
+const MAX_PORT_ATTEMPTS: usize = 20;
+/// Time the server gets to exit on its own when stopped, overridable with
+/// `OPENCHAMBER_OPENCODE_SHUTDOWN_GRACE_SECS`.
+const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// Lifecycle of the OpenCode server process.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenCodeState {
+    Starting,
+    Ready,
+    Stopped,
+    /// The process exited without being asked to.
+    Crashed,
+    /// The external server stopped answering. It is not ours to respawn, so it is
+    /// reconnected to once it answers again.
+    Unreachable,
+}
+
+/// Result of the periodic health checks against a running server.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenCodeHealth {
+    /// No check has run against the current process yet.
+    Unknown,
+    Healthy,
+    /// The last check failed; the process is respawned if failures continue.
+    Unhealthy,
+}
+
+/// Where the OpenCode server can be reached and what it is doing, published on every
+/// change so the webview and SSE listeners follow restarts onto new ports.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenCodeStatus {
+    pub host: String,
+    pub port: Option<u16>,
+    pub api_prefix: String,
+    pub state: OpenCodeState,
+    pub health: OpenCodeHealth,
+    pub pid: Option<u32>,
+    /// Set when the last start found its port taken.
+    pub port_conflict: Option<PortConflict>,
+    /// Connected to a server the user runs themselves rather than one we spawned.
+    pub external: bool,
+}
+
+/// An OpenCode server the user runs themselves, configured as `opencode.external`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExternalServer {
+    pub host: String,
+    pub port: u16,
+    pub api_prefix: String,
+}
+
+impl ExternalServer {
+    pub fn from_settings(settings: &Value) -> Option<Self> {
+        let external = settings.get("opencode")?.get("external")?;
+        let port = external
+            .get("port")
+            .and_then(Value::as_u64)
+            .and_then(|port| u16::try_from(port).ok())
+            .filter(|port| *port > 0)?;
+        let host = external
+            .get("host")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|host| !host.is_empty())
+            .unwrap_or(LOCAL_HOST);
+        let Some(host) = url_host(host) else {
+            warn!("[desktop:opencode] ignoring external server with invalid host {host:?}");
+            return None;
+        };
+        let api_prefix = external
+            .get("apiPrefix")
+            .and_then(Value::as_str)
+            .map(normalize_api_prefix)
+            .unwrap_or_default();
+        Some(Self {
+            host,
+            port,
+            api_prefix,
+        })
+    }
+}
+
+/// The host as it goes into a URL or Host header, with IPv6 literals bracketed. `None` for
+/// anything but an IP address or a hostname, e.g. a host with a port, path or credentials.
+fn url_host(host: &str) -> Option<String> {
+    let unbracketed = host
+        .strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host);
+    match unbracketed.parse::<IpAddr>() {
+        Ok(IpAddr::V6(ip)) => return Some(format!("[{ip}]")),
+        Ok(IpAddr::V4(ip)) => return Some(ip.to_string()),
+        Err(_) => {}
+    }
+
+    let is_label = |label: &str| {
+        (1..=63).contains(&label.len())
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+    (host.len() <= 253 && host.split('.').all(is_label)).then(|| host.to_ascii_lowercase())
+}
+
+/// The port OpenCode was meant to use was held by another process.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortConflict {
+    pub port: u16,
+    /// Where the server was started instead; `None` when it could not be started.
+    pub fallback: Option<u16>,
+    /// The port is pinned in settings, so no other port was tried.
+    pub pinned: bool,
+}
+
+/// Which port the OpenCode server is started on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PortPolicy {
+    /// Let the server pick any free port.
+    Any,
+    /// The first free port in the inclusive range.
+    Range(u16, u16),
+    /// Exactly this port; a conflict is an error rather than worked around.
+    Pinned(u16),
+}
+
+impl PortPolicy {
+    /// Read the pinned `opencodePort`, or else the `opencodePortRange` to pick from.
+    pub fn from_settings(settings: &Value) -> Self {
+        let port = |value: Option<&Value>| {
+            value
+                .and_then(Value::as_u64)
+                .and_then(|port| u16::try_from(port).ok())
+                .filter(|port| *port > 0)
+        };
+        if let Some(port) = port(settings.get("opencodePort")) {
+            return Self::Pinned(port);
+        }
+        let range = settings.get("opencodePortRange");
+        match (
+            port(range.and_then(|range| range.get("start"))),
+            port(range.and_then(|range| range.get("end"))),
+        ) {
+            (Some(start), Some(end)) if start <= end => Self::Range(start, end),
+            _ => Self::Any,
+        }
+    }
+
+    fn candidates(&self) -> Vec<u16> {
+        match *self {
+            Self::Any => vec![0],
+            Self::Range(start, end) => (start..=end).take(MAX_PORT_ATTEMPTS).collect(),
+            Self::Pinned(port) => vec![port],
+        }
+    }
+}
+
+impl OpenCodeStatus {
+    fn stopped() -> Self {
+        Self {
+            host: LOCAL_HOST.to_string(),
+            port: None,
+            api_prefix: String::new(),
+            state: OpenCodeState::Stopped,
+            health: OpenCodeHealth::Unknown,
+            pid: None,
+            port_conflict: None,
+            external: false,
+        }
+    }
+
+    /// Base URL of the OpenCode API, while a process is starting or running.
+    pub fn base_url(&self) -> Option<String> {
+        match self.state {
+            OpenCodeState::Starting | OpenCodeState::Ready => self
+                .port
+                .map(|port| format!("http://{}:{port}{}", self.host, self.api_prefix)),
+            OpenCodeState::Stopped | OpenCodeState::Crashed | OpenCodeState::Unreachable => None,
+        }
+    }
+}
+
+/// Resolve once the server is no longer reachable at `base_url`, because it stopped or
+/// came back on another port or prefix.
+pub async fn server_moved(status: &mut watch::Receiver<OpenCodeStatus>, base_url: &str) {
+    let _ = status
+        .wait_for(|status| status.base_url().as_deref() != Some(base_url))
+        .await;
+}
+
+/// Resolve once the stored auth token changes after the last one `token` saw.
+pub async fn auth_token_changed(token: &mut watch::Receiver<Option<String>>) {
+    if token.changed().await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// `request` with `token` as its bearer token, for calls that carry the token of the
+/// manager they got their base URL from.
+pub fn with_auth(request: RequestBuilder, token: Option<&str>) -> RequestBuilder {
+    match token {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    }
+}
 
 #[derive(Clone)]
 pub struct OpenCodeManager {
@@ -34,12 +265,34 @@ pub struct OpenCodeManager {
     args: Vec<String>,
     env: HashMap<String, String>,
     working_dir: Arc<RwLock<PathBuf>>,
+    /// Port pinned through `OPENCHAMBER_OPENCODE_PORT`, overriding the settings.
     desired_port: u16,
+    port_policy: Arc<RwLock<PortPolicy>>,
+    /// The current child reported that its port was taken.
+    port_in_use: Arc<AtomicBool>,
+    /// Server from the settings to use instead of spawning one.
+    external: Arc<RwLock<Option<ExternalServer>>>,
+    /// The host, port, and prefix currently point at an external server.
+    attached: Arc<AtomicBool>,
+    /// Bearer token for the external server, as read from the secret store. Published so
+    /// streams the server rejected can retry when it changes.
+    auth_token: Arc<watch::Sender<Option<String>>>,
     child: Arc<Mutex<Option<Child>>>,
+    host: Arc<RwLock<String>>,
     port: Arc<RwLock<Option<u16>>>,
     api_prefix: Arc<RwLock<String>>,
     is_ready: Arc<AtomicBool>,
     shutting_down: Arc<AtomicBool>,
+    /// Exit code of a child that died on its own, until the watchdog takes it. The inner
+    /// `None` means it was killed by a signal.
+    unexpected_exit: Arc<RwLock<Option<Option<i32>>>>,
+    status: Arc<watch::Sender<OpenCodeStatus>>,
+    /// Event stream shape the running process serves; cleared on every spawn so the next
+    /// connection negotiates it again.
+    event_endpoint: Arc<RwLock<Option<EventEndpoint>>>,
+    output_log: Option<OpenCodeLog>,
+    /// How long the process gets to exit after being asked before it is killed.
+    shutdown_grace: Duration,
     http_client: Client,
 }
 
@@ -63,6 +316,12 @@ impl OpenCodeManager {
             .and_then(|raw| raw.parse::<u16>().ok())
             .unwrap_or(0);
 
+        let shutdown_grace = std::env::var("OPENCHAMBER_OPENCODE_SHUTDOWN_GRACE_SECS")
+            .ok()
+            .and_then(|raw| raw.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_SHUTDOWN_GRACE);
+
         let binary = resolve_opencode_binary();
 
         if let Some(ref bin) = binary {
@@ -75,11 +334,7 @@ impl OpenCodeManager {
             warn!("[desktop:opencode] OpenCode CLI not found - app will run in limited mode");
         }
 
-        let mut args = vec![
-            "serve".to_string(),
-            "--port".to_string(),
-            desired_port.to_string(),
-        ];
+        let mut args = vec!["serve".to_string()];
         if let Ok(config) = std::env::var("OPENCHAMBER_OPENCODE_CONFIG") {
             if !config.is_empty() {
                 args.push("--config".to_string());
@@ -102,11 +357,26 @@ impl OpenCodeManager {
             env,
             working_dir: Arc::new(RwLock::new(working_dir)),
             desired_port,
+            port_policy: Arc::new(RwLock::new(if desired_port > 0 {
+                PortPolicy::Pinned(desired_port)
+            } else {
+                PortPolicy::Any
+            })),
+            port_in_use: Arc::new(AtomicBool::new(false)),
+            external: Arc::new(RwLock::new(None)),
+            attached: Arc::new(AtomicBool::new(false)),
+            auth_token: Arc::new(watch::Sender::new(None)),
             child: Arc::new(Mutex::new(None)),
+            host: Arc::new(RwLock::new(LOCAL_HOST.to_string())),
             port: Arc::new(RwLock::new(None)),
             api_prefix: Arc::new(RwLock::new(String::new())),
             is_ready: Arc::new(AtomicBool::new(false)),
             shutting_down: Arc::new(AtomicBool::new(false)),
+            unexpected_exit: Arc::new(RwLock::new(None)),
+            status: Arc::new(watch::Sender::new(OpenCodeStatus::stopped())),
+            event_endpoint: Arc::new(RwLock::new(None)),
+            output_log: OpenCodeLog::spawn(),
+            shutdown_grace,
             http_client: Client::builder()
                 .timeout(Duration::from_secs(2))
                 .build()
@@ -114,29 +384,141 @@ impl OpenCodeManager {
         }
     }
 
+    /// A separate server for one project, sharing this manager's binary, environment, and
+    /// output log. It always picks its own free port.
+    pub fn for_project(&self, directory: PathBuf) -> Self {
+        Self {
+            binary: self.binary.clone(),
+            args: self.args.clone(),
+            env: self.env.clone(),
+            working_dir: Arc::new(RwLock::new(directory)),
+            desired_port: 0,
+            port_policy: Arc::new(RwLock::new(PortPolicy::Any)),
+            port_in_use: Arc::new(AtomicBool::new(false)),
+            external: Arc::new(RwLock::new(None)),
+            attached: Arc::new(AtomicBool::new(false)),
+            auth_token: Arc::new(watch::Sender::new(None)),
+            child: Arc::new(Mutex::new(None)),
+            host: Arc::new(RwLock::new(LOCAL_HOST.to_string())),
+            port: Arc::new(RwLock::new(None)),
+            api_prefix: Arc::new(RwLock::new(String::new())),
+            is_ready: Arc::new(AtomicBool::new(false)),
+            shutting_down: Arc::new(AtomicBool::new(false)),
+            unexpected_exit: Arc::new(RwLock::new(None)),
+            status: Arc::new(watch::Sender::new(OpenCodeStatus::stopped())),
+            event_endpoint: Arc::new(RwLock::new(None)),
+            output_log: self.output_log.clone(),
+            shutdown_grace: self.shutdown_grace,
+            http_client: self.http_client.clone(),
+        }
+    }
+
+    /// Whether there is a server to run: the CLI is installed or an external server is
+    /// configured.
     pub fn is_cli_available(&self) -> bool {
-        self.binary.is_some()
+        self.binary.is_some() || self.is_external()
+    }
+
+    pub fn is_external(&self) -> bool {
+        self.external.read().is_some()
     }
 
     pub async fn ensure_running(&self) -> Result<()> {
+        let external = self.external.read().clone();
+        if let Some(external) = external {
+            return self.attach_external(external).await;
+        }
         if self.binary.is_none() {
             return Err(anyhow!("OpenCode CLI is not available"));
         }
 
-        let mut guard = self.child.lock().await;
-        if let Some(child) = guard.as_mut() {
-            if child.try_wait()?.is_none() && self.is_ready.load(Ordering::SeqCst) {
-                return Ok(());
+        {
+            let mut guard = self.child.lock().await;
+            if let Some(child) = guard.as_mut() {
+                if child.try_wait()?.is_none() && self.is_ready.load(Ordering::SeqCst) {
+                    return Ok(());
+                }
             }
         }
 
+        // A conflict stays published until a start settles it, so retries against a port
+        // that is still taken do not announce it again.
         self.is_ready.store(false, Ordering::SeqCst);
-        let child = self.spawn_process().await?;
+
+        let policy = self.port_policy.read().clone();
+        let mut taken: Option<u16> = None;
+        for port in policy.candidates() {
+            match self.start_on_port(port).await {
+                Ok(()) => {
+                    self.is_ready.store(true, Ordering::SeqCst);
+                    let fallback = self.current_port();
+                    self.publish_status(|status| {
+                        status.state = OpenCodeState::Ready;
+                        status.health = OpenCodeHealth::Healthy;
+                        status.port_conflict = taken.map(|port| PortConflict {
+                            port,
+                            fallback,
+                            pinned: false,
+                        });
+                    });
+                    if let Some(port) = fallback {
+                        info!("[desktop:opencode] ready on port {port}");
+                    }
+                    return Ok(());
+                }
+                Err(err) if self.port_in_use.load(Ordering::SeqCst) => {
+                    warn!("[desktop:opencode] {err}");
+                    self.discard_child().await;
+                    taken.get_or_insert(port);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        let port = taken.unwrap_or_default();
+        let pinned = matches!(policy, PortPolicy::Pinned(_));
+        self.publish_status(|status| {
+            status.port_conflict = Some(PortConflict {
+                port,
+                fallback: None,
+                pinned,
+            });
+        });
+        match policy {
+            PortPolicy::Range(start, end) => Err(anyhow!(
+                "No free port for OpenCode between {start} and {end}"
+            )),
+            _ => Err(anyhow!(
+                "OpenCode port {port} is already in use by another process"
+            )),
+        }
+    }
+
+    /// Spawn the server on `port` (0 lets it choose) and wait until it answers. Fails with
+    /// `port_in_use` set when the port turns out to be taken.
+    async fn start_on_port(&self, port: u16) -> Result<()> {
+        self.port_in_use.store(false, Ordering::SeqCst);
+        if port != 0 && std::net::TcpListener::bind((LOCAL_HOST, port)).is_err() {
+            self.port_in_use.store(true, Ordering::SeqCst);
+            return Err(anyhow!("port {port} is already in use"));
+        }
+
+        let mut guard = self.child.lock().await;
+        let spawned = self.spawn_process(port).await;
+        let child = match spawned {
+            Ok(child) => child,
+            Err(err) => {
+                drop(guard);
+                // The exit may be read before the output explaining it.
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                return Err(err);
+            }
+        };
         *guard = Some(child);
         drop(guard);
 
         // Wait for port detection from logs
-        if self.desired_port == 0 {
+        if port == 0 {
             self.wait_for_port_detection().await?;
         }
 
@@ -144,29 +526,130 @@ impl OpenCodeManager {
         let _ = self.detect_api_prefix().await;
 
         // Wait for OpenCode to become ready by polling endpoints
-        self.wait_for_ready().await?;
+        self.wait_for_ready().await
+    }
 
-        self.is_ready.store(true, Ordering::SeqCst);
-        if let Some(port) = self.current_port() {
-            info!("[desktop:opencode] ready on port {port}");
+    /// Drop a child that failed to start without the port cleanup `graceful_stop` does,
+    /// which would hit whichever process holds the port.
+    async fn discard_child(&self) {
+        if let Some(mut child) = self.child.lock().await.take() {
+            let _ = child.kill().await;
         }
+        *self.port.write() = None;
+        self.publish_stopped();
+    }
+
+    /// Point at the external server instead of spawning one, and wait until it answers.
+    async fn attach_external(&self, external: ExternalServer) -> Result<()> {
+        if self.attached.load(Ordering::SeqCst) && self.is_ready() {
+            return Ok(());
+        }
+
+        self.attached.store(true, Ordering::SeqCst);
+        *self.host.write() = external.host.clone();
+        *self.port.write() = Some(external.port);
+        *self.api_prefix.write() = external.api_prefix;
+        *self.event_endpoint.write() = None;
+        self.publish_status(|status| {
+            // Retries against an unreachable server keep it reported as such.
+            if status.state != OpenCodeState::Unreachable {
+                status.state = OpenCodeState::Starting;
+                status.health = OpenCodeHealth::Unknown;
+            }
+            status.pid = None;
+            status.port_conflict = None;
+        });
+
+        if let Err(err) = self.wait_for_ready().await {
+            self.mark_unreachable();
+            return Err(err);
+        }
+
+        self.is_ready.store(true, Ordering::SeqCst);
+        self.publish_status(|status| {
+            status.state = OpenCodeState::Ready;
+            status.health = OpenCodeHealth::Healthy;
+        });
+        info!(
+            "[desktop:opencode] connected to external server at {}:{}",
+            external.host, external.port
+        );
         Ok(())
     }
 
+    fn mark_unreachable(&self) {
+        self.is_ready.store(false, Ordering::SeqCst);
+        self.publish_status(|status| {
+            status.state = OpenCodeState::Unreachable;
+            status.health = OpenCodeHealth::Unhealthy;
+        });
+    }
+
+    /// Apply the port and external server settings from the next start on. A port pinned
+    /// through `OPENCHAMBER_OPENCODE_PORT` takes precedence over the port settings.
+    /// Returns true if the external server changed, when the caller should restart.
+    pub fn apply_settings(&self, settings: &Value) -> bool {
+        if self.desired_port == 0 {
+            *self.port_policy.write() = PortPolicy::from_settings(settings);
+        }
+        let external = ExternalServer::from_settings(settings);
+        let mut current = self.external.write();
+        if *current == external {
+            return false;
+        }
+        *current = external;
+        true
+    }
+
+    pub fn host(&self) -> String {
+        self.host.read().clone()
+    }
+
+    /// Send `token` to the external server from now on; `None` stops sending one.
+    pub fn set_auth_token(&self, token: Option<String>) {
+        let token = token
+            .map(|token| token.trim().to_string())
+            .filter(|token| !token.is_empty());
+        self.auth_token.send_if_modified(|current| {
+            let changed = *current != token;
+            *current = token;
+            changed
+        });
+    }
+
+    /// Changes to the stored token; see `auth_token_changed`.
+    pub fn subscribe_auth_token(&self) -> watch::Receiver<Option<String>> {
+        self.auth_token.subscribe()
+    }
+
+    /// The token for the server requests currently go to. Only external servers get one;
+    /// a spawned server does not check credentials.
+    pub fn auth_token(&self) -> Option<String> {
+        if !self.attached.load(Ordering::SeqCst) {
+            return None;
+        }
+        self.auth_token.borrow().clone()
+    }
+
+    /// `request` with the server's auth token, if it takes one.
+    pub fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        with_auth(request, self.auth_token().as_deref())
+    }
+
     pub async fn restart(&self) -> Result<()> {
         info!("[desktop:opencode] restarting...");
         self.is_ready.store(false, Ordering::SeqCst);
 
         self.graceful_stop().await?;
+        self.publish_stopped();
 
         // Brief delay to let OS release resources
         tokio::time::sleep(Duration::from_millis(250)).await;
 
         // Reset state
-        if self.desired_port == 0 {
-            *self.port.write() = None;
-        }
+        *self.port.write() = None;
         *self.api_prefix.write() = String::new();
+        self.publish_status(|_| {});
 
         self.ensure_running().await
     }
@@ -174,10 +657,11 @@ impl OpenCodeManager {
     pub async fn shutdown(&self) -> Result<()> {
         self.shutting_down.store(true, Ordering::SeqCst);
         self.is_ready.store(false, Ordering::SeqCst);
-        self.graceful_stop().await
+        let result = self.graceful_stop().await;
+        self.publish_stopped();
+        result
     }
 
-    #[allow(dead_code)]
     pub async fn set_working_directory(&self, new_dir: PathBuf) -> Result<()> {
         *self.working_dir.write() = new_dir;
         Ok(())
@@ -196,20 +680,17 @@ impl OpenCodeManager {
         // Try no prefix first, then /api (compatibility).
         let candidates = ["", "/api"];
         for candidate in candidates {
-            let base = if candidate.is_empty() {
-                format!("http://127.0.0.1:{port}")
-            } else {
-                format!("http://127.0.0.1:{port}{candidate}")
-            };
+            let base = format!("http://{}:{port}{candidate}", self.host());
 
             let url = format!("{base}/config");
-            match self.http_client.get(&url).send().await {
+            match self.authorize(self.http_client.get(&url)).send().await {
                 Ok(resp) if resp.status().is_success() => {
                     // Validate it's actually JSON config, not HTML
                     if let Ok(text) = resp.text().await {
                         if text.trim().starts_with('{') || text.trim().starts_with('[') {
                             info!("[desktop:opencode] Detected API prefix: {:?}", candidate);
                             *self.api_prefix.write() = normalize_api_prefix(candidate);
+                            self.publish_status(|_| {});
                             return Ok(());
                         }
                     }
@@ -220,6 +701,7 @@ impl OpenCodeManager {
 
         info!("[desktop:opencode] No API prefix detected, using empty prefix");
         *self.api_prefix.write() = String::new();
+        self.publish_status(|_| {});
         Ok(())
     }
 
@@ -231,6 +713,49 @@ impl OpenCodeManager {
         self.api_prefix.read().clone()
     }
 
+    pub fn event_endpoint(&self) -> Option<EventEndpoint> {
+        *self.event_endpoint.read()
+    }
+
+    pub fn set_event_endpoint(&self, endpoint: EventEndpoint) {
+        *self.event_endpoint.write() = Some(endpoint);
+    }
+
+    pub fn status(&self) -> OpenCodeStatus {
+        self.status.borrow().clone()
+    }
+
+    /// Follow status changes; the receiver starts out holding the current status.
+    pub fn subscribe_status(&self) -> watch::Receiver<OpenCodeStatus> {
+        self.status.subscribe()
+    }
+
+    /// Apply `update` and refresh the port and prefix, notifying subscribers only when
+    /// something actually changed.
+    fn publish_status(&self, update: impl FnOnce(&mut OpenCodeStatus)) {
+        let host = self.host();
+        let port = self.current_port();
+        let api_prefix = self.api_prefix();
+        let external = self.attached.load(Ordering::SeqCst);
+        self.status.send_if_modified(|status| {
+            let before = status.clone();
+            update(status);
+            status.host = host;
+            status.port = port;
+            status.api_prefix = api_prefix;
+            status.external = external;
+            *status != before
+        });
+    }
+
+    fn publish_stopped(&self) {
+        self.publish_status(|status| {
+            status.state = OpenCodeState::Stopped;
+            status.health = OpenCodeHealth::Unknown;
+            status.pid = None;
+        });
+    }
+
     pub fn is_ready(&self) -> bool {
         self.is_ready.load(Ordering::SeqCst)
     }
@@ -240,13 +765,23 @@ impl OpenCodeManager {
     }
 
     pub async fn is_child_running(&self) -> Result<bool> {
+        if self.attached.load(Ordering::SeqCst) {
+            return Ok(self.is_ready());
+        }
         let mut guard = self.child.lock().await;
         if let Some(child) = guard.as_mut() {
             match child.try_wait()? {
                 None => return Ok(true),
-                Some(_status) => {
+                Some(status) => {
+                    warn!("[desktop:opencode] process exited unexpectedly: {status}");
                     *guard = None;
                     self.is_ready.store(false, Ordering::SeqCst);
+                    *self.unexpected_exit.write() = Some(status.code());
+                    self.publish_status(|status| {
+                        status.state = OpenCodeState::Crashed;
+                        status.health = OpenCodeHealth::Unknown;
+                        status.pid = None;
+                    });
                     return Ok(false);
                 }
             }
@@ -254,6 +789,12 @@ impl OpenCodeManager {
         Ok(false)
     }
 
+    /// The exit code of the last child seen dying on its own, if not taken yet. Stops made
+    /// through `restart` or `shutdown` never show up here.
+    pub fn take_unexpected_exit(&self) -> Option<Option<i32>> {
+        self.unexpected_exit.write().take()
+    }
+
     pub fn rewrite_path(&self, incoming_path: &str) -> String {
         // Strip /api prefix to get OpenCode path
         let result = incoming_path
@@ -269,17 +810,23 @@ impl OpenCodeManager {
         result
     }
 
-    async fn spawn_process(&self) -> Result<Child> {
+    async fn spawn_process(&self, port: u16) -> Result<Child> {
         let binary = self
             .binary
             .as_ref()
             .ok_or_else(|| anyhow!("Cannot spawn process: OpenCode CLI is not available"))?;
 
-        info!("[desktop:opencode] launching {} {:?}", binary, self.args);
+        info!(
+            "[desktop:opencode] launching {} {:?} on port {port}",
+            binary, self.args
+        );
+        *self.event_endpoint.write() = None;
+        *self.host.write() = LOCAL_HOST.to_string();
 
         let working_dir = self.working_dir.read().clone();
         let mut cmd = Command::new(binary);
         cmd.args(&self.args)
+            .args(["--port".to_string(), port.to_string()])
             .current_dir(&working_dir)
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
@@ -301,9 +848,15 @@ impl OpenCodeManager {
         })?;
 
         // Set port immediately if pre-configured
-        if self.desired_port > 0 {
-            *self.port.write() = Some(self.desired_port);
+        if port > 0 {
+            *self.port.write() = Some(port);
         }
+        let pid = child.id();
+        self.publish_status(|status| {
+            status.state = OpenCodeState::Starting;
+            status.health = OpenCodeHealth::Unknown;
+            status.pid = pid;
+        });
 
         // Wait for first signal (stdout/stderr) within 750ms to confirm startup
         let first_signal_received = Arc::new(AtomicBool::new(false));
@@ -358,12 +911,18 @@ impl OpenCodeManager {
                 }
 
                 debug!("[opencode:{label}] {line}");
+                if let Some(output_log) = &manager.output_log {
+                    output_log.write(label, &line);
+                }
                 manager.ingest_output_line(&line);
             }
         });
     }
 
     fn ingest_output_line(&self, line: &str) {
+        if PORT_IN_USE_REGEX.is_match(line) {
+            self.port_in_use.store(true, Ordering::SeqCst);
+        }
         if let Some(captures) = URL_REGEX.captures(line) {
             if let Some(port_match) = captures
                 .name("port")
@@ -378,6 +937,7 @@ impl OpenCodeManager {
                     *self.api_prefix.write() = value.to_string();
                 }
             }
+            self.publish_status(|_| {});
         }
     }
 
@@ -404,6 +964,9 @@ impl OpenCodeManager {
         let mut last_error: Option<String> = None;
 
         while tokio::time::Instant::now() < deadline {
+            if self.port_in_use.load(Ordering::SeqCst) {
+                return Err(anyhow!("port {port} is already in use"));
+            }
             let api_prefix = self.api_prefix();
 
             // Try /config, /agent endpoints
@@ -426,15 +989,108 @@ impl OpenCodeManager {
         ))
     }
 
+    /// Poll the running server and respawn it after repeated failures, so a hung process
+    /// is noticed without waiting for an SSE stream to error out. Exits are left to the
+    /// watchdog; this only covers a process that is alive but not answering.
+    pub fn spawn_health_monitor(&self) {
+        let manager = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut failures = 0;
+            let mut backoff = RESPAWN_BACKOFF_INITIAL;
+            let mut last_respawn: Option<std::time::Instant> = None;
+            let mut check_failures = RepeatedLog::default();
+            let mut unreachable = RepeatedLog::default();
+
+            loop {
+                tokio::time::sleep(HEALTH_CHECK_INTERVAL).await;
+                if manager.is_shutting_down() {
+                    break;
+                }
+                if !manager.is_ready() {
+                    failures = 0;
+                    continue;
+                }
+
+                match manager.check_health().await {
+                    Ok(()) => {
+                        check_failures.settle(|line| warn!("{line}"));
+                        unreachable.settle(|line| warn!("{line}"));
+                        if failures > 0 {
+                            info!("[desktop:health] OpenCode is responding again");
+                        }
+                        failures = 0;
+                        manager.publish_status(|status| status.health = OpenCodeHealth::Healthy);
+                        continue;
+                    }
+                    Err(err) => {
+                        failures += 1;
+                        // The attempt count stays out of the line so repeats collapse.
+                        check_failures.record(
+                            format!("[desktop:health] Health check failed: {err}"),
+                            |line| warn!("{line}"),
+                        );
+                        manager.publish_status(|status| status.health = OpenCodeHealth::Unhealthy);
+                    }
+                }
+                if failures < HEALTH_CHECK_FAILURE_LIMIT {
+                    continue;
+                }
+                if manager.attached.load(Ordering::SeqCst) {
+                    // Not ours to respawn; the watchdog reconnects once it answers again.
+                    unreachable.record(
+                        "[desktop:health] External OpenCode server is unreachable".to_string(),
+                        |line| warn!("{line}"),
+                    );
+                    failures = 0;
+                    manager.mark_unreachable();
+                    continue;
+                }
+
+                if last_respawn.is_some_and(|at| at.elapsed() < RESPAWN_BACKOFF_RESET) {
+                    info!(
+                        "[desktop:health] Waiting {}s before respawning OpenCode",
+                        backoff.as_secs()
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(RESPAWN_BACKOFF_MAX);
+                } else {
+                    backoff = RESPAWN_BACKOFF_INITIAL;
+                }
+                if manager.is_shutting_down() {
+                    break;
+                }
+
+                warn!("[desktop:health] OpenCode is unresponsive; respawning");
+                failures = 0;
+                last_respawn = Some(std::time::Instant::now());
+                if let Err(err) = manager.restart().await {
+                    warn!("[desktop:health] Failed to respawn OpenCode: {err}");
+                }
+            }
+        });
+    }
+
+    async fn check_health(&self) -> Result<()> {
+        let port = self
+            .current_port()
+            .ok_or_else(|| anyhow!("no port assigned"))?;
+        let url = format!("http://{}:{port}{}/config", self.host(), self.api_prefix());
+        let response = self.authorize(self.http_client.get(&url)).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("/config returned {}", response.status()));
+        }
+        Ok(())
+    }
+
     async fn check_endpoints(&self, port: u16, prefix: &str) -> Result<()> {
-        let base_url = format!("http://127.0.0.1:{port}{prefix}");
+        let base_url = format!("http://{}:{port}{prefix}", self.host());
 
         let config_url = format!("{base_url}/config");
         let agent_url = format!("{base_url}/agent");
 
         let (config_resp, agent_resp) = tokio::join!(
-            self.http_client.get(&config_url).send(),
-            self.http_client.get(&agent_url).send()
+            self.authorize(self.http_client.get(&config_url)).send(),
+            self.authorize(self.http_client.get(&agent_url)).send()
         );
 
         let config_resp = config_resp?;
@@ -451,7 +1107,13 @@ impl OpenCodeManager {
     }
 
     async fn graceful_stop(&self) -> Result<()> {
+        if self.attached.swap(false, Ordering::SeqCst) {
+            // The external server keeps running; only detach from it.
+            return Ok(());
+        }
+
         let port_to_kill = self.current_port();
+        let base_url = self.status().base_url();
 
         let mut guard = self.child.lock().await;
         let Some(mut child) = guard.take() else {
@@ -461,14 +1123,21 @@ impl OpenCodeManager {
             return Ok(());
         };
 
-        if child.try_wait()?.is_some() {
+        if let Some(status) = child.try_wait()? {
+            info!("[desktop:opencode] already exited ({status})");
             // Already exited, but still clean up by port
             drop(guard);
             kill_process_on_port(port_to_kill);
             return Ok(());
         }
 
-        // SIGTERM
+        // Let the server flush and release its storage before it is signalled.
+        if let Some(base_url) = base_url {
+            self.request_dispose(&base_url).await;
+        }
+
+        // SIGTERM. Windows has no equivalent for console processes, so there the dispose
+        // request above is all the warning the server gets.
         #[cfg(unix)]
         {
             use nix::{
@@ -480,21 +1149,22 @@ impl OpenCodeManager {
                 info!("[desktop:opencode] sent SIGTERM");
             }
         }
-        #[cfg(windows)]
-        {
-            let _ = child.kill().await;
-        }
 
-        // Wait 3 seconds for graceful exit
-        match timeout(Duration::from_secs(3), child.wait()).await {
-            Ok(_) => {
-                info!("[desktop:opencode] exited gracefully");
+        match timeout(self.shutdown_grace, child.wait()).await {
+            Ok(Ok(status)) => {
+                info!("[desktop:opencode] exited gracefully ({status})");
                 drop(guard);
                 kill_process_on_port(port_to_kill);
                 return Ok(());
             }
+            Ok(Err(err)) => {
+                warn!("[desktop:opencode] failed to wait for exit: {err}; killing");
+            }
             Err(_) => {
-                warn!("[desktop:opencode] did not exit after SIGTERM, sending SIGKILL");
+                warn!(
+                    "[desktop:opencode] did not exit within {:?}, killing",
+                    self.shutdown_grace
+                );
             }
         }
 
@@ -502,11 +1172,11 @@ impl OpenCodeManager {
         let _ = child.kill().await;
 
         match timeout(Duration::from_secs(2), child.wait()).await {
-            Ok(_) => {
-                info!("[desktop:opencode] exited after SIGKILL");
+            Ok(Ok(status)) => {
+                info!("[desktop:opencode] exited after kill ({status})");
             }
-            Err(_) => {
-                warn!("[desktop:opencode] unresponsive after SIGKILL, continuing anyway");
+            _ => {
+                warn!("[desktop:opencode] unresponsive after kill, continuing anyway");
             }
         }
 
@@ -515,6 +1185,28 @@ impl OpenCodeManager {
 
         Ok(())
     }
+
+    /// Ask the server to dispose of its instance, writing out pending state. Servers
+    /// without the endpoint just get the signal.
+    async fn request_dispose(&self, base_url: &str) {
+        let request = self
+            .http_client
+            .post(format!("{base_url}/instance/dispose"));
+        match self.authorize(request).send().await {
+            Ok(response) if response.status().is_success() => {
+                debug!("[desktop:opencode] instance disposed");
+            }
+            Ok(response) => {
+                debug!(
+                    "[desktop:opencode] dispose request returned {}",
+                    response.status()
+                );
+            }
+            Err(err) => {
+                debug!("[desktop:opencode] dispose request failed: {err}");
+            }
+        }
+    }
 }
 
 fn kill_process_on_port(port: Option<u16>) {
@@ -546,6 +1238,24 @@ fn kill_process_on_port(port: Option<u16>) {
     }
 }
 
+/// What `opencode --version` prints for the CLI the desktop would launch.
+pub async fn cli_version() -> Option<String> {
+    let binary = tokio::task::spawn_blocking(resolve_opencode_binary)
+        .await
+        .ok()??;
+    let output = timeout(
+        Duration::from_secs(5),
+        Command::new(binary).arg("--version").output(),
+    )
+    .await
+    .ok()?
+    .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
 /// Check if CLI binary exists (can be called dynamically for polling)
 pub fn check_cli_exists() -> bool {
     if std::env::var("OPENCHAMBER_DISABLE_CLI").is_ok() {
@@ -749,3 +1459,39 @@ fn detect_login_shell_path() -> Result<String> {
         .path
         .ok_or_else(|| anyhow!("shell PATH detection failed"))
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn external_host(host: &str) -> Option<String> {
+        let settings = json!({ "opencode": { "external": { "host": host, "port": 4096 } } });
+        ExternalServer::from_settings(&settings).map(|server| server.host)
+    }
+
+    #[test]
+    fn external_hosts_are_validated_and_bracketed() {
+        let cases = [
+            ("localhost", Some("localhost")),
+            ("  OpenCode.Example.com ", Some("opencode.example.com")),
+            ("192.168.1.20", Some("192.168.1.20")),
+            ("::1", Some("[::1]")),
+            ("[::1]", Some("[::1]")),
+            ("fe80::1", Some("[fe80::1]")),
+            ("", Some(LOCAL_HOST)),
+            ("example.com:8080", None),
+            ("user@example.com", None),
+            ("example.com/api", None),
+            ("exa mple.com", None),
+            ("-example.com", None),
+            ("example..com", None),
+            ("[example.com]", None),
+            ("host\r\nX-Injected: 1", None),
+        ];
+        for (host, expected) in cases {
+            assert_eq!(external_host(host).as_deref(), expected, "host {host:?}");
+        }
+    }
+}
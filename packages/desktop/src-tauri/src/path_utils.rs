@@ -1,20 +1,484 @@
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 
-pub fn expand_tilde_path(value: &str) -> PathBuf {
-    let trimmed = value.trim();
-    if trimmed.is_empty() {
-        return PathBuf::from(trimmed);
+use log::warn;
+
+/// Expand a leading `~` and environment variables: `$VAR` and `${VAR}` on Unix, `%VAR%`
+/// on Windows. Variables that are not set are left as written, with a warning.
+pub fn expand_path(value: &str) -> PathBuf {
+    let (expanded, undefined) = expand_variables(value.trim());
+    if !undefined.is_empty() {
+        warn!(
+            "[desktop] Path {value:?} refers to undefined variable(s): {}",
+            undefined.join(", ")
+        );
+    }
+    expand_tilde(&expanded)
+}
+
+/// Like `expand_path`, but a path that refers to an undefined variable is treated as
+/// missing rather than taken literally with the `$` in it.
+pub fn try_expand_path(value: &str) -> Option<PathBuf> {
+    let (expanded, undefined) = expand_variables(value.trim());
+    if !undefined.is_empty() {
+        warn!(
+            "[desktop] Ignoring path {value:?}: undefined variable(s) {}",
+            undefined.join(", ")
+        );
+        return None;
+    }
+    Some(expand_tilde(&expanded))
+}
+
+/// Whether two paths name the same location, ignoring symlinks (`/var` and
+/// `/private/var` on macOS), trailing separators, and case on Windows.
+pub fn paths_equivalent(a: &Path, b: &Path) -> bool {
+    comparable_path(a) == comparable_path(b)
+}
+
+/// A form of `path` suitable for comparing against others: canonical where the path
+/// exists, lexically normalized where it does not, and lowercased on Windows.
+pub fn comparable_path(path: &Path) -> PathBuf {
+    let resolved = std::fs::canonicalize(path).unwrap_or_else(|_| normalize_lexically(path));
+    if cfg!(windows) {
+        PathBuf::from(resolved.to_string_lossy().to_lowercase())
+    } else {
+        resolved
+    }
+}
+
+/// A project directory as sent to OpenCode, which tells directories apart by their text:
+/// `~` and variables expanded, relative paths taken from the home directory, canonical
+/// where the directory exists and without trailing separators where it does not.
+pub fn normalize_directory(path: &Path) -> PathBuf {
+    let expanded = expand_path(&path.to_string_lossy());
+    let absolute = if expanded.is_relative() {
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("/"))
+            .join(expanded)
+    } else {
+        expanded
+    };
+    std::fs::canonicalize(&absolute).unwrap_or_else(|_| normalize_lexically(&absolute))
+}
+
+/// `path` relative to `base` when it lies inside it, seeing through symlinks and case
+/// differences the way `paths_equivalent` does.
+pub fn relative_path(path: &Path, base: &Path) -> Option<PathBuf> {
+    if let Ok(relative) = normalize_lexically(path).strip_prefix(normalize_lexically(base)) {
+        return Some(relative.to_path_buf());
+    }
+    comparable_path(path)
+        .strip_prefix(comparable_path(base))
+        .ok()
+        .map(Path::to_path_buf)
+}
+
+/// Drop `.` and empty components and resolve `..` without touching the file system.
+/// Rebuilding from components also drops trailing separators.
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(component);
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+fn expand_tilde(value: &str) -> PathBuf {
+    if value.is_empty() {
+        return PathBuf::from(value);
     }
 
     let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
 
-    if trimmed == "~" {
+    if value == "~" {
         return home;
     }
 
-    if trimmed.starts_with("~/") || trimmed.starts_with("~\\") {
-        return home.join(&trimmed[2..]);
+    if value.starts_with("~/") || value.starts_with("~\\") {
+        return home.join(&value[2..]);
+    }
+
+    PathBuf::from(value)
+}
+
+/// Substitute set variables and return the names of the ones that are not set.
+#[cfg(not(windows))]
+fn expand_variables(value: &str) -> (String, Vec<String>) {
+    let mut result = String::with_capacity(value.len());
+    let mut undefined = Vec::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        let (name, written_len) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) if is_variable_name(&braced[..end]) => (&braced[..end], end + 2),
+                _ => ("", 0),
+            }
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            if is_variable_name(&after[..end]) {
+                (&after[..end], end)
+            } else {
+                ("", 0)
+            }
+        };
+
+        let written = &rest[start..start + 1 + written_len];
+        if name.is_empty() {
+            // A lone `$` is part of the path.
+            result.push('$');
+        } else {
+            substitute(name, written, &mut result, &mut undefined);
+        }
+        rest = &rest[start + 1 + written_len..];
+    }
+    result.push_str(rest);
+    (result, undefined)
+}
+
+/// Substitute set variables and return the names of the ones that are not set.
+#[cfg(windows)]
+fn expand_variables(value: &str) -> (String, Vec<String>) {
+    let mut result = String::with_capacity(value.len());
+    let mut undefined = Vec::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find('%') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('%') {
+            Some(end) if end > 0 && !after[..end].contains(['\\', '/']) => {
+                let written = &rest[start..start + end + 2];
+                substitute(&after[..end], written, &mut result, &mut undefined);
+                rest = &after[end + 1..];
+            }
+            _ => {
+                // An unpaired `%` is part of the path.
+                result.push('%');
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+    (result, undefined)
+}
+
+#[cfg(not(windows))]
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn substitute(name: &str, written: &str, result: &mut String, undefined: &mut Vec<String>) {
+    match std::env::var_os(name) {
+        Some(value) => result.push_str(&value.to_string_lossy()),
+        None => {
+            result.push_str(written);
+            undefined.push(name.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn home() -> String {
+        dirs::home_dir()
+            .expect("home directory")
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn expanded(input: &str) -> String {
+        expand_path(input).to_string_lossy().into_owned()
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn expand_path_cases() {
+        std::env::set_var("OPENCHAMBER_TEST_EXPAND_ROOT", "/srv/projects");
+        std::env::set_var("OPENCHAMBER_TEST_EXPAND_NAME", "acme");
+        let home = home();
+
+        let cases = [
+            ("", String::new()),
+            ("  /tmp/work  ", "/tmp/work".to_string()),
+            ("/tmp/work/", "/tmp/work/".to_string()),
+            ("~", home.clone()),
+            ("~/", format!("{home}/")),
+            ("~/work/acme", format!("{home}/work/acme")),
+            ("~/work/acme/", format!("{home}/work/acme/")),
+            ("~\\work", format!("{home}/work")),
+            ("~\\work/acme\\src", format!("{home}/work/acme\\src")),
+            ("~other/work", "~other/work".to_string()),
+            ("/tmp/~/work", "/tmp/~/work".to_string()),
+            (
+                "$OPENCHAMBER_TEST_EXPAND_ROOT/acme",
+                "/srv/projects/acme".to_string(),
+            ),
+            (
+                "${OPENCHAMBER_TEST_EXPAND_ROOT}/$OPENCHAMBER_TEST_EXPAND_NAME/",
+                "/srv/projects/acme/".to_string(),
+            ),
+            (
+                "${OPENCHAMBER_TEST_EXPAND_ROOT}-old",
+                "/srv/projects-old".to_string(),
+            ),
+            (
+                "$OPENCHAMBER_TEST_EXPAND_ROOT\\acme/src",
+                "/srv/projects\\acme/src".to_string(),
+            ),
+            ("~/$OPENCHAMBER_TEST_EXPAND_NAME", format!("{home}/acme")),
+            ("/prices/$5/list", "/prices/$5/list".to_string()),
+            ("/tmp/$/work", "/tmp/$/work".to_string()),
+            ("/tmp/${unclosed/work", "/tmp/${unclosed/work".to_string()),
+            ("/tmp/${}/work", "/tmp/${}/work".to_string()),
+            (
+                "$OPENCHAMBER_TEST_EXPAND_UNSET/work",
+                "$OPENCHAMBER_TEST_EXPAND_UNSET/work".to_string(),
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(expanded(input), expected, "expand_path({input:?})");
+        }
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn expand_path_cases() {
+        std::env::set_var("OPENCHAMBER_TEST_EXPAND_ROOT", "D:\\projects");
+        let home = home();
+
+        let cases = [
+            ("", String::new()),
+            ("~", home.clone()),
+            ("~\\work\\acme\\", format!("{home}\\work\\acme\\")),
+            ("~/work/acme", format!("{home}\\work/acme")),
+            (
+                "%OPENCHAMBER_TEST_EXPAND_ROOT%\\acme/src",
+                "D:\\projects\\acme/src".to_string(),
+            ),
+            ("C:\\100%\\work", "C:\\100%\\work".to_string()),
+            ("C:\\a%\\b%\\c", "C:\\a%\\b%\\c".to_string()),
+            ("%%\\work", "%%\\work".to_string()),
+            (
+                "%OPENCHAMBER_TEST_EXPAND_UNSET%\\work",
+                "%OPENCHAMBER_TEST_EXPAND_UNSET%\\work".to_string(),
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(expanded(input), expected, "expand_path({input:?})");
+        }
+    }
+
+    #[test]
+    fn try_expand_path_rejects_undefined_variables() {
+        let undefined = if cfg!(windows) {
+            "%OPENCHAMBER_TEST_TRY_UNSET%\\work"
+        } else {
+            "$OPENCHAMBER_TEST_TRY_UNSET/work"
+        };
+        assert_eq!(try_expand_path(undefined), None);
+        assert_eq!(try_expand_path("~"), dirs::home_dir());
+        assert_eq!(
+            try_expand_path(" /tmp/work/ "),
+            Some(PathBuf::from("/tmp/work/"))
+        );
     }
 
-    PathBuf::from(trimmed)
+    /// Directories that do not exist are normalized by their text alone.
+    #[cfg(not(windows))]
+    #[test]
+    fn normalize_directory_cases() {
+        let home = home();
+        let missing = "openchamber-test-missing";
+
+        let cases = [
+            (
+                "/openchamber-test/work",
+                "/openchamber-test/work".to_string(),
+            ),
+            (
+                "/openchamber-test/work/",
+                "/openchamber-test/work".to_string(),
+            ),
+            (
+                "/openchamber-test/work//",
+                "/openchamber-test/work".to_string(),
+            ),
+            (
+                "/openchamber-test/./work",
+                "/openchamber-test/work".to_string(),
+            ),
+            (
+                "/openchamber-test/other/../work",
+                "/openchamber-test/work".to_string(),
+            ),
+            (
+                " /openchamber-test/work/ ",
+                "/openchamber-test/work".to_string(),
+            ),
+            (
+                "~/openchamber-test-missing/acme",
+                format!("{home}/{missing}/acme"),
+            ),
+            (
+                "~/openchamber-test-missing/acme/",
+                format!("{home}/{missing}/acme"),
+            ),
+            ("~\\openchamber-test-missing", format!("{home}/{missing}")),
+            // Relative paths are taken from the home directory.
+            (
+                "openchamber-test-missing/acme",
+                format!("{home}/{missing}/acme"),
+            ),
+            (
+                "./openchamber-test-missing/acme/",
+                format!("{home}/{missing}/acme"),
+            ),
+            (
+                "openchamber-test-missing/other/../acme",
+                format!("{home}/{missing}/acme"),
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                normalize_directory(Path::new(input)),
+                PathBuf::from(&expected),
+                "normalize_directory({input:?})"
+            );
+        }
+    }
+
+    #[test]
+    fn normalize_directory_resolves_existing_directories() {
+        let dir =
+            std::env::temp_dir().join(format!("openchamber-normalize-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("acme")).unwrap();
+        let canonical = std::fs::canonicalize(dir.join("acme")).unwrap();
+
+        let inputs = [
+            dir.join("acme"),
+            dir.join("acme").join(""),
+            dir.join(".").join("acme"),
+            dir.join("acme").join("..").join("acme"),
+        ];
+        for input in inputs {
+            assert_eq!(normalize_directory(&input), canonical, "{input:?}");
+        }
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn paths_equivalent_cases() {
+        let cases = [
+            ("/openchamber-test/work", "/openchamber-test/work", true),
+            ("/openchamber-test/work", "/openchamber-test/work/", true),
+            ("/openchamber-test/work//", "/openchamber-test/work", true),
+            ("/openchamber-test/./work", "/openchamber-test/work", true),
+            (
+                "/openchamber-test/other/../work",
+                "/openchamber-test/work",
+                true,
+            ),
+            (
+                "/openchamber-test/work",
+                "/openchamber-test/workspace",
+                false,
+            ),
+            (
+                "/openchamber-test/work",
+                "/openchamber-test/work/src",
+                false,
+            ),
+            ("/", "/", true),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(
+                paths_equivalent(Path::new(a), Path::new(b)),
+                expected,
+                "paths_equivalent({a:?}, {b:?})"
+            );
+        }
+    }
+
+    /// `/var` is a symlink to `/private/var` on macOS, and temp directories live under it.
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn private_var_matches_var() {
+        let cases = [
+            ("/private/var", "/var"),
+            ("/private/var/", "/var"),
+            ("/var/", "/private/var"),
+            ("/private/var/tmp", "/var/tmp/"),
+        ];
+        for (a, b) in cases {
+            assert!(
+                paths_equivalent(Path::new(a), Path::new(b)),
+                "paths_equivalent({a:?}, {b:?})"
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_match_their_target() {
+        let dir = std::env::temp_dir().join(format!("openchamber-paths-{}", std::process::id()));
+        let target = dir.join("target");
+        let link = dir.join("link");
+        std::fs::create_dir_all(&target).unwrap();
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        assert!(paths_equivalent(&link, &target));
+        assert!(paths_equivalent(&link.join(""), &target));
+        assert!(!paths_equivalent(&link, &dir));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_paths_ignore_case_and_trailing_separators() {
+        let cases = [
+            (
+                "C:\\OpenChamber-Test\\Work",
+                "c:\\openchamber-test\\work\\",
+                true,
+            ),
+            (
+                "C:\\openchamber-test\\work",
+                "C:/openchamber-test/work",
+                true,
+            ),
+            (
+                "C:\\openchamber-test\\work",
+                "D:\\openchamber-test\\work",
+                false,
+            ),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(
+                paths_equivalent(Path::new(a), Path::new(b)),
+                expected,
+                "paths_equivalent({a:?}, {b:?})"
+            );
+        }
+    }
 }
@@ -0,0 +1,205 @@
+use log::{info, warn};
+use tokio::sync::{mpsc, watch};
+
+use crate::DesktopRuntime;
+
+/// Whether the machine is running or about to be suspended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerState {
+    Awake,
+    Asleep,
+}
+
+/// Resolves with the new state on the next sleep or wake. Never resolves when no power
+/// events are available on this platform.
+pub async fn power_state_changed(power: &mut watch::Receiver<PowerState>) -> PowerState {
+    if power.changed().await.is_err() {
+        return std::future::pending().await;
+    }
+    *power.borrow_and_update()
+}
+
+/// Follow the platform's sleep and wake notifications and publish them on the runtime.
+/// Must be called on the main thread: on macOS the observers attach to its run loop.
+pub fn spawn_power_monitor(runtime: DesktopRuntime) -> tauri::async_runtime::JoinHandle<()> {
+    let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+    if let Err(err) = platform::listen(events_tx) {
+        warn!("[desktop:power] Sleep/wake notifications unavailable: {err}");
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let mut shutdown_rx = runtime.subscribe_shutdown();
+        loop {
+            let state = tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                state = events_rx.recv() => match state {
+                    Some(state) => state,
+                    None => break,
+                },
+            };
+            match state {
+                PowerState::Asleep => info!("[desktop:power] System is going to sleep"),
+                PowerState::Awake => info!("[desktop:power] System woke up"),
+            }
+            runtime.set_power_state(state);
+        }
+    })
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ptr::NonNull;
+
+    use anyhow::{anyhow, Result};
+    use block2::RcBlock;
+    use objc2::msg_send;
+    use objc2::runtime::{AnyClass, AnyObject};
+    use objc2_foundation::NSString;
+    use tokio::sync::mpsc;
+
+    use super::PowerState;
+
+    pub(super) fn listen(events: mpsc::UnboundedSender<PowerState>) -> Result<()> {
+        let workspace_class =
+            AnyClass::get(c"NSWorkspace").ok_or_else(|| anyhow!("NSWorkspace is missing"))?;
+        unsafe {
+            let workspace: *mut AnyObject = msg_send![workspace_class, sharedWorkspace];
+            let center: *mut AnyObject = msg_send![workspace, notificationCenter];
+            if center.is_null() {
+                return Err(anyhow!("NSWorkspace has no notification center"));
+            }
+
+            for (name, state) in [
+                ("NSWorkspaceWillSleepNotification", PowerState::Asleep),
+                ("NSWorkspaceDidWakeNotification", PowerState::Awake),
+            ] {
+                let events = events.clone();
+                let block = RcBlock::new(move |_notification: NonNull<AnyObject>| {
+                    let _ = events.send(state);
+                });
+                let name = NSString::from_str(name);
+                // The center keeps the observer, and a copy of the block, for the life
+                // of the app.
+                let _: *mut AnyObject = msg_send![
+                    center,
+                    addObserverForName: &*name,
+                    object: std::ptr::null::<AnyObject>(),
+                    queue: std::ptr::null::<AnyObject>(),
+                    usingBlock: &*block
+                ];
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::ffi::c_void;
+
+    use anyhow::{anyhow, Result};
+    use tokio::sync::mpsc;
+    use windows_sys::Win32::System::Power::{
+        RegisterSuspendResumeNotification, DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        DEVICE_NOTIFY_CALLBACK, PBT_APMRESUMEAUTOMATIC, PBT_APMSUSPEND,
+    };
+
+    use super::PowerState;
+
+    pub(super) fn listen(events: mpsc::UnboundedSender<PowerState>) -> Result<()> {
+        // Both live for the life of the process, as does the registration.
+        let context = Box::into_raw(Box::new(events));
+        let parameters = Box::leak(Box::new(DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS {
+            Callback: Some(on_power_broadcast),
+            Context: context.cast(),
+        }));
+        let registration = unsafe {
+            RegisterSuspendResumeNotification(
+                (parameters as *mut DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS).cast(),
+                DEVICE_NOTIFY_CALLBACK,
+            )
+        };
+        if registration.is_null() {
+            return Err(anyhow!("RegisterSuspendResumeNotification failed"));
+        }
+        Ok(())
+    }
+
+    /// Receives `WM_POWERBROADCAST` codes. `PBT_APMRESUMEAUTOMATIC` arrives on every
+    /// resume, whether or not the user is present.
+    unsafe extern "system" fn on_power_broadcast(
+        context: *const c_void,
+        kind: u32,
+        _setting: *const c_void,
+    ) -> u32 {
+        let events = &*(context as *const mpsc::UnboundedSender<PowerState>);
+        let state = match kind {
+            PBT_APMSUSPEND => Some(PowerState::Asleep),
+            PBT_APMRESUMEAUTOMATIC => Some(PowerState::Awake),
+            _ => None,
+        };
+        if let Some(state) = state {
+            let _ = events.send(state);
+        }
+        0
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use anyhow::Result;
+    use futures_util::StreamExt;
+    use log::warn;
+    use tokio::sync::mpsc;
+
+    use super::PowerState;
+
+    pub(super) fn listen(events: mpsc::UnboundedSender<PowerState>) -> Result<()> {
+        tauri::async_runtime::spawn(async move {
+            if let Err(err) = follow_logind(&events).await {
+                warn!("[desktop:power] Lost logind sleep signals: {err}");
+            }
+        });
+        Ok(())
+    }
+
+    /// logind sends `PrepareForSleep(true)` before suspending and `PrepareForSleep(false)`
+    /// after resuming.
+    async fn follow_logind(events: &mpsc::UnboundedSender<PowerState>) -> Result<()> {
+        let connection = zbus::Connection::system().await?;
+        let manager = zbus::Proxy::new(
+            &connection,
+            "org.freedesktop.login1",
+            "/org/freedesktop/login1",
+            "org.freedesktop.login1.Manager",
+        )
+        .await?;
+        let mut signals = manager.receive_signal("PrepareForSleep").await?;
+        while let Some(message) = signals.next().await {
+            let going_to_sleep: bool = message.body().deserialize()?;
+            let state = if going_to_sleep {
+                PowerState::Asleep
+            } else {
+                PowerState::Awake
+            };
+            if events.send(state).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+mod platform {
+    use anyhow::{anyhow, Result};
+    use tokio::sync::mpsc;
+
+    use super::PowerState;
+
+    pub(super) fn listen(_events: mpsc::UnboundedSender<PowerState>) -> Result<()> {
+        Err(anyhow!("not supported on this platform"))
+    }
+}
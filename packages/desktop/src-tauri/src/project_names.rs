@@ -0,0 +1,204 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+use tauri::{AppHandle, Manager};
+
+use crate::desktop_settings::{DesktopSettings, ProjectEntry};
+use crate::path_utils::{comparable_path, normalize_directory};
+use crate::settings_watcher::next_settings_change;
+use crate::DesktopRuntime;
+
+#[derive(Default)]
+struct Catalog {
+    /// Display names keyed by the comparable form of each project's directory.
+    names: HashMap<PathBuf, String>,
+    projects: usize,
+}
+
+/// Display names of the configured projects, so notifications, the tray and webhooks
+/// can say which project a session belongs to. Rebuilt from `projects` whenever
+/// settings change rather than on every lookup.
+#[derive(Default)]
+pub struct ProjectNames {
+    catalog: RwLock<Catalog>,
+}
+
+impl ProjectNames {
+    fn replace(&self, settings: &DesktopSettings) {
+        let names = settings
+            .projects
+            .iter()
+            .filter(|project| !project.path.trim().is_empty())
+            .filter_map(|project| {
+                let key = comparable_path(&normalize_directory(Path::new(&project.path)));
+                project_name(project).map(|name| (key, name))
+            })
+            .collect();
+        if let Ok(mut catalog) = self.catalog.write() {
+            *catalog = Catalog {
+                names,
+                projects: settings.projects.len(),
+            };
+        }
+    }
+
+    /// The project's label, or its folder name when it has none or `directory` is not a
+    /// configured project.
+    pub fn display_name(&self, directory: &str) -> Option<String> {
+        let directory = directory.trim();
+        if directory.is_empty() {
+            return None;
+        }
+        let directory = normalize_directory(Path::new(directory));
+        let key = comparable_path(&directory);
+        self.catalog
+            .read()
+            .ok()
+            .and_then(|catalog| catalog.names.get(&key).cloned())
+            // The key is lowercased on Windows; the folder name keeps its case.
+            .or_else(|| folder_name(&directory))
+    }
+
+    /// The name to put in front of a notification title, or `None` when at most one
+    /// project is configured and the name would tell nothing apart.
+    pub fn title_prefix(&self, directory: &str) -> Option<String> {
+        let projects = self.catalog.read().map(|catalog| catalog.projects).ok()?;
+        if projects <= 1 {
+            return None;
+        }
+        self.display_name(directory)
+    }
+}
+
+/// A project's `label`, or the last component of its path.
+pub(crate) fn project_name(project: &ProjectEntry) -> Option<String> {
+    if let Some(label) = project
+        .label
+        .as_deref()
+        .map(str::trim)
+        .filter(|label| !label.is_empty())
+    {
+        return Some(label.to_string());
+    }
+    if project.path.is_empty() {
+        return None;
+    }
+    folder_name(Path::new(&project.path))
+}
+
+fn folder_name(path: &Path) -> Option<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+/// Keep `ProjectNames` in step with the `projects` setting.
+pub fn spawn_project_names(
+    app: AppHandle,
+    runtime: DesktopRuntime,
+) -> tauri::async_runtime::JoinHandle<()> {
+    tauri::async_runtime::spawn(async move {
+        let mut shutdown_rx = runtime.subscribe_shutdown();
+        let mut settings_changes = runtime.subscribe_settings_changes();
+        let names = app.state::<ProjectNames>();
+        if let Ok(settings) = runtime.settings().load_typed().await {
+            names.replace(&settings);
+        }
+
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                change = next_settings_change(&mut settings_changes) => {
+                    if change.projects_changed() {
+                        names.replace(&change.current);
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(path: &str, label: Option<&str>) -> ProjectEntry {
+        ProjectEntry {
+            id: path.to_string(),
+            path: path.to_string(),
+            label: label.map(str::to_string),
+            ..ProjectEntry::default()
+        }
+    }
+
+    fn names(projects: Vec<ProjectEntry>) -> ProjectNames {
+        let names = ProjectNames::default();
+        names.replace(&DesktopSettings {
+            projects,
+            ..DesktopSettings::default()
+        });
+        names
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn display_name_cases() {
+        let home = dirs::home_dir().expect("home directory");
+        let home = home.to_string_lossy();
+        let names = names(vec![
+            project("~/openchamber-test/acme", Some("Acme")),
+            project("/openchamber-test/widgets/", None),
+            project("  ", Some("Blank")),
+        ]);
+
+        let cases = [
+            // Configured projects, however the directory is spelled.
+            ("~/openchamber-test/acme".to_string(), Some("Acme")),
+            (format!("{home}/openchamber-test/acme/"), Some("Acme")),
+            ("openchamber-test/acme".to_string(), Some("Acme")),
+            ("/openchamber-test/widgets".to_string(), Some("widgets")),
+            (
+                "/openchamber-test/other/../widgets/".to_string(),
+                Some("widgets"),
+            ),
+            // Directories no project points at are named after their folder.
+            (
+                "/openchamber-test/Scratch-Repo/".to_string(),
+                Some("Scratch-Repo"),
+            ),
+            ("~/openchamber-test/notes".to_string(), Some("notes")),
+            (format!("{home}/openchamber-test/notes"), Some("notes")),
+            ("/".to_string(), None),
+            ("".to_string(), None),
+            ("   ".to_string(), None),
+        ];
+        for (directory, expected) in cases {
+            assert_eq!(
+                names.display_name(&directory).as_deref(),
+                expected,
+                "display_name({directory:?})"
+            );
+        }
+    }
+
+    #[test]
+    fn title_prefix_needs_more_than_one_project() {
+        let one = names(vec![project("/openchamber-test/acme", Some("Acme"))]);
+        assert_eq!(one.title_prefix("/openchamber-test/acme"), None);
+
+        let two = names(vec![
+            project("/openchamber-test/acme", Some("Acme")),
+            project("/openchamber-test/widgets", None),
+        ]);
+        assert_eq!(
+            two.title_prefix("/openchamber-test/acme").as_deref(),
+            Some("Acme")
+        );
+        assert_eq!(
+            two.title_prefix("/openchamber-test/notes").as_deref(),
+            Some("notes")
+        );
+    }
+}
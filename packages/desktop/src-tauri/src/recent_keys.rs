@@ -0,0 +1,67 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+/// Default bound for dedup sets fed by SSE events. Large enough that anything the server
+/// replays after a reconnect is still remembered.
+pub const DEFAULT_CAPACITY: usize = 5_000;
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A set of recently seen keys that forgets the oldest entries once it holds more than
+/// `capacity` keys or they are older than `max_age`.
+pub struct RecentKeys {
+    capacity: usize,
+    max_age: Duration,
+    inserted_at: HashMap<String, Instant>,
+    order: VecDeque<(String, Instant)>,
+}
+
+impl Default for RecentKeys {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY, DEFAULT_MAX_AGE)
+    }
+}
+
+impl RecentKeys {
+    pub fn new(capacity: usize, max_age: Duration) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            max_age,
+            inserted_at: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.inserted_at
+            .get(key)
+            .is_some_and(|at| at.elapsed() < self.max_age)
+    }
+
+    /// Remember `key`. Returns false if it was already present; its age is not refreshed.
+    pub fn insert(&mut self, key: impl Into<String>) -> bool {
+        let now = Instant::now();
+        self.evict(now);
+
+        let key = key.into();
+        if self.inserted_at.contains_key(&key) {
+            return false;
+        }
+        self.inserted_at.insert(key.clone(), now);
+        self.order.push_back((key, now));
+        self.evict(now);
+        true
+    }
+
+    fn evict(&mut self, now: Instant) {
+        while let Some((key, at)) = self.order.front() {
+            let expired = now.duration_since(*at) >= self.max_age;
+            if !expired && self.order.len() <= self.capacity {
+                break;
+            }
+            self.inserted_at.remove(key);
+            self.order.pop_front();
+        }
+    }
+}
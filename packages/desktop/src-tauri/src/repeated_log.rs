@@ -0,0 +1,114 @@
+use std::time::{Duration, Instant};
+
+/// How long identical lines are collapsed before the count is written out.
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Logs at most once per interval, for lines that differ every time so `RepeatedLog`
+/// cannot collapse them. Lines dropped meanwhile are counted in the next one logged.
+pub struct ThrottledLog {
+    interval: Duration,
+    last: Option<Instant>,
+    dropped: u64,
+}
+
+impl Default for ThrottledLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW)
+    }
+}
+
+impl ThrottledLog {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: None,
+            dropped: 0,
+        }
+    }
+
+    /// Pass `message` to `log` unless a line was logged within the interval.
+    pub fn record(&mut self, message: String, mut log: impl FnMut(&str)) {
+        if self.last.is_some_and(|last| last.elapsed() < self.interval) {
+            self.dropped += 1;
+            return;
+        }
+        match self.dropped {
+            0 => log(&message),
+            dropped => log(&format!(
+                "{message} ({dropped} similar lines skipped in the last {}s)",
+                self.interval.as_secs()
+            )),
+        }
+        self.last = Some(Instant::now());
+        self.dropped = 0;
+    }
+}
+
+/// Collapses a line that one call site logs over and over, such as a reconnect loop's
+/// error while the server is down. The first occurrence is always logged; repeats within
+/// the window are counted and reported when the message changes, the window runs out, or
+/// the caller settles. Logging stays with the caller so records keep its target.
+pub struct RepeatedLog {
+    window: Duration,
+    last: Option<Repeat>,
+}
+
+struct Repeat {
+    message: String,
+    since: Instant,
+    count: u64,
+}
+
+impl Default for RepeatedLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW)
+    }
+}
+
+impl RepeatedLog {
+    pub fn new(window: Duration) -> Self {
+        Self { window, last: None }
+    }
+
+    /// Pass `message` to `log` unless it repeats the previous one within the window.
+    pub fn record(&mut self, message: String, mut log: impl FnMut(&str)) {
+        if let Some(last) = self.last.as_mut() {
+            if last.message == message {
+                if last.since.elapsed() < self.window {
+                    last.count += 1;
+                    return;
+                }
+                let line = match last.count {
+                    0 => message.clone(),
+                    count => format!(
+                        "{message} (repeated {count} more times in the last {}s)",
+                        self.window.as_secs()
+                    ),
+                };
+                log(&line);
+                last.since = Instant::now();
+                last.count = 0;
+                return;
+            }
+        }
+        self.settle(&mut log);
+        log(&message);
+        self.last = Some(Repeat {
+            message,
+            since: Instant::now(),
+            count: 0,
+        });
+    }
+
+    /// The condition behind the last message is over: report repeats still uncounted.
+    pub fn settle(&mut self, mut log: impl FnMut(&str)) {
+        if let Some(last) = self.last.take() {
+            if last.count > 0 {
+                log(&format!(
+                    "{} (repeated {} more times)",
+                    last.message, last.count
+                ));
+            }
+        }
+    }
+}
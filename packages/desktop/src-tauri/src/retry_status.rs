@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use serde_json::Value;
+
+/// What a `session.status` of type `retry` says about the next attempt, parsed once and
+/// shared by the activity tracker and notifications.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RetryStatus {
+    /// Which attempt comes next, counting from 1.
+    pub attempt: Option<u64>,
+    /// Provider id as OpenCode reports it, e.g. `anthropic`.
+    pub provider: Option<String>,
+    /// The provider's error message, e.g. "Rate limit exceeded".
+    pub message: Option<String>,
+    /// When the next attempt starts, in milliseconds since the Unix epoch.
+    pub next_at: Option<i64>,
+}
+
+impl RetryStatus {
+    /// Parse the properties of a `session.status` event. `None` unless the session is
+    /// waiting to retry.
+    pub(crate) fn from_status_event(properties: &Value) -> Option<Self> {
+        let status = properties.get("status")?;
+        if status.get("type").and_then(Value::as_str) != Some("retry") {
+            return None;
+        }
+
+        let text = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| status.get(*key).and_then(Value::as_str))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        // `next` is a timestamp; some servers send a relative `delay` in milliseconds instead.
+        let next_at = status.get("next").and_then(Value::as_i64).or_else(|| {
+            status
+                .get("delay")
+                .and_then(Value::as_i64)
+                .map(|delay| Utc::now().timestamp_millis() + delay.max(0))
+        });
+
+        Some(Self {
+            attempt: status.get("attempt").and_then(Value::as_u64),
+            provider: text(&["providerID", "provider"]),
+            message: text(&["message"]),
+            next_at,
+        })
+    }
+
+    /// Time left until the next attempt, or `None` when the server did not say.
+    pub(crate) fn remaining(&self) -> Option<Duration> {
+        let next_at = self.next_at?;
+        let remaining = next_at.saturating_sub(Utc::now().timestamp_millis()).max(0);
+        Some(Duration::from_millis(remaining as u64))
+    }
+}
@@ -0,0 +1,406 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::{debug, info, warn};
+use serde_json::Value;
+use tokio::{fs, sync::Mutex};
+
+use crate::SettingsStore;
+
+/// Keychain service name; matches the bundle identifier.
+const KEYCHAIN_SERVICE: &str = "ai.opencode.openchamber";
+/// Looked up once at startup to find out whether the keychain answers at all.
+const PROBE_ENTRY: &str = "openchamber-keychain-probe";
+const NONCE_LEN: usize = 12;
+
+/// Secret names are the dotted settings path the value used to live at.
+pub(crate) const WEBHOOK_SECRET: &str = "notifications.webhookSecret";
+pub(crate) const TELEMETRY_SECRET: &str = "telemetry.secret";
+/// Sent as a bearer token to the external OpenCode server.
+pub(crate) const OPENCODE_AUTH_TOKEN: &str = "opencode.external.authToken";
+
+/// Settings the backend reads as credentials, moved into the secret store. Keys the
+/// frontend owns stay where they are, whatever they are called.
+const MIGRATED_SECRETS: &[&str] = &[WEBHOOK_SECRET, TELEMETRY_SECRET, OPENCODE_AUTH_TOKEN];
+
+/// Credentials kept out of `settings.json`: in the platform keychain (Keychain on macOS,
+/// Credential Manager on Windows, Secret Service on Linux), or, where no keychain is
+/// reachable, in an encrypted file next to the settings.
+pub(crate) struct SecretStore {
+    backend: Backend,
+}
+
+enum Backend {
+    Keychain,
+    File(EncryptedFile),
+}
+
+impl SecretStore {
+    pub(crate) fn new() -> Result<Self> {
+        let backend = match probe_keychain() {
+            Ok(()) => Backend::Keychain,
+            Err(err) => {
+                warn!(
+                    "[desktop] OS keychain unavailable ({err}); secrets are stored in an encrypted file instead"
+                );
+                Backend::File(EncryptedFile::new()?)
+            }
+        };
+        Ok(Self { backend })
+    }
+
+    pub(crate) async fn get(&self, name: &str) -> Result<Option<String>> {
+        match &self.backend {
+            Backend::Keychain => {
+                with_keychain_entry(name, |entry| match entry.get_password() {
+                    Ok(value) => Ok(Some(value)),
+                    Err(keyring::Error::NoEntry) => Ok(None),
+                    Err(err) => Err(err),
+                })
+                .await
+            }
+            Backend::File(file) => Ok(file.read_all().await?.remove(name)),
+        }
+    }
+
+    pub(crate) async fn set(&self, name: &str, value: &str) -> Result<()> {
+        match &self.backend {
+            Backend::Keychain => {
+                let value = value.to_string();
+                with_keychain_entry(name, move |entry| entry.set_password(&value)).await
+            }
+            Backend::File(file) => {
+                let _lock = file.guard.lock().await;
+                let mut secrets = file.read_all().await?;
+                secrets.insert(name.to_string(), value.to_string());
+                file.write_all(&secrets).await
+            }
+        }
+    }
+
+    pub(crate) async fn delete(&self, name: &str) -> Result<()> {
+        match &self.backend {
+            Backend::Keychain => {
+                with_keychain_entry(name, |entry| match entry.delete_credential() {
+                    Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+                    Err(err) => Err(err),
+                })
+                .await
+            }
+            Backend::File(file) => {
+                let _lock = file.guard.lock().await;
+                let mut secrets = file.read_all().await?;
+                if secrets.remove(name).is_some() {
+                    file.write_all(&secrets).await?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn probe_keychain() -> Result<()> {
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, PROBE_ENTRY)?;
+    match entry.get_password() {
+        Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Keychain calls block (and may show a system prompt), so they run off the runtime.
+async fn with_keychain_entry<T, F>(name: &str, op: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(keyring::Entry) -> keyring::Result<T> + Send + 'static,
+{
+    let name = name.to_string();
+    let result = tokio::task::spawn_blocking(move || {
+        let entry = keyring::Entry::new(KEYCHAIN_SERVICE, &name)?;
+        op(entry)
+    })
+    .await?;
+    Ok(result?)
+}
+
+/// AES-256-GCM encrypted secrets, one JSON map entry per secret, with the key in a file
+/// only the user can read. This keeps secrets out of plain sight, not away from someone
+/// who can read the user's home directory.
+struct EncryptedFile {
+    path: PathBuf,
+    key_path: PathBuf,
+    /// Held across a read-modify-write of the secrets file.
+    guard: Mutex<()>,
+    /// Held while the key is read or created, so concurrent first uses agree on one key.
+    /// Separate from `guard`, which is already held when writers get here.
+    key_guard: Mutex<()>,
+}
+
+impl EncryptedFile {
+    fn new() -> Result<Self> {
+        let home = dirs::home_dir().ok_or_else(|| anyhow!("No home directory"))?;
+        let dir = home.join(".config").join("openchamber");
+        std::fs::create_dir_all(&dir).ok();
+        Ok(Self {
+            path: dir.join("secrets.enc"),
+            key_path: dir.join("secrets.key"),
+            guard: Mutex::new(()),
+            key_guard: Mutex::new(()),
+        })
+    }
+
+    async fn cipher(&self) -> Result<Aes256Gcm> {
+        let _lock = self.key_guard.lock().await;
+        match fs::read(&self.key_path).await {
+            Ok(bytes) if bytes.len() == 32 => {
+                return Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&bytes)));
+            }
+            Ok(_) => return Err(anyhow!("Secrets key file is corrupt")),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        let key = Aes256Gcm::generate_key(OsRng);
+        write_private(&self.key_path, key.as_slice()).await?;
+        Ok(Aes256Gcm::new(&key))
+    }
+
+    async fn read_all(&self) -> Result<BTreeMap<String, String>> {
+        let bytes = match fs::read(&self.path).await {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let sealed: BTreeMap<String, String> = serde_json::from_slice(&bytes)?;
+        let cipher = self.cipher().await?;
+
+        let mut secrets = BTreeMap::new();
+        for (name, value) in sealed {
+            match open(&cipher, &value) {
+                Ok(value) => {
+                    secrets.insert(name, value);
+                }
+                Err(err) => warn!("[desktop] Failed to decrypt secret {name}: {err}"),
+            }
+        }
+        Ok(secrets)
+    }
+
+    async fn write_all(&self, secrets: &BTreeMap<String, String>) -> Result<()> {
+        let cipher = self.cipher().await?;
+        let sealed = secrets
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), seal(&cipher, value)?)))
+            .collect::<Result<BTreeMap<_, _>>>()?;
+        write_private(&self.path, &serde_json::to_vec_pretty(&sealed)?).await
+    }
+}
+
+fn seal(cipher: &Aes256Gcm, value: &str) -> Result<String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, value.as_bytes())
+        .map_err(|_| anyhow!("encryption failed"))?;
+    Ok(STANDARD.encode([nonce.as_slice(), &ciphertext].concat()))
+}
+
+fn open(cipher: &Aes256Gcm, sealed: &str) -> Result<String> {
+    let bytes = STANDARD.decode(sealed)?;
+    if bytes.len() < NONCE_LEN {
+        return Err(anyhow!("sealed value is too short"));
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("wrong key or corrupt value"))?;
+    Ok(String::from_utf8(plaintext)?)
+}
+
+async fn write_private(path: &std::path::Path, bytes: &[u8]) -> Result<()> {
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, bytes).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&temp_path, std::fs::Permissions::from_mode(0o600)).await?;
+    }
+    fs::rename(&temp_path, path).await?;
+    Ok(())
+}
+
+/// A credential found in the settings file.
+struct FoundSecret {
+    /// JSON pointer of the object holding the key.
+    parent: String,
+    key: String,
+    name: &'static str,
+    value: String,
+}
+
+/// Move the `MIGRATED_SECRETS` out of `settings.json` and into the secret store. Runs
+/// at startup and after every settings save, so a secret entered in the UI never stays on
+/// disk in the clear. A key is only removed once its value is stored. Returns the
+/// settings as written when anything moved.
+pub(crate) async fn migrate_settings_secrets(
+    settings: &SettingsStore,
+    secrets: &SecretStore,
+) -> Option<Value> {
+    let current = match settings.load().await {
+        Ok(current) => current,
+        Err(err) => {
+            warn!("[desktop] Failed to read settings for secret migration: {err}");
+            return None;
+        }
+    };
+
+    let mut moved = Vec::new();
+    for secret in collect_secrets(&current) {
+        match secrets.set(secret.name, &secret.value).await {
+            Ok(()) => moved.push(secret),
+            Err(err) => warn!(
+                "[desktop] Failed to move {} into the secret store: {err}",
+                secret.name
+            ),
+        }
+    }
+    if moved.is_empty() {
+        return None;
+    }
+
+    let updated = settings
+        .update(|mut current| {
+            for secret in &moved {
+                let Some(parent) = current
+                    .pointer_mut(&secret.parent)
+                    .and_then(Value::as_object_mut)
+                else {
+                    continue;
+                };
+                // Leave a value that changed since it was read for the next pass.
+                if parent.get(&secret.key).and_then(Value::as_str) == Some(secret.value.as_str()) {
+                    parent.remove(&secret.key);
+                }
+            }
+            current
+        })
+        .await;
+    match updated {
+        Ok(updated) => {
+            info!(
+                "[desktop] Moved {} secret(s) from settings into the secret store",
+                moved.len()
+            );
+            Some(updated)
+        }
+        Err(err) => {
+            warn!("[desktop] Failed to remove migrated secrets from settings: {err}");
+            None
+        }
+    }
+}
+
+/// Remove the `MIGRATED_SECRETS` from `settings`, for copies that leave the machine such
+/// as exports.
+pub(crate) fn strip_secrets(settings: &mut Value) {
+    for secret in collect_secrets(settings) {
+        if let Some(parent) = settings
+            .pointer_mut(&secret.parent)
+            .and_then(Value::as_object_mut)
+        {
+            parent.remove(&secret.key);
+        }
+    }
+}
+
+fn collect_secrets(settings: &Value) -> Vec<FoundSecret> {
+    MIGRATED_SECRETS
+        .iter()
+        .filter_map(|&name| {
+            let (parent, key) = name.rsplit_once('.').unwrap_or(("", name));
+            let parent: String = parent
+                .split('.')
+                .filter(|segment| !segment.is_empty())
+                .map(|segment| format!("/{segment}"))
+                .collect();
+            let value = settings
+                .pointer(&parent)?
+                .get(key)?
+                .as_str()
+                .filter(|value| !value.is_empty())?;
+            debug!("[desktop] Found secret {name} in settings");
+            Some(FoundSecret {
+                parent,
+                key: key.to_string(),
+                name,
+                value: value.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn only_backend_secrets_are_stripped() {
+        let mut settings = json!({
+            "notifications": { "webhookUrl": "https://example.com", "webhookSecret": "hook" },
+            "telemetry": { "enabled": true, "secret": "telemetry" },
+            "opencode": { "external": { "port": 4096, "authToken": "remote" } },
+            "providers": { "anthropic": { "apiKey": "sk-frontend" } },
+            "githubToken": "frontend",
+        });
+
+        strip_secrets(&mut settings);
+
+        assert_eq!(
+            settings,
+            json!({
+                "notifications": { "webhookUrl": "https://example.com" },
+                "telemetry": { "enabled": true },
+                "opencode": { "external": { "port": 4096 } },
+                "providers": { "anthropic": { "apiKey": "sk-frontend" } },
+                "githubToken": "frontend",
+            })
+        );
+    }
+
+    #[test]
+    fn empty_and_non_string_values_are_left_alone() {
+        let settings = json!({
+            "notifications": { "webhookSecret": "" },
+            "telemetry": { "secret": 42 },
+            "opencode": "external",
+        });
+
+        assert!(collect_secrets(&settings).is_empty());
+    }
+
+    #[tokio::test]
+    async fn concurrent_first_use_creates_one_key() {
+        let dir = std::env::temp_dir().join(format!("openchamber-secrets-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = EncryptedFile {
+            path: dir.join("secrets.enc"),
+            key_path: dir.join("secrets.key"),
+            guard: Mutex::new(()),
+            key_guard: Mutex::new(()),
+        };
+
+        let ciphers = futures_util::future::join_all((0..16).map(|_| file.cipher())).await;
+        let ciphers: Vec<Aes256Gcm> = ciphers.into_iter().map(Result::unwrap).collect();
+        let sealed = seal(&ciphers[0], "value").unwrap();
+        for cipher in &ciphers {
+            assert_eq!(open(cipher, &sealed).unwrap(), "value");
+        }
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
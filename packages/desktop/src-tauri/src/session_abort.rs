@@ -0,0 +1,95 @@
+use std::{path::Path, time::Duration};
+
+use log::{info, warn};
+use serde::Serialize;
+use serde_json::json;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::events::SessionPayload;
+use crate::session_activity::BusySessions;
+use crate::simulated_events::SimulatedEvents;
+use crate::DesktopRuntime;
+
+const ABORT_TIMEOUT: Duration = Duration::from_secs(5);
+const SESSION_ABORTED_EVENT: &str = "openchamber:session-aborted";
+
+/// How an abort request for one session went.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AbortResult {
+    pub session_id: String,
+    pub aborted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AbortResult {
+    fn failed(session_id: &str, error: String) -> Self {
+        warn!("[desktop] Aborting session {session_id} failed: {error}");
+        Self {
+            session_id: session_id.to_string(),
+            aborted: false,
+            error: Some(error),
+        }
+    }
+}
+
+/// Ask the server running the session to stop it. On success the UI hears
+/// `openchamber:session-aborted` and the activity tracker settles the session right away
+/// instead of waiting for the server's idle event.
+pub async fn abort_session(app: &AppHandle, session_id: &str) -> AbortResult {
+    let runtime = app.state::<DesktopRuntime>();
+    let directory = app
+        .state::<BusySessions>()
+        .get(session_id)
+        .and_then(|session| session.directory);
+    let manager = runtime
+        .opencode_instances()
+        .manager_for_directory(directory.as_deref().map(Path::new));
+    let Some(base) = manager.status().base_url() else {
+        return AbortResult::failed(session_id, "OpenCode is not running".to_string());
+    };
+
+    let url = format!("{base}/session/{}/abort", urlencoding::encode(session_id));
+    let mut request = manager
+        .authorize(runtime.http().api().post(&url))
+        .timeout(ABORT_TIMEOUT);
+    if let Some(directory) = &directory {
+        request = request.query(&[("directory", directory)]);
+    }
+    let error = match request.send().await {
+        Ok(response) if response.status().is_success() => None,
+        Ok(response) => Some(format!("server returned {}", response.status())),
+        Err(err) => Some(runtime.http().describe_error(&url, &err)),
+    };
+    if let Some(error) = error {
+        return AbortResult::failed(session_id, error);
+    }
+
+    info!("[desktop] Aborted session {session_id}");
+    let _ = app.emit(SESSION_ABORTED_EVENT, SessionPayload { session_id });
+    // The idle the server is about to send, fed to the activity tracker only.
+    let idle = json!({
+        "directory": directory,
+        "payload": { "type": "session.idle", "properties": { "sessionID": session_id } },
+    });
+    let _ = app
+        .state::<SimulatedEvents>()
+        .send(&idle.to_string(), false);
+    AbortResult {
+        session_id: session_id.to_string(),
+        aborted: true,
+        error: None,
+    }
+}
+
+/// Abort every session the activity tracker sees running or waiting for input, all at
+/// once. Sessions in cooldown or error have nothing left to stop.
+pub async fn abort_all_busy_sessions(app: &AppHandle) -> Vec<AbortResult> {
+    let sessions = app.state::<BusySessions>().snapshot();
+    let aborts = sessions
+        .iter()
+        .filter(|session| session.abortable())
+        .map(|session| abort_session(app, &session.session_id));
+    futures_util::future::join_all(aborts).await
+}
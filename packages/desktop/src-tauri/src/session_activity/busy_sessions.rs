@@ -0,0 +1,65 @@
+use serde::Serialize;
+use tokio::sync::watch;
+
+/// A session the activity tracker currently sees doing something.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BusySession {
+    pub session_id: String,
+    /// `busy`, `retry`, `compacting`, `cooldown`, `waiting-for-input`, or `error`, as in
+    /// activity events.
+    pub phase: &'static str,
+    pub directory: Option<String>,
+}
+
+impl BusySession {
+    /// Only a session that is still running, or waiting to, can be aborted.
+    pub fn abortable(&self) -> bool {
+        matches!(
+            self.phase,
+            "busy" | "retry" | "compacting" | "waiting-for-input"
+        )
+    }
+}
+
+/// Every non-idle session, kept current by the activity tracker for the tray menu.
+/// Sorted by session id so unchanged state compares equal and wakes no one.
+pub struct BusySessions {
+    sessions: watch::Sender<Vec<BusySession>>,
+}
+
+impl Default for BusySessions {
+    fn default() -> Self {
+        Self {
+            sessions: watch::channel(Vec::new()).0,
+        }
+    }
+}
+
+impl BusySessions {
+    pub(super) fn replace(&self, sessions: Vec<BusySession>) {
+        self.sessions.send_if_modified(|current| {
+            if *current == sessions {
+                return false;
+            }
+            *current = sessions;
+            true
+        });
+    }
+
+    pub fn get(&self, session_id: &str) -> Option<BusySession> {
+        self.sessions
+            .borrow()
+            .iter()
+            .find(|session| session.session_id == session_id)
+            .cloned()
+    }
+
+    pub fn snapshot(&self) -> Vec<BusySession> {
+        self.sessions.borrow().clone()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Vec<BusySession>> {
+        self.sessions.subscribe()
+    }
+}
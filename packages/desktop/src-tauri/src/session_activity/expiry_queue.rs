@@ -0,0 +1,175 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    time::Instant,
+};
+
+use tokio::sync::mpsc;
+
+pub(super) enum ExpiryCommand {
+    /// Set (or replace) the session's deadline.
+    Schedule(String, Instant),
+    Cancel(String),
+    Clear,
+}
+
+/// Deadlines ordered by time, with at most one entry per session.
+#[derive(Default)]
+pub(super) struct ExpiryQueue {
+    ordered: BTreeSet<(Instant, String)>,
+    by_session: HashMap<String, Instant>,
+}
+
+impl ExpiryQueue {
+    pub(super) fn apply(&mut self, command: ExpiryCommand) {
+        match command {
+            ExpiryCommand::Schedule(session_id, at) => {
+                self.cancel(&session_id);
+                self.ordered.insert((at, session_id.clone()));
+                self.by_session.insert(session_id, at);
+            }
+            ExpiryCommand::Cancel(session_id) => self.cancel(&session_id),
+            ExpiryCommand::Clear => {
+                self.ordered.clear();
+                self.by_session.clear();
+            }
+        }
+    }
+
+    pub(super) fn next_deadline(&self) -> Option<Instant> {
+        self.ordered.first().map(|(at, _)| *at)
+    }
+
+    /// Remove every entry due at or before `now`, returning their sessions in deadline order.
+    pub(super) fn pop_due(&mut self, now: Instant) -> Vec<String> {
+        let mut due = Vec::new();
+        while let Some((at, _)) = self.ordered.first() {
+            if *at > now {
+                break;
+            }
+            if let Some((_, session_id)) = self.ordered.pop_first() {
+                self.by_session.remove(&session_id);
+                due.push(session_id);
+            }
+        }
+        due
+    }
+
+    fn cancel(&mut self, session_id: &str) {
+        if let Some(at) = self.by_session.remove(session_id) {
+            self.ordered.remove(&(at, session_id.to_string()));
+        }
+    }
+}
+
+/// Drive the queue: apply commands as they arrive and call `on_due` with the sessions whose
+/// deadlines passed. Ends once every sender has been dropped.
+pub(super) async fn run_expiry_queue<F, Fut>(
+    mut commands: mpsc::UnboundedReceiver<ExpiryCommand>,
+    mut on_due: F,
+) where
+    F: FnMut(Vec<String>) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let mut queue = ExpiryQueue::default();
+
+    loop {
+        let next = queue.next_deadline();
+        tokio::select! {
+            command = commands.recv() => {
+                match command {
+                    Some(command) => queue.apply(command),
+                    None => break,
+                }
+            }
+            _ = async {
+                match next {
+                    Some(at) => tokio::time::sleep_until(at.into()).await,
+                    None => std::future::pending::<()>().await,
+                }
+            } => {
+                // Read the time from tokio's clock so it agrees with `sleep_until`.
+                let due = queue.pop_due(tokio::time::Instant::now().into_std());
+                if !due.is_empty() {
+                    on_due(due).await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use super::*;
+
+    const SESSIONS: u64 = 5_000;
+
+    #[tokio::test(start_paused = true)]
+    async fn only_surviving_deadlines_fire_in_order() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let fired = Arc::new(Mutex::new(Vec::new()));
+        let recorder = fired.clone();
+        tokio::spawn(run_expiry_queue(rx, move |due| {
+            let fired = recorder.clone();
+            async move {
+                let now = tokio::time::Instant::now().into_std();
+                fired
+                    .lock()
+                    .unwrap()
+                    .extend(due.into_iter().map(|id| (now, id)));
+            }
+        }));
+
+        let start = tokio::time::Instant::now().into_std();
+        let mut expected = Vec::new();
+        for i in 0..SESSIONS {
+            let session_id = format!("ses_{i}");
+            // Spread deadlines over 1..=SESSIONS ms in an order unrelated to `i`.
+            let offset = (i * 7_919) % SESSIONS + 1;
+            let at = start + Duration::from_millis(offset);
+            tx.send(ExpiryCommand::Schedule(session_id.clone(), at))
+                .unwrap();
+
+            match i % 4 {
+                // Cancelled: must never fire.
+                0 | 1 => tx.send(ExpiryCommand::Cancel(session_id)).unwrap(),
+                // Rescheduled: only the later deadline counts.
+                2 => {
+                    let later = at + Duration::from_millis(SESSIONS * 2);
+                    tx.send(ExpiryCommand::Schedule(session_id.clone(), later))
+                        .unwrap();
+                    expected.push((later, session_id));
+                }
+                _ => expected.push((at, session_id)),
+            }
+        }
+        expected.sort();
+
+        tokio::time::sleep(Duration::from_millis(SESSIONS * 4)).await;
+
+        let fired = fired.lock().unwrap();
+        let fired_ids: Vec<&str> = fired.iter().map(|(_, id)| id.as_str()).collect();
+        let expected_ids: Vec<&str> = expected.iter().map(|(_, id)| id.as_str()).collect();
+        assert_eq!(fired_ids, expected_ids);
+        for ((fired_at, _), (deadline, _)) in fired.iter().zip(&expected) {
+            assert!(fired_at >= deadline);
+        }
+    }
+
+    #[test]
+    fn clear_drops_every_deadline() {
+        let mut queue = ExpiryQueue::default();
+        let now = Instant::now();
+        for i in 0..SESSIONS {
+            queue.apply(ExpiryCommand::Schedule(format!("ses_{i}"), now));
+        }
+        queue.apply(ExpiryCommand::Clear);
+
+        assert_eq!(queue.next_deadline(), None);
+        assert!(queue.pop_due(now + Duration::from_secs(1)).is_empty());
+    }
+}
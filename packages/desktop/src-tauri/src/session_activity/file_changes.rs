@@ -0,0 +1,85 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+use crate::path_utils::relative_path;
+
+const FILE_CHANGED_EVENT: &str = "openchamber:file-changed";
+/// Edits to one path within this window reach the webview as a single event.
+const COALESCE_WINDOW: Duration = Duration::from_millis(200);
+
+/// Events that say a file in a project changed.
+pub(super) fn is_file_event(event_type: &str) -> bool {
+    matches!(event_type, "file.edited" | "file.watcher.updated")
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FileChanged {
+    directory: Option<String>,
+    /// Relative to `directory` when the file lies inside it, absolute otherwise.
+    path: String,
+    session_id: Option<String>,
+}
+
+/// Forwards file edits to the webview so open diffs and the file tree can refresh without
+/// polling. The first edit to a path opens a window; later ones only update it.
+#[derive(Clone, Default)]
+pub(super) struct FileChanges {
+    pending: Arc<Mutex<HashMap<(Option<String>, String), FileChanged>>>,
+}
+
+impl FileChanges {
+    pub(super) fn forward(&self, app: &AppHandle, properties: &Value, directory: Option<&Path>) {
+        let Some(file) = properties.get("file").and_then(Value::as_str) else {
+            return;
+        };
+        let file = match directory {
+            Some(directory) if Path::new(file).is_relative() => directory.join(file),
+            _ => PathBuf::from(file),
+        };
+        let path = directory
+            .and_then(|directory| relative_path(&file, directory))
+            .unwrap_or(file);
+        let changed = FileChanged {
+            directory: directory.map(|directory| directory.to_string_lossy().to_string()),
+            path: path.to_string_lossy().to_string(),
+            session_id: properties
+                .get("sessionID")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+        };
+
+        let key = (changed.directory.clone(), changed.path.clone());
+        let Ok(mut pending) = self.pending.lock() else {
+            return;
+        };
+        if let Some(queued) = pending.get_mut(&key) {
+            if changed.session_id.is_some() {
+                queued.session_id = changed.session_id;
+            }
+            return;
+        }
+        pending.insert(key.clone(), changed);
+
+        let pending = self.pending.clone();
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(COALESCE_WINDOW).await;
+            let changed = pending
+                .lock()
+                .ok()
+                .and_then(|mut pending| pending.remove(&key));
+            if let Some(changed) = changed {
+                let _ = app.emit(FILE_CHANGED_EVENT, changed);
+            }
+        });
+    }
+}
@@ -0,0 +1,858 @@
+mod busy_sessions;
+mod expiry_queue;
+mod file_changes;
+mod state_machine;
+mod stream_health;
+mod window_projects;
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex as StdMutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use futures_util::TryStreamExt;
+use log::{debug, info, warn};
+use serde::Deserialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::{broadcast, mpsc, Mutex};
+use tokio_util::io::StreamReader;
+
+use crate::busy_time::{announce_daily_summaries, BusyTime};
+use crate::connectivity::OFFLINE_RETRY;
+use crate::desktop_settings::DesktopSettings;
+use crate::event_stream::{
+    active_project_moved, auth_rejection, connect_event_stream, is_keepalive, sleep_unless_woken,
+    stream_idle, unparsed_prefix, wait_for_reauth, WakeReceiver,
+};
+use crate::events::{AuthRequiredPayload, Batched, SessionActivityPayload, SseStatusPayload};
+use crate::opencode_instances::{follow_project_instances, ProjectInstance};
+use crate::opencode_manager::{server_moved, OpenCodeManager};
+use crate::power_events::{power_state_changed, PowerState};
+use crate::repeated_log::{RepeatedLog, ThrottledLog};
+use crate::session_info::SessionInfoCache;
+use crate::session_lifecycle::SessionLifecycleEvents;
+use crate::settings_watcher::next_settings_change;
+use crate::simulated_events::SimulatedEvents;
+use crate::sse_capture::SseCapture;
+use crate::task_registry::{ChildTask, TaskHandle};
+use crate::telemetry::ReconnectReason;
+use crate::DesktopRuntime;
+use expiry_queue::{run_expiry_queue, ExpiryCommand};
+use file_changes::{is_file_event, FileChanges};
+use state_machine::{ActivityStateMachine, EventEnvelope, PhaseTransition, DEFAULT_COOLDOWN};
+
+pub use busy_sessions::{BusySession, BusySessions};
+pub use stream_health::{EventStreamHealth, StreamHealth, StreamState};
+pub use window_projects::WindowProjects;
+
+const DEFAULT_ERROR_DECAY_SECS: u64 = 10;
+const EMIT_COALESCE_WINDOW: Duration = Duration::from_millis(50);
+const SESSION_ACTIVITY_EVENT: &str = "openchamber:session-activity";
+const EVENT_STREAM_STATUS_EVENT: &str = "openchamber:event-stream-status";
+const AUTH_REQUIRED_EVENT: &str = "openchamber:auth-required";
+/// Event types that always name their session. One without a session id means the
+/// server's event format moved on, and is counted as a schema-sentinel hit.
+const SESSION_SCOPED_EVENTS: &[&str] = &[
+    "session.status",
+    "session.idle",
+    "session.compacted",
+    "message.updated",
+    "message.part.updated",
+    "question.asked",
+    "permission.asked",
+    "permission.updated",
+];
+
+#[derive(Deserialize)]
+struct MultiplexedEventEnvelope {
+    #[serde(default)]
+    directory: Option<String>,
+    payload: EventEnvelope,
+}
+
+/// Tauri-side adapter around the pure state machine: owns the lock, the expiry queue,
+/// and the webview emission.
+#[derive(Clone)]
+struct ActivityState {
+    machine: Arc<Mutex<ActivityStateMachine>>,
+    /// Feeds the single background task that returns cooldown and error phases to idle.
+    expiry_tx: mpsc::UnboundedSender<ExpiryCommand>,
+    emit_buffer: Arc<Mutex<EmitBuffer>>,
+    /// Project directory of each session seen on a stream, added to emitted payloads so
+    /// the UI can tell instances apart.
+    directories: Arc<StdMutex<HashMap<String, String>>>,
+    /// Latest settings, kept current by the settings watcher, for per-project cooldowns.
+    settings: Arc<StdMutex<Arc<DesktopSettings>>>,
+    file_changes: FileChanges,
+}
+
+impl ActivityState {
+    fn new(app: &AppHandle) -> Self {
+        let machine = Arc::new(Mutex::new(ActivityStateMachine::default()));
+        let emit_buffer = Arc::new(Mutex::new(EmitBuffer::default()));
+        let directories = Arc::new(StdMutex::new(HashMap::new()));
+        let (expiry_tx, expiry_rx) = mpsc::unbounded_channel();
+
+        // The expiry task only holds the machine and emit buffer, so it stops once the
+        // tracker drops the last sender.
+        let app = app.clone();
+        let task_machine = machine.clone();
+        let task_buffer = emit_buffer.clone();
+        let task_directories = directories.clone();
+        tauri::async_runtime::spawn(run_expiry_queue(expiry_rx, move |_| {
+            let app = app.clone();
+            let machine = task_machine.clone();
+            let emit_buffer = task_buffer.clone();
+            let directories = task_directories.clone();
+            async move {
+                let transitions = {
+                    let mut machine = machine.lock().await;
+                    let transitions = machine.expire(Instant::now());
+                    refresh_busy_sessions(&app, &machine, &directories);
+                    transitions
+                };
+                for transition in transitions {
+                    let payload = tagged_payload(&app, &directories, &transition);
+                    emit_coalesced(&app, payload, &emit_buffer).await;
+                }
+            }
+        }));
+
+        Self {
+            machine,
+            expiry_tx,
+            emit_buffer,
+            directories,
+            settings: Arc::new(StdMutex::new(Arc::new(DesktopSettings::default()))),
+            file_changes: FileChanges::default(),
+        }
+    }
+
+    fn settings(&self) -> Arc<DesktopSettings> {
+        self.settings
+            .lock()
+            .map(|settings| settings.clone())
+            .unwrap_or_default()
+    }
+
+    fn set_settings(&self, settings: Arc<DesktopSettings>) {
+        if let Ok(mut current) = self.settings.lock() {
+            *current = settings;
+        }
+    }
+
+    /// The cooldown for a session in `directory`, or in the active project when the
+    /// event carried no directory.
+    fn cooldown_for(&self, directory: Option<&str>) -> Duration {
+        self.settings()
+            .effective_settings(directory.map(Path::new))
+            .cooldown_seconds
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_COOLDOWN)
+    }
+}
+
+/// The transition's payload with the session's project directory, when known.
+fn tagged_payload(
+    app: &AppHandle,
+    directories: &StdMutex<HashMap<String, String>>,
+    transition: &PhaseTransition,
+) -> SessionActivityPayload {
+    let info = app
+        .state::<SessionInfoCache>()
+        .get(&transition.session_id)
+        .unwrap_or_default();
+    let directory = directories
+        .lock()
+        .ok()
+        .and_then(|directories| directories.get(&transition.session_id).cloned())
+        .or(info.directory);
+    let mut payload = transition.payload(directory);
+    payload.title = info.title;
+    payload.parent_id = info.parent_id;
+    payload
+}
+
+fn emit_stream_status(
+    app: &AppHandle,
+    directory: Option<&Path>,
+    state: StreamState,
+    error: Option<String>,
+) {
+    app.state::<EventStreamHealth>()
+        .record(directory, state, error.as_deref());
+    let _ = app.emit(
+        EVENT_STREAM_STATUS_EVENT,
+        SseStatusPayload {
+            connected: state == StreamState::Connected,
+            state,
+            directory,
+            error,
+        },
+    );
+}
+
+/// Coalesces bursts of activity payloads into a single webview event.
+#[derive(Default)]
+struct EmitBuffer {
+    last_emit: Option<Instant>,
+    pending: Vec<SessionActivityPayload>,
+    flush_scheduled: bool,
+}
+
+pub fn spawn_session_activity_tracker(
+    app: AppHandle,
+    runtime: DesktopRuntime,
+) -> tauri::async_runtime::JoinHandle<()> {
+    let tasks = runtime.tasks().clone();
+    let shutdown_rx = runtime.subscribe_shutdown();
+    tasks.supervise(app.clone(), "session-activity", shutdown_rx, move |task| {
+        run_activity_tracker(app.clone(), runtime.clone(), task)
+    })
+}
+
+async fn run_activity_tracker(app: AppHandle, runtime: DesktopRuntime, task: TaskHandle) {
+    let mut shutdown_rx = runtime.subscribe_shutdown();
+    let state = ActivityState::new(&app);
+    let opencode = runtime.opencode_manager();
+
+    // In multi-instance mode every project server gets a stream of its own.
+    let start_project_stream = {
+        let app = app.clone();
+        let runtime = runtime.clone();
+        let state = state.clone();
+        move |instance| {
+            tauri::async_runtime::spawn(run_project_stream(
+                app.clone(),
+                runtime.clone(),
+                state.clone(),
+                instance,
+            ))
+        }
+    };
+    let projects = ChildTask::spawn(follow_project_instances(
+        runtime.opencode_instances(),
+        start_project_stream,
+    ));
+    if let Ok(settings) = runtime.settings().load_typed().await {
+        state.set_settings(Arc::new(settings));
+    }
+    let settings_follower = ChildTask::spawn(follow_settings(runtime.clone(), state.clone()));
+    let power_follower = ChildTask::spawn(follow_power_events(
+        app.clone(),
+        runtime.clone(),
+        state.clone(),
+    ));
+    let pause_follower =
+        ChildTask::spawn(follow_pause(app.clone(), runtime.clone(), state.clone()));
+    let daily_summaries = ChildTask::spawn(announce_daily_summaries(app.clone()));
+    let simulated = ChildTask::spawn(follow_simulated_events(app.clone(), state.clone()));
+    // While the server is down every reconnect fails the same way.
+    let mut loop_errors = RepeatedLog::default();
+    let mut wake = runtime.subscribe_stream_wake();
+
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.recv() => {
+                info!("[desktop:activity] Shutdown received, stopping SSE listener");
+                drop(projects);
+                drop(settings_follower);
+                drop(power_follower);
+                drop(pause_follower);
+                drop(daily_summaries);
+                drop(simulated);
+                break;
+            }
+            _ = async {
+                task.heartbeat();
+                match run_once(&app, &runtime, &opencode, None, &state, &wake).await {
+                    Ok(()) => loop_errors.settle(|line| warn!("{line}")),
+                    Err(err) => loop_errors.record(
+                        format!("[desktop:activity] SSE loop error: {err:?}"),
+                        |line| warn!("{line}"),
+                    ),
+                }
+                sleep_unless_woken(&mut wake, Duration::from_secs(2)).await;
+            } => {}
+        }
+    }
+}
+
+/// Listen to one project instance until it is stopped.
+async fn run_project_stream(
+    app: AppHandle,
+    runtime: DesktopRuntime,
+    state: ActivityState,
+    instance: ProjectInstance,
+) {
+    let mut loop_errors = RepeatedLog::default();
+    let mut wake = runtime.subscribe_stream_wake();
+    while !instance.manager.is_shutting_down() {
+        let result = run_once(
+            &app,
+            &runtime,
+            &instance.manager,
+            Some(&instance.directory),
+            &state,
+            &wake,
+        )
+        .await;
+        match result {
+            Ok(()) => loop_errors.settle(|line| warn!("{line}")),
+            Err(err) => loop_errors.record(
+                format!(
+                    "[desktop:activity] SSE loop error for {:?}: {err:?}",
+                    instance.directory
+                ),
+                |line| warn!("{line}"),
+            ),
+        }
+        sleep_unless_woken(&mut wake, Duration::from_secs(2)).await;
+    }
+}
+
+/// Follow one server's event stream until it ends. `directory` is the project of a
+/// dedicated instance; events without a directory of their own are attributed to it.
+async fn run_once(
+    app: &AppHandle,
+    runtime: &DesktopRuntime,
+    opencode: &OpenCodeManager,
+    directory: Option<&Path>,
+    state: &ActivityState,
+    wake: &WakeReceiver,
+) -> Result<()> {
+    runtime.wait_until_awake().await;
+    if runtime.is_paused() {
+        emit_stream_status(app, directory, StreamState::Paused, None);
+        runtime.wait_until_resumed().await;
+    }
+    let mut power = runtime.subscribe_power();
+    // Picked up per connection so proxy changes apply on the next reconnect.
+    let client = runtime.http().streaming();
+    let mut auth_token = opencode.subscribe_auth_token();
+    let mut status = opencode.subscribe_status();
+    let mut settings_changes = runtime.subscribe_settings_changes();
+    let base = status.borrow_and_update().base_url();
+    let Some(base) = base else {
+        info!("[desktop:activity] OpenCode not running; waiting for it to start");
+        runtime.wait_for_opencode_change(&mut status, wake).await;
+        return Ok(());
+    };
+    if runtime.is_offline_for(&base) {
+        let error = "Network is offline".to_string();
+        emit_stream_status(app, directory, StreamState::Offline, Some(error));
+        if !runtime.wait_until_online(OFFLINE_RETRY).await {
+            return Ok(());
+        }
+    }
+    let connected = connect_event_stream(
+        runtime,
+        opencode,
+        &client,
+        &base,
+        directory,
+        "[desktop:activity]",
+    )
+    .await;
+    let (response, scope) = match connected {
+        Ok(connected) => {
+            emit_stream_status(app, directory, StreamState::Connected, None);
+            connected
+        }
+        Err(err) => {
+            if let Some(rejected) = auth_rejection(&err) {
+                warn!("[desktop:activity] {rejected}; waiting for new credentials");
+                let error = Some(rejected.to_string());
+                emit_stream_status(app, directory, StreamState::AuthFailed, error);
+                let _ = app.emit(
+                    AUTH_REQUIRED_EVENT,
+                    AuthRequiredPayload {
+                        endpoint: &rejected.url,
+                        status: rejected.status,
+                        directory,
+                    },
+                );
+                wait_for_reauth(
+                    runtime,
+                    &mut auth_token,
+                    &mut status,
+                    &mut settings_changes,
+                    &base,
+                )
+                .await;
+                return Ok(());
+            }
+            emit_stream_status(
+                app,
+                directory,
+                StreamState::Disconnected,
+                Some(err.to_string()),
+            );
+            runtime
+                .telemetry()
+                .record_reconnect(ReconnectReason::ConnectFailed);
+            return Err(err);
+        }
+    };
+    state
+        .machine
+        .lock()
+        .await
+        .set_error_decay(resolve_error_decay(runtime).await);
+    // Session lookups use the token the stream connected with.
+    let token = opencode.auth_token();
+
+    use tokio::io::AsyncBufReadExt;
+
+    let stream = response
+        .bytes_stream()
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err));
+    let mut reader = StreamReader::new(stream);
+    let mut buf = Vec::new();
+    let mut data_lines: Vec<String> = Vec::new();
+    let mut unparsed = ThrottledLog::default();
+    let idle_timeout = state.settings().events.activity_idle_timeout();
+
+    loop {
+        buf.clear();
+        let read = tokio::select! {
+            read = reader.read_until(b'\n', &mut buf) => read,
+            _ = runtime.wait_until_paused() => {
+                info!("[desktop:activity] Background streams paused; disconnecting SSE");
+                emit_stream_status(app, directory, StreamState::Paused, None);
+                return Ok(());
+            }
+            _ = stream_idle(idle_timeout) => {
+                info!("[desktop:activity] SSE stream went silent; reconnecting");
+                runtime
+                    .telemetry()
+                    .record_reconnect(ReconnectReason::IdleTimeout);
+                return Ok(());
+            }
+            _ = server_moved(&mut status, &base) => {
+                info!("[desktop:activity] OpenCode server changed; reconnecting SSE");
+                runtime
+                    .telemetry()
+                    .record_reconnect(ReconnectReason::ServerChanged);
+                return Ok(());
+            }
+            // A directory-scoped stream follows the active project. Project instance
+            // streams stay pinned to their own directory.
+            _ = active_project_moved(&mut settings_changes, &scope), if directory.is_none() => {
+                info!("[desktop:activity] Active project changed; reconnecting SSE");
+                runtime
+                    .telemetry()
+                    .record_reconnect(ReconnectReason::DirectoryChanged);
+                return Ok(());
+            }
+            // Drop the connection on sleep rather than wait for it to time out, and
+            // start over on wake in case the sleep notification never arrived.
+            _ = power_state_changed(&mut power) => {
+                info!("[desktop:activity] System sleep state changed; reconnecting SSE");
+                return Ok(());
+            }
+        };
+        let bytes_read = match read {
+            Ok(n) => n,
+            Err(err) => {
+                warn!("[desktop:activity] Read error in SSE stream: {err:?}");
+                runtime
+                    .telemetry()
+                    .record_reconnect(ReconnectReason::ReadError);
+                return Err(err.into());
+            }
+        };
+        if bytes_read == 0 {
+            runtime
+                .telemetry()
+                .record_reconnect(ReconnectReason::StreamEnded);
+            break;
+        }
+
+        let line = match std::str::from_utf8(&buf) {
+            Ok(s) => s.trim_end_matches(&['\r', '\n'][..]).to_string(),
+            Err(err) => {
+                warn!("[desktop:activity] Non-UTF8 SSE chunk: {err}");
+                continue;
+            }
+        };
+
+        if line.is_empty() {
+            if data_lines.is_empty() {
+                continue;
+            }
+            let raw = data_lines.join("\n");
+            data_lines.clear();
+            app.state::<SseCapture>().record(&raw, directory);
+
+            match parse_event_envelope(&raw) {
+                Ok(None) => runtime.telemetry().record_keepalive(),
+                Ok(Some((event, event_directory))) => {
+                    let directory = event_directory
+                        .or_else(|| directory.map(|path| path.to_string_lossy().to_string()));
+                    runtime.sse_events().record(
+                        "activity",
+                        &event.event_type,
+                        directory.as_deref(),
+                        &event.properties,
+                    );
+                    handle_event(app, event, directory, &base, token.as_deref(), state).await
+                }
+                Err(err) => {
+                    runtime.telemetry().record_parse_failure();
+                    unparsed.record(
+                        format!(
+                            "[desktop:activity] Failed to parse SSE data: {err}; raw={}",
+                            unparsed_prefix(&raw)
+                        ),
+                        |line| warn!("{line}"),
+                    );
+                }
+            };
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("data:") {
+            data_lines.push(rest.trim_start().to_string());
+        }
+    }
+
+    Ok(())
+}
+
+/// `None` for a keepalive frame, which carries no event.
+fn parse_event_envelope(raw: &str) -> Result<Option<(EventEnvelope, Option<String>)>> {
+    if is_keepalive(raw) {
+        return Ok(None);
+    }
+    if let Ok(event) = serde_json::from_str::<EventEnvelope>(raw) {
+        return Ok(Some((event, None)));
+    }
+
+    let multiplexed = serde_json::from_str::<MultiplexedEventEnvelope>(raw)?;
+    Ok(Some((multiplexed.payload, multiplexed.directory)))
+}
+
+/// Whether `raw` parses the way a streamed event must, for `simulate_desktop_event`.
+pub(crate) fn validate_event(raw: &str) -> Result<()> {
+    parse_event_envelope(raw)?
+        .map(|_| ())
+        .ok_or_else(|| anyhow!("A keepalive carries no event"))
+}
+
+/// Handle simulated and replayed events the way `run_once` handles streamed ones.
+async fn follow_simulated_events(app: AppHandle, state: ActivityState) {
+    let mut events = app.state::<SimulatedEvents>().subscribe();
+    loop {
+        let raw = match events.recv().await {
+            Ok(event) => event.raw,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        // Checked before the event was sent.
+        let Ok(Some((event, directory))) = parse_event_envelope(&raw) else {
+            continue;
+        };
+        debug!("[desktop:activity] Simulated {}", event.event_type);
+        // Session details are looked up on the server when one is running.
+        let runtime = app.state::<DesktopRuntime>();
+        let opencode = runtime.opencode_manager();
+        let base = opencode
+            .subscribe_status()
+            .borrow()
+            .base_url()
+            .unwrap_or_default();
+        let token = opencode.auth_token();
+        handle_event(&app, event, directory, &base, token.as_deref(), &state).await;
+    }
+}
+
+/// Apply settings edits as they are saved rather than on the next reconnect.
+async fn follow_settings(runtime: DesktopRuntime, state: ActivityState) {
+    let mut settings_changes = runtime.subscribe_settings_changes();
+    loop {
+        let change = next_settings_change(&mut settings_changes).await;
+        state.set_settings(change.current.clone());
+        if !change.activity_changed() {
+            continue;
+        }
+        let seconds = change
+            .current
+            .activity_error_decay_seconds
+            .unwrap_or(DEFAULT_ERROR_DECAY_SECS);
+        debug!("[desktop:activity] Error decay changed to {seconds}s");
+        state
+            .machine
+            .lock()
+            .await
+            .set_error_decay(Duration::from_secs(seconds));
+    }
+}
+
+/// Phases seen before a sleep are stale by the time the machine wakes; sessions that
+/// are still busy report so again once the streams reconnect.
+async fn follow_power_events(app: AppHandle, runtime: DesktopRuntime, state: ActivityState) {
+    let mut power = runtime.subscribe_power();
+    loop {
+        match power_state_changed(&mut power).await {
+            PowerState::Asleep => app.state::<BusyTime>().suspend(),
+            PowerState::Awake => reset_and_emit_all_phases(&app, &state).await,
+        }
+    }
+}
+
+/// Nothing is emitted while paused, so the phases the UI last saw are stale on resume;
+/// sessions that are still busy report so again once the streams reconnect, and the
+/// notification stream refreshes pending questions as it does on every connect.
+async fn follow_pause(app: AppHandle, runtime: DesktopRuntime, state: ActivityState) {
+    let mut paused = runtime.subscribe_paused();
+    while paused.changed().await.is_ok() {
+        if !*paused.borrow_and_update() {
+            reset_and_emit_all_phases(&app, &state).await;
+        }
+    }
+}
+
+async fn resolve_error_decay(runtime: &DesktopRuntime) -> Duration {
+    let seconds = runtime
+        .settings()
+        .load_typed()
+        .await
+        .ok()
+        .and_then(|settings| settings.activity_error_decay_seconds)
+        .unwrap_or(DEFAULT_ERROR_DECAY_SECS);
+    Duration::from_secs(seconds)
+}
+
+/// `base` is the server the event came from, where unknown sessions are looked up with
+/// its auth `token`.
+async fn handle_event(
+    app: &AppHandle,
+    event: EventEnvelope,
+    directory: Option<String>,
+    base: &str,
+    token: Option<&str>,
+    state: &ActivityState,
+) {
+    let settings = state.settings();
+    // Filtered types are still tracked; only what reaches the webview is trimmed.
+    let forwarded = settings.events.forwards(&event.event_type);
+    app.state::<SessionLifecycleEvents>().forward(
+        app,
+        &event.event_type,
+        &event.properties,
+        directory.as_deref(),
+        forwarded,
+    );
+    if is_file_event(&event.event_type) {
+        if settings.forward_file_changes && forwarded {
+            // The main stream follows the active project.
+            let directory = directory
+                .map(PathBuf::from)
+                .or_else(|| settings.project_directory());
+            state
+                .file_changes
+                .forward(app, &event.properties, directory.as_deref());
+        }
+        return;
+    }
+    let runtime = app.state::<DesktopRuntime>();
+    let usage = runtime.usage().clone();
+    match event.event_type.as_str() {
+        "message.updated" => usage.record_message(app, &event.properties),
+        "message.part.updated" => usage.record_part(&event.properties),
+        "session.status" => usage.record_session_status(&event.properties),
+        _ => {}
+    }
+    // Later payloads, notifications and the tray show what the fetch finds.
+    match event_session_id(&event.properties) {
+        Some(session_id) => app.state::<SessionInfoCache>().observe(
+            app,
+            runtime.http().api(),
+            base,
+            token,
+            session_id,
+            directory.as_deref(),
+        ),
+        None if SESSION_SCOPED_EVENTS.contains(&event.event_type.as_str()) => {
+            runtime.telemetry().record_schema_sentinel();
+        }
+        None => {}
+    }
+
+    let cooldown = state.cooldown_for(directory.as_deref());
+    let transitions = {
+        let mut machine = state.machine.lock().await;
+        machine.apply_event(&event, Instant::now(), cooldown)
+    };
+    if let (Some(directory), Ok(mut directories)) = (directory, state.directories.lock()) {
+        for transition in &transitions {
+            directories.insert(transition.session_id.clone(), directory.clone());
+        }
+    }
+    publish_transitions(app, transitions, state).await;
+}
+
+/// The session an event is about, wherever its type keeps it. `session.updated` carries
+/// the session itself and is left to the cache.
+fn event_session_id(properties: &Value) -> Option<&str> {
+    ["/sessionID", "/info/sessionID", "/part/sessionID"]
+        .iter()
+        .find_map(|pointer| properties.pointer(pointer))
+        .and_then(Value::as_str)
+}
+
+/// Emit transitions and keep the expiry queue in line with the machine's deadlines.
+async fn publish_transitions(
+    app: &AppHandle,
+    transitions: Vec<PhaseTransition>,
+    state: &ActivityState,
+) {
+    for transition in transitions {
+        let deadline = {
+            let machine = state.machine.lock().await;
+            machine.deadline(&transition.session_id)
+        };
+        let session_id = transition.session_id.clone();
+        let command = match deadline {
+            Some(at) => ExpiryCommand::Schedule(session_id, at),
+            None => ExpiryCommand::Cancel(session_id),
+        };
+        let _ = state.expiry_tx.send(command);
+        let directory = state
+            .directories
+            .lock()
+            .ok()
+            .and_then(|directories| directories.get(&transition.session_id).cloned());
+        app.state::<BusyTime>().set_busy(
+            &transition.session_id,
+            directory.as_deref(),
+            transition.phase.is_busy(),
+        );
+        let payload = tagged_payload(app, &state.directories, &transition);
+        emit_coalesced(app, payload, &state.emit_buffer).await;
+    }
+    let machine = state.machine.lock().await;
+    refresh_busy_sessions(app, &machine, &state.directories);
+}
+
+/// Mirror the machine's non-idle sessions into `BusySessions` for the tray.
+fn refresh_busy_sessions(
+    app: &AppHandle,
+    machine: &ActivityStateMachine,
+    directories: &StdMutex<HashMap<String, String>>,
+) {
+    let directories = directories.lock().ok();
+    let mut sessions: Vec<BusySession> = machine
+        .active_phases()
+        .map(|(session_id, phase)| BusySession {
+            session_id: session_id.to_string(),
+            phase,
+            directory: directories
+                .as_ref()
+                .and_then(|directories| directories.get(session_id).cloned()),
+        })
+        .collect();
+    sessions.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+    app.state::<BusySessions>().replace(sessions);
+}
+
+/// Emit immediately after a quiet period; otherwise queue the payload and flush everything
+/// queued as one batched event (an array, in arrival order) once the coalescing window ends.
+async fn emit_coalesced(
+    app: &AppHandle,
+    payload: SessionActivityPayload,
+    emit_buffer: &Arc<Mutex<EmitBuffer>>,
+) {
+    let mut buffer = emit_buffer.lock().await;
+    let now = Instant::now();
+    let quiet = buffer
+        .last_emit
+        .map(|last| now.saturating_duration_since(last) >= EMIT_COALESCE_WINDOW)
+        .unwrap_or(true);
+
+    if quiet && !buffer.flush_scheduled {
+        buffer.last_emit = Some(now);
+        emit_activity(app, vec![payload]);
+        return;
+    }
+
+    buffer.pending.push(payload);
+    if buffer.flush_scheduled {
+        return;
+    }
+    buffer.flush_scheduled = true;
+
+    let delay = buffer
+        .last_emit
+        .map(|last| EMIT_COALESCE_WINDOW.saturating_sub(now.saturating_duration_since(last)))
+        .unwrap_or_default();
+    let app_clone = app.clone();
+    let buffer_clone = emit_buffer.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(delay).await;
+        let mut buffer = buffer_clone.lock().await;
+        let batch = std::mem::take(&mut buffer.pending);
+        buffer.flush_scheduled = false;
+        buffer.last_emit = Some(Instant::now());
+        emit_activity(&app_clone, batch);
+    });
+}
+
+/// Send payloads only to the windows showing their project. Payloads without a directory,
+/// or for a project no window registered, go to every window. Nothing is sent while
+/// background streams are paused.
+fn emit_activity(app: &AppHandle, payloads: Vec<SessionActivityPayload>) {
+    if app.state::<DesktopRuntime>().is_paused() {
+        return;
+    }
+    let projects = app.state::<WindowProjects>();
+    let mut everywhere = Vec::new();
+    let mut by_window: HashMap<String, Vec<SessionActivityPayload>> = HashMap::new();
+    for payload in payloads {
+        let labels = payload
+            .directory
+            .as_deref()
+            .and_then(|directory| projects.windows_showing(directory));
+        match labels {
+            Some(labels) => {
+                for label in labels {
+                    by_window.entry(label).or_default().push(payload.clone());
+                }
+            }
+            None => everywhere.push(payload),
+        }
+    }
+
+    if !everywhere.is_empty() {
+        let _ = app.emit(SESSION_ACTIVITY_EVENT, Batched::from(everywhere));
+    }
+    for (label, payloads) in by_window {
+        let _ = app.emit_to(
+            label.as_str(),
+            SESSION_ACTIVITY_EVENT,
+            Batched::from(payloads),
+        );
+    }
+}
+
+async fn reset_and_emit_all_phases(app: &AppHandle, state: &ActivityState) {
+    // Cancel any cooldown timers and set all phases to idle.
+    let _ = state.expiry_tx.send(ExpiryCommand::Clear);
+
+    let transitions = {
+        let mut machine = state.machine.lock().await;
+        let transitions = machine.reset_all();
+        refresh_busy_sessions(app, &machine, &state.directories);
+        transitions
+    };
+    for transition in transitions {
+        app.state::<BusyTime>().discard(&transition.session_id);
+        let payload = tagged_payload(app, &state.directories, &transition);
+        emit_coalesced(app, payload, &state.emit_buffer).await;
+    }
+}
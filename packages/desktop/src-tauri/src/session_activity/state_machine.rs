@@ -0,0 +1,893 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::compaction::is_compacting_status;
+use crate::events::{ErrorFields, PendingInputFields, RetryFields, SessionActivityPayload};
+use crate::retry_status::RetryStatus;
+
+/// How long a finished session cools down unless its project overrides it.
+pub(super) const DEFAULT_COOLDOWN: Duration = Duration::from_secs(2);
+const DEFAULT_ERROR_DECAY: Duration = Duration::from_secs(10);
+const ERROR_SUMMARY_MAX_CHARS: usize = 200;
+
+#[derive(Deserialize)]
+pub(super) struct EventEnvelope {
+    #[serde(rename = "type")]
+    pub(super) event_type: String,
+    #[serde(default)]
+    pub(super) properties: Value,
+}
+
+/// `Retry` is busy, but waiting out a provider error (usually rate limiting) before the
+/// next attempt. `Compacting` is busy summarizing the session's context, during which the
+/// session takes no new prompts.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum ActivityPhase {
+    Idle,
+    Busy,
+    Retry(RetryStatus),
+    Compacting,
+    Cooldown,
+    WaitingForInput,
+    Error { error_type: String, summary: String },
+}
+
+impl ActivityPhase {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ActivityPhase::Idle => "idle",
+            ActivityPhase::Busy => "busy",
+            ActivityPhase::Retry(_) => "retry",
+            ActivityPhase::Compacting => "compacting",
+            ActivityPhase::Cooldown => "cooldown",
+            ActivityPhase::WaitingForInput => "waiting-for-input",
+            ActivityPhase::Error { .. } => "error",
+        }
+    }
+
+    /// Whether the agent is working, as counted for busy time.
+    pub(super) fn is_busy(&self) -> bool {
+        matches!(
+            self,
+            ActivityPhase::Busy | ActivityPhase::Retry(_) | ActivityPhase::Compacting
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum PendingInputKind {
+    Question,
+    Permission,
+}
+
+impl PendingInputKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PendingInputKind::Question => "question",
+            PendingInputKind::Permission => "permission",
+        }
+    }
+}
+
+/// A question or permission prompt the session is blocked on.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct PendingInput {
+    kind: PendingInputKind,
+    id: String,
+}
+
+/// Why a session changed phase, so the UI can tell a finished run from a wake reset.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum TransitionReason {
+    StatusEvent,
+    StreamingPart,
+    FinishStop,
+    UserMessage,
+    SessionError,
+    InputRequested,
+    InputResolved,
+    CompactionFinished,
+    CooldownExpired,
+    ErrorExpired,
+    WakeReset,
+    SessionDeleted,
+}
+
+impl TransitionReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TransitionReason::StatusEvent => "status-event",
+            TransitionReason::StreamingPart => "streaming-part",
+            TransitionReason::FinishStop => "finish-stop",
+            TransitionReason::UserMessage => "user-message",
+            TransitionReason::SessionError => "session-error",
+            TransitionReason::InputRequested => "input-requested",
+            TransitionReason::InputResolved => "input-resolved",
+            TransitionReason::CompactionFinished => "compaction-finished",
+            TransitionReason::CooldownExpired => "cooldown-expired",
+            TransitionReason::ErrorExpired => "error-expired",
+            TransitionReason::WakeReset => "wake-reset",
+            TransitionReason::SessionDeleted => "session-deleted",
+        }
+    }
+}
+
+/// A phase change that should be reported to the webview.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct PhaseTransition {
+    pub(super) session_id: String,
+    pub(super) phase: ActivityPhase,
+    pub(super) previous: Option<ActivityPhase>,
+    pub(super) reason: TransitionReason,
+    /// The prompt the session is waiting on, for WaitingForInput transitions.
+    pub(super) input: Option<PendingInput>,
+}
+
+impl PhaseTransition {
+    /// The transition as the webview receives it, tagged with the session's project when
+    /// it is known.
+    pub(super) fn payload(&self, directory: Option<String>) -> SessionActivityPayload {
+        SessionActivityPayload {
+            session_id: self.session_id.clone(),
+            phase: self.phase.as_str(),
+            previous_phase: self.previous.as_ref().map(ActivityPhase::as_str),
+            reason: self.reason.as_str(),
+            input: self.input.as_ref().map(|input| PendingInputFields {
+                input_kind: input.kind.as_str(),
+                input_id: input.id.clone(),
+            }),
+            error: match &self.phase {
+                ActivityPhase::Error {
+                    error_type,
+                    summary,
+                } => Some(ErrorFields {
+                    error_type: error_type.clone(),
+                    error_summary: summary.clone(),
+                }),
+                _ => None,
+            },
+            retry: match &self.phase {
+                ActivityPhase::Retry(retry) => Some(RetryFields {
+                    retry_attempt: retry.attempt,
+                    retry_provider: retry.provider.clone(),
+                    retry_message: retry.message.clone(),
+                    retry_at: retry.next_at,
+                }),
+                _ => None,
+            },
+            directory,
+            title: None,
+            parent_id: None,
+        }
+    }
+}
+
+/// Session activity phases derived from OpenCode events.
+///
+/// Pure and synchronous: callers feed events and the current time in and get the resulting
+/// transitions back. Timed phases (cooldown, error) only expire when `expire` is called with
+/// a time past their deadline, so the caller owns the clock.
+pub(super) struct ActivityStateMachine {
+    phases: HashMap<String, ActivityPhase>,
+    /// Outstanding prompts per session, oldest first. While non-empty the session
+    /// reports WaitingForInput regardless of busy/cooldown transitions.
+    pending_inputs: HashMap<String, Vec<PendingInput>>,
+    /// When a timed phase returns to idle, keyed by session.
+    deadlines: HashMap<String, (ActivityPhase, Instant)>,
+    /// Sessions being compacted, with the phase to return to when compaction finishes.
+    compacting: HashMap<String, ActivityPhase>,
+    /// Latest user message id per session, so re-sent updates of the same message
+    /// don't count as a new turn.
+    last_user_messages: HashMap<String, String>,
+    error_decay: Duration,
+}
+
+impl Default for ActivityStateMachine {
+    fn default() -> Self {
+        Self {
+            phases: HashMap::new(),
+            pending_inputs: HashMap::new(),
+            deadlines: HashMap::new(),
+            compacting: HashMap::new(),
+            last_user_messages: HashMap::new(),
+            error_decay: DEFAULT_ERROR_DECAY,
+        }
+    }
+}
+
+impl ActivityStateMachine {
+    pub(super) fn set_error_decay(&mut self, decay: Duration) {
+        self.error_decay = decay;
+    }
+
+    /// The deadline of the session's current timed phase, if any.
+    pub(super) fn deadline(&self, session_id: &str) -> Option<Instant> {
+        self.deadlines.get(session_id).map(|(_, at)| *at)
+    }
+
+    /// Apply one event. `cooldown` is how long the session cools down if the event
+    /// finishes its run.
+    pub(super) fn apply_event(
+        &mut self,
+        event: &EventEnvelope,
+        now: Instant,
+        cooldown: Duration,
+    ) -> Vec<PhaseTransition> {
+        let mut transitions = Vec::new();
+        let properties = &event.properties;
+
+        match event.event_type.as_str() {
+            "session.status" => {
+                let session_id = properties.get("sessionID").and_then(Value::as_str);
+                let status = properties
+                    .get("status")
+                    .and_then(|s| s.get("type"))
+                    .and_then(Value::as_str);
+
+                if let (Some(id), Some(status_type)) = (session_id, status) {
+                    let phase = match RetryStatus::from_status_event(properties) {
+                        Some(retry) => ActivityPhase::Retry(retry),
+                        None if status_type == "busy" => ActivityPhase::Busy,
+                        None if is_compacting_status(properties) => ActivityPhase::Compacting,
+                        None => ActivityPhase::Idle,
+                    };
+                    self.set_phase(id, phase, TransitionReason::StatusEvent, &mut transitions);
+                }
+            }
+            "session.idle" => {
+                if let Some(id) = properties.get("sessionID").and_then(Value::as_str) {
+                    self.set_phase(
+                        id,
+                        ActivityPhase::Idle,
+                        TransitionReason::StatusEvent,
+                        &mut transitions,
+                    );
+                }
+            }
+            "session.compacted" => {
+                if let Some(id) = properties.get("sessionID").and_then(Value::as_str) {
+                    if let Some(underlying) = self.compacting.remove(id) {
+                        self.set_phase(
+                            id,
+                            underlying,
+                            TransitionReason::CompactionFinished,
+                            &mut transitions,
+                        );
+                    }
+                }
+            }
+            "message.updated" => {
+                let Some(info) = properties.get("info") else {
+                    return transitions;
+                };
+                let role = info.get("role").and_then(Value::as_str).unwrap_or_default();
+                if role == "user" {
+                    self.handle_user_message(info, &mut transitions);
+                    return transitions;
+                }
+                if role != "assistant" || !has_finish_stop(info) {
+                    return transitions;
+                }
+
+                if let Some(id) = info.get("sessionID").and_then(Value::as_str) {
+                    self.enter_cooldown_if_busy(id, now, cooldown, &mut transitions);
+                }
+            }
+            "message.part.updated" => {
+                let Some(info) = properties.get("info") else {
+                    return transitions;
+                };
+
+                let role = info.get("role").and_then(Value::as_str).unwrap_or_default();
+                if role != "assistant" {
+                    return transitions;
+                }
+
+                let Some(id) = info.get("sessionID").and_then(Value::as_str) else {
+                    return transitions;
+                };
+
+                // Mark session busy when we see assistant parts streaming (covers cases where session.status is missing).
+                if is_streaming_assistant_part(properties) {
+                    self.set_phase(
+                        id,
+                        ActivityPhase::Busy,
+                        TransitionReason::StreamingPart,
+                        &mut transitions,
+                    );
+                }
+
+                // Derive cooldown from info.finish === 'stop' when present.
+                if has_finish_stop(info) {
+                    self.enter_cooldown_if_busy(id, now, cooldown, &mut transitions);
+                }
+            }
+            "session.error" => {
+                let Some(id) = properties.get("sessionID").and_then(Value::as_str) else {
+                    return transitions;
+                };
+                let (error_type, summary) = summarize_session_error(properties.get("error"));
+                let phase = ActivityPhase::Error {
+                    error_type,
+                    summary,
+                };
+                if self.set_phase(
+                    id,
+                    phase.clone(),
+                    TransitionReason::SessionError,
+                    &mut transitions,
+                ) {
+                    self.deadlines
+                        .insert(id.to_string(), (phase, now + self.error_decay));
+                }
+            }
+            "session.deleted" => {
+                let id = properties
+                    .get("info")
+                    .and_then(|info| info.get("id"))
+                    .or_else(|| properties.get("sessionID"))
+                    .and_then(Value::as_str);
+                if let Some(id) = id {
+                    self.remove_session(id, &mut transitions);
+                }
+            }
+            "question.asked" | "permission.updated" | "permission.asked" => {
+                let kind = if event.event_type.starts_with("question.") {
+                    PendingInputKind::Question
+                } else {
+                    PendingInputKind::Permission
+                };
+                let session_id = properties.get("sessionID").and_then(Value::as_str);
+                let input_id = properties.get("id").and_then(Value::as_str);
+                if let (Some(session_id), Some(input_id)) = (session_id, input_id) {
+                    let input = PendingInput {
+                        kind,
+                        id: input_id.to_string(),
+                    };
+                    self.add_pending_input(session_id, input, &mut transitions);
+                }
+            }
+            "question.answered" | "question.replied" | "question.rejected"
+            | "permission.replied" => {
+                let session_id = properties.get("sessionID").and_then(Value::as_str);
+                let input_id = properties
+                    .get("requestID")
+                    .or_else(|| properties.get("permissionID"))
+                    .or_else(|| properties.get("id"))
+                    .and_then(Value::as_str);
+                if let (Some(session_id), Some(input_id)) = (session_id, input_id) {
+                    self.resolve_pending_input(session_id, input_id, &mut transitions);
+                }
+            }
+            _ => {}
+        }
+
+        transitions
+    }
+
+    /// Return timed phases whose deadline has passed to idle.
+    ///
+    /// Any phase change drops the session's deadline, and the phase is re-checked here, so a
+    /// timer that fires after the session moved on (e.g. back to busy) is a no-op.
+    pub(super) fn expire(&mut self, now: Instant) -> Vec<PhaseTransition> {
+        let due: Vec<(String, ActivityPhase)> = self
+            .deadlines
+            .iter()
+            .filter(|(_, (_, at))| *at <= now)
+            .map(|(id, (phase, _))| (id.clone(), phase.clone()))
+            .collect();
+
+        let mut transitions = Vec::new();
+        for (session_id, expiring) in due {
+            self.deadlines.remove(&session_id);
+            if self.phases.get(&session_id) == Some(&expiring) {
+                let reason = if matches!(expiring, ActivityPhase::Error { .. }) {
+                    TransitionReason::ErrorExpired
+                } else {
+                    TransitionReason::CooldownExpired
+                };
+                self.apply_phase(&session_id, ActivityPhase::Idle, reason, &mut transitions);
+            }
+        }
+        transitions
+    }
+
+    /// Sessions in any phase but idle, with the phase as reported in activity events.
+    pub(super) fn active_phases(&self) -> impl Iterator<Item = (&str, &'static str)> + '_ {
+        self.phases
+            .iter()
+            .filter(|(_, phase)| **phase != ActivityPhase::Idle)
+            .map(|(session_id, phase)| (session_id.as_str(), phase.as_str()))
+    }
+
+    /// Drop timers and pending prompts and report every known session as idle.
+    pub(super) fn reset_all(&mut self) -> Vec<PhaseTransition> {
+        self.deadlines.clear();
+        self.pending_inputs.clear();
+        self.compacting.clear();
+
+        self.phases
+            .iter_mut()
+            .map(|(session_id, phase)| {
+                let previous = std::mem::replace(phase, ActivityPhase::Idle);
+                PhaseTransition {
+                    session_id: session_id.clone(),
+                    phase: ActivityPhase::Idle,
+                    previous: Some(previous),
+                    reason: TransitionReason::WakeReset,
+                    input: None,
+                }
+            })
+            .collect()
+    }
+
+    /// Forget a deleted session, reporting it idle one last time if it was doing anything.
+    fn remove_session(&mut self, session_id: &str, transitions: &mut Vec<PhaseTransition>) {
+        self.deadlines.remove(session_id);
+        self.pending_inputs.remove(session_id);
+        self.compacting.remove(session_id);
+        self.last_user_messages.remove(session_id);
+
+        let Some(previous) = self.phases.remove(session_id) else {
+            return;
+        };
+        if previous == ActivityPhase::Idle {
+            return;
+        }
+        transitions.push(PhaseTransition {
+            session_id: session_id.to_string(),
+            phase: ActivityPhase::Idle,
+            previous: Some(previous),
+            reason: TransitionReason::SessionDeleted,
+            input: None,
+        });
+    }
+
+    /// A new user message starts a new turn: cut any cooldown short and go straight to busy
+    /// rather than waiting for session.status to catch up.
+    fn handle_user_message(&mut self, info: &Value, transitions: &mut Vec<PhaseTransition>) {
+        let session_id = info.get("sessionID").and_then(Value::as_str);
+        let message_id = info.get("id").and_then(Value::as_str);
+        let (Some(session_id), Some(message_id)) = (session_id, message_id) else {
+            return;
+        };
+
+        if self.last_user_messages.get(session_id).map(String::as_str) == Some(message_id) {
+            return;
+        }
+        self.last_user_messages
+            .insert(session_id.to_string(), message_id.to_string());
+
+        self.set_phase(
+            session_id,
+            ActivityPhase::Busy,
+            TransitionReason::UserMessage,
+            transitions,
+        );
+    }
+
+    fn enter_cooldown_if_busy(
+        &mut self,
+        session_id: &str,
+        now: Instant,
+        cooldown: Duration,
+        transitions: &mut Vec<PhaseTransition>,
+    ) {
+        if !self
+            .phases
+            .get(session_id)
+            .is_some_and(ActivityPhase::is_busy)
+        {
+            return;
+        }
+
+        if self.set_phase(
+            session_id,
+            ActivityPhase::Cooldown,
+            TransitionReason::FinishStop,
+            transitions,
+        ) {
+            self.deadlines.insert(
+                session_id.to_string(),
+                (ActivityPhase::Cooldown, now + cooldown),
+            );
+        }
+    }
+
+    fn add_pending_input(
+        &mut self,
+        session_id: &str,
+        input: PendingInput,
+        transitions: &mut Vec<PhaseTransition>,
+    ) {
+        let entries = self
+            .pending_inputs
+            .entry(session_id.to_string())
+            .or_default();
+        if entries.contains(&input) {
+            return;
+        }
+        entries.push(input);
+
+        self.deadlines.remove(session_id);
+        let previous = self
+            .phases
+            .insert(session_id.to_string(), ActivityPhase::WaitingForInput);
+        transitions.push(self.waiting_transition(
+            session_id,
+            previous,
+            TransitionReason::InputRequested,
+        ));
+    }
+
+    fn resolve_pending_input(
+        &mut self,
+        session_id: &str,
+        input_id: &str,
+        transitions: &mut Vec<PhaseTransition>,
+    ) {
+        let Some(entries) = self.pending_inputs.get_mut(session_id) else {
+            return;
+        };
+        let before = entries.len();
+        entries.retain(|entry| entry.id != input_id);
+        if entries.len() == before {
+            return;
+        }
+
+        if !entries.is_empty() {
+            // Another prompt is still open; re-emit so the payload points at it.
+            transitions.push(self.waiting_transition(
+                session_id,
+                Some(ActivityPhase::WaitingForInput),
+                TransitionReason::InputResolved,
+            ));
+            return;
+        }
+        self.pending_inputs.remove(session_id);
+
+        // The agent resumes work once its prompt is resolved.
+        self.set_phase(
+            session_id,
+            ActivityPhase::Busy,
+            TransitionReason::InputResolved,
+            transitions,
+        );
+    }
+
+    /// Apply a phase derived from an event, honoring phase precedence.
+    /// Returns true when the phase actually changed.
+    fn set_phase(
+        &mut self,
+        session_id: &str,
+        phase: ActivityPhase,
+        reason: TransitionReason,
+        transitions: &mut Vec<PhaseTransition>,
+    ) -> bool {
+        let ends_run = matches!(phase, ActivityPhase::Idle | ActivityPhase::Error { .. });
+        if self.pending_inputs.contains_key(session_id) {
+            // Waiting for input outranks busy/cooldown. Going idle or failing means the run
+            // ended and any prompt left open was abandoned.
+            if !ends_run {
+                return false;
+            }
+            self.pending_inputs.remove(session_id);
+        }
+
+        // An error stays visible until it decays or the session gets busy again, even though
+        // session.idle usually follows session.error immediately.
+        if matches!(phase, ActivityPhase::Idle)
+            && matches!(
+                self.phases.get(session_id),
+                Some(ActivityPhase::Error { .. })
+            )
+        {
+            return false;
+        }
+
+        if phase == ActivityPhase::Compacting {
+            if !self.compacting.contains_key(session_id) {
+                // A cooldown or error timer does not survive compaction, so only whether
+                // the agent was working is remembered.
+                let underlying = match self.phases.get(session_id) {
+                    Some(current) if current.is_busy() => ActivityPhase::Busy,
+                    _ => ActivityPhase::Idle,
+                };
+                self.compacting.insert(session_id.to_string(), underlying);
+            }
+        } else if let Some(underlying) = self.compacting.get_mut(session_id) {
+            match reason {
+                // The server's own status and errors end compaction.
+                TransitionReason::StatusEvent | TransitionReason::SessionError => {
+                    self.compacting.remove(session_id);
+                }
+                // A prompt sent meanwhile starts once compaction is done.
+                TransitionReason::UserMessage => {
+                    *underlying = phase;
+                    return false;
+                }
+                // Parts and finishes streamed meanwhile belong to the summary.
+                _ => return false,
+            }
+        }
+
+        self.apply_phase(session_id, phase, reason, transitions)
+    }
+
+    /// Store a phase, dropping any deadline belonging to the old phase.
+    fn apply_phase(
+        &mut self,
+        session_id: &str,
+        phase: ActivityPhase,
+        reason: TransitionReason,
+        transitions: &mut Vec<PhaseTransition>,
+    ) -> bool {
+        if self.phases.get(session_id) == Some(&phase) {
+            return false;
+        }
+        let previous = self.phases.insert(session_id.to_string(), phase.clone());
+        self.deadlines.remove(session_id);
+
+        transitions.push(PhaseTransition {
+            session_id: session_id.to_string(),
+            phase,
+            previous,
+            reason,
+            input: None,
+        });
+        true
+    }
+
+    fn waiting_transition(
+        &self,
+        session_id: &str,
+        previous: Option<ActivityPhase>,
+        reason: TransitionReason,
+    ) -> PhaseTransition {
+        PhaseTransition {
+            session_id: session_id.to_string(),
+            phase: ActivityPhase::WaitingForInput,
+            previous,
+            reason,
+            input: self
+                .pending_inputs
+                .get(session_id)
+                .and_then(|entries| entries.first())
+                .cloned(),
+        }
+    }
+}
+
+fn is_streaming_assistant_part(properties: &Value) -> bool {
+    let Some(part) = properties.get("part") else {
+        return false;
+    };
+    let part_type = part.get("type").and_then(Value::as_str).unwrap_or_default();
+    matches!(
+        part_type,
+        "step-start" | "text" | "tool" | "reasoning" | "file" | "patch"
+    )
+}
+
+fn has_finish_stop(info: &Value) -> bool {
+    info.get("finish").and_then(Value::as_str) == Some("stop")
+}
+
+fn summarize_session_error(error: Option<&Value>) -> (String, String) {
+    let error_type = error
+        .and_then(|e| e.get("name"))
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty())
+        .unwrap_or("UnknownError")
+        .to_string();
+
+    let message = error
+        .and_then(|e| e.get("data"))
+        .and_then(|data| data.get("message"))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .unwrap_or(&error_type);
+
+    let mut summary: String = message.chars().take(ERROR_SUMMARY_MAX_CHARS).collect();
+    if message.chars().count() > ERROR_SUMMARY_MAX_CHARS {
+        summary.push('…');
+    }
+
+    (error_type, summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const COOLDOWN: Duration = Duration::from_secs(2);
+
+    fn event(event_type: &str, properties: Value) -> EventEnvelope {
+        EventEnvelope {
+            event_type: event_type.to_string(),
+            properties,
+        }
+    }
+
+    fn status(session_id: &str, status: Value) -> EventEnvelope {
+        event(
+            "session.status",
+            json!({ "sessionID": session_id, "status": status }),
+        )
+    }
+
+    fn busy(session_id: &str) -> EventEnvelope {
+        status(session_id, json!({ "type": "busy" }))
+    }
+
+    fn finish_stop(session_id: &str) -> EventEnvelope {
+        event(
+            "message.updated",
+            json!({ "info": { "id": "msg_1", "sessionID": session_id, "role": "assistant", "finish": "stop" } }),
+        )
+    }
+
+    /// `(phase, previous, reason)` of each transition, for compact assertions.
+    fn summary(
+        transitions: &[PhaseTransition],
+    ) -> Vec<(&'static str, Option<&'static str>, &'static str)> {
+        transitions
+            .iter()
+            .map(|transition| {
+                (
+                    transition.phase.as_str(),
+                    transition.previous.as_ref().map(ActivityPhase::as_str),
+                    transition.reason.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn busy_cools_down_then_goes_idle_at_the_deadline() {
+        let mut machine = ActivityStateMachine::default();
+        let start = Instant::now();
+
+        let transitions = machine.apply_event(&busy("ses_1"), start, COOLDOWN);
+        assert_eq!(summary(&transitions), [("busy", None, "status-event")]);
+
+        let finished = start + Duration::from_secs(5);
+        let transitions = machine.apply_event(&finish_stop("ses_1"), finished, COOLDOWN);
+        assert_eq!(
+            summary(&transitions),
+            [("cooldown", Some("busy"), "finish-stop")]
+        );
+        assert_eq!(machine.deadline("ses_1"), Some(finished + COOLDOWN));
+
+        assert!(machine
+            .expire(finished + COOLDOWN - Duration::from_millis(1))
+            .is_empty());
+        let transitions = machine.expire(finished + COOLDOWN);
+        assert_eq!(
+            summary(&transitions),
+            [("idle", Some("cooldown"), "cooldown-expired")]
+        );
+        assert_eq!(machine.deadline("ses_1"), None);
+        assert_eq!(machine.active_phases().count(), 0);
+    }
+
+    #[test]
+    fn retry_carries_its_metadata_and_counts_as_busy() {
+        let mut machine = ActivityStateMachine::default();
+        let start = Instant::now();
+        let retry = status(
+            "ses_1",
+            json!({
+                "type": "retry",
+                "attempt": 3,
+                "providerID": "anthropic",
+                "message": "Rate limit exceeded",
+                "next": 1_760_000_045_000_i64,
+            }),
+        );
+
+        let transitions = machine.apply_event(&retry, start, COOLDOWN);
+        assert_eq!(summary(&transitions), [("retry", None, "status-event")]);
+        let payload = transitions[0].payload(None);
+        let fields = payload.retry.expect("retry fields");
+        assert_eq!(fields.retry_attempt, Some(3));
+        assert_eq!(fields.retry_provider.as_deref(), Some("anthropic"));
+        assert_eq!(fields.retry_at, Some(1_760_000_045_000));
+
+        let transitions = machine.apply_event(&busy("ses_1"), start, COOLDOWN);
+        assert_eq!(
+            summary(&transitions),
+            [("busy", Some("retry"), "status-event")]
+        );
+
+        // A finish while still retrying ends the run like any busy phase.
+        machine.apply_event(&retry, start, COOLDOWN);
+        let transitions = machine.apply_event(&finish_stop("ses_1"), start, COOLDOWN);
+        assert_eq!(
+            summary(&transitions),
+            [("cooldown", Some("retry"), "finish-stop")]
+        );
+    }
+
+    #[test]
+    fn duplicate_events_report_one_transition() {
+        let mut machine = ActivityStateMachine::default();
+        let now = Instant::now();
+
+        assert_eq!(machine.apply_event(&busy("ses_1"), now, COOLDOWN).len(), 1);
+        assert!(machine
+            .apply_event(&busy("ses_1"), now, COOLDOWN)
+            .is_empty());
+
+        let user_message = event(
+            "message.updated",
+            json!({ "info": { "id": "msg_user", "sessionID": "ses_2", "role": "user" } }),
+        );
+        assert_eq!(
+            summary(&machine.apply_event(&user_message, now, COOLDOWN)),
+            [("busy", None, "user-message")]
+        );
+        machine.apply_event(&finish_stop("ses_2"), now, COOLDOWN);
+        // A re-sent update of the same user message is not a new turn.
+        assert!(machine.apply_event(&user_message, now, COOLDOWN).is_empty());
+
+        let asked = event(
+            "question.asked",
+            json!({ "id": "que_1", "sessionID": "ses_1" }),
+        );
+        assert_eq!(machine.apply_event(&asked, now, COOLDOWN).len(), 1);
+        assert!(machine.apply_event(&asked, now, COOLDOWN).is_empty());
+    }
+
+    #[test]
+    fn finish_without_a_run_is_ignored() {
+        let mut machine = ActivityStateMachine::default();
+        let now = Instant::now();
+
+        // The finish arrives before any sign of the run it ends.
+        assert!(machine
+            .apply_event(&finish_stop("ses_1"), now, COOLDOWN)
+            .is_empty());
+        assert_eq!(machine.deadline("ses_1"), None);
+
+        machine.apply_event(&busy("ses_1"), now, COOLDOWN);
+        let idle = event("session.idle", json!({ "sessionID": "ses_1" }));
+        assert_eq!(
+            summary(&machine.apply_event(&idle, now, COOLDOWN)),
+            [("idle", Some("busy"), "status-event")]
+        );
+        // The finish for that run shows up after the server already said idle.
+        assert!(machine
+            .apply_event(&finish_stop("ses_1"), now, COOLDOWN)
+            .is_empty());
+        assert_eq!(machine.active_phases().count(), 0);
+    }
+
+    #[test]
+    fn stale_cooldown_deadline_does_not_idle_a_busy_session() {
+        let mut machine = ActivityStateMachine::default();
+        let start = Instant::now();
+
+        machine.apply_event(&busy("ses_1"), start, COOLDOWN);
+        machine.apply_event(&finish_stop("ses_1"), start, COOLDOWN);
+        let transitions = machine.apply_event(&busy("ses_1"), start, COOLDOWN);
+        assert_eq!(
+            summary(&transitions),
+            [("busy", Some("cooldown"), "status-event")]
+        );
+
+        assert!(machine.expire(start + COOLDOWN * 2).is_empty());
+        assert_eq!(
+            machine.active_phases().collect::<Vec<_>>(),
+            [("ses_1", "busy")]
+        );
+    }
+}
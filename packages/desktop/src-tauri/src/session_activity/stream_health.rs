@@ -0,0 +1,82 @@
+use std::{collections::BTreeMap, path::Path, sync::Mutex};
+
+use chrono::Utc;
+use serde::Serialize;
+
+/// Last reported state of each activity stream, keyed by project directory with the main
+/// server under the empty key. Kept for diagnostics.
+#[derive(Default)]
+pub struct EventStreamHealth {
+    streams: Mutex<BTreeMap<String, StreamHealth>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StreamState {
+    Connected,
+    /// Failed to connect and retrying.
+    Disconnected,
+    /// Holding off while the network is down.
+    Offline,
+    /// The server refused our credentials; parked until `retry_sse_connections` or a
+    /// settings change.
+    AuthFailed,
+    /// Disconnected on purpose until `resume_background_streams`.
+    Paused,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamHealth {
+    /// Project of a dedicated instance; absent for the main server.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub directory: Option<String>,
+    pub connected: bool,
+    pub state: StreamState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// When the stream last connected or started failing, in epoch milliseconds.
+    pub since: i64,
+    /// Connection attempts that failed in a row.
+    pub consecutive_failures: u64,
+}
+
+impl EventStreamHealth {
+    pub(super) fn record(&self, directory: Option<&Path>, state: StreamState, error: Option<&str>) {
+        let Ok(mut streams) = self.streams.lock() else {
+            return;
+        };
+        let directory = directory.map(|directory| directory.to_string_lossy().to_string());
+        let connected = state == StreamState::Connected;
+        let now = Utc::now().timestamp_millis();
+        let entry = streams
+            .entry(directory.clone().unwrap_or_default())
+            .or_insert_with(|| StreamHealth {
+                directory,
+                connected,
+                state,
+                error: None,
+                since: now,
+                consecutive_failures: 0,
+            });
+        if entry.connected != connected {
+            entry.since = now;
+        }
+        entry.connected = connected;
+        entry.state = state;
+        entry.error = error.map(str::to_string);
+        // A pause is no failure.
+        entry.consecutive_failures = if connected || state == StreamState::Paused {
+            0
+        } else {
+            entry.consecutive_failures + 1
+        };
+    }
+
+    pub fn snapshot(&self) -> Vec<StreamHealth> {
+        self.streams
+            .lock()
+            .map(|streams| streams.values().cloned().collect())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,56 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::path_utils::comparable_path;
+
+/// The project directory each window is showing, as registered by the frontend, keyed by
+/// window label. Activity for a project goes only to the windows showing it.
+#[derive(Default)]
+pub struct WindowProjects {
+    windows: Mutex<HashMap<String, PathBuf>>,
+}
+
+impl WindowProjects {
+    /// `None` when the window shows no project, like the settings window.
+    pub fn register(&self, label: &str, directory: Option<&str>) {
+        let Ok(mut windows) = self.windows.lock() else {
+            return;
+        };
+        match directory
+            .map(str::trim)
+            .filter(|directory| !directory.is_empty())
+        {
+            Some(directory) => {
+                windows.insert(label.to_string(), comparable_path(Path::new(directory)));
+            }
+            None => {
+                windows.remove(label);
+            }
+        }
+    }
+
+    pub fn window_closed(&self, label: &str) {
+        if let Ok(mut windows) = self.windows.lock() {
+            windows.remove(label);
+        }
+    }
+
+    /// Labels of the windows showing `directory`, or `None` when no window registered it
+    /// and the event has to go to every window.
+    pub(super) fn windows_showing(&self, directory: &str) -> Option<Vec<String>> {
+        let windows = self.windows.lock().ok()?;
+        if windows.is_empty() {
+            return None;
+        }
+        let directory = comparable_path(Path::new(directory));
+        let labels: Vec<String> = windows
+            .iter()
+            .filter(|(_, shown)| **shown == directory)
+            .map(|(label, _)| label.clone())
+            .collect();
+        (!labels.is_empty()).then_some(labels)
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use log::debug;
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::assistant_notifications::truncate_graphemes;
+use crate::opencode_manager::with_auth;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(2);
+/// Known sessions are fetched again when seen after this long, in case an update event
+/// was missed while a stream was down.
+const INFO_TTL: Duration = Duration::from_secs(10 * 60);
+/// A failed fetch is tried again when the session next shows up, but not sooner than this.
+const RETRY_AFTER: Duration = Duration::from_secs(60);
+const TITLE_LENGTH: usize = 60;
+/// Forget everything past this many sessions; details are cheap to fetch again.
+const CACHE_CAPACITY: usize = 500;
+
+/// What the server knows about a session beyond its id.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInfo {
+    /// `None` while the session has no meaningful title yet.
+    pub title: Option<String>,
+    /// Set for a subagent's session.
+    pub parent_id: Option<String>,
+    pub directory: Option<String>,
+}
+
+impl SessionInfo {
+    fn from_session(session: &Value) -> Self {
+        let text = |key: &str| {
+            session
+                .get(key)
+                .and_then(Value::as_str)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        Self {
+            title: session
+                .get("title")
+                .and_then(Value::as_str)
+                .and_then(meaningful_title),
+            parent_id: text("parentID"),
+            directory: text("directory"),
+        }
+    }
+}
+
+enum Entry {
+    Known { info: SessionInfo, at: Instant },
+    Fetching,
+    Failed { at: Instant },
+}
+
+/// Session details by session id, shared by the activity tracker, notifications and the
+/// tray so none of them has to show a bare id. Filled by `session.updated` events and by
+/// background fetches for sessions first seen in other events.
+#[derive(Default)]
+pub struct SessionInfoCache {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl SessionInfoCache {
+    /// The session's details if already known, without fetching them.
+    pub fn get(&self, session_id: &str) -> Option<SessionInfo> {
+        match self.entries.lock().ok()?.get(session_id)? {
+            Entry::Known { info, .. } => Some(info.clone()),
+            Entry::Fetching | Entry::Failed { .. } => None,
+        }
+    }
+
+    pub fn title(&self, session_id: &str) -> Option<String> {
+        self.get(session_id)?.title
+    }
+
+    fn insert(&self, session_id: &str, entry: Entry) {
+        if let Ok(mut entries) = self.entries.lock() {
+            put(&mut entries, session_id, entry);
+        }
+    }
+
+    /// Whether `session_id` should be fetched now, marking it as being fetched if so.
+    fn claim(&self, session_id: &str) -> bool {
+        let Ok(mut entries) = self.entries.lock() else {
+            return false;
+        };
+        let due = match entries.get(session_id) {
+            None => true,
+            Some(Entry::Known { at, .. }) => at.elapsed() >= INFO_TTL,
+            Some(Entry::Fetching) => false,
+            Some(Entry::Failed { at }) => at.elapsed() >= RETRY_AFTER,
+        };
+        if due {
+            put(&mut entries, session_id, Entry::Fetching);
+        }
+        due
+    }
+
+    /// Keep the cache in step with `session.updated` events, which carry the full session.
+    pub fn update_from_event(&self, info: &Value) {
+        let Some(session_id) = info.get("id").and_then(Value::as_str) else {
+            return;
+        };
+        self.insert(
+            session_id,
+            Entry::Known {
+                info: SessionInfo::from_session(info),
+                at: Instant::now(),
+            },
+        );
+    }
+
+    pub fn remove(&self, session_id: &str) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.remove(session_id);
+        }
+    }
+
+    /// Fetch a session seen in an event in the background, unless it is known, already
+    /// being fetched, or failed too recently. Returns at once.
+    pub fn observe(
+        &self,
+        app: &AppHandle,
+        client: Client,
+        base: &str,
+        token: Option<&str>,
+        session_id: &str,
+        directory: Option<&str>,
+    ) {
+        if session_id.is_empty() || base.is_empty() || !self.claim(session_id) {
+            return;
+        }
+        let app = app.clone();
+        let base = base.to_string();
+        let token = token.map(str::to_string);
+        let session_id = session_id.to_string();
+        let directory = directory.map(str::to_string);
+        tauri::async_runtime::spawn(async move {
+            let info = fetch(
+                &client,
+                &base,
+                token.as_deref(),
+                &session_id,
+                directory.as_deref(),
+            )
+            .await;
+            app.state::<SessionInfoCache>().settle(&session_id, info);
+        });
+    }
+
+    fn settle(&self, session_id: &str, info: Option<SessionInfo>) {
+        let at = Instant::now();
+        let entry = match info {
+            Some(info) => Entry::Known { info, at },
+            None => Entry::Failed { at },
+        };
+        self.insert(session_id, entry);
+    }
+}
+
+fn put(entries: &mut HashMap<String, Entry>, session_id: &str, entry: Entry) {
+    if entries.len() >= CACHE_CAPACITY && !entries.contains_key(session_id) {
+        entries.clear();
+    }
+    entries.insert(session_id.to_string(), entry);
+}
+
+/// The session's details from the cache, or fetched with a short timeout. `None` on any
+/// failure so callers fall back to their usual text.
+pub async fn session_info(
+    app: &AppHandle,
+    client: &Client,
+    base: &str,
+    token: Option<&str>,
+    session_id: &str,
+    directory: Option<&str>,
+) -> Option<SessionInfo> {
+    if session_id.is_empty() {
+        return None;
+    }
+    let cache = app.state::<SessionInfoCache>();
+    if let Some(info) = cache.get(session_id) {
+        return Some(info);
+    }
+    let info = fetch(client, base, token, session_id, directory).await;
+    cache.settle(session_id, info.clone());
+    info
+}
+
+async fn fetch(
+    client: &Client,
+    base: &str,
+    token: Option<&str>,
+    session_id: &str,
+    directory: Option<&str>,
+) -> Option<SessionInfo> {
+    let url = format!("{base}/session/{session_id}");
+    let mut request = with_auth(client.get(&url), token).timeout(FETCH_TIMEOUT);
+    if let Some(directory) = directory {
+        request = request.query(&[("directory", directory)]);
+    }
+
+    let session = match request.send().await {
+        Ok(response) if response.status().is_success() => response.json::<Value>().await.ok()?,
+        Ok(response) => {
+            debug!(
+                "[desktop] Session fetch for {session_id} returned status {}",
+                response.status()
+            );
+            return None;
+        }
+        Err(err) => {
+            debug!("[desktop] Session fetch for {session_id} failed: {err}");
+            return None;
+        }
+    };
+    Some(SessionInfo::from_session(&session))
+}
+
+/// Sessions start out with a generated placeholder title until the first exchange names
+/// them; those say nothing useful in a notification.
+fn meaningful_title(raw: &str) -> Option<String> {
+    let title = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+    let placeholder = ["New session - ", "Child session - "]
+        .iter()
+        .any(|prefix| title.starts_with(prefix));
+    (!title.is_empty() && !placeholder).then(|| truncate_graphemes(&title, TITLE_LENGTH))
+}
@@ -0,0 +1,103 @@
+use std::sync::Mutex;
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast;
+
+use crate::recent_keys::RecentKeys;
+
+const SESSION_LIFECYCLE_EVENT: &str = "openchamber:session-lifecycle";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LifecycleKind {
+    Created,
+    /// Title and other session fields changed.
+    Updated,
+    Deleted,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionLifecycle {
+    pub kind: LifecycleKind,
+    pub session_id: String,
+    /// Project directory of the session, when known.
+    pub directory: Option<String>,
+    /// The session as OpenCode sent it.
+    pub info: Value,
+}
+
+/// Session creation, updates and deletion as seen on the event streams, for the webview
+/// and for backend modules that keep per-session state.
+pub struct SessionLifecycleEvents {
+    /// Sessions already announced as created; a reconnect can replay their events.
+    created: Mutex<RecentKeys>,
+    tx: broadcast::Sender<SessionLifecycle>,
+}
+
+impl Default for SessionLifecycleEvents {
+    fn default() -> Self {
+        Self {
+            created: Mutex::new(RecentKeys::default()),
+            tx: broadcast::channel(64).0,
+        }
+    }
+}
+
+impl SessionLifecycleEvents {
+    #[allow(dead_code)]
+    pub fn subscribe(&self) -> broadcast::Receiver<SessionLifecycle> {
+        self.tx.subscribe()
+    }
+
+    /// Emit `openchamber:session-lifecycle` for a session event, unless `emit` is false
+    /// because the type is not forwarded; backend subscribers hear of it either way.
+    /// Other events are ignored. `directory` is used when the session does not name its own.
+    pub fn forward(
+        &self,
+        app: &AppHandle,
+        event_type: &str,
+        properties: &Value,
+        directory: Option<&str>,
+        emit: bool,
+    ) {
+        let kind = match event_type {
+            "session.created" => LifecycleKind::Created,
+            "session.updated" => LifecycleKind::Updated,
+            "session.deleted" => LifecycleKind::Deleted,
+            _ => return,
+        };
+        let Some(info) = properties.get("info") else {
+            return;
+        };
+        let Some(session_id) = info.get("id").and_then(Value::as_str) else {
+            return;
+        };
+        if kind == LifecycleKind::Created
+            && !self
+                .created
+                .lock()
+                .map(|mut created| created.insert(session_id))
+                .unwrap_or(true)
+        {
+            return;
+        }
+
+        let event = SessionLifecycle {
+            kind,
+            session_id: session_id.to_string(),
+            directory: info
+                .get("directory")
+                .and_then(Value::as_str)
+                .or(directory)
+                .map(str::to_string),
+            info: info.clone(),
+        };
+        if emit {
+            let _ = app.emit(SESSION_LIFECYCLE_EVENT, &event);
+        }
+        let _ = self.tx.send(event);
+    }
+}
@@ -0,0 +1,113 @@
+use std::{
+    path::Path,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use log::{debug, info, warn};
+use tokio::sync::broadcast;
+
+use crate::desktop_settings::DesktopSettings;
+use crate::DesktopRuntime;
+
+/// How often the settings file is checked for edits made outside the app. Writes through
+/// `SettingsStore` wake the watcher right away.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Published when the settings file changes. Carries both snapshots so each subscriber
+/// decides whether the part it cares about moved.
+#[derive(Clone, Debug)]
+pub(crate) struct SettingsChanged {
+    pub previous: Arc<DesktopSettings>,
+    pub current: Arc<DesktopSettings>,
+}
+
+impl SettingsChanged {
+    pub(crate) fn project_directory_changed(&self) -> bool {
+        self.previous.project_directory() != self.current.project_directory()
+    }
+
+    /// Global preferences, quiet hours and sounds included, or a project's level or label.
+    pub(crate) fn notifications_changed(&self) -> bool {
+        self.previous.notifications != self.current.notifications
+            || self.previous.projects != self.current.projects
+    }
+
+    pub(crate) fn projects_changed(&self) -> bool {
+        self.previous.projects != self.current.projects
+    }
+
+    pub(crate) fn http_changed(&self) -> bool {
+        self.previous.http != self.current.http
+    }
+
+    /// How the OpenCode server is reached, credentials included.
+    pub(crate) fn opencode_changed(&self) -> bool {
+        self.previous.extra.get("opencode") != self.current.extra.get("opencode")
+    }
+
+    pub(crate) fn activity_changed(&self) -> bool {
+        self.previous.activity_error_decay_seconds != self.current.activity_error_decay_seconds
+    }
+}
+
+/// Wait for the next settings change. Changes missed by a slow subscriber are skipped,
+/// so subscribers compare against `current` rather than replaying each step.
+pub(crate) async fn next_settings_change(
+    changes: &mut broadcast::Receiver<SettingsChanged>,
+) -> SettingsChanged {
+    loop {
+        match changes.recv().await {
+            Ok(change) => return change,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => std::future::pending().await,
+        }
+    }
+}
+
+/// Reload settings whenever the file changes and publish the difference.
+pub fn spawn_settings_watcher(runtime: DesktopRuntime) -> tauri::async_runtime::JoinHandle<()> {
+    tauri::async_runtime::spawn(async move {
+        let mut shutdown_rx = runtime.subscribe_shutdown();
+        let store = runtime.settings();
+        let mut stamp = file_stamp(store.path()).await;
+        let mut snapshot = Arc::new(store.load_typed().await.unwrap_or_default());
+
+        loop {
+            let written = tokio::select! {
+                _ = shutdown_rx.recv() => {
+                    info!("[desktop] Shutdown received, stopping settings watcher");
+                    break;
+                }
+                _ = store.wait_for_write() => true,
+                _ = tokio::time::sleep(POLL_INTERVAL) => false,
+            };
+
+            let next_stamp = file_stamp(store.path()).await;
+            if !written && next_stamp == stamp {
+                continue;
+            }
+            stamp = next_stamp;
+
+            let current = match store.load_typed().await {
+                Ok(settings) => Arc::new(settings),
+                Err(err) => {
+                    warn!("[desktop] Failed to reload settings: {err}");
+                    continue;
+                }
+            };
+            if current == snapshot {
+                continue;
+            }
+
+            debug!("[desktop] Settings changed; notifying listeners");
+            let previous = std::mem::replace(&mut snapshot, current.clone());
+            runtime.publish_settings_change(SettingsChanged { previous, current });
+        }
+    })
+}
+
+async fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = tokio::fs::metadata(path).await.ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
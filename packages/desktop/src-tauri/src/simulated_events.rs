@@ -0,0 +1,46 @@
+use anyhow::Result;
+use tokio::sync::broadcast;
+
+/// Room for a burst of simulated events while a listener is still handling earlier ones,
+/// such as a sped-up replay.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// An event fed in by `simulate_desktop_event` or a capture replay.
+#[derive(Clone)]
+pub struct SimulatedEvent {
+    pub raw: String,
+    /// Whether the notification listener handles it too. Notifying looks sessions up on
+    /// the server and marks events as notified, which a replay leaves alone by default.
+    pub notify: bool,
+}
+
+/// Events the activity tracker and the notification listener handle as if their own
+/// streams had read them.
+pub struct SimulatedEvents {
+    tx: broadcast::Sender<SimulatedEvent>,
+}
+
+impl Default for SimulatedEvents {
+    fn default() -> Self {
+        let (tx, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { tx }
+    }
+}
+
+impl SimulatedEvents {
+    pub fn subscribe(&self) -> broadcast::Receiver<SimulatedEvent> {
+        self.tx.subscribe()
+    }
+
+    /// Parse `raw` the way both listeners do, then hand it to them. Nothing is sent
+    /// unless both accept it.
+    pub fn send(&self, raw: &str, notify: bool) -> Result<()> {
+        crate::session_activity::validate_event(raw)?;
+        crate::assistant_notifications::validate_event(raw)?;
+        let _ = self.tx.send(SimulatedEvent {
+            raw: raw.to_string(),
+            notify,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use std::path::Path;
+
+use log::info;
+use tauri::{AppHandle, Url};
+
+use crate::deep_links::{handle_deep_links, project_link};
+use crate::path_utils::expand_path;
+use crate::tray::show_main_window;
+
+/// Set to any value to allow a second instance, for example one started against another
+/// data directory while developing. Two instances sharing a data directory fight over
+/// its servers and storage.
+const ALLOW_MULTIPLE_INSTANCES_ENV: &str = "OPENCHAMBER_ALLOW_MULTIPLE_INSTANCES";
+
+/// Whether a second launch should hand over to the running instance.
+pub fn single_instance_enforced() -> bool {
+    std::env::var_os(ALLOW_MULTIPLE_INSTANCES_ENV).is_none()
+}
+
+/// Runs in the first instance when the app is launched again. The new process has already
+/// exited; its arguments are followed here, and the window comes up even if it was hidden
+/// to the tray.
+pub fn handle_second_instance(app: &AppHandle, argv: Vec<String>, cwd: String) {
+    info!("[desktop] Another launch was forwarded to this instance");
+    show_main_window(app);
+    let links = argv
+        .iter()
+        .skip(1)
+        .filter_map(|argument| argument_link(argument, Path::new(&cwd)))
+        .collect();
+    handle_deep_links(app, links);
+}
+
+/// Follow the project path the app was launched with, if any. Links are read by the deep
+/// link plugin at launch.
+pub fn handle_launch_arguments(app: &AppHandle) {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let links = std::env::args()
+        .skip(1)
+        .filter(|argument| !argument.starts_with("openchamber:"))
+        .filter_map(|argument| argument_link(&argument, &cwd))
+        .collect();
+    handle_deep_links(app, links);
+}
+
+/// An `openchamber://` link as given, or any other path, relative to `cwd`, as the link
+/// opening it as a project; one that is not a folder is reported when followed. Flags are
+/// ignored.
+fn argument_link(argument: &str, cwd: &Path) -> Option<Url> {
+    let argument = argument.trim();
+    if argument.is_empty() || argument.starts_with('-') {
+        return None;
+    }
+    if argument.starts_with("openchamber:") {
+        return Url::parse(argument).ok();
+    }
+
+    project_link(&cwd.join(expand_path(argument)))
+}
@@ -0,0 +1,395 @@
+use std::{sync::Mutex, time::Duration};
+
+use chrono::Utc;
+use log::{info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use tokio::time::Instant;
+
+use crate::session_activity::{BusySession, BusySessions};
+use crate::settings_watcher::next_settings_change;
+use crate::DesktopRuntime;
+
+/// How long every session must have been idle before sleep is allowed again, so a
+/// session that pauses between steps does not flap the assertion.
+const RELEASE_AFTER_IDLE: Duration = Duration::from_secs(60);
+const INHIBIT_REASON: &str = "OpenChamber sessions are working";
+
+/// What `get_power_state` reports to the settings UI.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SleepInhibitionState {
+    /// `power.preventSleepWhileBusy`.
+    pub enabled: bool,
+    /// System sleep is currently blocked.
+    pub inhibiting: bool,
+    /// When the current inhibition began, in milliseconds since the Unix epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<i64>,
+    /// Why the last attempt to block sleep failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The inhibitor's state, readable from commands.
+#[derive(Default)]
+pub struct SleepInhibition {
+    state: Mutex<SleepInhibitionState>,
+}
+
+impl SleepInhibition {
+    pub fn snapshot(&self) -> SleepInhibitionState {
+        self.state
+            .lock()
+            .map(|state| state.clone())
+            .unwrap_or_default()
+    }
+
+    fn update(&self, apply: impl FnOnce(&mut SleepInhibitionState)) {
+        if let Ok(mut state) = self.state.lock() {
+            apply(&mut state);
+        }
+    }
+}
+
+/// A compacting session is still mid-run, so it holds sleep off like a busy one.
+fn is_working(session: &BusySession) -> bool {
+    matches!(session.phase, "busy" | "retry" | "compacting")
+}
+
+/// Tracks how long sessions have been idle while sleep is blocked, to decide when to let
+/// it go. Reads tokio's clock, so tests can drive it with a paused one.
+#[derive(Default)]
+struct IdleRelease {
+    idle_since: Option<Instant>,
+}
+
+impl IdleRelease {
+    fn working(&mut self) {
+        self.idle_since = None;
+    }
+
+    /// Whether to release now, with every session idle. Releases right away once the
+    /// setting is off; otherwise after `RELEASE_AFTER_IDLE` of idling.
+    fn should_release(&mut self, enabled: bool) -> bool {
+        let idle_for = self.idle_since.get_or_insert_with(Instant::now).elapsed();
+        let release = !enabled || idle_for >= RELEASE_AFTER_IDLE;
+        if release {
+            self.idle_since = None;
+        }
+        release
+    }
+
+    /// When the idle period in progress runs out.
+    fn release_at(&self) -> Option<Instant> {
+        self.idle_since.map(|since| since + RELEASE_AFTER_IDLE)
+    }
+}
+
+/// Block system sleep while any session is working, when `power.preventSleepWhileBusy` is
+/// on. Display sleep stays allowed.
+pub fn spawn_sleep_inhibitor(
+    app: AppHandle,
+    runtime: DesktopRuntime,
+) -> tauri::async_runtime::JoinHandle<()> {
+    tauri::async_runtime::spawn(async move {
+        let mut shutdown_rx = runtime.subscribe_shutdown();
+        let mut settings_changes = runtime.subscribe_settings_changes();
+        let mut busy = app.state::<BusySessions>().subscribe();
+        let status = app.state::<SleepInhibition>();
+        let mut enabled = runtime
+            .settings()
+            .load_typed()
+            .await
+            .map(|settings| settings.power.prevent_sleep_while_busy)
+            .unwrap_or(false);
+        let mut inhibitor: Option<platform::Inhibitor> = None;
+        let mut idle = IdleRelease::default();
+
+        loop {
+            let working = busy.borrow_and_update().iter().any(is_working);
+            if enabled && working {
+                idle.working();
+                if inhibitor.is_none() {
+                    match platform::Inhibitor::acquire(INHIBIT_REASON).await {
+                        Ok(acquired) => {
+                            info!("[desktop:power] Blocking system sleep while sessions work");
+                            inhibitor = Some(acquired);
+                            status.update(|state| {
+                                state.since = Some(Utc::now().timestamp_millis());
+                                state.error = None;
+                            });
+                        }
+                        Err(err) => {
+                            warn!("[desktop:power] Failed to block system sleep: {err}");
+                            status.update(|state| state.error = Some(err.to_string()));
+                        }
+                    }
+                }
+            } else if inhibitor.is_some() && idle.should_release(enabled) {
+                inhibitor = None;
+                info!("[desktop:power] Allowing system sleep again");
+            }
+            status.update(|state| {
+                state.enabled = enabled;
+                state.inhibiting = inhibitor.is_some();
+                if inhibitor.is_none() {
+                    state.since = None;
+                }
+            });
+
+            let release_at = idle.release_at().filter(|_| inhibitor.is_some());
+            tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                Ok(()) = busy.changed() => {}
+                change = next_settings_change(&mut settings_changes) => {
+                    enabled = change.current.power.prevent_sleep_while_busy;
+                }
+                _ = tokio::time::sleep_until(release_at.unwrap_or_else(Instant::now)),
+                    if release_at.is_some() => {}
+            }
+        }
+
+        drop(inhibitor);
+        status.update(|state| {
+            state.inhibiting = false;
+            state.since = None;
+        });
+    })
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::{c_char, c_void, CStr, CString};
+
+    use anyhow::{anyhow, Result};
+
+    type CFStringRef = *const c_void;
+    type IOPMAssertionID = u32;
+
+    const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+    const K_IOPM_ASSERTION_LEVEL_ON: u32 = 255;
+    const K_IO_RETURN_SUCCESS: i32 = 0;
+    /// Blocks idle system sleep but not display sleep.
+    const PREVENT_IDLE_SYSTEM_SLEEP: &CStr = c"PreventUserIdleSystemSleep";
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringCreateWithCString(
+            allocator: *const c_void,
+            value: *const c_char,
+            encoding: u32,
+        ) -> CFStringRef;
+        fn CFRelease(value: *const c_void);
+    }
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IOPMAssertionCreateWithName(
+            assertion_type: CFStringRef,
+            level: u32,
+            name: CFStringRef,
+            assertion_id: *mut IOPMAssertionID,
+        ) -> i32;
+        fn IOPMAssertionRelease(assertion_id: IOPMAssertionID) -> i32;
+    }
+
+    /// An IOKit power assertion, released on drop.
+    pub(super) struct Inhibitor(IOPMAssertionID);
+
+    impl Inhibitor {
+        pub(super) async fn acquire(reason: &str) -> Result<Self> {
+            let reason = CString::new(reason)?;
+            let mut assertion_id: IOPMAssertionID = 0;
+            let result = unsafe {
+                let assertion_type = CFStringCreateWithCString(
+                    std::ptr::null(),
+                    PREVENT_IDLE_SYSTEM_SLEEP.as_ptr(),
+                    K_CF_STRING_ENCODING_UTF8,
+                );
+                let name = CFStringCreateWithCString(
+                    std::ptr::null(),
+                    reason.as_ptr(),
+                    K_CF_STRING_ENCODING_UTF8,
+                );
+                if assertion_type.is_null() || name.is_null() {
+                    for value in [assertion_type, name] {
+                        if !value.is_null() {
+                            CFRelease(value);
+                        }
+                    }
+                    return Err(anyhow!("CFStringCreateWithCString failed"));
+                }
+                let result = IOPMAssertionCreateWithName(
+                    assertion_type,
+                    K_IOPM_ASSERTION_LEVEL_ON,
+                    name,
+                    &mut assertion_id,
+                );
+                CFRelease(assertion_type);
+                CFRelease(name);
+                result
+            };
+            if result != K_IO_RETURN_SUCCESS {
+                return Err(anyhow!("IOPMAssertionCreateWithName returned {result:#x}"));
+            }
+            Ok(Self(assertion_id))
+        }
+    }
+
+    impl Drop for Inhibitor {
+        fn drop(&mut self) {
+            unsafe {
+                IOPMAssertionRelease(self.0);
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::sync::mpsc;
+
+    use anyhow::{anyhow, Result};
+    use windows_sys::Win32::System::Power::{
+        SetThreadExecutionState, ES_CONTINUOUS, ES_SYSTEM_REQUIRED,
+    };
+
+    /// `SetThreadExecutionState` belongs to the calling thread, so a dedicated thread holds
+    /// it until the sender is dropped.
+    pub(super) struct Inhibitor(#[allow(dead_code)] mpsc::Sender<()>);
+
+    impl Inhibitor {
+        pub(super) async fn acquire(_reason: &str) -> Result<Self> {
+            let (release_tx, release_rx) = mpsc::channel::<()>();
+            let (ready_tx, ready_rx) = tokio::sync::oneshot::channel();
+            std::thread::Builder::new()
+                .name("sleep-inhibitor".to_string())
+                .spawn(move || {
+                    // ES_DISPLAY_REQUIRED is left out so the display can still sleep.
+                    let previous =
+                        unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) };
+                    let _ = ready_tx.send(previous != 0);
+                    if previous == 0 {
+                        return;
+                    }
+                    // Returns once the sender is dropped.
+                    let _ = release_rx.recv();
+                    unsafe {
+                        SetThreadExecutionState(ES_CONTINUOUS);
+                    }
+                })?;
+            match ready_rx.await {
+                Ok(true) => Ok(Self(release_tx)),
+                _ => Err(anyhow!("SetThreadExecutionState failed")),
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use anyhow::Result;
+    use zbus::zvariant::OwnedFd;
+
+    /// A logind `sleep` inhibitor lock, held for as long as its descriptor is open.
+    pub(super) struct Inhibitor(#[allow(dead_code)] OwnedFd);
+
+    impl Inhibitor {
+        pub(super) async fn acquire(reason: &str) -> Result<Self> {
+            let connection = zbus::Connection::system().await?;
+            let manager = zbus::Proxy::new(
+                &connection,
+                "org.freedesktop.login1",
+                "/org/freedesktop/login1",
+                "org.freedesktop.login1.Manager",
+            )
+            .await?;
+            // `sleep` rather than `idle`, so the screen can still blank and lock.
+            let fd: OwnedFd = manager
+                .call("Inhibit", &("sleep", "OpenChamber", reason, "block"))
+                .await?;
+            Ok(Self(fd))
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+mod platform {
+    use anyhow::{anyhow, Result};
+
+    pub(super) struct Inhibitor;
+
+    impl Inhibitor {
+        pub(super) async fn acquire(_reason: &str) -> Result<Self> {
+            Err(anyhow!("not supported on this platform"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn releases_after_a_minute_of_idling() {
+        let mut idle = IdleRelease::default();
+
+        assert!(!idle.should_release(true));
+        let release_at = idle.release_at().expect("idle period started");
+        assert_eq!(release_at, Instant::now() + RELEASE_AFTER_IDLE);
+
+        tokio::time::advance(RELEASE_AFTER_IDLE - Duration::from_secs(1)).await;
+        assert!(!idle.should_release(true));
+
+        tokio::time::sleep_until(release_at).await;
+        assert!(idle.should_release(true));
+        assert_eq!(idle.release_at(), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn work_restarts_the_idle_period() {
+        let mut idle = IdleRelease::default();
+
+        assert!(!idle.should_release(true));
+        tokio::time::advance(Duration::from_secs(45)).await;
+        idle.working();
+        assert_eq!(idle.release_at(), None);
+
+        // A short pause between steps does not add up with the earlier one.
+        assert!(!idle.should_release(true));
+        tokio::time::advance(Duration::from_secs(45)).await;
+        assert!(!idle.should_release(true));
+        tokio::time::advance(Duration::from_secs(15)).await;
+        assert!(idle.should_release(true));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn turning_the_setting_off_releases_at_once() {
+        let mut idle = IdleRelease::default();
+
+        assert!(!idle.should_release(true));
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert!(idle.should_release(false));
+    }
+
+    #[test]
+    fn running_phases_count_as_working() {
+        let cases = [
+            ("busy", true),
+            ("retry", true),
+            ("compacting", true),
+            ("cooldown", false),
+            ("waiting-for-input", false),
+            ("error", false),
+        ];
+        for (phase, working) in cases {
+            let session = BusySession {
+                session_id: "ses_1".to_string(),
+                phase,
+                directory: None,
+            };
+            assert_eq!(is_working(&session), working, "{phase}");
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::diagnostics::{is_secret_key, REDACTED};
+use crate::logging::log_directory;
+use crate::simulated_events::SimulatedEvents;
+
+/// A capture stops growing here; the frames up to the cap are kept.
+const MAX_CAPTURE_BYTES: u64 = 25 * 1024 * 1024;
+/// How much of any text the agent or user wrote is kept, enough to tell messages apart.
+const TEXT_PREFIX_CHARS: usize = 24;
+/// Keys whose string values, at any depth below them, are conversation or file content.
+const TEXT_KEYS: &[&str] = &[
+    "text",
+    "content",
+    "output",
+    "input",
+    "delta",
+    "diff",
+    "diffs",
+    "before",
+    "after",
+    "patch",
+    "snapshot",
+    "question",
+    "questions",
+    "header",
+    "label",
+    "description",
+    "title",
+    "message",
+    "prompt",
+    "body",
+    "metadata",
+];
+
+/// One captured SSE data frame, a line of the ndjson file.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CapturedFrame {
+    /// Milliseconds since the capture started.
+    at: u64,
+    /// The project a dedicated instance's stream belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    directory: Option<String>,
+    /// The event, redacted; a frame that was not JSON is kept as a string.
+    data: Value,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureStatus {
+    pub path: String,
+    pub frames: u64,
+    pub bytes: u64,
+    /// Whether the size cap was reached and later frames were dropped.
+    pub truncated: bool,
+}
+
+struct ActiveCapture {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    started: Instant,
+    frames: u64,
+    bytes: u64,
+    truncated: bool,
+}
+
+impl ActiveCapture {
+    fn status(&self) -> CaptureStatus {
+        CaptureStatus {
+            path: self.path.to_string_lossy().to_string(),
+            frames: self.frames,
+            bytes: self.bytes,
+            truncated: self.truncated,
+        }
+    }
+}
+
+/// Raw SSE frames written to a file for bug reports, so a stuck indicator can be
+/// reproduced later with `replay_sse_capture`. Off until started; only the activity
+/// streams are captured since every listener sees the same frames.
+#[derive(Default)]
+pub struct SseCapture {
+    active: Mutex<Option<ActiveCapture>>,
+}
+
+impl SseCapture {
+    /// Start a new capture file in the log directory, ending any capture in progress.
+    pub fn start(&self) -> Result<CaptureStatus> {
+        let directory = log_directory().ok_or_else(|| anyhow!("No log directory"))?;
+        std::fs::create_dir_all(&directory)?;
+        let path = directory.join(format!(
+            "sse-capture-{}.ndjson",
+            Utc::now().format("%Y%m%d-%H%M%S")
+        ));
+        let writer = BufWriter::new(File::create(&path)?);
+        let capture = ActiveCapture {
+            path,
+            writer,
+            started: Instant::now(),
+            frames: 0,
+            bytes: 0,
+            truncated: false,
+        };
+        let status = capture.status();
+        let mut active = self
+            .active
+            .lock()
+            .map_err(|_| anyhow!("Capture lock poisoned"))?;
+        if let Some(mut previous) = active.replace(capture) {
+            let _ = previous.writer.flush();
+        }
+        info!("[desktop:capture] Capturing SSE frames to {}", status.path);
+        Ok(status)
+    }
+
+    /// End the capture in progress, if any, and say where it went.
+    pub fn stop(&self) -> Option<CaptureStatus> {
+        let mut capture = self.active.lock().ok()?.take()?;
+        if let Err(err) = capture.writer.flush() {
+            warn!("[desktop:capture] Failed to flush capture: {err}");
+        }
+        let status = capture.status();
+        info!(
+            "[desktop:capture] Captured {} frames to {}",
+            status.frames, status.path
+        );
+        Some(status)
+    }
+
+    /// Append one data frame as the stream delivered it.
+    pub fn record(&self, raw: &str, directory: Option<&Path>) {
+        let Ok(mut active) = self.active.lock() else {
+            return;
+        };
+        let Some(capture) = active.as_mut() else {
+            return;
+        };
+        if capture.truncated {
+            return;
+        }
+
+        let mut data = serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.into()));
+        // A frame that is not JSON cannot be told apart from text, so it is cut as text.
+        let text = data.is_string();
+        redact(&mut data, text);
+        let frame = CapturedFrame {
+            at: capture.started.elapsed().as_millis() as u64,
+            directory: directory.map(|path| path.to_string_lossy().to_string()),
+            data,
+        };
+        let Ok(mut line) = serde_json::to_vec(&frame) else {
+            return;
+        };
+        line.push(b'\n');
+        if capture.bytes + line.len() as u64 > MAX_CAPTURE_BYTES {
+            capture.truncated = true;
+            let _ = capture.writer.flush();
+            warn!(
+                "[desktop:capture] Capture reached {} MiB; later frames are dropped",
+                MAX_CAPTURE_BYTES / 1024 / 1024
+            );
+            return;
+        }
+        // Flushed per frame so a capture survives the crash it is meant to explain.
+        let written = capture
+            .writer
+            .write_all(&line)
+            .and_then(|()| capture.writer.flush());
+        match written {
+            Ok(()) => {
+                capture.frames += 1;
+                capture.bytes += line.len() as u64;
+            }
+            Err(err) => {
+                warn!("[desktop:capture] Failed to write capture, stopping: {err}");
+                *active = None;
+            }
+        }
+    }
+}
+
+/// Feed a capture's frames to the listeners, keeping their recorded spacing divided by
+/// `speed`. Frames go to the activity tracker only unless `notify` is set. Returns how
+/// many frames were replayed.
+pub async fn replay(
+    simulated: &SimulatedEvents,
+    path: &Path,
+    speed: f64,
+    notify: bool,
+) -> Result<u64> {
+    if !speed.is_finite() || speed <= 0.0 {
+        return Err(anyhow!("Replay speed must be a positive number"));
+    }
+    let frames = read_frames(path)?;
+    info!(
+        "[desktop:capture] Replaying {} frames from {} at {speed}x",
+        frames.len(),
+        path.display()
+    );
+
+    let mut replayed = 0;
+    let mut previous_at = frames.first().map(|frame| frame.at).unwrap_or_default();
+    for frame in frames {
+        let gap = frame.at.saturating_sub(previous_at);
+        previous_at = frame.at;
+        if gap > 0 {
+            tokio::time::sleep(Duration::from_secs_f64(gap as f64 / 1000.0 / speed)).await;
+        }
+        let raw = match (frame.directory, frame.data) {
+            (Some(directory), data) if data.get("payload").is_none() => {
+                json!({ "directory": directory, "payload": data }).to_string()
+            }
+            (_, Value::String(raw)) => raw,
+            (_, data) => data.to_string(),
+        };
+        // Frames that did not parse when captured do not parse now either.
+        if simulated.send(&raw, notify).is_ok() {
+            replayed += 1;
+        }
+    }
+    Ok(replayed)
+}
+
+fn read_frames(path: &Path) -> Result<Vec<CapturedFrame>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut frames = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let frame = serde_json::from_str(&line)
+            .map_err(|err| anyhow!("Line {} is not a captured frame: {err}", index + 1))?;
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// Cut conversation and file content to a short prefix and mask anything stored under
+/// a secret-looking key. Ids, types and timestamps are kept whole; replay needs them.
+fn redact(value: &mut Value, text: bool) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if !value.is_null() && is_secret_key(key) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact(value, text || TEXT_KEYS.contains(&key.as_str()));
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| redact(item, text)),
+        Value::String(content) if text && content.chars().count() > TEXT_PREFIX_CHARS => {
+            let mut prefix: String = content.chars().take(TEXT_PREFIX_CHARS).collect();
+            prefix.push('…');
+            *content = prefix;
+        }
+        _ => {}
+    }
+}
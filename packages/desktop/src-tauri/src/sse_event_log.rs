@@ -0,0 +1,236 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::diagnostics::{is_secret_key, REDACTED};
+
+pub const DEFAULT_CAPACITY: usize = 500;
+pub const MAX_CAPACITY: usize = 10_000;
+
+/// Longest string kept in a preview; message text would otherwise dominate the buffer.
+const PREVIEW_STRING_CHARS: usize = 64;
+const PREVIEW_ARRAY_ITEMS: usize = 3;
+const PREVIEW_OBJECT_KEYS: usize = 16;
+const PREVIEW_DEPTH: usize = 3;
+/// Distinct event types counted; a misbehaving server cannot grow the table past this.
+const MAX_COUNTED_TYPES: usize = 256;
+
+/// Values that look like credentials wherever they appear: provider API keys, access
+/// tokens and bearer headers.
+static API_KEY_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)\bbearer\s+\S+|\b(?:sk|pk|rk)[-_][A-Za-z0-9_-]{16,}|\bgh[pousr]_[A-Za-z0-9]{20,}|\bgithub_pat_[A-Za-z0-9_]{20,}|\bxox[abprs]-[A-Za-z0-9-]{10,}|\bAKIA[0-9A-Z]{16}\b|\bAIza[0-9A-Za-z_-]{30,}",
+    )
+    .expect("valid regex")
+});
+
+/// One envelope as the backend parsed it.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SseEventRecord {
+    #[serde(rename = "type")]
+    pub event_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub directory: Option<String>,
+    /// The properties cut down to their shape: short strings, a few array items and
+    /// nothing that looks like a key.
+    pub properties: Value,
+    /// Receipt time in epoch milliseconds.
+    pub received_at: i64,
+    /// The listener whose stream carried the event, `activity` or `notifications`.
+    pub stream: &'static str,
+}
+
+/// How often one event type was received.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventTypeCount {
+    #[serde(rename = "type")]
+    pub event_type: String,
+    /// Envelopes of this type across all listeners since launch.
+    pub count: u64,
+}
+
+/// The most recent SSE envelopes received by the listeners, oldest first, for working out
+/// what the backend saw when a session's state looks wrong.
+pub struct SseEventLog {
+    capacity: AtomicUsize,
+    events: Mutex<VecDeque<SseEventRecord>>,
+    /// Counted even while the buffer is off, so every type seen since launch is known.
+    type_counts: Mutex<BTreeMap<String, u64>>,
+}
+
+impl Default for SseEventLog {
+    fn default() -> Self {
+        Self {
+            capacity: AtomicUsize::new(DEFAULT_CAPACITY),
+            events: Mutex::new(VecDeque::new()),
+            type_counts: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl SseEventLog {
+    /// Keep at most `capacity` envelopes; 0 stops recording. `None` restores the default.
+    pub fn set_capacity(&self, capacity: Option<u64>) {
+        let capacity = capacity
+            .map(|value| (value as usize).min(MAX_CAPACITY))
+            .unwrap_or(DEFAULT_CAPACITY);
+        self.capacity.store(capacity, Ordering::Relaxed);
+        if let Ok(mut events) = self.events.lock() {
+            let excess = events.len().saturating_sub(capacity);
+            events.drain(..excess);
+        }
+    }
+
+    pub fn record(
+        &self,
+        stream: &'static str,
+        event_type: &str,
+        directory: Option<&str>,
+        properties: &Value,
+    ) {
+        if let Ok(mut counts) = self.type_counts.lock() {
+            if let Some(count) = counts.get_mut(event_type) {
+                *count += 1;
+            } else if counts.len() < MAX_COUNTED_TYPES {
+                counts.insert(event_type.to_string(), 1);
+            }
+        }
+        let capacity = self.capacity.load(Ordering::Relaxed);
+        if capacity == 0 {
+            return;
+        }
+        let record = SseEventRecord {
+            event_type: event_type.to_string(),
+            directory: directory.map(str::to_string),
+            properties: preview(properties, 0),
+            received_at: Utc::now().timestamp_millis(),
+            stream,
+        };
+        let Ok(mut events) = self.events.lock() else {
+            return;
+        };
+        while events.len() >= capacity {
+            events.pop_front();
+        }
+        events.push_back(record);
+    }
+
+    /// The last `limit` envelopes, oldest first. `type_filter` matches an event type
+    /// exactly or as a dotted prefix, so `session` covers `session.status`.
+    pub fn recent(&self, limit: Option<usize>, type_filter: Option<&str>) -> Vec<SseEventRecord> {
+        let Ok(events) = self.events.lock() else {
+            return Vec::new();
+        };
+        let type_filter = type_filter
+            .map(str::trim)
+            .filter(|filter| !filter.is_empty());
+        let mut recent: Vec<SseEventRecord> = events
+            .iter()
+            .rev()
+            .filter(|event| {
+                type_filter.map_or(true, |filter| matches_type(&event.event_type, filter))
+            })
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect();
+        recent.reverse();
+        recent
+    }
+
+    /// Every event type received since launch, by name.
+    pub fn known_types(&self) -> Vec<EventTypeCount> {
+        let Ok(counts) = self.type_counts.lock() else {
+            return Vec::new();
+        };
+        counts
+            .iter()
+            .map(|(event_type, count)| EventTypeCount {
+                event_type: event_type.clone(),
+                count: *count,
+            })
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut events) = self.events.lock() {
+            events.clear();
+        }
+    }
+}
+
+fn matches_type(event_type: &str, filter: &str) -> bool {
+    event_type
+        .strip_prefix(filter)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+fn preview(value: &Value, depth: usize) -> Value {
+    match value {
+        Value::String(text) => Value::String(preview_string(text)),
+        Value::Array(items) if depth >= PREVIEW_DEPTH => {
+            Value::String(format!("[{} items]", items.len()))
+        }
+        Value::Object(map) if depth >= PREVIEW_DEPTH => {
+            Value::String(format!("{{{} keys}}", map.len()))
+        }
+        Value::Array(items) => {
+            let mut preview_items: Vec<Value> = items
+                .iter()
+                .take(PREVIEW_ARRAY_ITEMS)
+                .map(|item| preview(item, depth + 1))
+                .collect();
+            if items.len() > PREVIEW_ARRAY_ITEMS {
+                preview_items.push(Value::String(format!(
+                    "… {} more",
+                    items.len() - PREVIEW_ARRAY_ITEMS
+                )));
+            }
+            Value::Array(preview_items)
+        }
+        Value::Object(map) => {
+            let mut preview_map = Map::new();
+            for (key, value) in map.iter().take(PREVIEW_OBJECT_KEYS) {
+                let value = if !value.is_null() && is_secret_key(key) {
+                    Value::String(REDACTED.to_string())
+                } else {
+                    preview(value, depth + 1)
+                };
+                preview_map.insert(key.clone(), value);
+            }
+            if map.len() > PREVIEW_OBJECT_KEYS {
+                preview_map.insert(
+                    "…".to_string(),
+                    Value::String(format!("{} more keys", map.len() - PREVIEW_OBJECT_KEYS)),
+                );
+            }
+            Value::Object(preview_map)
+        }
+        _ => value.clone(),
+    }
+}
+
+/// Redact before cutting, so a key straddling the cut cannot leak its first half. Only a
+/// bounded head is scanned; anything past it is cut regardless.
+fn preview_string(text: &str) -> String {
+    let head: String = text.chars().take(PREVIEW_STRING_CHARS * 4).collect();
+    let cut_off = head.len() < text.len();
+    let text = API_KEY_PATTERN.replace_all(&head, REDACTED);
+    if !cut_off && text.chars().count() <= PREVIEW_STRING_CHARS {
+        return text.into_owned();
+    }
+    let mut cut: String = text.chars().take(PREVIEW_STRING_CHARS).collect();
+    cut.push('…');
+    cut
+}
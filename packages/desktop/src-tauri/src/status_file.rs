@@ -0,0 +1,124 @@
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use log::{info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::assistant_notifications::PendingQuestions;
+use crate::opencode_manager::OpenCodeStatus;
+use crate::session_activity::{BusySession, BusySessions};
+use crate::settings_watcher::next_settings_change;
+use crate::DesktopRuntime;
+
+const STATUS_FILE: &str = "status.json";
+/// Bursts of activity changes are written once.
+const WRITE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// What `status.json` holds.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StatusSnapshot {
+    /// Milliseconds since the Unix epoch.
+    updated_at: i64,
+    /// Every session that is not idle, as in the tray menu.
+    sessions: Vec<BusySession>,
+    pending_questions: usize,
+    server: OpenCodeStatus,
+}
+
+/// `~/.config/openchamber/status.json`, next to the settings file.
+pub fn status_file_path() -> Result<PathBuf> {
+    let mut path = dirs::home_dir().ok_or_else(|| anyhow!("No home directory"))?;
+    path.push(".config");
+    path.push("openchamber");
+    path.push(STATUS_FILE);
+    Ok(path)
+}
+
+/// Keep `status.json` current while `integrations.statusFile` is on, so widgets and
+/// scripts can follow the agents without the app's IPC. The file is removed when the
+/// setting is turned off and when the app quits, so a stale one never reads as live.
+pub fn spawn_status_file(
+    app: AppHandle,
+    runtime: DesktopRuntime,
+) -> tauri::async_runtime::JoinHandle<()> {
+    tauri::async_runtime::spawn(async move {
+        let mut shutdown_rx = runtime.subscribe_shutdown();
+        let mut settings_changes = runtime.subscribe_settings_changes();
+        let mut sessions = app.state::<BusySessions>().subscribe();
+        let mut questions = app.state::<PendingQuestions>().subscribe_count();
+        let mut server = runtime.opencode_manager().subscribe_status();
+        let mut enabled = runtime
+            .settings()
+            .load_typed()
+            .await
+            .map(|settings| settings.integrations.status_file)
+            .unwrap_or(false);
+        // Left behind by a run that did not shut down cleanly.
+        let mut written = status_file_path().is_ok_and(|path| path.exists());
+
+        loop {
+            if enabled {
+                let snapshot = StatusSnapshot {
+                    updated_at: Utc::now().timestamp_millis(),
+                    sessions: sessions.borrow_and_update().clone(),
+                    pending_questions: *questions.borrow_and_update(),
+                    server: server.borrow_and_update().clone(),
+                };
+                match write(&snapshot).await {
+                    Ok(()) => written = true,
+                    Err(err) => warn!("[desktop] Failed to write status file: {err}"),
+                }
+            } else if written {
+                remove().await;
+                written = false;
+            }
+
+            tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                Ok(()) = sessions.changed() => {}
+                Ok(()) = questions.changed() => {}
+                Ok(()) = server.changed() => {}
+                change = next_settings_change(&mut settings_changes) => {
+                    let now_enabled = change.current.integrations.status_file;
+                    if now_enabled && !enabled {
+                        info!("[desktop] Writing status file");
+                    } else if enabled && !now_enabled {
+                        info!("[desktop] Status file disabled");
+                    }
+                    enabled = now_enabled;
+                }
+            }
+            tokio::time::sleep(WRITE_DEBOUNCE).await;
+        }
+
+        if written {
+            remove().await;
+        }
+    })
+}
+
+/// Write through a temporary file so readers never see half a snapshot.
+async fn write(snapshot: &StatusSnapshot) -> Result<()> {
+    let path = status_file_path()?;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let temp_path = path.with_extension("json.tmp");
+    tokio::fs::write(&temp_path, serde_json::to_vec_pretty(snapshot)?).await?;
+    tokio::fs::rename(&temp_path, &path).await?;
+    Ok(())
+}
+
+async fn remove() {
+    let Ok(path) = status_file_path() else {
+        return;
+    };
+    match tokio::fs::remove_file(&path).await {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => warn!("[desktop] Failed to remove status file: {err}"),
+    }
+}
@@ -0,0 +1,222 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use futures_util::FutureExt;
+use log::{error, info};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast;
+
+use crate::crash_reports::in_task;
+
+/// Failures in a row after which a supervised task is left stopped.
+const MAX_CONSECUTIVE_CRASHES: u32 = 5;
+const INITIAL_RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+/// A task that ran this long before failing starts counting failures from zero again.
+const HEALTHY_RUN: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskState {
+    Running,
+    /// The task returned, normally because of the shutdown broadcast.
+    Stopped,
+    Panicked,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStatus {
+    pub name: String,
+    pub state: TaskState,
+    /// Unix milliseconds of the last loop iteration, or `None` before the first one.
+    pub last_heartbeat: Option<i64>,
+    pub restarts: u32,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskCrashed {
+    name: String,
+    message: String,
+    consecutive_crashes: u32,
+    /// False once the supervisor has given up on the task.
+    restarting: bool,
+}
+
+/// Named long-lived background tasks and whether they are still alive.
+#[derive(Default)]
+pub struct TaskRegistry {
+    tasks: Mutex<BTreeMap<String, TaskStatus>>,
+}
+
+impl TaskRegistry {
+    /// Run `body` as the task `name`, restarting it with backoff when it panics or returns
+    /// before shutdown. Each failure is logged and emitted as `openchamber:task-crashed`;
+    /// after `MAX_CONSECUTIVE_CRASHES` in a row the task is left stopped. Once the shutdown
+    /// broadcast arrives the task is never restarted.
+    pub fn supervise<F, Fut>(
+        self: &Arc<Self>,
+        app: AppHandle,
+        name: &str,
+        mut shutdown_rx: broadcast::Receiver<()>,
+        body: F,
+    ) -> tauri::async_runtime::JoinHandle<()>
+    where
+        F: Fn(TaskHandle) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let registry = self.clone();
+        let name = name.to_string();
+        tauri::async_runtime::spawn(async move {
+            let mut crashes = 0;
+            loop {
+                registry.register(&name);
+                let handle = TaskHandle {
+                    name: name.clone(),
+                    registry: registry.clone(),
+                };
+                let started = Instant::now();
+                // The panic hook writes the crash report, naming the task from this scope.
+                let outcome = AssertUnwindSafe(in_task(name.clone(), body(handle.clone())))
+                    .catch_unwind()
+                    .await;
+                let message = match outcome {
+                    Ok(()) => {
+                        handle.set_state(TaskState::Stopped);
+                        "exited unexpectedly".to_string()
+                    }
+                    Err(payload) => {
+                        handle.set_state(TaskState::Panicked);
+                        format!("panicked: {}", panic_message(payload.as_ref()))
+                    }
+                };
+                if shutdown_requested(&mut shutdown_rx) {
+                    info!("[desktop] Background task {name} stopped");
+                    return;
+                }
+
+                if started.elapsed() >= HEALTHY_RUN {
+                    crashes = 0;
+                }
+                crashes += 1;
+                let restarting = crashes < MAX_CONSECUTIVE_CRASHES;
+                let _ = app.emit(
+                    "openchamber:task-crashed",
+                    TaskCrashed {
+                        name: name.clone(),
+                        message: message.clone(),
+                        consecutive_crashes: crashes,
+                        restarting,
+                    },
+                );
+                if !restarting {
+                    error!(
+                        "[desktop] Background task {name} {message}; giving up after {crashes} failures"
+                    );
+                    return;
+                }
+
+                let delay = INITIAL_RESTART_DELAY
+                    .saturating_mul(1 << (crashes - 1))
+                    .min(MAX_RESTART_DELAY);
+                error!("[desktop] Background task {name} {message}; restarting in {delay:?}");
+                tokio::select! {
+                    _ = shutdown_rx.recv() => return,
+                    _ = tokio::time::sleep(delay) => {}
+                }
+            }
+        })
+    }
+
+    pub fn snapshot(&self) -> Vec<TaskStatus> {
+        self.tasks.lock().values().cloned().collect()
+    }
+
+    fn register(&self, name: &str) {
+        let mut tasks = self.tasks.lock();
+        match tasks.get_mut(name) {
+            Some(status) => {
+                status.state = TaskState::Running;
+                status.restarts += 1;
+            }
+            None => {
+                tasks.insert(
+                    name.to_string(),
+                    TaskStatus {
+                        name: name.to_string(),
+                        state: TaskState::Running,
+                        last_heartbeat: None,
+                        restarts: 0,
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// Given to a registered task so it can report that its loop is still turning.
+#[derive(Clone)]
+pub struct TaskHandle {
+    name: String,
+    registry: Arc<TaskRegistry>,
+}
+
+impl TaskHandle {
+    pub fn heartbeat(&self) {
+        if let Some(status) = self.registry.tasks.lock().get_mut(&self.name) {
+            status.last_heartbeat = Some(Utc::now().timestamp_millis());
+        }
+    }
+
+    fn set_state(&self, state: TaskState) {
+        if let Some(status) = self.registry.tasks.lock().get_mut(&self.name) {
+            status.state = state;
+        }
+    }
+}
+
+/// A task spawned by a supervised task, aborted when its owner returns or unwinds so a
+/// restart does not leave the previous run's children behind.
+pub struct ChildTask(tauri::async_runtime::JoinHandle<()>);
+
+impl ChildTask {
+    pub fn spawn<Fut>(task: Fut) -> Self
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        Self(tauri::async_runtime::spawn(task))
+    }
+}
+
+impl Drop for ChildTask {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Whether the shutdown broadcast has been sent, or its sender is gone.
+fn shutdown_requested(shutdown_rx: &mut broadcast::Receiver<()>) -> bool {
+    !matches!(
+        shutdown_rx.try_recv(),
+        Err(broadcast::error::TryRecvError::Empty)
+    )
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "(non-string panic payload)".to_string()
+    }
+}
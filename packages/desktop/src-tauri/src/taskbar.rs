@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use log::warn;
+use tauri::{
+    image::Image,
+    window::{ProgressBarState, ProgressBarStatus},
+    AppHandle, Manager,
+};
+
+use crate::assistant_notifications::PendingQuestions;
+use crate::session_activity::{BusySession, BusySessions};
+use crate::DesktopRuntime;
+
+/// Phases can flip several times a second; the taskbar follows at most this often.
+const MIN_UPDATE_INTERVAL: Duration = Duration::from_millis(500);
+
+const BADGE_SIZE: u32 = 16;
+const BADGE_COLOR: [u8; 4] = [0xd9, 0x3a, 0x3a, 0xff];
+const GLYPH_SCALE: u32 = 2;
+/// 3x5 glyphs for `0`-`9` and `+`, one row per byte, most significant of three bits
+/// leftmost.
+const GLYPHS: [[u8; 5]; 11] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+    [0b000, 0b010, 0b111, 0b010, 0b000],
+];
+const PLUS_GLYPH: usize = 10;
+
+/// Mirror session activity on the Windows taskbar button: an indeterminate progress bar
+/// while anything is working, a paused one while a question waits, and the number of
+/// pending questions as an overlay badge.
+pub fn spawn_taskbar_status(
+    app: AppHandle,
+    runtime: DesktopRuntime,
+) -> tauri::async_runtime::JoinHandle<()> {
+    tauri::async_runtime::spawn(async move {
+        let mut shutdown_rx = runtime.subscribe_shutdown();
+        let mut busy = app.state::<BusySessions>().subscribe();
+        let mut questions = app.state::<PendingQuestions>().subscribe_count();
+        let mut shown = TaskbarStatus::default();
+
+        loop {
+            let status =
+                TaskbarStatus::new(&busy.borrow_and_update(), *questions.borrow_and_update());
+            if status != shown {
+                status.apply(&app);
+                shown = status;
+
+                tokio::select! {
+                    _ = shutdown_rx.recv() => break,
+                    _ = tokio::time::sleep(MIN_UPDATE_INTERVAL) => {}
+                }
+            }
+
+            tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                Ok(()) = busy.changed() => {}
+                Ok(()) = questions.changed() => {}
+            }
+        }
+
+        TaskbarStatus::default().apply(&app);
+    })
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct TaskbarStatus {
+    working: bool,
+    waiting: bool,
+    pending_questions: usize,
+}
+
+impl TaskbarStatus {
+    fn new(sessions: &[BusySession], pending_questions: usize) -> Self {
+        Self {
+            working: sessions
+                .iter()
+                .any(|session| matches!(session.phase, "busy" | "retry")),
+            waiting: pending_questions > 0
+                || sessions
+                    .iter()
+                    .any(|session| session.phase == "waiting-for-input"),
+            pending_questions,
+        }
+    }
+
+    fn apply(&self, app: &AppHandle) {
+        let Some(window) = app.get_webview_window("main") else {
+            return;
+        };
+
+        // A waiting question needs the user, so it wins over work still in progress.
+        let progress = if self.waiting {
+            ProgressBarState {
+                status: Some(ProgressBarStatus::Paused),
+                progress: Some(100),
+            }
+        } else if self.working {
+            ProgressBarState {
+                status: Some(ProgressBarStatus::Indeterminate),
+                progress: None,
+            }
+        } else {
+            ProgressBarState {
+                status: Some(ProgressBarStatus::None),
+                progress: None,
+            }
+        };
+        if let Err(err) = window.set_progress_bar(progress) {
+            warn!("[desktop] Failed to update taskbar progress: {err}");
+        }
+
+        let badge = (self.pending_questions > 0).then(|| badge_icon(self.pending_questions));
+        if let Err(err) = window.set_overlay_icon(badge) {
+            warn!("[desktop] Failed to update taskbar badge: {err}");
+        }
+    }
+}
+
+/// A red dot with the count in white; counts above nine read `9+`.
+fn badge_icon(count: usize) -> Image<'static> {
+    let glyphs: Vec<usize> = if count > 9 {
+        vec![9, PLUS_GLYPH]
+    } else {
+        vec![count]
+    };
+
+    let mut rgba = vec![0u8; (BADGE_SIZE * BADGE_SIZE * 4) as usize];
+    let radius = BADGE_SIZE as f32 / 2.0;
+    for y in 0..BADGE_SIZE {
+        for x in 0..BADGE_SIZE {
+            let dx = x as f32 + 0.5 - radius;
+            let dy = y as f32 + 0.5 - radius;
+            if dx * dx + dy * dy <= radius * radius {
+                let offset = ((y * BADGE_SIZE + x) * 4) as usize;
+                rgba[offset..offset + 4].copy_from_slice(&BADGE_COLOR);
+            }
+        }
+    }
+
+    let glyph_width = 3 * GLYPH_SCALE;
+    let gap = GLYPH_SCALE;
+    let text_width = glyphs.len() as u32 * glyph_width + (glyphs.len() as u32 - 1) * gap;
+    let left = (BADGE_SIZE - text_width) / 2;
+    let top = (BADGE_SIZE - 5 * GLYPH_SCALE) / 2;
+    for (index, glyph) in glyphs.iter().enumerate() {
+        let glyph_left = left + index as u32 * (glyph_width + gap);
+        for (row, bits) in GLYPHS[*glyph].iter().enumerate() {
+            for column in 0..3 {
+                if bits & (0b100 >> column) == 0 {
+                    continue;
+                }
+                for sy in 0..GLYPH_SCALE {
+                    for sx in 0..GLYPH_SCALE {
+                        let x = glyph_left + column * GLYPH_SCALE + sx;
+                        let y = top + row as u32 * GLYPH_SCALE + sy;
+                        let offset = ((y * BADGE_SIZE + x) * 4) as usize;
+                        rgba[offset..offset + 4].copy_from_slice(&[0xff; 4]);
+                    }
+                }
+            }
+        }
+    }
+
+    Image::new_owned(rgba, BADGE_SIZE, BADGE_SIZE)
+}
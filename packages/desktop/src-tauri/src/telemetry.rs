@@ -0,0 +1,376 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use chrono::Local;
+use log::debug;
+use serde::Serialize;
+
+use crate::assistant_notifications::webhook::{signature, SIGNATURE_HEADER};
+use crate::busy_time::day_ended;
+use crate::secrets::TELEMETRY_SECRET;
+use crate::DesktopRuntime;
+
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(10);
+const UPLOAD_MAX_ATTEMPTS: u32 = 3;
+
+/// Why an SSE listener had to reconnect.
+#[derive(Clone, Copy, Debug)]
+pub enum ReconnectReason {
+    StreamEnded,
+    ReadError,
+    ConnectFailed,
+    DirectoryChanged,
+    /// The OpenCode server moved to another port or stopped.
+    ServerChanged,
+    /// Nothing arrived within the listener's idle timeout.
+    IdleTimeout,
+}
+
+/// Local, in-memory counters describing event pipeline health.
+///
+/// Only plain counts live here. Nothing that could identify a user, project,
+/// session, or model is ever recorded.
+#[derive(Default)]
+pub struct TelemetryCounters {
+    reconnect_stream_ended: AtomicU64,
+    reconnect_read_error: AtomicU64,
+    reconnect_connect_failed: AtomicU64,
+    reconnect_directory_changed: AtomicU64,
+    reconnect_server_changed: AtomicU64,
+    reconnect_idle_timeout: AtomicU64,
+    parse_failures: AtomicU64,
+    schema_sentinel_hits: AtomicU64,
+    keepalives_ignored: AtomicU64,
+    notifications_shown: AtomicU64,
+    notifications_failed: AtomicU64,
+}
+
+impl TelemetryCounters {
+    pub fn record_reconnect(&self, reason: ReconnectReason) {
+        let counter = match reason {
+            ReconnectReason::StreamEnded => &self.reconnect_stream_ended,
+            ReconnectReason::ReadError => &self.reconnect_read_error,
+            ReconnectReason::ConnectFailed => &self.reconnect_connect_failed,
+            ReconnectReason::DirectoryChanged => &self.reconnect_directory_changed,
+            ReconnectReason::ServerChanged => &self.reconnect_server_changed,
+            ReconnectReason::IdleTimeout => &self.reconnect_idle_timeout,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_parse_failure(&self) {
+        self.parse_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// An event of a type the listeners handle arrived without the fields they key on,
+    /// which usually means the server's event format changed.
+    pub fn record_schema_sentinel(&self) {
+        self.schema_sentinel_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_keepalive(&self) {
+        self.keepalives_ignored.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_notification<T, E>(&self, outcome: &Result<T, E>) {
+        let counter = if outcome.is_ok() {
+            &self.notifications_shown
+        } else {
+            &self.notifications_failed
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> CounterSnapshot {
+        CounterSnapshot {
+            reconnect_stream_ended: self.reconnect_stream_ended.load(Ordering::Relaxed),
+            reconnect_read_error: self.reconnect_read_error.load(Ordering::Relaxed),
+            reconnect_connect_failed: self.reconnect_connect_failed.load(Ordering::Relaxed),
+            reconnect_directory_changed: self.reconnect_directory_changed.load(Ordering::Relaxed),
+            reconnect_server_changed: self.reconnect_server_changed.load(Ordering::Relaxed),
+            reconnect_idle_timeout: self.reconnect_idle_timeout.load(Ordering::Relaxed),
+            parse_failures: self.parse_failures.load(Ordering::Relaxed),
+            schema_sentinel_hits: self.schema_sentinel_hits.load(Ordering::Relaxed),
+            keepalives_ignored: self.keepalives_ignored.load(Ordering::Relaxed),
+            notifications_shown: self.notifications_shown.load(Ordering::Relaxed),
+            notifications_failed: self.notifications_failed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Subtract a previously uploaded snapshot so counts are only reported once.
+    fn consume(&self, sent: &CounterSnapshot) {
+        self.reconnect_stream_ended
+            .fetch_sub(sent.reconnect_stream_ended, Ordering::Relaxed);
+        self.reconnect_read_error
+            .fetch_sub(sent.reconnect_read_error, Ordering::Relaxed);
+        self.reconnect_connect_failed
+            .fetch_sub(sent.reconnect_connect_failed, Ordering::Relaxed);
+        self.reconnect_directory_changed
+            .fetch_sub(sent.reconnect_directory_changed, Ordering::Relaxed);
+        self.reconnect_server_changed
+            .fetch_sub(sent.reconnect_server_changed, Ordering::Relaxed);
+        self.reconnect_idle_timeout
+            .fetch_sub(sent.reconnect_idle_timeout, Ordering::Relaxed);
+        self.parse_failures
+            .fetch_sub(sent.parse_failures, Ordering::Relaxed);
+        self.schema_sentinel_hits
+            .fetch_sub(sent.schema_sentinel_hits, Ordering::Relaxed);
+        self.keepalives_ignored
+            .fetch_sub(sent.keepalives_ignored, Ordering::Relaxed);
+        self.notifications_shown
+            .fetch_sub(sent.notifications_shown, Ordering::Relaxed);
+        self.notifications_failed
+            .fetch_sub(sent.notifications_failed, Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CounterSnapshot {
+    reconnect_stream_ended: u64,
+    reconnect_read_error: u64,
+    reconnect_connect_failed: u64,
+    reconnect_directory_changed: u64,
+    reconnect_server_changed: u64,
+    reconnect_idle_timeout: u64,
+    parse_failures: u64,
+    schema_sentinel_hits: u64,
+    keepalives_ignored: u64,
+    notifications_shown: u64,
+    notifications_failed: u64,
+}
+
+impl CounterSnapshot {
+    fn is_empty(&self) -> bool {
+        self.reconnect_stream_ended == 0
+            && self.reconnect_read_error == 0
+            && self.reconnect_connect_failed == 0
+            && self.reconnect_directory_changed == 0
+            && self.reconnect_server_changed == 0
+            && self.reconnect_idle_timeout == 0
+            && self.parse_failures == 0
+            && self.schema_sentinel_hits == 0
+            && self.keepalives_ignored == 0
+            && self.notifications_shown == 0
+            && self.notifications_failed == 0
+    }
+}
+
+/// The complete daily upload. Every field is listed here explicitly; the payload is
+/// never derived from settings or runtime state, so paths, session ids, model names,
+/// and message content cannot leak into it.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TelemetrySummary {
+    day: String,
+    os: &'static str,
+    arch: &'static str,
+    app_version: &'static str,
+    counters: CounterSnapshot,
+}
+
+impl TelemetrySummary {
+    fn new(day: String, counters: CounterSnapshot) -> Self {
+        Self {
+            day,
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            app_version: env!("CARGO_PKG_VERSION"),
+            counters,
+        }
+    }
+}
+
+struct TelemetrySettings {
+    enabled: bool,
+    endpoint: Option<String>,
+}
+
+pub(crate) async fn is_enabled(runtime: &DesktopRuntime) -> bool {
+    load_telemetry_settings(runtime).await.enabled
+}
+
+async fn load_telemetry_settings(runtime: &DesktopRuntime) -> TelemetrySettings {
+    let telemetry = runtime
+        .settings()
+        .load_typed()
+        .await
+        .unwrap_or_default()
+        .telemetry;
+    let endpoint = telemetry
+        .endpoint
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string);
+    TelemetrySettings {
+        enabled: telemetry.enabled,
+        endpoint,
+    }
+}
+
+/// Upload each day's counters at local midnight, on its own schedule so the upload does
+/// not depend on busy-time accounting running.
+pub fn spawn_telemetry_reports(runtime: DesktopRuntime) -> tauri::async_runtime::JoinHandle<()> {
+    tauri::async_runtime::spawn(async move {
+        let mut shutdown_rx = runtime.subscribe_shutdown();
+        let mut current = Local::now().date_naive();
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                day = day_ended(&mut current) => {
+                    // Uploads retry for a while; they must not hold up the next midnight.
+                    let runtime = runtime.clone();
+                    tauri::async_runtime::spawn(async move { report_day(&runtime, day).await });
+                }
+            }
+        }
+    })
+}
+
+/// Upload the counters gathered over `day`, a `YYYY-MM-DD` date, when telemetry is on.
+/// Failures are only logged at debug level and retried a few times; unsent counts carry
+/// over to the next day.
+async fn report_day(runtime: &DesktopRuntime, day: String) {
+    let settings = load_telemetry_settings(runtime).await;
+    let counters = runtime.telemetry();
+
+    if !settings.enabled {
+        // Drop anything aggregated while opted out; it must never be sent later.
+        counters.consume(&counters.snapshot());
+        return;
+    }
+
+    let Some(endpoint) = settings.endpoint else {
+        return;
+    };
+
+    let snapshot = counters.snapshot();
+    if snapshot.is_empty() {
+        return;
+    }
+
+    let body = match serde_json::to_vec(&TelemetrySummary::new(day, snapshot.clone())) {
+        Ok(body) => body,
+        Err(err) => {
+            debug!("[desktop:telemetry] Failed to encode summary: {err}");
+            return;
+        }
+    };
+    // Signed the way notification webhooks are, when a secret is stored.
+    let signature = match runtime.secrets().get(TELEMETRY_SECRET).await {
+        Ok(secret) => secret
+            .filter(|secret| !secret.is_empty())
+            .map(|secret| signature(&secret, &body)),
+        Err(err) => {
+            debug!("[desktop:telemetry] Failed to read the signing secret: {err}");
+            None
+        }
+    };
+
+    for attempt in 1..=UPLOAD_MAX_ATTEMPTS {
+        let mut request = runtime
+            .http()
+            .api()
+            .post(&endpoint)
+            .timeout(UPLOAD_TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.clone());
+        if let Some(signature) = &signature {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                counters.consume(&snapshot);
+                return;
+            }
+            Ok(response) => {
+                debug!(
+                    "[desktop:telemetry] Upload attempt {attempt} rejected with status {}",
+                    response.status()
+                );
+            }
+            Err(err) => {
+                debug!("[desktop:telemetry] Upload attempt {attempt} failed: {err}");
+            }
+        }
+        if attempt < UPLOAD_MAX_ATTEMPTS {
+            tokio::time::sleep(Duration::from_secs(30 * attempt as u64)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use serde_json::Value;
+
+    use super::*;
+
+    fn keys(value: &Value) -> BTreeSet<&str> {
+        value
+            .as_object()
+            .expect("an object")
+            .keys()
+            .map(String::as_str)
+            .collect()
+    }
+
+    #[test]
+    fn summary_serializes_only_allow_listed_fields() {
+        let counters = TelemetryCounters::default();
+        counters.record_reconnect(ReconnectReason::ReadError);
+        counters.record_parse_failure();
+        counters.record_schema_sentinel();
+        counters.record_notification::<(), ()>(&Err(()));
+
+        let summary = TelemetrySummary::new("2026-10-15".to_string(), counters.snapshot());
+        let value = serde_json::to_value(&summary).expect("summary serializes");
+
+        assert_eq!(
+            keys(&value),
+            BTreeSet::from(["appVersion", "arch", "counters", "day", "os"])
+        );
+        assert_eq!(
+            keys(&value["counters"]),
+            BTreeSet::from([
+                "keepalivesIgnored",
+                "notificationsFailed",
+                "notificationsShown",
+                "parseFailures",
+                "reconnectConnectFailed",
+                "reconnectDirectoryChanged",
+                "reconnectIdleTimeout",
+                "reconnectReadError",
+                "reconnectServerChanged",
+                "reconnectStreamEnded",
+                "schemaSentinelHits",
+            ])
+        );
+        // Counters are plain numbers; nothing free-form can ride along in them.
+        assert!(value["counters"]
+            .as_object()
+            .expect("an object")
+            .values()
+            .all(Value::is_u64));
+        assert_eq!(value["counters"]["schemaSentinelHits"], 1);
+        assert_eq!(value["day"], "2026-10-15");
+    }
+
+    #[test]
+    fn consumed_counts_are_not_reported_again() {
+        let counters = TelemetryCounters::default();
+        counters.record_parse_failure();
+        let sent = counters.snapshot();
+        counters.record_parse_failure();
+
+        counters.consume(&sent);
+
+        assert_eq!(counters.snapshot().parse_failures, 1);
+        counters.consume(&counters.snapshot());
+        assert!(counters.snapshot().is_empty());
+    }
+}
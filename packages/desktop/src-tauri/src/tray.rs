@@ -0,0 +1,380 @@
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use tauri::{
+    menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu},
+    tray::{TrayIcon, TrayIconBuilder},
+    AppHandle, Emitter, Manager, Wry,
+};
+
+use crate::assistant_notifications::PendingQuestions;
+use crate::events::SessionPayload;
+use crate::project_names::ProjectNames;
+use crate::session_abort::{abort_all_busy_sessions, abort_session};
+use crate::session_activity::{BusySession, BusySessions};
+use crate::session_info::SessionInfoCache;
+use crate::settings_watcher::next_settings_change;
+use crate::window_title::status_title;
+use crate::{quit_gracefully, DesktopRuntime};
+
+pub(crate) const TRAY_ID: &str = "openchamber-sessions";
+/// Phase changes arriving this close together share one menu rebuild.
+const REBUILD_DEBOUNCE: Duration = Duration::from_millis(250);
+/// On Linux, a rebuild slower than this switches the tray to a static menu; some
+/// appindicator hosts re-create the whole menu on every change.
+const SLOW_REBUILD: Duration = Duration::from_millis(500);
+
+const MENU_HEADER_ID: &str = "tray:header";
+const MENU_SHOW_WINDOW_ID: &str = "tray:show-window";
+const MENU_QUIT_ID: &str = "tray:quit";
+const MENU_ABORT_ALL_ID: &str = "tray:abort-all";
+const MENU_TOGGLE_PAUSE_ID: &str = "tray:toggle-pause";
+const MENU_OPEN_PREFIX: &str = "tray:open:";
+const MENU_ABORT_PREFIX: &str = "tray:abort:";
+const MENU_MARK_READ_PREFIX: &str = "tray:mark-read:";
+
+/// Show a tray icon whose menu lists the sessions the activity tracker sees working,
+/// for as long as `showTrayIcon` is not turned off.
+pub fn spawn_session_tray(
+    app: AppHandle,
+    runtime: DesktopRuntime,
+) -> tauri::async_runtime::JoinHandle<()> {
+    tauri::async_runtime::spawn(async move {
+        let mut shutdown_rx = runtime.subscribe_shutdown();
+        let mut settings_changes = runtime.subscribe_settings_changes();
+        let mut busy = app.state::<BusySessions>().subscribe();
+        let mut paused = runtime.subscribe_paused();
+        let mut enabled = runtime
+            .settings()
+            .load_typed()
+            .await
+            .map(|settings| settings.tray_icon_enabled())
+            .unwrap_or(true);
+        let mut tray: Option<SessionTray> = None;
+
+        loop {
+            if !enabled {
+                if tray.take().is_some() {
+                    let _ = app.remove_tray_by_id(TRAY_ID);
+                }
+            } else if tray.is_none() {
+                match SessionTray::create(&app) {
+                    Ok(created) => tray = Some(created),
+                    Err(err) => warn!("[desktop] Failed to create tray icon: {err}"),
+                }
+            }
+            if let Some(tray) = &mut tray {
+                let sessions = busy.borrow_and_update().clone();
+                tray.update(&app, &sessions, *paused.borrow_and_update());
+            }
+
+            tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                change = next_settings_change(&mut settings_changes) => {
+                    enabled = change.current.tray_icon_enabled();
+                }
+                Ok(()) = busy.changed() => {
+                    tokio::time::sleep(REBUILD_DEBOUNCE).await;
+                }
+                Ok(()) = paused.changed() => {}
+            }
+        }
+
+        if tray.is_some() {
+            let _ = app.remove_tray_by_id(TRAY_ID);
+        }
+    })
+}
+
+struct SessionTray {
+    icon: TrayIcon,
+    /// What the menu currently shows, so unchanged state never rebuilds it.
+    shown: Option<MenuModel>,
+    /// Set once rebuilds proved too slow; the menu then only offers to show the window
+    /// or quit.
+    static_menu: bool,
+}
+
+impl SessionTray {
+    fn create(app: &AppHandle) -> tauri::Result<Self> {
+        // Kept current by the window title task from here on.
+        let tooltip = status_title(
+            &app.state::<BusySessions>().subscribe().borrow(),
+            *app.state::<PendingQuestions>().subscribe_count().borrow(),
+        );
+        let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+            .tooltip(tooltip)
+            .on_menu_event(handle_menu_event);
+        if let Some(icon) = app.default_window_icon() {
+            builder = builder.icon(icon.clone());
+        }
+        Ok(Self {
+            icon: builder.build(app)?,
+            shown: None,
+            static_menu: false,
+        })
+    }
+
+    fn update(&mut self, app: &AppHandle, sessions: &[BusySession], paused: bool) {
+        let model = MenuModel::new(app, sessions, paused);
+        if self.shown.as_ref() == Some(&model) {
+            return;
+        }
+        if self.static_menu && self.shown.is_some() {
+            self.shown = Some(model);
+            return;
+        }
+
+        let started = Instant::now();
+        let menu = if self.static_menu {
+            static_menu(app)
+        } else {
+            session_menu(app, &model)
+        };
+        match menu {
+            Ok(menu) => {
+                let _ = self.icon.set_menu(Some(menu));
+            }
+            Err(err) => warn!("[desktop] Failed to build tray menu: {err}"),
+        }
+
+        if cfg!(target_os = "linux") && !self.static_menu && started.elapsed() > SLOW_REBUILD {
+            info!(
+                "[desktop] Tray menu rebuild took {:?}; switching to a static menu",
+                started.elapsed()
+            );
+            self.static_menu = true;
+            if let Ok(menu) = static_menu(app) {
+                let _ = self.icon.set_menu(Some(menu));
+            }
+        }
+        self.shown = Some(model);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct MenuModel {
+    /// "2 working, 1 waiting for input".
+    header: String,
+    entries: Vec<MenuEntry>,
+    /// Whether background streams are paused, which the pause entry offers to undo.
+    paused: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct MenuEntry {
+    session_id: String,
+    label: String,
+    /// Only a session that is still running can be aborted.
+    abortable: bool,
+}
+
+impl MenuModel {
+    fn new(app: &AppHandle, sessions: &[BusySession], paused: bool) -> Self {
+        let titles = app.state::<SessionInfoCache>();
+        let projects = app.state::<ProjectNames>();
+        let count = |phase: &str| {
+            sessions
+                .iter()
+                .filter(|session| session.phase == phase)
+                .count()
+        };
+        let parts: Vec<String> = [
+            (count("busy"), "working"),
+            (count("retry"), "retrying"),
+            (count("compacting"), "compacting"),
+            (count("waiting-for-input"), "waiting for input"),
+            (count("error"), "failed"),
+        ]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, label)| format!("{count} {label}"))
+        .collect();
+        let header = if parts.is_empty() {
+            "No active sessions".to_string()
+        } else {
+            parts.join(", ")
+        };
+
+        let entries = sessions
+            .iter()
+            .map(|session| {
+                let title = titles
+                    .title(&session.session_id)
+                    .unwrap_or_else(|| fallback_title(session, &projects));
+                MenuEntry {
+                    session_id: session.session_id.clone(),
+                    label: format!("{title} — {}", phase_label(session.phase)),
+                    abortable: session.abortable(),
+                }
+            })
+            .collect();
+        Self {
+            header,
+            entries,
+            paused,
+        }
+    }
+}
+
+fn phase_label(phase: &str) -> &'static str {
+    match phase {
+        "busy" => "Working",
+        "retry" => "Retrying",
+        "compacting" => "Compacting",
+        "waiting-for-input" => "Waiting for input",
+        "cooldown" => "Finishing",
+        "error" => "Error",
+        _ => "Active",
+    }
+}
+
+/// The project's name, or the session id when the session's project is unknown.
+fn fallback_title(session: &BusySession, projects: &ProjectNames) -> String {
+    session
+        .directory
+        .as_deref()
+        .and_then(|directory| projects.display_name(directory))
+        .map(|name| format!("Session in {name}"))
+        .unwrap_or_else(|| format!("Session {}", session.session_id))
+}
+
+fn session_menu(app: &AppHandle, model: &MenuModel) -> tauri::Result<Menu<Wry>> {
+    let menu = Menu::new(app)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        MENU_HEADER_ID,
+        &model.header,
+        false,
+        None::<&str>,
+    )?)?;
+    if !model.entries.is_empty() {
+        menu.append(&PredefinedMenuItem::separator(app)?)?;
+    }
+    for entry in &model.entries {
+        let open = MenuItem::with_id(
+            app,
+            format!("{MENU_OPEN_PREFIX}{}", entry.session_id),
+            "Open",
+            true,
+            None::<&str>,
+        )?;
+        let abort = MenuItem::with_id(
+            app,
+            format!("{MENU_ABORT_PREFIX}{}", entry.session_id),
+            "Abort",
+            entry.abortable,
+            None::<&str>,
+        )?;
+        let mark_read = MenuItem::with_id(
+            app,
+            format!("{MENU_MARK_READ_PREFIX}{}", entry.session_id),
+            "Mark read",
+            true,
+            None::<&str>,
+        )?;
+        menu.append(&Submenu::with_items(
+            app,
+            &entry.label,
+            true,
+            &[&open, &abort, &mark_read],
+        )?)?;
+    }
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    if model.entries.iter().any(|entry| entry.abortable) {
+        menu.append(&MenuItem::with_id(
+            app,
+            MENU_ABORT_ALL_ID,
+            "Abort all running sessions",
+            true,
+            None::<&str>,
+        )?)?;
+    }
+    let pause_label = if model.paused {
+        "Resume background activity"
+    } else {
+        "Pause background activity"
+    };
+    menu.append(&MenuItem::with_id(
+        app,
+        MENU_TOGGLE_PAUSE_ID,
+        pause_label,
+        true,
+        None::<&str>,
+    )?)?;
+    menu.append(&show_window_item(app)?)?;
+    menu.append(&quit_item(app)?)?;
+    Ok(menu)
+}
+
+fn static_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let menu = Menu::new(app)?;
+    menu.append(&show_window_item(app)?)?;
+    menu.append(&quit_item(app)?)?;
+    Ok(menu)
+}
+
+fn show_window_item(app: &AppHandle) -> tauri::Result<MenuItem<Wry>> {
+    MenuItem::with_id(
+        app,
+        MENU_SHOW_WINDOW_ID,
+        "Show OpenChamber",
+        true,
+        None::<&str>,
+    )
+}
+
+/// The way out when closing the window only hides it.
+fn quit_item(app: &AppHandle) -> tauri::Result<MenuItem<Wry>> {
+    MenuItem::with_id(app, MENU_QUIT_ID, "Quit OpenChamber", true, None::<&str>)
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    let id = event.id().as_ref();
+    if id == MENU_SHOW_WINDOW_ID {
+        show_main_window(app);
+    } else if id == MENU_QUIT_ID {
+        tauri::async_runtime::spawn(quit_gracefully(app.clone()));
+    } else if let Some(session_id) = id.strip_prefix(MENU_OPEN_PREFIX) {
+        show_main_window(app);
+        let _ = app.emit(
+            "openchamber:navigate-session",
+            SessionPayload { session_id },
+        );
+    } else if id == MENU_TOGGLE_PAUSE_ID {
+        let runtime = app.state::<DesktopRuntime>().inner().clone();
+        tauri::async_runtime::spawn(async move {
+            let paused = !runtime.is_paused();
+            if let Err(err) = runtime.set_background_paused(paused).await {
+                warn!("[desktop] Failed to save the paused state: {err}");
+            }
+        });
+    } else if id == MENU_ABORT_ALL_ID {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            abort_all_busy_sessions(&app).await;
+        });
+    } else if let Some(session_id) = id.strip_prefix(MENU_ABORT_PREFIX) {
+        let app = app.clone();
+        let session_id = session_id.to_string();
+        tauri::async_runtime::spawn(async move {
+            abort_session(&app, &session_id).await;
+        });
+    } else if let Some(session_id) = id.strip_prefix(MENU_MARK_READ_PREFIX) {
+        let _ = app.emit(
+            "openchamber:mark-session-read",
+            SessionPayload { session_id },
+        );
+    }
+}
+
+/// Bring the main window up, also when it is hidden to the tray or minimized.
+pub(crate) fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        if window.is_minimized().unwrap_or(false) {
+            let _ = window.unminimize();
+        }
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
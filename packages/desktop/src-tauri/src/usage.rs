@@ -0,0 +1,693 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use chrono::{Local, NaiveDate, TimeZone, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+const USAGE_FILE: &str = "usage.json";
+const USAGE_UPDATED_EVENT: &str = "openchamber:usage-updated";
+const SAVE_DEBOUNCE: Duration = Duration::from_secs(2);
+/// Messages older than this drop out of every total.
+const RETENTION_DAYS: u64 = 90;
+/// A session's totals are re-emitted once they move by this much, or a message completes.
+const EMIT_COST_STEP: f64 = 0.01;
+const EMIT_TOKEN_STEP: u64 = 1_000;
+const DAY_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub reasoning_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_write_tokens: u64,
+    /// In US dollars, as reported by OpenCode.
+    pub cost: f64,
+    /// Assistant messages counted.
+    pub messages: u64,
+}
+
+impl Usage {
+    /// The usage an assistant `message.updated` reports, if any.
+    fn from_message(info: &Value) -> Option<Self> {
+        let tokens = info.get("tokens");
+        let count = |value: Option<&Value>| value.and_then(Value::as_u64).unwrap_or(0);
+        let cache = tokens.and_then(|tokens| tokens.get("cache"));
+        let usage = Self {
+            input_tokens: count(tokens.and_then(|tokens| tokens.get("input"))),
+            output_tokens: count(tokens.and_then(|tokens| tokens.get("output"))),
+            reasoning_tokens: count(tokens.and_then(|tokens| tokens.get("reasoning"))),
+            cache_read_tokens: count(cache.and_then(|cache| cache.get("read"))),
+            cache_write_tokens: count(cache.and_then(|cache| cache.get("write"))),
+            cost: info
+                .get("cost")
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0)
+                .max(0.0),
+            messages: 1,
+        };
+        (usage.total_tokens() > 0 || usage.cost > 0.0).then_some(usage)
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens
+            + self.output_tokens
+            + self.reasoning_tokens
+            + self.cache_read_tokens
+            + self.cache_write_tokens
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.reasoning_tokens += other.reasoning_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_write_tokens += other.cache_write_tokens;
+        self.cost += other.cost;
+        self.messages += other.messages;
+    }
+
+    fn subtract(&mut self, other: &Self) {
+        self.input_tokens = self.input_tokens.saturating_sub(other.input_tokens);
+        self.output_tokens = self.output_tokens.saturating_sub(other.output_tokens);
+        self.reasoning_tokens = self.reasoning_tokens.saturating_sub(other.reasoning_tokens);
+        self.cache_read_tokens = self
+            .cache_read_tokens
+            .saturating_sub(other.cache_read_tokens);
+        self.cache_write_tokens = self
+            .cache_write_tokens
+            .saturating_sub(other.cache_write_tokens);
+        self.cost = (self.cost - other.cost).max(0.0);
+        self.messages = self.messages.saturating_sub(other.messages);
+    }
+
+    fn moved_from(&self, previous: &Self) -> bool {
+        (self.cost - previous.cost).abs() >= EMIT_COST_STEP
+            || self.total_tokens().abs_diff(previous.total_tokens()) >= EMIT_TOKEN_STEP
+    }
+}
+
+/// The latest usage seen for one assistant message. Replays and later updates of the
+/// same message replace it, so nothing is counted twice.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessageUsage {
+    session_id: String,
+    /// Local date the message was created, `YYYY-MM-DD`.
+    day: String,
+    #[serde(default)]
+    completed: bool,
+    usage: Usage,
+}
+
+/// How long one assistant message kept the user waiting, in milliseconds. Each message
+/// is measured from when its step started: the user's message, the session going busy,
+/// or the previous step of the same turn finishing.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageLatency {
+    pub session_id: String,
+    /// `provider/model`, when the message said.
+    pub model: Option<String>,
+    /// Local date the message streamed, `YYYY-MM-DD`.
+    pub day: String,
+    /// Until the first text or reasoning part streamed.
+    pub first_token_ms: Option<u64>,
+    /// Until the message finished with `stop`.
+    pub duration_ms: Option<u64>,
+}
+
+/// Where an assistant message that is still streaming is measured from.
+struct PendingTiming {
+    session_id: String,
+    started_at: i64,
+    model: Option<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct UsageFile {
+    #[serde(default)]
+    messages: HashMap<String, MessageUsage>,
+    #[serde(default)]
+    latencies: HashMap<String, MessageLatency>,
+}
+
+#[derive(Default)]
+struct Ledger {
+    messages: HashMap<String, MessageUsage>,
+    /// Totals derived from `messages`.
+    sessions: HashMap<String, Usage>,
+    days: BTreeMap<String, Usage>,
+    /// Session totals as last emitted.
+    emitted: HashMap<String, Usage>,
+    latencies: HashMap<String, MessageLatency>,
+    /// When each session's current step started waiting on the model, in epoch millis.
+    step_starts: HashMap<String, i64>,
+    /// Assistant messages still streaming, by message id.
+    pending: HashMap<String, PendingTiming>,
+}
+
+impl Ledger {
+    fn new(file: UsageFile) -> Self {
+        let mut ledger = Self {
+            messages: file.messages,
+            latencies: file.latencies,
+            ..Self::default()
+        };
+        ledger.prune();
+        ledger
+    }
+
+    /// Drop messages past retention and recompute the totals.
+    fn prune(&mut self) {
+        let cutoff = Local::now()
+            .date_naive()
+            .checked_sub_days(chrono::Days::new(RETENTION_DAYS))
+            .map(|date| date.format(DAY_FORMAT).to_string())
+            .unwrap_or_default();
+        self.messages.retain(|_, message| message.day >= cutoff);
+        self.latencies.retain(|_, latency| latency.day >= cutoff);
+        self.sessions.clear();
+        self.days.clear();
+        for message in self.messages.values() {
+            count(&mut self.sessions, &mut self.days, message, Usage::add);
+        }
+    }
+
+    /// Store `message` under `id`, replacing what was counted for it before. Returns
+    /// whether anything changed.
+    fn replace(&mut self, id: &str, message: MessageUsage) -> bool {
+        let previous = self.messages.insert(id.to_string(), message.clone());
+        if previous.as_ref() == Some(&message) {
+            return false;
+        }
+        if let Some(previous) = previous {
+            count(
+                &mut self.sessions,
+                &mut self.days,
+                &previous,
+                Usage::subtract,
+            );
+        }
+        count(&mut self.sessions, &mut self.days, &message, Usage::add);
+        true
+    }
+
+    /// Follow an assistant `message.updated`: start measuring a message that began
+    /// streaming, and take the duration of one that finished. Returns whether a latency
+    /// was recorded.
+    fn time_assistant_message(&mut self, id: &str, session_id: &str, info: &Value) -> bool {
+        let time = info.get("time");
+        let Some(completed_at) = time
+            .and_then(|time| time.get("completed"))
+            .and_then(Value::as_i64)
+        else {
+            // Replays of a message measured before are not measured again.
+            if !self.latencies.contains_key(id) {
+                if let Some(&started_at) = self.step_starts.get(session_id) {
+                    self.pending
+                        .entry(id.to_string())
+                        .or_insert_with(|| PendingTiming {
+                            session_id: session_id.to_string(),
+                            started_at,
+                            model: message_model(info),
+                        });
+                }
+            }
+            return false;
+        };
+
+        let Some(pending) = self.pending.remove(id) else {
+            return false;
+        };
+        // The turn's next step waits on the model from here.
+        self.step_starts
+            .insert(session_id.to_string(), completed_at);
+        if info.get("finish").and_then(Value::as_str) != Some("stop") {
+            return false;
+        }
+        let latency = self
+            .latencies
+            .entry(id.to_string())
+            .or_insert_with(|| new_latency(session_id, pending.model));
+        if latency.duration_ms.is_some() {
+            return false;
+        }
+        latency.duration_ms = Some(elapsed_ms(pending.started_at, completed_at));
+        true
+    }
+
+    /// Take the first-token latency from the first text or reasoning part of a message
+    /// being measured. Returns whether it was recorded.
+    fn time_first_part(&mut self, part: &Value) -> bool {
+        if !matches!(
+            part.get("type").and_then(Value::as_str),
+            Some("text" | "reasoning")
+        ) {
+            return false;
+        }
+        let (Some(id), Some(session_id)) = (
+            part.get("messageID").and_then(Value::as_str),
+            part.get("sessionID").and_then(Value::as_str),
+        ) else {
+            return false;
+        };
+        let Some(pending) = self.pending.get(id) else {
+            return false;
+        };
+        if self
+            .latencies
+            .get(id)
+            .is_some_and(|latency| latency.first_token_ms.is_some())
+        {
+            return false;
+        }
+        let streamed_at = part
+            .get("time")
+            .and_then(|time| time.get("start"))
+            .and_then(Value::as_i64)
+            .unwrap_or_else(now_millis);
+        let first_token_ms = elapsed_ms(pending.started_at, streamed_at);
+        let model = pending.model.clone();
+        self.latencies
+            .entry(id.to_string())
+            .or_insert_with(|| new_latency(session_id, model))
+            .first_token_ms = Some(first_token_ms);
+        true
+    }
+}
+
+fn new_latency(session_id: &str, model: Option<String>) -> MessageLatency {
+    MessageLatency {
+        session_id: session_id.to_string(),
+        model,
+        day: today(),
+        first_token_ms: None,
+        duration_ms: None,
+    }
+}
+
+fn message_model(info: &Value) -> Option<String> {
+    let model = info.get("modelID").and_then(Value::as_str)?;
+    Some(match info.get("providerID").and_then(Value::as_str) {
+        Some(provider) => format!("{provider}/{model}"),
+        None => model.to_string(),
+    })
+}
+
+fn elapsed_ms(from: i64, to: i64) -> u64 {
+    to.saturating_sub(from).max(0) as u64
+}
+
+fn now_millis() -> i64 {
+    Utc::now().timestamp_millis()
+}
+
+/// Apply `message` to its session's and its day's totals.
+fn count(
+    sessions: &mut HashMap<String, Usage>,
+    days: &mut BTreeMap<String, Usage>,
+    message: &MessageUsage,
+    apply: fn(&mut Usage, &Usage),
+) {
+    apply(
+        sessions.entry(message.session_id.clone()).or_default(),
+        &message.usage,
+    );
+    apply(days.entry(message.day.clone()).or_default(), &message.usage);
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageUpdated {
+    session_id: String,
+    session: Usage,
+    today: Usage,
+    /// Timings of the message that moved the totals, once any were measured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    latency: Option<MessageLatency>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DayUsage {
+    pub date: String,
+    pub usage: Usage,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageSummary {
+    pub period: String,
+    /// First day included; absent for `all`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    pub totals: Usage,
+    /// Days with usage, oldest first.
+    pub days: Vec<DayUsage>,
+}
+
+/// Median and 95th percentile of one timing, in milliseconds, over the messages that
+/// have it.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyPercentiles {
+    pub count: usize,
+    pub median: Option<u64>,
+    pub p95: Option<u64>,
+}
+
+impl LatencyPercentiles {
+    fn from_samples(mut samples: Vec<u64>) -> Self {
+        samples.sort_unstable();
+        Self {
+            count: samples.len(),
+            median: percentile(&samples, 0.5),
+            p95: percentile(&samples, 0.95),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelLatency {
+    /// `provider/model`, or `unknown` for messages that did not say.
+    pub model: String,
+    /// Messages with any timing.
+    pub count: usize,
+    pub first_token: LatencyPercentiles,
+    pub duration: LatencyPercentiles,
+}
+
+/// Token usage and cost per session and per day, taken from assistant messages.
+#[derive(Clone, Default)]
+pub struct UsageTracker {
+    ledger: Arc<Mutex<Ledger>>,
+    persist: Arc<AtomicBool>,
+    save_generation: Arc<AtomicU64>,
+}
+
+impl UsageTracker {
+    /// Start from the stored ledger, if there is one.
+    pub fn load() -> Self {
+        let file = usage_file_path()
+            .and_then(|path| Ok(std::fs::read(path)?))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<UsageFile>(&bytes).ok())
+            .unwrap_or_default();
+        Self {
+            ledger: Arc::new(Mutex::new(Ledger::new(file))),
+            persist: Arc::new(AtomicBool::new(true)),
+            ..Self::default()
+        }
+    }
+
+    /// Whether the ledger is written to disk. Turning it off leaves the file as it was.
+    pub fn set_persist(&self, persist: bool) {
+        self.persist.store(persist, Ordering::Relaxed);
+    }
+
+    /// Take the usage and timings from a `message.updated` event. A user message starts
+    /// the clock for the reply.
+    pub fn record_message(&self, app: &AppHandle, properties: &Value) {
+        let Some(info) = properties.get("info") else {
+            return;
+        };
+        let (Some(id), Some(session_id)) = (
+            info.get("id").and_then(Value::as_str),
+            info.get("sessionID").and_then(Value::as_str),
+        ) else {
+            return;
+        };
+        match info.get("role").and_then(Value::as_str) {
+            Some("assistant") => {}
+            Some("user") => {
+                let sent_at = info
+                    .get("time")
+                    .and_then(|time| time.get("created"))
+                    .and_then(Value::as_i64)
+                    .unwrap_or_else(now_millis);
+                if let Ok(mut ledger) = self.ledger.lock() {
+                    ledger.step_starts.insert(session_id.to_string(), sent_at);
+                }
+                return;
+            }
+            _ => return,
+        }
+        let timed = self
+            .ledger
+            .lock()
+            .is_ok_and(|mut ledger| ledger.time_assistant_message(id, session_id, info));
+        let Some(usage) = Usage::from_message(info) else {
+            if timed {
+                self.schedule_save();
+            }
+            return;
+        };
+        let time = info.get("time");
+        let day = time
+            .and_then(|time| time.get("created"))
+            .and_then(Value::as_i64)
+            .and_then(|millis| Local.timestamp_millis_opt(millis).single())
+            .unwrap_or_else(Local::now)
+            .format(DAY_FORMAT)
+            .to_string();
+        let completed = time.and_then(|time| time.get("completed")).is_some();
+        let message = MessageUsage {
+            session_id: session_id.to_string(),
+            day,
+            completed,
+            usage,
+        };
+
+        let updated = {
+            let Ok(mut ledger) = self.ledger.lock() else {
+                return;
+            };
+            let was_completed = ledger
+                .messages
+                .get(id)
+                .is_some_and(|message| message.completed);
+            if !ledger.replace(id, message) && !timed {
+                return;
+            }
+            let session = ledger.sessions.get(session_id).copied().unwrap_or_default();
+            let emitted = ledger.emitted.get(session_id).copied().unwrap_or_default();
+            let meaningful = (completed && !was_completed) || session.moved_from(&emitted);
+            meaningful.then(|| {
+                ledger.emitted.insert(session_id.to_string(), session);
+                UsageUpdated {
+                    session_id: session_id.to_string(),
+                    session,
+                    today: ledger.days.get(&today()).copied().unwrap_or_default(),
+                    latency: ledger.latencies.get(id).cloned(),
+                }
+            })
+        };
+
+        if let Some(updated) = updated {
+            let _ = app.emit(USAGE_UPDATED_EVENT, updated);
+        }
+        self.schedule_save();
+    }
+
+    /// Take the first-token latency from a `message.part.updated` event.
+    pub fn record_part(&self, properties: &Value) {
+        let Some(part) = properties.get("part") else {
+            return;
+        };
+        let timed = self
+            .ledger
+            .lock()
+            .is_ok_and(|mut ledger| ledger.time_first_part(part));
+        if timed {
+            self.schedule_save();
+        }
+    }
+
+    /// A session going busy starts the clock unless its user message already did; going
+    /// idle ends the turn.
+    pub fn record_session_status(&self, properties: &Value) {
+        let Some(session_id) = properties.get("sessionID").and_then(Value::as_str) else {
+            return;
+        };
+        let status = properties
+            .get("status")
+            .and_then(|status| status.get("type"))
+            .and_then(Value::as_str);
+        let Ok(mut ledger) = self.ledger.lock() else {
+            return;
+        };
+        match status {
+            Some("busy") => {
+                ledger
+                    .step_starts
+                    .entry(session_id.to_string())
+                    .or_insert_with(now_millis);
+            }
+            Some("idle") => {
+                ledger.step_starts.remove(session_id);
+                ledger
+                    .pending
+                    .retain(|_, pending| pending.session_id != session_id);
+            }
+            _ => {}
+        }
+    }
+
+    /// First-token latency and generation duration per model, for one session or all.
+    pub fn latency_stats(&self, session_id: Option<&str>) -> Vec<ModelLatency> {
+        let Ok(ledger) = self.ledger.lock() else {
+            return Vec::new();
+        };
+        let mut by_model: BTreeMap<&str, Vec<&MessageLatency>> = BTreeMap::new();
+        for latency in ledger.latencies.values() {
+            if session_id.is_some_and(|session_id| latency.session_id != session_id) {
+                continue;
+            }
+            let model = latency.model.as_deref().unwrap_or("unknown");
+            by_model.entry(model).or_default().push(latency);
+        }
+        by_model
+            .into_iter()
+            .map(|(model, latencies)| ModelLatency {
+                model: model.to_string(),
+                count: latencies.len(),
+                first_token: LatencyPercentiles::from_samples(
+                    latencies
+                        .iter()
+                        .filter_map(|latency| latency.first_token_ms)
+                        .collect(),
+                ),
+                duration: LatencyPercentiles::from_samples(
+                    latencies
+                        .iter()
+                        .filter_map(|latency| latency.duration_ms)
+                        .collect(),
+                ),
+            })
+            .collect()
+    }
+
+    pub fn session_usage(&self, session_id: &str) -> Usage {
+        self.ledger
+            .lock()
+            .ok()
+            .and_then(|ledger| ledger.sessions.get(session_id).copied())
+            .unwrap_or_default()
+    }
+
+    /// Totals for `today`, `week` (the last 7 days), `month` (the last 30) or `all`.
+    pub fn summary(&self, period: &str) -> Result<UsageSummary, String> {
+        let period = period.trim().to_ascii_lowercase();
+        let days_back = match period.as_str() {
+            "today" => Some(0),
+            "week" => Some(6),
+            "month" => Some(29),
+            "all" => None,
+            _ => {
+                return Err(format!(
+                    "Unknown period \"{period}\"; expected today, week, month or all"
+                ))
+            }
+        };
+        let from = days_back.and_then(|days| {
+            Local::now()
+                .date_naive()
+                .checked_sub_days(chrono::Days::new(days))
+                .map(|date: NaiveDate| date.format(DAY_FORMAT).to_string())
+        });
+
+        let ledger = self
+            .ledger
+            .lock()
+            .map_err(|_| "Usage ledger unavailable".to_string())?;
+        let mut totals = Usage::default();
+        let days: Vec<DayUsage> = ledger
+            .days
+            .range(from.clone().unwrap_or_default()..)
+            .map(|(date, usage)| {
+                totals.add(usage);
+                DayUsage {
+                    date: date.clone(),
+                    usage: *usage,
+                }
+            })
+            .collect();
+        Ok(UsageSummary {
+            period,
+            from,
+            totals,
+            days,
+        })
+    }
+
+    fn schedule_save(&self) {
+        if !self.persist.load(Ordering::Relaxed) {
+            return;
+        }
+        let generation = self.save_generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let tracker = self.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(SAVE_DEBOUNCE).await;
+            if tracker.save_generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            if let Err(err) = tracker.save().await {
+                warn!("[desktop] Failed to save usage: {err}");
+            }
+        });
+    }
+
+    async fn save(&self) -> Result<()> {
+        let data = {
+            let mut ledger = self
+                .ledger
+                .lock()
+                .map_err(|_| anyhow!("Usage ledger poisoned"))?;
+            ledger.prune();
+            serde_json::to_vec(&UsageFile {
+                messages: ledger.messages.clone(),
+                latencies: ledger.latencies.clone(),
+            })?
+        };
+        let path = usage_file_path()?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let temp_path = path.with_extension("json.tmp");
+        tokio::fs::write(&temp_path, data).await?;
+        tokio::fs::rename(&temp_path, &path).await?;
+        Ok(())
+    }
+}
+
+/// Nearest-rank percentile of sorted samples.
+fn percentile(sorted: &[u64], fraction: f64) -> Option<u64> {
+    let rank = (fraction * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.saturating_sub(1)).copied()
+}
+
+fn today() -> String {
+    Local::now().format(DAY_FORMAT).to_string()
+}
+
+fn usage_file_path() -> Result<PathBuf> {
+    let mut path = dirs::home_dir().ok_or_else(|| anyhow!("No home directory"))?;
+    path.push(".config");
+    path.push("openchamber");
+    path.push(USAGE_FILE);
+    Ok(path)
+}
@@ -1,15 +1,27 @@
 use anyhow::{anyhow, Result};
+use log::warn;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::{BTreeMap, HashSet},
     path::PathBuf,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
 };
-use tauri::{LogicalPosition, LogicalSize, WebviewWindow, Window};
+use tauri::{LogicalSize, Monitor, PhysicalPosition, PhysicalSize, Window};
 use tokio::fs as async_fs;
 
 const WINDOW_STATE_FILE: &str = "window-state.json";
+/// Moves and resizes arrive in bursts while dragging; one write follows the last of them.
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+const MIN_WIDTH: f64 = 400.0;
+const MIN_HEIGHT: f64 = 300.0;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Geometry of one window. Sizes and positions are logical pixels at the scale of
+/// `monitor`, so a window keeps its apparent size on displays of different density.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WindowState {
     pub width: f64,
@@ -17,6 +29,11 @@ pub struct WindowState {
     pub x: f64,
     pub y: f64,
     pub is_maximized: bool,
+    #[serde(default)]
+    pub is_fullscreen: bool,
+    /// Name of the display the window was on. Unknown for state saved by older builds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<String>,
 }
 
 impl Default for WindowState {
@@ -27,53 +44,135 @@ impl Default for WindowState {
             x: 0.0,
             y: 0.0,
             is_maximized: false,
+            is_fullscreen: false,
+            monitor: None,
         }
     }
 }
 
-#[derive(Serialize, Deserialize)]
+/// Saved geometry keyed by window label. Files from older builds only hold
+/// `windowState`, the main window's.
+#[derive(Default, Serialize, Deserialize)]
 struct WindowStateFile {
-    #[serde(rename = "windowState")]
-    pub window_state: WindowState,
+    #[serde(default)]
+    windows: BTreeMap<String, WindowState>,
+    #[serde(
+        rename = "windowState",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    legacy_window_state: Option<WindowState>,
 }
 
-#[derive(Clone)]
+/// Geometry of every window, kept current from window events and written to disk a
+/// moment after a window stops moving.
+#[derive(Clone, Default)]
 pub struct WindowStateManager {
-    inner: Arc<Mutex<WindowState>>,
+    states: Arc<Mutex<BTreeMap<String, WindowState>>>,
+    /// Windows whose saved geometry was applied already; restoring happens once per run.
+    restored: Arc<Mutex<HashSet<String>>>,
+    save_generation: Arc<AtomicU64>,
 }
 
 impl WindowStateManager {
-    pub fn new(initial: WindowState) -> Self {
+    pub fn new(initial: BTreeMap<String, WindowState>) -> Self {
         Self {
-            inner: Arc::new(Mutex::new(initial)),
+            states: Arc::new(Mutex::new(initial)),
+            ..Self::default()
         }
     }
 
-    pub fn snapshot(&self) -> WindowState {
-        self.inner.lock().expect("window state poisoned").clone()
+    pub fn snapshot(&self) -> BTreeMap<String, WindowState> {
+        self.states.lock().expect("window state poisoned").clone()
     }
 
-    pub fn update_position(&self, x: f64, y: f64, is_maximized: bool) {
-        if is_maximized {
+    /// Apply the saved geometry of `window` the first time it is seen.
+    pub fn restore(&self, window: &Window) {
+        let first = self
+            .restored
+            .lock()
+            .map(|mut restored| restored.insert(window.label().to_string()))
+            .unwrap_or(false);
+        if !first {
             return;
         }
-        if let Ok(mut state) = self.inner.lock() {
-            if !state.is_maximized {
-                state.x = x;
-                state.y = y;
+        let saved = self
+            .states
+            .lock()
+            .ok()
+            .and_then(|states| states.get(window.label()).cloned());
+        if let Some(saved) = saved {
+            if let Err(err) = apply_window_state(window, &saved) {
+                warn!(
+                    "Failed to restore geometry of window {}: {}",
+                    window.label(),
+                    err
+                );
             }
         }
     }
 
-    pub fn update_size(&self, width: f64, height: f64, is_maximized: bool) {
-        if let Ok(mut state) = self.inner.lock() {
-            if !is_maximized {
-                state.width = width;
-                state.height = height;
+    /// Record where `window` is now and schedule a save.
+    pub fn track(&self, window: &Window) {
+        if self.capture(window) {
+            self.schedule_save();
+        }
+    }
+
+    /// Forget the saved geometry of `label`, or of every window when `None`.
+    pub fn forget(&self, label: Option<&str>) {
+        if let Ok(mut states) = self.states.lock() {
+            match label {
+                Some(label) => {
+                    states.remove(label);
+                }
+                None => states.clear(),
             }
-            state.is_maximized = is_maximized;
         }
     }
+
+    /// Returns true if the recorded geometry changed.
+    fn capture(&self, window: &Window) -> bool {
+        // A minimized window reports a parking position far off-screen on Windows.
+        if window.is_minimized().unwrap_or(false) || !window.is_visible().unwrap_or(false) {
+            return false;
+        }
+        // Until the saved geometry is applied, the window sits at its default bounds.
+        let restored = self
+            .restored
+            .lock()
+            .map(|restored| restored.contains(window.label()))
+            .unwrap_or(false);
+        if !restored {
+            return false;
+        }
+        let Ok(mut states) = self.states.lock() else {
+            return false;
+        };
+        let previous = states.get(window.label()).cloned();
+        let Some(next) = read_window_state(window, previous.as_ref()) else {
+            return false;
+        };
+        if previous.as_ref() == Some(&next) {
+            return false;
+        }
+        states.insert(window.label().to_string(), next);
+        true
+    }
+
+    fn schedule_save(&self) {
+        let generation = self.save_generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let manager = self.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(SAVE_DEBOUNCE).await;
+            if manager.save_generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            if let Err(err) = save_window_states(&manager.snapshot()).await {
+                warn!("Failed to save window state: {}", err);
+            }
+        });
+    }
 }
 
 fn state_file_path() -> Result<PathBuf> {
@@ -84,84 +183,153 @@ fn state_file_path() -> Result<PathBuf> {
     Ok(path)
 }
 
-pub async fn load_window_state() -> Result<Option<WindowState>> {
+pub async fn load_window_states() -> Result<BTreeMap<String, WindowState>> {
     let path = state_file_path()?;
     match async_fs::read(&path).await {
         Ok(bytes) => {
             let file: WindowStateFile = serde_json::from_slice(&bytes)?;
-            Ok(Some(file.window_state))
+            let mut windows = file.windows;
+            if let Some(legacy) = file.legacy_window_state {
+                windows.entry("main".to_string()).or_insert(legacy);
+            }
+            Ok(windows)
         }
-        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
         Err(err) => Err(err.into()),
     }
 }
 
-pub async fn save_window_state(state: &WindowState) -> Result<()> {
+pub async fn save_window_states(windows: &BTreeMap<String, WindowState>) -> Result<()> {
     let path = state_file_path()?;
     if let Some(parent) = path.parent() {
         async_fs::create_dir_all(parent).await?;
     }
     let payload = WindowStateFile {
-        window_state: state.clone(),
+        windows: windows.clone(),
+        legacy_window_state: None,
     };
     let data = serde_json::to_vec_pretty(&payload)?;
     async_fs::write(&path, data).await?;
     Ok(())
 }
 
-pub fn apply_window_state(window: &WebviewWindow, state: &WindowState) -> Result<()> {
-    let mut normalized = state.clone();
-    clamp_to_visible_region(window, &mut normalized);
+/// Put `window` back where it was. A window saved on a display that is gone opens
+/// centered on the primary display, and one that would hang off its display is moved
+/// and shrunk to fit.
+pub fn apply_window_state(window: &Window, state: &WindowState) -> Result<()> {
+    let monitors = window.available_monitors()?;
+    let saved_monitor = match &state.monitor {
+        Some(name) => monitors
+            .iter()
+            .find(|monitor| monitor.name() == Some(name))
+            .cloned(),
+        // Older state has no monitor name; look for the display containing its origin.
+        None => monitors
+            .iter()
+            .find(|monitor| contains_origin(monitor, state))
+            .cloned(),
+    };
 
-    if normalized.width > 0.0 && normalized.height > 0.0 {
-        let _ = window.set_size(LogicalSize::new(normalized.width, normalized.height));
+    match saved_monitor {
+        Some(monitor) => {
+            let (position, size) = fit_to_monitor(&monitor, state);
+            window.set_size(size)?;
+            window.set_position(position)?;
+        }
+        None => {
+            let target = window
+                .primary_monitor()?
+                .or_else(|| monitors.first().cloned());
+            if let Some(monitor) = target {
+                let (_, size) = fit_to_monitor(&monitor, state);
+                window.set_size(size)?;
+            }
+            window.center()?;
+        }
     }
-    let _ = window.set_position(LogicalPosition::new(normalized.x, normalized.y));
-    if state.is_maximized {
-        let _ = window.maximize();
+
+    if state.is_fullscreen {
+        window.set_fullscreen(true)?;
+    } else if state.is_maximized {
+        window.maximize()?;
     } else {
-        let _ = window.unmaximize();
+        window.unmaximize()?;
     }
     Ok(())
 }
 
-pub async fn persist_window_state(window: &Window, manager: &WindowStateManager) -> Result<()> {
-    let mut snapshot = manager.snapshot();
-    let is_maximized = window.is_maximized().unwrap_or(snapshot.is_maximized);
-    snapshot.is_maximized = is_maximized;
-
-    if !is_maximized {
-        let scale_factor = window.scale_factor().unwrap_or(1.0);
-        if let Ok(size) = window.outer_size() {
-            let logical: LogicalSize<f64> = size.to_logical(scale_factor);
-            snapshot.width = logical.width.max(200.0);
-            snapshot.height = logical.height.max(200.0);
-        }
-        if let Ok(position) = window.outer_position() {
-            let logical: LogicalPosition<f64> = position.to_logical(scale_factor);
-            snapshot.x = logical.x;
-            snapshot.y = logical.y;
-        }
+/// Save every open window's geometry now, for quitting.
+pub async fn persist_window_state(windows: &[Window], manager: &WindowStateManager) -> Result<()> {
+    for window in windows {
+        manager.capture(window);
     }
+    save_window_states(&manager.snapshot()).await
+}
 
-    save_window_state(&snapshot).await
+/// Give `window` its default size and center it, leaving maximized and fullscreen.
+pub fn reset_window(window: &Window) -> Result<()> {
+    let defaults = WindowState::default();
+    window.set_fullscreen(false)?;
+    window.unmaximize()?;
+    window.set_size(LogicalSize::new(defaults.width, defaults.height))?;
+    window.center()?;
+    Ok(())
 }
 
-fn clamp_to_visible_region(window: &WebviewWindow, state: &mut WindowState) {
-    let monitor = match window.current_monitor() {
-        Ok(Some(monitor)) => monitor,
-        _ => return,
-    };
-    let scale_factor = monitor.scale_factor();
-    let monitor_size: LogicalSize<f64> = monitor.size().to_logical(scale_factor);
-    let monitor_position: LogicalPosition<f64> = monitor.position().to_logical(scale_factor);
+/// The window's current geometry. While maximized or fullscreen only the flags and the
+/// monitor are updated, so restoring and then un-maximizing lands on the old bounds.
+fn read_window_state(window: &Window, previous: Option<&WindowState>) -> Option<WindowState> {
+    let mut state = previous.cloned().unwrap_or_default();
+    state.is_maximized = window.is_maximized().unwrap_or(false);
+    state.is_fullscreen = window.is_fullscreen().unwrap_or(false);
+    if let Ok(Some(monitor)) = window.current_monitor() {
+        state.monitor = monitor.name().cloned();
+    }
+    if state.is_maximized || state.is_fullscreen {
+        return Some(state);
+    }
+
+    let scale_factor = window.scale_factor().ok()?;
+    let size = window.inner_size().ok()?.to_logical::<f64>(scale_factor);
+    let position = window
+        .outer_position()
+        .ok()?
+        .to_logical::<f64>(scale_factor);
+    state.width = size.width.max(MIN_WIDTH);
+    state.height = size.height.max(MIN_HEIGHT);
+    state.x = position.x;
+    state.y = position.y;
+    Some(state)
+}
+
+fn contains_origin(monitor: &Monitor, state: &WindowState) -> bool {
+    let area = monitor.work_area();
+    let scale = monitor.scale_factor();
+    let (x, y) = ((state.x * scale) as i64, (state.y * scale) as i64);
+    let (left, top) = (area.position.x as i64, area.position.y as i64);
+    x >= left && y >= top && x < left + area.size.width as i64 && y < top + area.size.height as i64
+}
+
+/// Physical bounds for `state` on `monitor`, clamped inside its work area.
+fn fit_to_monitor(
+    monitor: &Monitor,
+    state: &WindowState,
+) -> (PhysicalPosition<i32>, PhysicalSize<u32>) {
+    let area = monitor.work_area();
+    let scale = monitor.scale_factor();
+    let area_width = area.size.width as f64;
+    let area_height = area.size.height as f64;
 
-    state.width = state.width.clamp(400.0, monitor_size.width);
-    state.height = state.height.clamp(300.0, monitor_size.height);
+    let width = (state.width * scale).clamp((MIN_WIDTH * scale).min(area_width), area_width);
+    let height = (state.height * scale).clamp((MIN_HEIGHT * scale).min(area_height), area_height);
 
-    let max_x = monitor_position.x + (monitor_size.width - state.width).max(0.0);
-    let max_y = monitor_position.y + (monitor_size.height - state.height).max(0.0);
+    let left = area.position.x as f64;
+    let top = area.position.y as f64;
+    let x = (state.x * scale).clamp(left, left + area_width - width);
+    let y = (state.y * scale).clamp(top, top + area_height - height);
 
-    state.x = state.x.clamp(monitor_position.x, max_x);
-    state.y = state.y.clamp(monitor_position.y, max_y);
+    (
+        PhysicalPosition::new(x.round() as i32, y.round() as i32),
+        PhysicalSize::new(width.round() as u32, height.round() as u32),
+    )
 }
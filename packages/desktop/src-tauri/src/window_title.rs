@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use log::warn;
+use tauri::{AppHandle, Manager};
+
+use crate::assistant_notifications::PendingQuestions;
+use crate::session_activity::{BusySession, BusySessions};
+use crate::settings_watcher::next_settings_change;
+use crate::tray::TRAY_ID;
+use crate::DesktopRuntime;
+
+/// The main window's title from `tauri.conf.json`, shown when nothing is going on.
+const APP_TITLE: &str = "OpenChamber";
+/// The title and tooltip follow activity at most this often.
+const MIN_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// "OpenChamber — 2 working, 1 question", or just "OpenChamber" when every session is
+/// idle and nothing is asked.
+pub(crate) fn status_title(sessions: &[BusySession], pending_questions: usize) -> String {
+    let working = sessions
+        .iter()
+        .filter(|session| matches!(session.phase, "busy" | "retry" | "compacting"))
+        .count();
+    let mut parts = Vec::new();
+    if working > 0 {
+        parts.push(format!("{working} working"));
+    }
+    match pending_questions {
+        0 => {}
+        1 => parts.push("1 question".to_string()),
+        count => parts.push(format!("{count} questions")),
+    }
+    if parts.is_empty() {
+        APP_TITLE.to_string()
+    } else {
+        format!("{APP_TITLE} — {}", parts.join(", "))
+    }
+}
+
+/// Put the live status in the main window's title, so the window can be told apart in
+/// Mission Control or Alt-Tab, and in the tray icon's tooltip. Only the main window is
+/// decorated, and `window.statusInTitle` set to false keeps its plain title.
+pub fn spawn_window_title(
+    app: AppHandle,
+    runtime: DesktopRuntime,
+) -> tauri::async_runtime::JoinHandle<()> {
+    tauri::async_runtime::spawn(async move {
+        let mut shutdown_rx = runtime.subscribe_shutdown();
+        let mut settings_changes = runtime.subscribe_settings_changes();
+        let mut busy = app.state::<BusySessions>().subscribe();
+        let mut questions = app.state::<PendingQuestions>().subscribe_count();
+        let mut enabled = runtime
+            .settings()
+            .load_typed()
+            .await
+            .map(|settings| settings.window.status_in_title())
+            .unwrap_or(true);
+        let mut shown_title = APP_TITLE.to_string();
+        let mut shown_tooltip = APP_TITLE.to_string();
+
+        loop {
+            let status = status_title(&busy.borrow_and_update(), *questions.borrow_and_update());
+            let title = if enabled { status.as_str() } else { APP_TITLE };
+            let changed = title != shown_title || status != shown_tooltip;
+            if title != shown_title {
+                set_main_title(&app, title);
+                shown_title = title.to_string();
+            }
+            if status != shown_tooltip {
+                // The tray may be turned off; it picks the status up when created.
+                if let Some(tray) = app.tray_by_id(TRAY_ID) {
+                    let _ = tray.set_tooltip(Some(&status));
+                }
+                shown_tooltip = status;
+            }
+            if changed {
+                tokio::select! {
+                    _ = shutdown_rx.recv() => break,
+                    _ = tokio::time::sleep(MIN_UPDATE_INTERVAL) => {}
+                }
+            }
+
+            tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                Ok(()) = busy.changed() => {}
+                Ok(()) = questions.changed() => {}
+                change = next_settings_change(&mut settings_changes) => {
+                    enabled = change.current.window.status_in_title();
+                }
+            }
+        }
+
+        if shown_title != APP_TITLE {
+            set_main_title(&app, APP_TITLE);
+        }
+    })
+}
+
+fn set_main_title(app: &AppHandle, title: &str) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    if let Err(err) = window.set_title(title) {
+        warn!("[desktop] Failed to update window title: {err}");
+    }
+}
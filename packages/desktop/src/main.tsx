@@ -47,6 +47,8 @@ declare global {
 
 const CHECK_FOR_UPDATES_EVENT = 'openchamber:check-for-updates';
 const MENU_ACTION_EVENT = 'openchamber:menu-action';
+const NAVIGATE_SESSION_EVENT = 'openchamber:navigate-session';
+const TOAST_EVENT = 'openchamber:toast';
 
 const cleanupFunctions: Array<() => void | Promise<void>> = [];
 
@@ -68,6 +70,16 @@ try {
   });
   cleanupFunctions.push(() => menuActionUnlisten());
 
+  const navigateSessionUnlisten = await listen<{ sessionId: string }>(NAVIGATE_SESSION_EVENT, (event) => {
+    window.dispatchEvent(new CustomEvent(NAVIGATE_SESSION_EVENT, { detail: event.payload }));
+  });
+  cleanupFunctions.push(() => navigateSessionUnlisten());
+
+  const toastUnlisten = await listen<{ kind: string; message: string }>(TOAST_EVENT, (event) => {
+    window.dispatchEvent(new CustomEvent(TOAST_EVENT, { detail: event.payload }));
+  });
+  cleanupFunctions.push(() => toastUnlisten());
+
   requestInitialNotificationPermission().catch(err => {
     console.error('[main] Failed to request notification permission:', err);
   });
@@ -177,6 +189,13 @@ window.opencodeDesktop = {
   },
   markRendererReady() {
 
+  },
+  async navigationReady() {
+    try {
+      await invoke('deep_links_ready');
+    } catch (error) {
+      console.error('[desktop] Failed to process pending links:', error);
+    }
   },
   async requestDirectoryAccess(directoryPath?: string) {
     try {
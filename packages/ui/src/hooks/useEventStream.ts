@@ -117,6 +117,26 @@ const formatModelID = (raw: string): string => {
     .join(' ');
 };
 
+// The desktop reports finer phases than the store tracks; fold them the same way
+// session.status is folded. A session retrying, compacting its context or waiting on
+// a question is still mid-run, so it counts as busy; an error ends the run.
+const toActivityPhase = (phase: string | null): 'idle' | 'busy' | 'cooldown' | null => {
+  switch (phase) {
+    case 'idle':
+    case 'busy':
+    case 'cooldown':
+      return phase;
+    case 'retry':
+    case 'compacting':
+    case 'waiting-for-input':
+      return 'busy';
+    case 'error':
+      return 'idle';
+    default:
+      return null;
+  }
+};
+
 export const useEventStream = () => {
   const {
     addStreamingPart,
@@ -649,8 +669,8 @@ export const useEventStream = () => {
       }
       case 'openchamber:session-activity': {
         const sessionId = typeof props.sessionId === 'string' ? props.sessionId : null;
-        const phase = typeof props.phase === 'string' ? props.phase : null;
-        if (sessionId && (phase === 'idle' || phase === 'busy' || phase === 'cooldown')) {
+        const phase = toActivityPhase(typeof props.phase === 'string' ? props.phase : null);
+        if (sessionId && phase) {
           updateSessionActivityPhase(sessionId, phase);
           requestSessionMetadataRefresh(sessionId, typeof props.directory === 'string' ? props.directory : null);
         }
@@ -1571,14 +1591,19 @@ export const useEventStream = () => {
       window.__messageTracker = trackMessage;
     }
 
-    let desktopActivityHandler: ((event: CustomEvent<{ sessionId?: string; phase?: string }>) => void) | null = null;
+    type DesktopActivityChange = { sessionId?: string; phase?: string };
+    let desktopActivityHandler: ((event: CustomEvent<DesktopActivityChange | DesktopActivityChange[]>) => void) | null = null;
     if (isDesktopRuntimeRef.current && typeof window !== 'undefined') {
-      desktopActivityHandler = (event: CustomEvent<{ sessionId?: string; phase?: string }>) => {
-        const sessionId = typeof event.detail?.sessionId === 'string' ? event.detail.sessionId : null;
-        const phase = typeof event.detail?.phase === 'string' ? event.detail.phase : null;
-        if (sessionId && (phase === 'idle' || phase === 'busy' || phase === 'cooldown')) {
-          updateSessionActivityPhase(sessionId, phase);
-          requestSessionMetadataRefresh(sessionId);
+      desktopActivityHandler = (event: CustomEvent<DesktopActivityChange | DesktopActivityChange[]>) => {
+        // Bursts of changes arrive batched as an array, in order.
+        const changes = Array.isArray(event.detail) ? event.detail : [event.detail];
+        for (const change of changes) {
+          const sessionId = typeof change?.sessionId === 'string' ? change.sessionId : null;
+          const phase = toActivityPhase(typeof change?.phase === 'string' ? change.phase : null);
+          if (sessionId && phase) {
+            updateSessionActivityPhase(sessionId, phase);
+            requestSessionMetadataRefresh(sessionId);
+          }
         }
       };
       window.addEventListener('openchamber:session-activity', desktopActivityHandler as EventListener);
@@ -6,11 +6,13 @@ import { useProjectsStore } from '@/stores/useProjectsStore';
 import { useThemeSystem } from '@/contexts/useThemeSystem';
 import { getRegisteredRuntimeAPIs } from '@/contexts/runtimeAPIRegistry';
 import { sessionEvents } from '@/lib/sessionEvents';
-import { isDesktopRuntime } from '@/lib/desktop';
+import { getDesktopApi, isDesktopRuntime } from '@/lib/desktop';
 import { useFileSystemAccess } from '@/hooks/useFileSystemAccess';
 import { createWorktreeSession } from '@/lib/worktreeSessionCreator';
 
 const MENU_ACTION_EVENT = 'openchamber:menu-action';
+const NAVIGATE_SESSION_EVENT = 'openchamber:navigate-session';
+const TOAST_EVENT = 'openchamber:toast';
 
 type MenuAction =
   | 'about'
@@ -33,7 +35,7 @@ type MenuAction =
 export const useMenuActions = (
   onToggleMemoryDebug?: () => void
 ) => {
-  const { openNewSessionDraft } = useSessionStore();
+  const { openNewSessionDraft, setCurrentSession } = useSessionStore();
   const {
     toggleCommandPalette,
     toggleHelpDialog,
@@ -211,4 +213,35 @@ export const useMenuActions = (
     onToggleMemoryDebug,
     handleChangeWorkspace,
   ]);
+
+  React.useEffect(() => {
+    const handleNavigateSession = (event: Event) => {
+      const sessionId = (event as CustomEvent<{ sessionId?: string }>).detail?.sessionId;
+      if (!sessionId) {
+        return;
+      }
+      setActiveMainTab('chat');
+      void setCurrentSession(sessionId);
+    };
+
+    const handleToast = (event: Event) => {
+      const detail = (event as CustomEvent<{ kind?: string; message?: string }>).detail;
+      if (!detail?.message) {
+        return;
+      }
+      if (detail.kind === 'error') {
+        toast.error(detail.message);
+      } else {
+        toast(detail.message);
+      }
+    };
+
+    window.addEventListener(NAVIGATE_SESSION_EVENT, handleNavigateSession);
+    window.addEventListener(TOAST_EVENT, handleToast);
+    void getDesktopApi()?.navigationReady?.();
+    return () => {
+      window.removeEventListener(NAVIGATE_SESSION_EVENT, handleNavigateSession);
+      window.removeEventListener(TOAST_EVENT, handleToast);
+    };
+  }, [setActiveMainTab, setCurrentSession]);
 };
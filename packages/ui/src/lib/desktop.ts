@@ -76,6 +76,8 @@ export type DesktopApi = {
   restartOpenCode: () => Promise<{ success: boolean }>;
   shutdown: () => Promise<{ success: boolean }>;
   markRendererReady?: () => Promise<void> | void;
+  // Called once navigation and toast events are handled; links opened before then are held.
+  navigationReady?: () => Promise<void> | void;
   windowControl?: (action: 'close' | 'minimize' | 'maximize') => Promise<{ success: boolean }>;
   getHomeDirectory?: () => Promise<{ success: boolean; path: string | null }>;
   getSettings?: () => Promise<DesktopSettings>;